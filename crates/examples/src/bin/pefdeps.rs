@@ -0,0 +1,261 @@
+//! Print the tree of libraries imported by a PEF fragment.
+//!
+//! Libraries are found in the search directories by their 'cfrg' fragment name,
+//! or by their file name if they have no 'cfrg' resource.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+use object::endian::BigEndian as BE;
+use object::read::pef::{MacFile, PefFile};
+use object::{pef, Object};
+
+/// A PEF container that was found in a file.
+struct Fragment {
+    path: PathBuf,
+    /// The 'cfrg' name if there is one, otherwise the file name.
+    name: Vec<u8>,
+    file_name: Vec<u8>,
+    architecture: u32,
+    /// The version from the 'cfrg' entry if there is one, otherwise from the container.
+    current_version: u32,
+    old_def_version: u32,
+    data: Vec<u8>,
+}
+
+fn main() {
+    let mut args = env::args();
+    if args.len() < 2 {
+        eprintln!(
+            "Usage: {} <file> [<search directory>...]",
+            args.next().unwrap()
+        );
+        process::exit(1);
+    }
+
+    args.next();
+    let file_path = PathBuf::from(args.next().unwrap());
+    let fragments = match load_fragments(&file_path) {
+        Ok(fragments) => fragments,
+        Err(err) => {
+            eprintln!("Failed to read '{}': {}", file_path.display(), err);
+            process::exit(1);
+        }
+    };
+    if fragments.is_empty() {
+        eprintln!("No PEF fragments in '{}'", file_path.display());
+        process::exit(1);
+    }
+
+    let mut libraries = Vec::new();
+    for dir in args {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                eprintln!("Failed to read directory '{}': {}", dir, err);
+                process::exit(1);
+            }
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && !is_apple_double(path))
+            .collect();
+        paths.sort();
+        for path in paths {
+            // Anything that isn't a PEF container is silently ignored.
+            if let Ok(fragments) = load_fragments(&path) {
+                libraries.extend(fragments);
+            }
+        }
+    }
+
+    let mut missing = false;
+    for fragment in &fragments {
+        println!(
+            "{} ({}, version {:#010x})",
+            String::from_utf8_lossy(&fragment.name),
+            architecture_name(fragment.architecture),
+            fragment.current_version
+        );
+        let mut stack = vec![fragment.path.clone()];
+        let mut seen = HashSet::new();
+        if let Err(err) =
+            print_imports(fragment, &libraries, 1, &mut stack, &mut seen, &mut missing)
+        {
+            eprintln!("Failed to parse '{}': {}", fragment.path.display(), err);
+            process::exit(1);
+        }
+    }
+    if missing {
+        process::exit(2);
+    }
+}
+
+/// Print the libraries imported by `fragment`, and recursively their imports.
+///
+/// `stack` contains the fragments currently being printed, for cycle detection.
+/// `seen` contains the fragments whose imports have already been printed.
+fn print_imports(
+    fragment: &Fragment,
+    libraries: &[Fragment],
+    depth: usize,
+    stack: &mut Vec<PathBuf>,
+    seen: &mut HashSet<(PathBuf, Vec<u8>)>,
+    missing: &mut bool,
+) -> Result<(), Box<dyn Error>> {
+    let file = PefFile::<&[u8]>::parse(&fragment.data)?;
+    let Some(loader) = file.pef_loader() else {
+        return Ok(());
+    };
+    let imports = file.imports()?;
+    let indent = "  ".repeat(depth);
+    for library in loader.libraries() {
+        let name = loader.library_name(library)?;
+        let weak = library.options & pef::WEAK_IMPORT_LIB != 0;
+        let current = library.current_version.get(BE);
+        let old_imp = library.old_imp_version.get(BE);
+        let symbols = imports
+            .iter()
+            .filter(|import| import.library() == name)
+            .count();
+
+        print!(
+            "{}{}{} [{} symbols, version {:#010x}, oldest {:#010x}]",
+            indent,
+            String::from_utf8_lossy(name),
+            if weak { " (weak)" } else { "" },
+            symbols,
+            current,
+            old_imp,
+        );
+
+        let candidates: Vec<_> = libraries
+            .iter()
+            .filter(|lib| lib.name == name || lib.file_name == name)
+            .filter(|lib| lib.architecture == fragment.architecture)
+            .collect();
+        let found = candidates
+            .iter()
+            .find(|lib| is_compatible(current, old_imp, lib.current_version, lib.old_def_version));
+        let Some(found) = found else {
+            if let Some(lib) = candidates.first() {
+                println!(
+                    " => {}: INCOMPATIBLE (version {:#010x}, oldest {:#010x})",
+                    lib.path.display(),
+                    lib.current_version,
+                    lib.old_def_version
+                );
+            } else {
+                println!(" => NOT FOUND");
+            }
+            if weak {
+                println!("{}  (weak import, fragment can still be prepared)", indent);
+            } else {
+                *missing = true;
+            }
+            continue;
+        };
+
+        println!(" => {}", found.path.display());
+        if stack.contains(&found.path) {
+            println!("{}  (cycle)", indent);
+            continue;
+        }
+        if !seen.insert((found.path.clone(), found.name.clone())) {
+            continue;
+        }
+        stack.push(found.path.clone());
+        print_imports(found, libraries, depth + 1, stack, seen, missing)?;
+        stack.pop();
+    }
+    Ok(())
+}
+
+/// Check the Code Fragment Manager compatibility rules for an import.
+///
+/// A version of zero means that the version was not specified.
+fn is_compatible(imp_current: u32, imp_old: u32, def_current: u32, def_old: u32) -> bool {
+    if imp_current == 0 || def_current == 0 {
+        return true;
+    }
+    if imp_current > def_current {
+        // The importer was linked against a newer library, so the library
+        // must be at least as new as the oldest version the importer can use.
+        imp_old <= def_current
+    } else {
+        // The library is newer, so it must still support the version the
+        // importer was linked against.
+        imp_current >= def_old
+    }
+}
+
+/// Load the PEF fragments from a file.
+///
+/// The file may be a raw PEF container, or wrapped in MacBinary or AppleSingle.
+/// The resource fork is also read from an AppleDouble `._` file next to the file.
+fn load_fragments(path: &Path) -> Result<Vec<Fragment>, Box<dyn Error>> {
+    let data = fs::read(path)?;
+    let sidecar = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file_name)) => {
+            let mut name = std::ffi::OsString::from("._");
+            name.push(file_name);
+            fs::read(dir.join(name)).ok()
+        }
+        _ => None,
+    };
+
+    let mut mac_file = MacFile::parse(&data)?;
+    if let Some(sidecar) = &sidecar {
+        let double = MacFile::parse(sidecar)?;
+        if mac_file.resource_fork().is_empty() && !double.resource_fork().is_empty() {
+            mac_file = MacFile::from_forks(mac_file.data_fork(), double.resource_fork());
+        }
+    }
+
+    let file_name = match mac_file.name() {
+        Some(name) => name.to_vec(),
+        None => path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned().into_bytes())
+            .unwrap_or_default(),
+    };
+    let mut fragments = Vec::new();
+    for fragment in mac_file.fragments()? {
+        let file = PefFile::<&[u8]>::parse(fragment.data())?;
+        let header = file.pef_header();
+        // The Code Fragment Manager uses the 'cfrg' versions when there is
+        // a 'cfrg' entry, and these can differ between members of a file.
+        let (current_version, old_def_version) = match fragment.member() {
+            Some(member) => (member.current_version(), member.old_def_version()),
+            None => (
+                header.current_version.get(BE),
+                header.old_def_version.get(BE),
+            ),
+        };
+        fragments.push(Fragment {
+            path: path.to_path_buf(),
+            name: fragment.name().unwrap_or(&file_name).to_vec(),
+            architecture: header.architecture.get(BE),
+            current_version,
+            old_def_version,
+            file_name: file_name.clone(),
+            data: fragment.data().to_vec(),
+        });
+    }
+    Ok(fragments)
+}
+
+fn is_apple_double(path: &Path) -> bool {
+    let name = path.file_name().map(|name| name.to_string_lossy());
+    name.as_deref().unwrap_or("").starts_with("._")
+}
+
+fn architecture_name(architecture: u32) -> &'static str {
+    match architecture {
+        pef::ARCHITECTURE_PPC => "PowerPC",
+        pef::ARCHITECTURE_68K => "68K",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,609 @@
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+use std::{env, fs, mem, process};
+
+use object::endian::BigEndian as BE;
+use object::read::pef::{
+    evaluate_relocations, LoaderRelocationTarget, LoaderSection, SectionTable,
+};
+use object::write::elf::{
+    FileHeader, ProgramHeader, Rel, SectionHeader, SectionIndex, Sym, Vernaux, Verneed,
+};
+use object::{elf, pef, Endianness};
+
+fn main() {
+    let mut args = env::args();
+    if args.len() != 3 {
+        eprintln!("Usage: {} <infile> <outfile>", args.next().unwrap());
+        process::exit(1);
+    }
+
+    args.next();
+    let in_file_path = args.next().unwrap();
+    let out_file_path = args.next().unwrap();
+
+    let in_file = match fs::File::open(&in_file_path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Failed to open file '{}': {}", in_file_path, err,);
+            process::exit(1);
+        }
+    };
+    let in_data = match unsafe { memmap2::Mmap::map(&in_file) } {
+        Ok(mmap) => mmap,
+        Err(err) => {
+            eprintln!("Failed to map file '{}': {}", in_file_path, err,);
+            process::exit(1);
+        }
+    };
+    let in_data = &*in_data;
+
+    let kind = match object::FileKind::parse(in_data) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Failed to parse file: {}", err);
+            process::exit(1);
+        }
+    };
+    let out_data = match kind {
+        object::FileKind::Pef => match copy_file(in_data) {
+            Ok(out_data) => out_data,
+            Err(err) => {
+                eprintln!("Failed to convert file: {}", err);
+                process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("Not a PEF file");
+            process::exit(1);
+        }
+    };
+    if let Err(err) = fs::write(&out_file_path, out_data) {
+        eprintln!("Failed to write file '{}': {}", out_file_path, err);
+        process::exit(1);
+    }
+}
+
+/// The alignment of the file offset and address of each instantiated section.
+///
+/// Sections are placed at an address that is equal to their file offset,
+/// and each instantiated section has its own loadable segment.
+const PAGE_SIZE: usize = 0x1000;
+
+struct Section {
+    index: SectionIndex,
+    instantiated: bool,
+    sh_flags: u32,
+    p_flags: u32,
+    alignment: u8,
+    data: Vec<u8>,
+    offset: usize,
+}
+
+struct Import<'data> {
+    name: &'data [u8],
+    library: usize,
+    weak: bool,
+    class: u8,
+}
+
+struct Export<'data> {
+    name: &'data [u8],
+    section: Option<usize>,
+    value: u32,
+    class: u8,
+}
+
+fn copy_file(in_data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let header = pef::PEFContainerHeader::parse(in_data)?;
+    if header.architecture.get(BE) != pef::ARCHITECTURE_PPC {
+        return Err("Unsupported PEF architecture".into());
+    }
+    let in_sections = SectionTable::parse(header, in_data)?;
+    let loader = in_sections.loader()?.ok_or("Missing PEF loader section")?;
+    let loader = LoaderSection::parse_section(in_data, loader)?;
+
+    // Convert sections, excluding the loader section.
+    // The vector is indexed by the PEF section index.
+    let mut sections = Vec::new();
+    let mut names = Vec::new();
+    for in_section in in_sections.iter() {
+        let kind = in_section.kind();
+        if kind == Some(pef::SectionKind::Loader) {
+            sections.push(None);
+            names.push(&[][..]);
+            continue;
+        }
+        names.push(match in_section.name(in_sections.strings())? {
+            Some(name) => name,
+            None => default_section_name(kind),
+        });
+        let instantiated = in_section.is_instantiated();
+        let data = if instantiated {
+            let mut data = in_section.pef_unpacked_data(in_data)?.into_owned();
+            let total_size = in_section.total_size.get(BE) as usize;
+            if total_size > data.len() {
+                data.resize(total_size, 0);
+            }
+            data
+        } else {
+            in_section.pef_data(in_data)?.to_vec()
+        };
+        let (sh_flags, p_flags) = match kind {
+            Some(pef::SectionKind::Code) => {
+                (elf::SHF_ALLOC | elf::SHF_EXECINSTR, elf::PF_R | elf::PF_X)
+            }
+            Some(pef::SectionKind::Constant) => (elf::SHF_ALLOC, elf::PF_R),
+            Some(pef::SectionKind::ExecutableData) => (
+                elf::SHF_ALLOC | elf::SHF_WRITE | elf::SHF_EXECINSTR,
+                elf::PF_R | elf::PF_W | elf::PF_X,
+            ),
+            _ if instantiated => (elf::SHF_ALLOC | elf::SHF_WRITE, elf::PF_R | elf::PF_W),
+            _ => (0, 0),
+        };
+        sections.push(Some(Section {
+            index: SectionIndex(0),
+            instantiated,
+            sh_flags,
+            p_flags,
+            alignment: in_section.alignment,
+            data,
+            offset: 0,
+        }));
+    }
+
+    // Evaluate the loader relocations.
+    let mut relocations = Vec::new();
+    for relocation_header in loader.relocation_headers() {
+        let section_index = usize::from(relocation_header.section_index.get(BE));
+        let section = sections
+            .get(section_index)
+            .and_then(Option::as_ref)
+            .filter(|section| section.instantiated)
+            .ok_or("Invalid PEF relocation section index")?;
+        let blocks = loader.relocation_instructions(relocation_header)?;
+        for relocation in evaluate_relocations(blocks, section.data.len() as u32)? {
+            relocations.push((section_index, relocation));
+        }
+    }
+
+    // Convert imports. The vector is indexed by the PEF imported symbol index.
+    let mut imports = Vec::new();
+    for (library_index, library) in loader.libraries().iter().enumerate() {
+        let first = library.first_imported_symbol.get(BE) as usize;
+        if first != imports.len() {
+            return Err("Unsupported PEF imported symbol order".into());
+        }
+        let library_weak = library.options & pef::WEAK_IMPORT_LIB != 0;
+        for symbol in loader.library_symbols(library)? {
+            imports.push(Import {
+                name: loader.imported_symbol_name(symbol)?,
+                library: library_index,
+                weak: library_weak || symbol.symbol_flags() & pef::WEAK_IMPORT_SYMBOL != 0,
+                class: symbol.symbol_class(),
+            });
+        }
+    }
+    if imports.len() != loader.imported_symbols().len() {
+        return Err("Unsupported PEF imported symbol order".into());
+    }
+
+    // Convert exports. Reexported imports are already undefined symbols.
+    let mut exports = Vec::new();
+    for (index, symbol) in loader.exported_symbols().iter().enumerate() {
+        let section_index = symbol.section_index.get(BE);
+        let section = match section_index {
+            pef::EXPORTED_ABSOLUTE_SECTION => None,
+            pef::EXPORTED_REEXPORTED_IMPORT_SECTION => continue,
+            _ => {
+                let section_index = usize::try_from(section_index)
+                    .ok()
+                    .filter(|index| matches!(sections.get(*index), Some(Some(_))))
+                    .ok_or("Invalid PEF exported symbol section index")?;
+                Some(section_index)
+            }
+        };
+        exports.push(Export {
+            name: loader.exported_symbol_name(index)?,
+            section,
+            value: symbol.symbol_value.get(BE),
+            class: symbol.symbol_class(),
+        });
+    }
+
+    let mut out_data = Vec::new();
+    let mut writer = object::write::elf::Writer::new(Endianness::Big, false, &mut out_data);
+
+    // Calculate sizes and indices.
+    let load_count = sections
+        .iter()
+        .flatten()
+        .filter(|section| section.instantiated)
+        .count();
+    writer.reserve_file_header();
+    writer.reserve_program_headers(load_count as u32 + 2);
+
+    writer.reserve_null_section_index();
+    for section in sections.iter_mut().flatten() {
+        section.index = writer.reserve_section_index();
+    }
+    let section_names: Vec<_> = sections
+        .iter()
+        .zip(&names)
+        .map(|(section, name)| section.as_ref().map(|_| writer.add_section_name(name)))
+        .collect();
+    let dynsym_index = writer.reserve_dynsym_section_index();
+    writer.reserve_dynstr_section_index();
+    writer.reserve_hash_section_index();
+    writer.reserve_gnu_versym_section_index();
+    if !loader.libraries().is_empty() {
+        writer.reserve_gnu_verneed_section_index();
+    }
+    let rela_name = if relocations.is_empty() {
+        None
+    } else {
+        writer.reserve_section_index();
+        Some(writer.add_section_name(b".rela.dyn"))
+    };
+    writer.reserve_dynamic_section_index();
+    writer.reserve_shstrtab_section_index();
+
+    // The dynamic section always refers to the string table, even if the
+    // file has no imports or exports.
+    writer.require_dynstr();
+    let mut library_names = Vec::new();
+    for library in loader.libraries() {
+        library_names.push(writer.add_dynamic_string(loader.library_name(library)?));
+    }
+    let import_names: Vec<_> = imports
+        .iter()
+        .map(|import| writer.add_dynamic_string(import.name))
+        .collect();
+    let export_names: Vec<_> = exports
+        .iter()
+        .map(|export| writer.add_dynamic_string(export.name))
+        .collect();
+
+    writer.reserve_null_dynamic_symbol_index();
+    let first_import_symbol = writer.dynamic_symbol_count();
+    for _ in 0..imports.len() + exports.len() {
+        writer.reserve_dynamic_symbol_index();
+    }
+    let dynamic_symbol_count = writer.dynamic_symbol_count();
+
+    let mut dynamic_count = library_names.len() + 7;
+    if !library_names.is_empty() {
+        dynamic_count += 2;
+    }
+    if !relocations.is_empty() {
+        dynamic_count += 3;
+    }
+
+    // Calculate the file layout.
+    let dynsym_offset = writer.reserve_dynsym();
+    let dynstr_offset = writer.reserve_dynstr();
+    let hash_bucket_count = dynamic_symbol_count;
+    let hash_offset = writer.reserve_hash(hash_bucket_count, dynamic_symbol_count);
+    let versym_offset = writer.reserve_gnu_versym();
+    let verneed_offset = writer.reserve_gnu_verneed(library_names.len(), library_names.len());
+    let rela_offset = writer.reserve_relocations(relocations.len(), true);
+    let dynamic_offset = writer.reserve_dynamic(dynamic_count);
+    let metadata_size = writer.reserved_len();
+    for section in sections.iter_mut().flatten() {
+        if section.instantiated {
+            section.offset = writer.reserve(section.data.len(), PAGE_SIZE);
+        }
+    }
+    for section in sections.iter_mut().flatten() {
+        if !section.instantiated {
+            section.offset = writer.reserve(section.data.len(), 1);
+        }
+    }
+    writer.reserve_shstrtab();
+    writer.reserve_section_headers();
+
+    // Apply relocations to sections, since sections now have addresses.
+    let mut relocation_entries = Vec::with_capacity(relocations.len());
+    for (section_index, relocation) in &relocations {
+        let (r_sym, target_address) = match relocation.target {
+            LoaderRelocationTarget::Section(target) => {
+                let target = sections
+                    .get(target.0)
+                    .and_then(Option::as_ref)
+                    .filter(|section| section.instantiated)
+                    .ok_or("Invalid PEF relocation target section")?;
+                (0, target.offset as u32)
+            }
+            LoaderRelocationTarget::Import(index) => {
+                if index as usize >= imports.len() {
+                    return Err("Invalid PEF relocation import index".into());
+                }
+                (first_import_symbol + index, 0)
+            }
+        };
+        let section = sections[*section_index].as_mut().unwrap();
+        let offset = relocation.offset as usize;
+        let place = &mut section.data[offset..offset + 4];
+        let value = u32::from_be_bytes(place.try_into().unwrap()).wrapping_add(target_address);
+        if r_sym == 0 {
+            place.copy_from_slice(&value.to_be_bytes());
+        }
+        relocation_entries.push(Rel {
+            r_offset: (section.offset + offset) as u64,
+            r_sym,
+            r_type: elf::R_PPC_ADDR32,
+            r_addend: i64::from(value as i32),
+        });
+    }
+
+    // The PEF main symbol is a transition vector, so the entry point is
+    // the code address in its first word, which relocations have already
+    // set to its final value.
+    let loader_header = loader.header();
+    let e_entry = match loader_header.main_section.get(BE) {
+        pef::NO_SECTION => 0,
+        main_section => {
+            let main_section = usize::try_from(main_section)
+                .ok()
+                .and_then(|index| sections.get(index))
+                .and_then(Option::as_ref)
+                .ok_or("Invalid PEF entry point section")?;
+            let offset = loader_header.main_offset.get(BE) as usize;
+            let code = main_section
+                .data
+                .get(offset..)
+                .and_then(|data| data.get(..4))
+                .ok_or("Invalid PEF entry point offset")?;
+            if relocation_entries.iter().any(|relocation| {
+                relocation.r_sym != 0
+                    && relocation.r_offset == (main_section.offset + offset) as u64
+            }) {
+                return Err("Unsupported PEF entry point import".into());
+            }
+            u64::from(u32::from_be_bytes(code.try_into().unwrap()))
+        }
+    };
+
+    // Write the file.
+    writer.write_file_header(&FileHeader {
+        os_abi: elf::ELFOSABI_SYSV,
+        abi_version: 0,
+        e_type: elf::ET_DYN,
+        e_machine: elf::EM_PPC,
+        e_entry,
+        e_flags: 0,
+    })?;
+
+    writer.write_align_program_headers();
+    writer.write_program_header(&ProgramHeader {
+        p_type: elf::PT_LOAD,
+        p_flags: elf::PF_R,
+        p_offset: 0,
+        p_vaddr: 0,
+        p_paddr: 0,
+        p_filesz: metadata_size as u64,
+        p_memsz: metadata_size as u64,
+        p_align: PAGE_SIZE as u64,
+    });
+    for section in sections.iter().flatten() {
+        if !section.instantiated {
+            continue;
+        }
+        writer.write_program_header(&ProgramHeader {
+            p_type: elf::PT_LOAD,
+            p_flags: section.p_flags,
+            p_offset: section.offset as u64,
+            p_vaddr: section.offset as u64,
+            p_paddr: section.offset as u64,
+            p_filesz: section.data.len() as u64,
+            p_memsz: section.data.len() as u64,
+            p_align: PAGE_SIZE as u64,
+        });
+    }
+    writer.write_program_header(&ProgramHeader {
+        p_type: elf::PT_DYNAMIC,
+        p_flags: elf::PF_R,
+        p_offset: dynamic_offset as u64,
+        p_vaddr: dynamic_offset as u64,
+        p_paddr: dynamic_offset as u64,
+        p_filesz: (dynamic_count * 8) as u64,
+        p_memsz: (dynamic_count * 8) as u64,
+        p_align: 4,
+    });
+
+    writer.write_null_dynamic_symbol();
+    for (import, name) in imports.iter().zip(import_names.iter()) {
+        let st_bind = if import.weak {
+            elf::STB_WEAK
+        } else {
+            elf::STB_GLOBAL
+        };
+        writer.write_dynamic_symbol(&Sym {
+            name: Some(*name),
+            section: None,
+            st_info: (st_bind << 4) | symbol_type(import.class),
+            st_other: elf::STV_DEFAULT,
+            st_shndx: elf::SHN_UNDEF,
+            st_value: 0,
+            st_size: 0,
+        });
+    }
+    for (export, name) in exports.iter().zip(export_names.iter()) {
+        let (section, st_shndx, st_value) = match export.section {
+            Some(index) => {
+                let section = sections[index].as_ref().unwrap();
+                (
+                    Some(section.index),
+                    0,
+                    if section.instantiated {
+                        section.offset as u64 + u64::from(export.value)
+                    } else {
+                        u64::from(export.value)
+                    },
+                )
+            }
+            None => (None, elf::SHN_ABS, u64::from(export.value)),
+        };
+        writer.write_dynamic_symbol(&Sym {
+            name: Some(*name),
+            section,
+            st_info: (elf::STB_GLOBAL << 4) | symbol_type(export.class),
+            st_other: elf::STV_DEFAULT,
+            st_shndx,
+            st_value,
+            st_size: 0,
+        });
+    }
+
+    writer.write_dynstr();
+
+    let symbol_names: Vec<_> = imports
+        .iter()
+        .map(|import| import.name)
+        .chain(exports.iter().map(|export| export.name))
+        .collect();
+    writer.write_hash(hash_bucket_count, dynamic_symbol_count, |index| {
+        let name = symbol_names.get(index.checked_sub(1)? as usize)?;
+        Some(elf::hash(name))
+    });
+
+    writer.write_null_gnu_versym();
+    for import in &imports {
+        writer.write_gnu_versym(import.library as u16 + 2);
+    }
+    for _ in &exports {
+        writer.write_gnu_versym(elf::VER_NDX_GLOBAL);
+    }
+
+    writer.write_align_gnu_verneed();
+    for (index, (library, name)) in loader.libraries().iter().zip(&library_names).enumerate() {
+        writer.write_gnu_verneed(&Verneed {
+            version: elf::VER_NEED_CURRENT,
+            aux_count: 1,
+            file: *name,
+        });
+        writer.write_gnu_vernaux(&Vernaux {
+            flags: if library.options & pef::WEAK_IMPORT_LIB != 0 {
+                elf::VER_FLG_WEAK
+            } else {
+                0
+            },
+            index: index as u16 + 2,
+            name: *name,
+        });
+    }
+
+    writer.write_align_relocation();
+    for relocation in &relocation_entries {
+        writer.write_relocation(true, relocation);
+    }
+
+    writer.write_align_dynamic();
+    for name in &library_names {
+        writer.write_dynamic_string(elf::DT_NEEDED, *name);
+    }
+    writer.write_dynamic(elf::DT_HASH, hash_offset as u64);
+    writer.write_dynamic(elf::DT_STRTAB, dynstr_offset as u64);
+    let dynstr_len = writer.dynstr_len();
+    writer.write_dynamic(elf::DT_STRSZ, dynstr_len as u64);
+    writer.write_dynamic(elf::DT_SYMTAB, dynsym_offset as u64);
+    writer.write_dynamic(
+        elf::DT_SYMENT,
+        mem::size_of::<elf::Sym32<Endianness>>() as u64,
+    );
+    writer.write_dynamic(elf::DT_VERSYM, versym_offset as u64);
+    if !library_names.is_empty() {
+        writer.write_dynamic(elf::DT_VERNEED, verneed_offset as u64);
+        writer.write_dynamic(elf::DT_VERNEEDNUM, library_names.len() as u64);
+    }
+    if !relocation_entries.is_empty() {
+        writer.write_dynamic(elf::DT_RELA, rela_offset as u64);
+        writer.write_dynamic(
+            elf::DT_RELASZ,
+            (relocation_entries.len() * mem::size_of::<elf::Rela32<Endianness>>()) as u64,
+        );
+        writer.write_dynamic(
+            elf::DT_RELAENT,
+            mem::size_of::<elf::Rela32<Endianness>>() as u64,
+        );
+    }
+    writer.write_dynamic(elf::DT_NULL, 0);
+
+    for section in sections.iter().flatten() {
+        writer.pad_until(section.offset);
+        writer.write(&section.data);
+    }
+
+    writer.write_shstrtab();
+
+    writer.write_null_section_header();
+    for (section, name) in sections.iter().zip(&section_names) {
+        let Some(section) = section else {
+            continue;
+        };
+        let sh_addr = if section.instantiated {
+            section.offset as u64
+        } else {
+            0
+        };
+        writer.write_section_header(&SectionHeader {
+            name: *name,
+            sh_type: elf::SHT_PROGBITS,
+            sh_flags: section.sh_flags.into(),
+            sh_addr,
+            sh_offset: section.offset as u64,
+            sh_size: section.data.len() as u64,
+            sh_link: 0,
+            sh_info: 0,
+            sh_addralign: 1 << section.alignment.min(31),
+            sh_entsize: 0,
+        });
+    }
+    writer.write_dynsym_section_header(dynsym_offset as u64, 1);
+    writer.write_dynstr_section_header(dynstr_offset as u64);
+    writer.write_hash_section_header(hash_offset as u64);
+    writer.write_gnu_versym_section_header(versym_offset as u64);
+    writer.write_gnu_verneed_section_header(verneed_offset as u64);
+    if let Some(name) = rela_name {
+        writer.write_section_header(&SectionHeader {
+            name: Some(name),
+            sh_type: elf::SHT_RELA,
+            sh_flags: elf::SHF_ALLOC.into(),
+            sh_addr: rela_offset as u64,
+            sh_offset: rela_offset as u64,
+            sh_size: (relocation_entries.len() * mem::size_of::<elf::Rela32<Endianness>>()) as u64,
+            sh_link: dynsym_index.0,
+            sh_info: 0,
+            sh_addralign: 4,
+            sh_entsize: mem::size_of::<elf::Rela32<Endianness>>() as u64,
+        });
+    }
+    writer.write_dynamic_section_header(dynamic_offset as u64);
+    writer.write_shstrtab_section_header();
+
+    debug_assert_eq!(writer.reserved_len(), writer.len());
+    Ok(out_data)
+}
+
+/// Return a name for a section that doesn't have one.
+fn default_section_name(kind: Option<pef::SectionKind>) -> &'static [u8] {
+    match kind {
+        Some(pef::SectionKind::Code) => b".text",
+        Some(pef::SectionKind::UnpackedData)
+        | Some(pef::SectionKind::PatternInitializedData)
+        | Some(pef::SectionKind::ExecutableData) => b".data",
+        Some(pef::SectionKind::Constant) => b".rodata",
+        Some(pef::SectionKind::Debug) => b".pef.debug",
+        Some(pef::SectionKind::Exception) => b".pef.exception",
+        Some(pef::SectionKind::Traceback) => b".pef.traceback",
+        _ => b".pef.unknown",
+    }
+}
+
+/// Return the ELF symbol type for a PEF symbol class.
+fn symbol_type(class: u8) -> u8 {
+    match class {
+        pef::CODE_SYMBOL | pef::GLUE_SYMBOL => elf::STT_FUNC,
+        _ => elf::STT_OBJECT,
+    }
+}
@@ -112,6 +112,24 @@ fn main() {
                 .action(ArgAction::SetTrue)
                 .help("Print the PE resource directory"),
         )
+        .arg(
+            Arg::new("pef-loader")
+                .long("pef-loader")
+                .action(ArgAction::SetTrue)
+                .help("Print the PEF loader info header"),
+        )
+        .arg(
+            Arg::new("pef-imports")
+                .long("pef-imports")
+                .action(ArgAction::SetTrue)
+                .help("Print the PEF imported libraries and symbols"),
+        )
+        .arg(
+            Arg::new("pef-exports")
+                .long("pef-exports")
+                .action(ArgAction::SetTrue)
+                .help("Print the PEF export hash table and exported symbols"),
+        )
         .arg(
             Arg::new("no-string-indices")
                 .long("no-string-indices")
@@ -136,6 +154,9 @@ fn main() {
         pe_imports: matches.get_flag("pe-imports"),
         pe_exports: matches.get_flag("pe-exports"),
         pe_resources: matches.get_flag("pe-resources"),
+        pef_loader: matches.get_flag("pef-loader"),
+        pef_imports: matches.get_flag("pef-imports"),
+        pef_exports: matches.get_flag("pef-exports"),
         ..readobj::PrintOptions::none()
     };
     if options == readobj::PrintOptions::none() {
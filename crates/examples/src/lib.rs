@@ -10,3 +10,6 @@ pub mod objdump;
 
 #[cfg(feature = "read")]
 pub mod readobj;
+
+#[cfg(all(feature = "read", feature = "write"))]
+pub mod xcofftopef;
@@ -112,6 +112,7 @@ pub fn copy(in_data: &[u8]) -> Vec<u8> {
                     containing_csect,
                 }
             }
+            SymbolFlags::Pef { symbol_class } => SymbolFlags::Pef { symbol_class },
             _ => panic!("unknown symbol flags for {:?}", in_symbol),
         };
         let out_symbol = write::Symbol {
@@ -1,6 +1,7 @@
 use object::read::archive::ArchiveFile;
 use object::read::coff;
 use object::read::macho::{DyldCache, FatArch, MachOFatFile32, MachOFatFile64};
+use object::read::SymbolVersion;
 use object::{Endianness, FileKind, Object, ObjectComdat, ObjectSection, ObjectSymbol};
 use std::io::{Result, Write};
 
@@ -232,7 +233,13 @@ fn dump_parsed_object<W: Write, E: Write>(w: &mut W, e: &mut E, file: &object::F
             if !imports.is_empty() {
                 writeln!(w)?;
                 for import in imports {
-                    writeln!(w, "{:x?}", import)?;
+                    write!(
+                        w,
+                        "Import {{ library: \"{}\", name: \"{}\"",
+                        String::from_utf8_lossy(import.library()),
+                        String::from_utf8_lossy(import.name()),
+                    )?;
+                    dump_version(w, import.version())?;
                 }
             }
         }
@@ -244,7 +251,13 @@ fn dump_parsed_object<W: Write, E: Write>(w: &mut W, e: &mut E, file: &object::F
             if !exports.is_empty() {
                 writeln!(w)?;
                 for export in exports {
-                    writeln!(w, "{:x?}", export)?;
+                    write!(
+                        w,
+                        "Export {{ name: \"{}\", address: {:x}",
+                        String::from_utf8_lossy(export.name()),
+                        export.address(),
+                    )?;
+                    dump_version(w, export.version())?;
                 }
             }
         }
@@ -260,6 +273,14 @@ fn dump_parsed_object<W: Write, E: Write>(w: &mut W, e: &mut E, file: &object::F
     Ok(())
 }
 
+/// Finish an import or export line, printing the symbol version if there is one.
+fn dump_version<W: Write>(w: &mut W, version: Option<SymbolVersion<'_>>) -> Result<()> {
+    if let Some(version) = version {
+        write!(w, ", version: {:?}", version)?;
+    }
+    writeln!(w, " }}")
+}
+
 fn dump_import<W: Write, E: Write>(w: &mut W, e: &mut E, data: &[u8]) -> Result<()> {
     let file = match coff::ImportFile::parse(data) {
         Ok(import) => import,
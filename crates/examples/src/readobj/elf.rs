@@ -245,6 +245,9 @@ fn print_section_headers<Elf: FileHeader>(
             && !(p.options.symbols && sh_type == SHT_SYMTAB)
             && !(p.options.relocations && sh_type == SHT_REL)
             && !(p.options.relocations && sh_type == SHT_RELA)
+            && !(p.options.relocations && sh_type == SHT_ANDROID_REL)
+            && !(p.options.relocations && sh_type == SHT_ANDROID_RELA)
+            && !(p.options.relocations && sh_type == SHT_CREL)
             && !(p.options.elf_dynamic && sh_type == SHT_DYNAMIC)
             && !(p.options.elf_dynamic_symbols && sh_type == SHT_DYNSYM)
             && !(p.options.elf_notes && sh_type == SHT_NOTE)
@@ -317,7 +320,11 @@ fn print_section_headers<Elf: FileHeader>(
                 }
                 SHT_REL => print_section_rel(p, endian, data, elf, sections, section),
                 SHT_RELA => print_section_rela(p, endian, data, elf, sections, section),
-                SHT_RELR => print_section_relr(p, endian, data, elf, section),
+                SHT_RELR | SHT_ANDROID_RELR => print_section_relr(p, endian, data, elf, section),
+                SHT_ANDROID_REL | SHT_ANDROID_RELA => {
+                    print_section_android_rel(p, endian, data, elf, sections, section)
+                }
+                SHT_CREL => print_section_crel(p, endian, data, elf, sections, section),
                 SHT_NOTE => print_section_notes(p, endian, data, elf, section),
                 SHT_DYNAMIC => print_section_dynamic(p, endian, data, elf, sections, section),
                 SHT_GROUP => print_section_group(p, endian, data, elf, sections, section),
@@ -508,6 +515,73 @@ fn print_section_rela<Elf: FileHeader>(
     }
 }
 
+fn print_section_android_rel<Elf: FileHeader>(
+    p: &mut Printer<'_>,
+    endian: Elf::Endian,
+    data: &[u8],
+    elf: &Elf,
+    sections: &SectionTable<Elf>,
+    section: &Elf::SectionHeader,
+) {
+    if !p.options.relocations {
+        return;
+    }
+    if let Some(Some((relocations, link))) = section.android_relocations(endian, data).print_err(p)
+    {
+        let symbols = sections
+            .symbol_table_by_index(endian, data, link)
+            .print_err(p);
+        let proc = rel_flag_type(endian, elf);
+        for relocation in relocations {
+            let Some(relocation) = relocation.print_err(p) else {
+                break;
+            };
+            p.group("Relocation", |p| {
+                p.field_hex("Offset", relocation.r_offset);
+                p.field_enum("Type", relocation.r_type, proc);
+                print_rel_symbol(p, endian, symbols, relocation.symbol());
+                let addend = relocation.r_addend as u64;
+                if addend != 0 {
+                    p.field_hex("Addend", addend);
+                }
+            });
+        }
+    }
+}
+
+fn print_section_crel<Elf: FileHeader>(
+    p: &mut Printer<'_>,
+    endian: Elf::Endian,
+    data: &[u8],
+    elf: &Elf,
+    sections: &SectionTable<Elf>,
+    section: &Elf::SectionHeader,
+) {
+    if !p.options.relocations {
+        return;
+    }
+    if let Some(Some((relocations, link))) = section.crel(endian, data).print_err(p) {
+        let symbols = sections
+            .symbol_table_by_index(endian, data, link)
+            .print_err(p);
+        let proc = rel_flag_type(endian, elf);
+        for relocation in relocations {
+            let Some(relocation) = relocation.print_err(p) else {
+                break;
+            };
+            p.group("Relocation", |p| {
+                p.field_hex("Offset", relocation.r_offset);
+                p.field_enum("Type", relocation.r_type, proc);
+                print_rel_symbol(p, endian, symbols, relocation.symbol());
+                let addend = relocation.r_addend as u64;
+                if addend != 0 {
+                    p.field_hex("Addend", addend);
+                }
+            });
+        }
+    }
+}
+
 fn print_rel_symbol<Elf: FileHeader>(
     p: &mut Printer<'_>,
     endian: Elf::Endian,
@@ -654,6 +728,8 @@ fn print_notes<Elf: FileHeader>(
                 ELF_NOTE_SOLARIS => FLAGS_NT_SOLARIS,
                 ELF_NOTE_GNU => FLAGS_NT_GNU,
                 ELF_NOTE_GO => FLAGS_NT_GO,
+                ELF_NOTE_FDO => FLAGS_NT_FDO,
+                ELF_NOTE_STAPSDT => FLAGS_NT_STAPSDT,
                 _ => {
                     // TODO: NT_VERSION
                     &[]
@@ -1392,7 +1468,11 @@ const FLAGS_SHT: &[Flag<u32>] = &flags!(
     SHT_GROUP,
     SHT_SYMTAB_SHNDX,
     SHT_RELR,
+    SHT_CREL,
+    SHT_ANDROID_REL,
+    SHT_ANDROID_RELA,
     SHT_LLVM_DEPENDENT_LIBRARIES,
+    SHT_ANDROID_RELR,
     SHT_GNU_ATTRIBUTES,
     SHT_GNU_HASH,
     SHT_GNU_LIBLIST,
@@ -3348,6 +3428,11 @@ const FLAGS_NT_CORE: &[Flag<u32>] = &flags!(
     NT_ARM_HW_WATCH,
     NT_ARM_SYSTEM_CALL,
     NT_ARM_SVE,
+    NT_ARM_PAC_MASK,
+    NT_ARM_PACA_KEYS,
+    NT_ARM_PACG_KEYS,
+    NT_ARM_TAGGED_ADDR_CTRL,
+    NT_ARM_PAC_ENABLED_KEYS,
     NT_VMCOREDD,
     NT_MIPS_DSP,
     NT_MIPS_FP_MODE,
@@ -3361,6 +3446,8 @@ const FLAGS_NT_GNU: &[Flag<u32>] = &flags!(
     NT_GNU_PROPERTY_TYPE_0,
 );
 const FLAGS_NT_GO: &[Flag<u32>] = &flags!(NT_GO_BUILD_ID);
+const FLAGS_NT_FDO: &[Flag<u32>] = &flags!(NT_FDO_PACKAGING_METADATA, NT_FDO_DLOPEN_METADATA);
+const FLAGS_NT_STAPSDT: &[Flag<u32>] = &flags!(NT_STAPSDT);
 const FLAGS_GNU_PROPERTY: &[Flag<u32>] = &flags!(
     GNU_PROPERTY_STACK_SIZE,
     GNU_PROPERTY_NO_COPY_ON_PROTECTED,
@@ -3427,6 +3514,16 @@ const FLAGS_DT: &[Flag<u32>] = &flags!(
     DT_PREINIT_ARRAY,
     DT_PREINIT_ARRAYSZ,
     DT_SYMTAB_SHNDX,
+    DT_RELRSZ,
+    DT_RELR,
+    DT_RELRENT,
+    DT_ANDROID_REL,
+    DT_ANDROID_RELSZ,
+    DT_ANDROID_RELA,
+    DT_ANDROID_RELASZ,
+    DT_ANDROID_RELR,
+    DT_ANDROID_RELRSZ,
+    DT_ANDROID_RELRENT,
     DT_GNU_PRELINKED,
     DT_GNU_CONFLICTSZ,
     DT_GNU_LIBLISTSZ,
@@ -31,6 +31,11 @@ pub struct PrintOptions {
     pub pe_exports: bool,
     pub pe_resources: bool,
 
+    // PEF specific selectors
+    pub pef_loader: bool,
+    pub pef_imports: bool,
+    pub pef_exports: bool,
+
     // Modifiers
     pub string_indices: bool,
 }
@@ -55,6 +60,9 @@ impl PrintOptions {
             pe_imports: true,
             pe_exports: true,
             pe_resources: true,
+            pef_loader: true,
+            pef_imports: true,
+            pef_exports: true,
             string_indices: true,
         }
     }
@@ -78,6 +86,9 @@ impl PrintOptions {
             pe_imports: false,
             pe_exports: false,
             pe_resources: false,
+            pef_loader: false,
+            pef_imports: false,
+            pef_exports: false,
             string_indices: true,
         }
     }
@@ -296,6 +307,7 @@ fn print_object(p: &mut Printer<'_>, data: &[u8], extra_files: &[&[u8]]) {
         object::FileKind::MachOFat64 => macho::print_macho_fat64(p, data),
         object::FileKind::Pe32 => pe::print_pe32(p, data),
         object::FileKind::Pe64 => pe::print_pe64(p, data),
+        object::FileKind::Pef => pef::print_pef(p, data),
         object::FileKind::Xcoff32 => xcoff::print_xcoff32(p, data),
         object::FileKind::Xcoff64 => xcoff::print_xcoff64(p, data),
         // TODO
@@ -373,4 +385,5 @@ impl<T, E: fmt::Display> PrintErr<T> for Result<T, E> {
 mod elf;
 mod macho;
 mod pe;
+mod pef;
 mod xcoff;
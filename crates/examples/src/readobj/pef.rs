@@ -0,0 +1,320 @@
+use super::*;
+use object::endian::BigEndian as BE;
+use object::pef::*;
+use object::read::pef::*;
+
+pub(super) fn print_pef(p: &mut Printer<'_>, data: &[u8]) {
+    if let Some(header) = PEFContainerHeader::parse(data).print_err(p) {
+        writeln!(p.w(), "Format: PEF").unwrap();
+        print_container_header(p, header);
+        if let Some(sections) = SectionTable::parse(header, data).print_err(p) {
+            print_sections(p, data, &sections);
+            if let Some(Some(loader)) = sections.loader().print_err(p) {
+                if let Some(loader) = LoaderSection::parse_section(data, loader).print_err(p) {
+                    print_loader_info(p, &loader);
+                    print_imports(p, &loader);
+                    print_relocations(p, &loader);
+                    print_exports(p, &loader);
+                }
+            }
+        }
+    }
+}
+
+fn print_container_header(p: &mut Printer<'_>, header: &PEFContainerHeader) {
+    if !p.options.file {
+        return;
+    }
+    p.group("ContainerHeader", |p| {
+        print_tag(p, "Tag1", header.tag1.get(BE));
+        print_tag(p, "Tag2", header.tag2.get(BE));
+        print_tag(p, "Architecture", header.architecture.get(BE));
+        p.field("FormatVersion", header.format_version.get(BE));
+        print_date(p, "DateTimeStamp", header.date_time_stamp.get(BE));
+        p.field_hex("OldDefVersion", header.old_def_version.get(BE));
+        p.field_hex("OldImpVersion", header.old_imp_version.get(BE));
+        p.field_hex("CurrentVersion", header.current_version.get(BE));
+        p.field("SectionCount", header.section_count.get(BE));
+        p.field("InstSectionCount", header.inst_section_count.get(BE));
+        p.field_hex("ReservedA", header.reserved_a.get(BE));
+    });
+}
+
+/// Print a four character code, such as `Joy!` or `pwpc`.
+fn print_tag(p: &mut Printer<'_>, name: &str, value: u32) {
+    p.field_name(name);
+    p.print_string(&value.to_be_bytes());
+    writeln!(p.w(), " (0x{:X})", value).unwrap();
+}
+
+/// Print a timestamp, which is the number of seconds since midnight, January 1, 1904.
+fn print_date(p: &mut Printer<'_>, name: &str, value: u32) {
+    let days = value / 86400;
+    let seconds = value % 86400;
+    // Convert the day number to a civil date. 1904 is a leap year, and the
+    // range of a u32 doesn't reach 2100, so every fourth year is a leap year.
+    let mut year = 1904 + 4 * (days / 1461);
+    let mut day = days % 1461;
+    for length in [366, 365, 365] {
+        if day < length {
+            break;
+        }
+        day -= length;
+        year += 1;
+    }
+    let mut month = 1;
+    for (index, length) in MONTH_DAYS.iter().enumerate() {
+        let length = if index == 1 && year & 3 == 0 {
+            length + 1
+        } else {
+            *length
+        };
+        if day < length {
+            break;
+        }
+        day -= length;
+        month += 1;
+    }
+    p.field_name(name);
+    writeln!(
+        p.w(),
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} (0x{:X})",
+        year,
+        month,
+        day + 1,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        value
+    )
+    .unwrap();
+}
+
+fn print_sections(p: &mut Printer<'_>, data: &[u8], sections: &SectionTable<'_>) {
+    if !p.options.sections {
+        return;
+    }
+    for (index, section) in sections.enumerate() {
+        p.group("SectionHeader", |p| {
+            p.field("Index", index.0);
+            let name = section.name(sections.strings()).print_err(p).flatten();
+            p.field_string_option("Name", section.name_offset.get(BE), name);
+            p.field_hex("DefaultAddress", section.default_address.get(BE));
+            p.field_hex("TotalSize", section.total_size.get(BE));
+            p.field_hex("UnpackedSize", section.unpacked_size.get(BE));
+            p.field_hex("PackedSize", section.packed_size.get(BE));
+            p.field_hex("ContainerOffset", section.container_offset.get(BE));
+            print_kind(
+                p,
+                "SectionKind",
+                section.section_kind,
+                SectionKind::from_u8(section.section_kind),
+            );
+            print_kind(
+                p,
+                "ShareKind",
+                section.share_kind,
+                ShareKind::from_u8(section.share_kind),
+            );
+            p.field("Alignment", section.alignment);
+            p.field_hex("ReservedA", section.reserved_a);
+            if section.section_kind == SectionKind::PatternInitializedData as u8 {
+                print_pattern_data(p, data, section);
+            }
+        });
+    }
+}
+
+fn print_kind<T: fmt::Debug>(p: &mut Printer<'_>, name: &str, value: u8, kind: Option<T>) {
+    if let Some(kind) = kind {
+        p.field_name(name);
+        writeln!(p.w(), "{:?} (0x{:X})", kind, value).unwrap();
+    } else {
+        p.field_hex(name, value);
+    }
+}
+
+fn print_pattern_data(p: &mut Printer<'_>, data: &[u8], section: &PEFSectionHeader) {
+    let Some(data) = section.pef_data(data).print_err(p) else {
+        return;
+    };
+    for instruction in PatternDataIterator::new(data) {
+        let Some(instruction) = instruction.print_err(p) else {
+            break;
+        };
+        p.group("PatternData", |p| match instruction {
+            PatternDataInstruction::Zero { size } => {
+                p.field("Opcode", "Zero");
+                p.field_hex("Size", size);
+            }
+            PatternDataInstruction::Block { data } => {
+                p.field("Opcode", "Block");
+                p.field_hex("Size", data.len());
+            }
+            PatternDataInstruction::Repeat { data, repeat_count } => {
+                p.field("Opcode", "Repeat");
+                p.field_hex("Size", data.len());
+                p.field("RepeatCount", repeat_count);
+            }
+            PatternDataInstruction::RepeatBlock {
+                common,
+                custom_size,
+                repeat_count,
+                ..
+            } => {
+                p.field("Opcode", "RepeatBlock");
+                p.field_hex("CommonSize", common.len());
+                p.field_hex("CustomSize", custom_size);
+                p.field("RepeatCount", repeat_count);
+            }
+            PatternDataInstruction::RepeatZero {
+                common_size,
+                custom_size,
+                repeat_count,
+                ..
+            } => {
+                p.field("Opcode", "RepeatZero");
+                p.field_hex("CommonSize", common_size);
+                p.field_hex("CustomSize", custom_size);
+                p.field("RepeatCount", repeat_count);
+            }
+        });
+    }
+}
+
+fn print_loader_info(p: &mut Printer<'_>, loader: &LoaderSection<'_>) {
+    if !p.options.pef_loader {
+        return;
+    }
+    let header = loader.header();
+    p.group("LoaderInfoHeader", |p| {
+        p.field("MainSection", header.main_section.get(BE));
+        p.field_hex("MainOffset", header.main_offset.get(BE));
+        p.field("InitSection", header.init_section.get(BE));
+        p.field_hex("InitOffset", header.init_offset.get(BE));
+        p.field("TermSection", header.term_section.get(BE));
+        p.field_hex("TermOffset", header.term_offset.get(BE));
+        p.field(
+            "ImportedLibraryCount",
+            header.imported_library_count.get(BE),
+        );
+        p.field(
+            "TotalImportedSymbolCount",
+            header.total_imported_symbol_count.get(BE),
+        );
+        p.field("RelocSectionCount", header.reloc_section_count.get(BE));
+        p.field_hex("RelocInstrOffset", header.reloc_instr_offset.get(BE));
+        p.field_hex("LoaderStringsOffset", header.loader_strings_offset.get(BE));
+        p.field_hex("ExportHashOffset", header.export_hash_offset.get(BE));
+        p.field(
+            "ExportHashTablePower",
+            header.export_hash_table_power.get(BE),
+        );
+        p.field("ExportedSymbolCount", header.exported_symbol_count.get(BE));
+    });
+}
+
+fn print_imports(p: &mut Printer<'_>, loader: &LoaderSection<'_>) {
+    if !p.options.pef_imports {
+        return;
+    }
+    for library in loader.libraries() {
+        p.group("ImportedLibrary", |p| {
+            let name_offset = library.name_offset.get(BE);
+            p.field_string("Name", name_offset, loader.library_name(library));
+            p.field_hex("OldImpVersion", library.old_imp_version.get(BE));
+            p.field_hex("CurrentVersion", library.current_version.get(BE));
+            p.field("ImportedSymbolCount", library.imported_symbol_count.get(BE));
+            p.field("FirstImportedSymbol", library.first_imported_symbol.get(BE));
+            p.field_hex("Options", library.options);
+            p.flags(library.options, 0, FLAGS_LIB);
+            p.field_hex("ReservedA", library.reserved_a);
+            p.field_hex("ReservedB", library.reserved_b.get(BE));
+        });
+    }
+    for (index, symbol) in loader.imported_symbols().iter().enumerate() {
+        p.group("ImportedSymbol", |p| {
+            p.field("Index", index);
+            let name = loader.imported_symbol_name(symbol);
+            p.field_string("Name", symbol.name_offset(), name);
+            p.field_enum("Class", symbol.symbol_class(), FLAGS_SYMBOL_CLASS);
+            p.field_hex("Flags", symbol.symbol_flags());
+            p.flags(symbol.symbol_flags(), 0, FLAGS_SYMBOL);
+        });
+    }
+}
+
+fn print_relocations(p: &mut Printer<'_>, loader: &LoaderSection<'_>) {
+    if !p.options.relocations {
+        return;
+    }
+    for header in loader.relocation_headers() {
+        p.group("RelocationHeader", |p| {
+            p.field("SectionIndex", header.section_index.get(BE));
+            p.field_hex("ReservedA", header.reserved_a.get(BE));
+            p.field("RelocCount", header.reloc_count.get(BE));
+            p.field_hex("FirstRelocOffset", header.first_reloc_offset.get(BE));
+            let Some(relocations) = loader.relocations(header).print_err(p) else {
+                return;
+            };
+            for relocation in relocations {
+                let Some(relocation) = relocation.print_err(p) else {
+                    break;
+                };
+                p.print_indent();
+                writeln!(p.w(), "{:?}", relocation).unwrap();
+            }
+        });
+    }
+}
+
+fn print_exports(p: &mut Printer<'_>, loader: &LoaderSection<'_>) {
+    if !p.options.pef_exports {
+        return;
+    }
+    for (index, slot) in loader.hash_slots().iter().enumerate() {
+        if slot.symbol_count() == 0 {
+            continue;
+        }
+        p.group("HashSlot", |p| {
+            p.field("Index", index);
+            p.field("SymbolCount", slot.symbol_count());
+            p.field("FirstSymbol", slot.first_symbol());
+        });
+    }
+    let keys = loader.export_keys();
+    for (index, symbol) in loader.exported_symbols().iter().enumerate() {
+        p.group("ExportedSymbol", |p| {
+            p.field("Index", index);
+            let name = loader.exported_symbol_name(index);
+            p.field_string("Name", symbol.name_offset(), name);
+            if let Some(key) = keys.get(index) {
+                p.field_hex("FullHashWord", key.full_hash_word.get(BE));
+            }
+            p.field_enum("Class", symbol.symbol_class(), FLAGS_SYMBOL_CLASS);
+            p.field_hex("SymbolValue", symbol.symbol_value.get(BE));
+            p.field_enum_display(
+                "SectionIndex",
+                symbol.section_index.get(BE),
+                FLAGS_EXPORTED_SECTION,
+            );
+        });
+    }
+}
+
+const MONTH_DAYS: [u32; 11] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30];
+
+const FLAGS_LIB: &[Flag<u8>] = &flags!(INIT_LIB_BEFORE, WEAK_IMPORT_LIB);
+const FLAGS_SYMBOL: &[Flag<u8>] = &flags!(WEAK_IMPORT_SYMBOL);
+const FLAGS_SYMBOL_CLASS: &[Flag<u8>] = &flags!(
+    CODE_SYMBOL,
+    DATA_SYMBOL,
+    TVECT_SYMBOL,
+    TOC_SYMBOL,
+    GLUE_SYMBOL,
+    UNDEFINED_SYMBOL,
+);
+const FLAGS_EXPORTED_SECTION: &[Flag<i16>] = &flags!(
+    EXPORTED_ABSOLUTE_SECTION,
+    EXPORTED_REEXPORTED_IMPORT_SECTION
+);
@@ -0,0 +1,352 @@
+use std::convert::TryInto;
+use std::error::Error;
+
+use object::endian::BigEndian as BE;
+use object::read::xcoff::{AuxHeader, SectionHeader, XcoffFile32};
+use object::write::{self, PefImportedLibrary, SectionId, SymbolId};
+use object::{
+    xcoff, Architecture, BinaryFormat, Endianness, ReadRef, RelocationEncoding, RelocationFlags,
+    RelocationKind, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+};
+
+/// The index of the first loader symbol in `l_symndx`.
+///
+/// Indices below this refer to the `.text`, `.data` and `.bss` sections.
+const FIRST_LOADER_SYMBOL: u32 = 3;
+
+/// An output section and the XCOFF section it was created from.
+struct OutSection {
+    id: SectionId,
+    address: u32,
+    size: u32,
+    data: Option<Vec<u8>>,
+    relocations: Vec<(u32, SymbolId)>,
+}
+
+/// Convert an XCOFF32 executable or shared object into a PEF container.
+///
+/// This performs the same job as `makePEF` in Apple's toolchain. The `.text`,
+/// `.data` and `.bss` sections are converted to code and data sections, and
+/// the imports, exports and relocations in the XCOFF loader section are
+/// converted to the equivalent PEF loader information.
+///
+/// Only `R_POS` loader relocations are supported, since PEF relocations can
+/// only add the address of a section or imported symbol to a 32-bit word.
+pub fn convert(in_data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let in_file = XcoffFile32::parse(in_data)?;
+    let in_sections = in_file.xcoff_section_table();
+
+    let mut out_object =
+        write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+
+    // Convert sections. The vector is indexed by XCOFF section number minus one.
+    let mut out_sections = Vec::new();
+    let mut implicit_sections = [None; FIRST_LOADER_SYMBOL as usize];
+    let mut loader = None;
+    for (index, in_section) in in_sections.iter().enumerate() {
+        let (kind, implicit_index) = match in_section.s_flags() as u16 {
+            xcoff::STYP_TEXT => (SectionKind::Text, 0),
+            xcoff::STYP_DATA => (SectionKind::Data, 1),
+            xcoff::STYP_BSS => (SectionKind::UninitializedData, 2),
+            xcoff::STYP_LOADER => {
+                loader = Some(in_section);
+                out_sections.push(None);
+                continue;
+            }
+            _ => {
+                out_sections.push(None);
+                continue;
+            }
+        };
+        let id = out_object.add_section(Vec::new(), in_section.name().to_vec(), kind);
+        let data = if kind == SectionKind::UninitializedData {
+            out_object
+                .section_mut(id)
+                .append_bss(in_section.s_size().into(), 16);
+            None
+        } else {
+            let data = in_section
+                .data(in_data)
+                .map_err(|()| "Invalid XCOFF section data")?;
+            Some(data.to_vec())
+        };
+        if implicit_sections[implicit_index].is_none() {
+            implicit_sections[implicit_index] = Some(index);
+        }
+        out_sections.push(Some(OutSection {
+            id,
+            address: in_section.s_vaddr(),
+            size: in_section.s_size(),
+            data,
+            relocations: Vec::new(),
+        }));
+    }
+
+    let loader = loader.ok_or("Missing XCOFF loader section")?;
+    let loader_data = loader
+        .data(in_data)
+        .map_err(|()| "Invalid XCOFF loader section data")?;
+    let loader_header = loader_data
+        .read_at::<xcoff::LoaderHeader32>(0)
+        .map_err(|()| "Invalid XCOFF loader header")?;
+    let loader_symbols = loader_data
+        .read_slice_at::<xcoff::LoaderSymbol32>(
+            core::mem::size_of::<xcoff::LoaderHeader32>() as u64,
+            loader_header.l_nsyms.get(BE) as usize,
+        )
+        .map_err(|()| "Invalid XCOFF loader symbols")?;
+    let loader_relocations = loader_data
+        .read_slice_at::<xcoff::LoaderRel32>(
+            core::mem::size_of::<xcoff::LoaderHeader32>() as u64
+                + core::mem::size_of_val(loader_symbols) as u64,
+            loader_header.l_nreloc.get(BE) as usize,
+        )
+        .map_err(|()| "Invalid XCOFF loader relocations")?;
+    let strings = loader_data
+        .read_bytes_at(
+            loader_header.l_stoff.get(BE).into(),
+            loader_header.l_stlen.get(BE).into(),
+        )
+        .map_err(|()| "Invalid XCOFF loader string table")?;
+
+    // Convert import file IDs to imported libraries. The first ID is the
+    // library search path, which PEF doesn't use.
+    let mut import_ids = loader_data
+        .read_bytes_at(
+            loader_header.l_impoff.get(BE).into(),
+            loader_header.l_istlen.get(BE).into(),
+        )
+        .map_err(|()| "Invalid XCOFF import file IDs")?
+        .split(|byte| *byte == 0);
+    let mut libraries = Vec::new();
+    for index in 0..loader_header.l_nimpid.get(BE) {
+        let (Some(_path), Some(base), Some(_member)) =
+            (import_ids.next(), import_ids.next(), import_ids.next())
+        else {
+            return Err("Invalid XCOFF import file IDs".into());
+        };
+        if index == 0 {
+            continue;
+        }
+        libraries.push(out_object.add_pef_imported_library(PefImportedLibrary {
+            name: base.to_vec(),
+            ..Default::default()
+        }));
+    }
+
+    // Convert loader symbols. Imported symbols are recorded so that
+    // relocations can refer to them.
+    let mut symbols = Vec::with_capacity(loader_symbols.len());
+    let mut main = None;
+    for loader_symbol in loader_symbols {
+        let name = loader_symbol_name(loader_symbol, strings)?;
+        let kind = match loader_symbol.l_smclas {
+            xcoff::XMC_PR | xcoff::XMC_GL | xcoff::XMC_DS => SymbolKind::Text,
+            _ => SymbolKind::Data,
+        };
+        let smtype = loader_symbol.l_smtype;
+        if smtype & xcoff::L_IMPORT != 0 {
+            let ifile = loader_symbol.l_ifile.get(BE) as usize;
+            let library = *ifile
+                .checked_sub(1)
+                .and_then(|index| libraries.get(index))
+                .ok_or("Invalid XCOFF import file ID for symbol")?;
+            let symbol = out_object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 0,
+                kind,
+                scope: SymbolScope::Dynamic,
+                weak: smtype & xcoff::L_WEAK != 0,
+                section: write::SymbolSection::Undefined,
+                flags: SymbolFlags::None,
+            });
+            out_object.set_pef_symbol_library(symbol, library);
+            symbols.push(symbol);
+            continue;
+        }
+        let section_number = loader_symbol.l_scnum.get(BE);
+        let (section, value) = if section_number == xcoff::N_ABS {
+            (
+                write::SymbolSection::Absolute,
+                loader_symbol.l_value.get(BE),
+            )
+        } else {
+            let out_section = section_number
+                .checked_sub(1)
+                .and_then(|index| out_sections.get(index as usize))
+                .and_then(Option::as_ref)
+                .ok_or("Invalid XCOFF section number for symbol")?;
+            let value = loader_symbol
+                .l_value
+                .get(BE)
+                .wrapping_sub(out_section.address);
+            (write::SymbolSection::Section(out_section.id), value)
+        };
+        let symbol = out_object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: value.into(),
+            size: 0,
+            kind,
+            scope: if smtype & xcoff::L_EXPORT != 0 {
+                SymbolScope::Dynamic
+            } else {
+                SymbolScope::Compilation
+            },
+            weak: false,
+            section,
+            flags: SymbolFlags::None,
+        });
+        if smtype & xcoff::L_ENTRY != 0 {
+            main = Some(symbol);
+        }
+        symbols.push(symbol);
+    }
+    if main.is_none() {
+        main = entry_symbol(&in_file, &mut out_object, &out_sections);
+    }
+    out_object.set_pef_main_symbol(main);
+
+    // Convert loader relocations. The relocated words contain absolute
+    // addresses, which need to be changed to section offsets.
+    for relocation in loader_relocations {
+        if relocation.l_rtype != xcoff::R_POS || relocation.l_rsize != 0x1f {
+            return Err(format!(
+                "Unsupported XCOFF loader relocation type {:#x} size {:#x}",
+                relocation.l_rtype, relocation.l_rsize
+            )
+            .into());
+        }
+        let symndx = relocation.l_symndx.get(BE);
+        let target_section = if symndx < FIRST_LOADER_SYMBOL {
+            Some(implicit_sections[symndx as usize].ok_or("Missing XCOFF relocation section")?)
+        } else {
+            let loader_symbol = loader_symbols
+                .get((symndx - FIRST_LOADER_SYMBOL) as usize)
+                .ok_or("Invalid XCOFF relocation symbol index")?;
+            if loader_symbol.l_smtype & xcoff::L_IMPORT != 0 {
+                None
+            } else {
+                let section_number = loader_symbol.l_scnum.get(BE);
+                if section_number == xcoff::N_ABS {
+                    // The value is already stored at the place.
+                    continue;
+                }
+                Some(
+                    section_number
+                        .checked_sub(1)
+                        .ok_or("Invalid XCOFF section number for symbol")?
+                        as usize,
+                )
+            }
+        };
+        let (target_address, target_symbol) = match target_section {
+            Some(index) => {
+                let out_section = out_sections
+                    .get(index)
+                    .and_then(Option::as_ref)
+                    .ok_or("Invalid XCOFF relocation section")?;
+                (
+                    out_section.address,
+                    out_object.section_symbol(out_section.id),
+                )
+            }
+            None => (0, symbols[(symndx - FIRST_LOADER_SYMBOL) as usize]),
+        };
+
+        let out_section = relocation
+            .l_rsecnm
+            .get(BE)
+            .checked_sub(1)
+            .and_then(|index| out_sections.get_mut(index as usize))
+            .and_then(Option::as_mut)
+            .ok_or("Invalid XCOFF relocated section")?;
+        let offset = relocation.l_vaddr.get(BE).wrapping_sub(out_section.address);
+        let data = out_section
+            .data
+            .as_mut()
+            .ok_or("Unsupported XCOFF relocation in .bss")?;
+        let place = data
+            .get_mut(offset as usize..)
+            .and_then(|place| place.get_mut(..4))
+            .ok_or("Invalid XCOFF relocation address")?;
+        let value = u32::from_be_bytes(place.try_into().unwrap());
+        place.copy_from_slice(&value.wrapping_sub(target_address).to_be_bytes());
+        out_section.relocations.push((offset, target_symbol));
+    }
+
+    for out_section in out_sections.into_iter().flatten() {
+        if let Some(data) = out_section.data {
+            debug_assert_eq!(data.len(), out_section.size as usize);
+            out_object.set_section_data(out_section.id, data, 16);
+        }
+        for (offset, symbol) in out_section.relocations {
+            out_object.add_relocation(
+                out_section.id,
+                write::Relocation {
+                    offset: offset.into(),
+                    symbol,
+                    addend: 0,
+                    flags: RelocationFlags::Generic {
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        size: 32,
+                    },
+                },
+            )?;
+        }
+    }
+
+    Ok(out_object.write()?)
+}
+
+/// Return the name of a loader symbol.
+fn loader_symbol_name<'data>(
+    symbol: &'data xcoff::LoaderSymbol32,
+    strings: &'data [u8],
+) -> Result<&'data [u8], Box<dyn Error>> {
+    let name = &symbol.l_name;
+    if name[..4] != [0; 4] {
+        let end = name
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(name.len());
+        return Ok(&name[..end]);
+    }
+    // The offset refers to the string, which is preceded by a 2-byte length.
+    let offset = u32::from_be_bytes(name[4..].try_into().unwrap()) as u64;
+    let length = offset
+        .checked_sub(2)
+        .and_then(|offset| strings.read_at::<object::U16<BE>>(offset).ok())
+        .ok_or("Invalid XCOFF loader symbol name offset")?;
+    let name = strings
+        .read_bytes_at(offset, length.get(BE).into())
+        .map_err(|()| "Invalid XCOFF loader symbol name")?;
+    Ok(name.strip_suffix(&[0]).unwrap_or(name))
+}
+
+/// Add a symbol for the entry point in the auxiliary header, if any.
+fn entry_symbol(
+    in_file: &XcoffFile32<'_>,
+    out_object: &mut write::Object<'_>,
+    out_sections: &[Option<OutSection>],
+) -> Option<SymbolId> {
+    let entry = in_file.xcoff_aux_header()?.o_entry();
+    if entry == 0 || entry == !0 {
+        return None;
+    }
+    let out_section = out_sections
+        .iter()
+        .flatten()
+        .find(|out_section| entry.wrapping_sub(out_section.address) < out_section.size)?;
+    Some(out_object.add_symbol(write::Symbol {
+        name: Vec::new(),
+        value: entry.wrapping_sub(out_section.address).into(),
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Section(out_section.id),
+        flags: SymbolFlags::None,
+    }))
+}
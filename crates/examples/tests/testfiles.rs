@@ -14,6 +14,22 @@ const DISABLED_TEST_DIRS: &[&str] = &[
     "xcoff",
 ];
 
+/// Directories containing test inputs that are not in the testfiles submodule.
+///
+/// These are used for any inputs that the submodule does not contain.
+const LOCAL_TEST_DIRS: &[(&str, &str)] = &[("pef", "tests/inputs/pef")];
+
+fn local_test_file(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent()?.file_name()?.to_str()?;
+    let (_, local_dir) = LOCAL_TEST_DIRS.iter().find(|(name, _)| *name == dir)?;
+    let local_path = Path::new(local_dir).join(path.file_name()?);
+    if local_path.is_file() {
+        Some(local_path)
+    } else {
+        None
+    }
+}
+
 fn test_dir_enabled(path: &Path) -> bool {
     match path.file_name().and_then(OsStr::to_str) {
         Some(dir) => !DISABLED_TEST_DIRS.contains(&dir),
@@ -46,6 +62,11 @@ fn testfiles() {
 
             let mut in_path = in_testfiles.join(&out_path);
             in_path.set_extension("");
+            if !in_path.is_file() {
+                if let Some(local_path) = local_test_file(&in_path) {
+                    in_path = local_path;
+                }
+            }
 
             if extension == "err" {
                 continue;
@@ -0,0 +1,292 @@
+#![cfg(all(feature = "read", feature = "write"))]
+
+use std::convert::TryInto;
+
+use object::endian::{BigEndian as BE, I16, U16, U32};
+use object::read::pef::{LoaderRelocation, LoaderRelocationTarget, PefFile};
+use object::read::{Object, ObjectSection};
+use object::{pef, xcoff, SectionIndex};
+use object_examples::xcofftopef;
+
+const TEXT_ADDRESS: u32 = 0x1000_0000;
+const DATA_ADDRESS: u32 = 0x2000_0000;
+const BSS_ADDRESS: u32 = 0x2000_0010;
+
+fn loader_symbol(
+    name: [u8; 8],
+    value: u32,
+    section: i16,
+    smtype: u8,
+    smclas: u8,
+    ifile: u32,
+) -> xcoff::LoaderSymbol32 {
+    xcoff::LoaderSymbol32 {
+        l_name: name,
+        l_value: U32::new(BE, value),
+        l_scnum: I16::new(BE, section),
+        l_smtype: smtype,
+        l_smclas: smclas,
+        l_ifile: U32::new(BE, ifile),
+        l_parm: U32::new(BE, 0),
+    }
+}
+
+fn loader_relocation(address: u32, symndx: u32) -> xcoff::LoaderRel32 {
+    xcoff::LoaderRel32 {
+        l_vaddr: U32::new(BE, address),
+        l_symndx: U32::new(BE, symndx),
+        l_rsize: 0x1f,
+        l_rtype: xcoff::R_POS,
+        l_rsecnm: I16::new(BE, 2),
+    }
+}
+
+/// Assemble an XCOFF32 executable with `.text`, `.data`, `.bss` and `.loader` sections.
+///
+/// The entry point in the auxiliary header is `.data+8`. If `entry_symbol` is true,
+/// then there is also an `L_ENTRY` loader symbol at `.data+0`.
+fn xcoff_executable(entry_symbol: bool) -> Vec<u8> {
+    use std::mem::size_of;
+
+    let text = [0x60, 0x00, 0x00, 0x00, 0x4e, 0x80, 0x00, 0x20];
+    let mut data = Vec::new();
+    // Relocated against the implicit `.text` section.
+    data.extend_from_slice(&(TEXT_ADDRESS + 4).to_be_bytes());
+    // Relocated against the implicit `.data` section.
+    data.extend_from_slice(&(DATA_ADDRESS + 8).to_be_bytes());
+    // Relocated against an imported symbol.
+    data.extend_from_slice(&0u32.to_be_bytes());
+    // Relocated against an exported symbol in `.text`.
+    data.extend_from_slice(&(TEXT_ADDRESS + 4).to_be_bytes());
+
+    let entry_name = b"entry_point_descriptor";
+    let mut strings = Vec::new();
+    strings.extend_from_slice(&(entry_name.len() as u16 + 1).to_be_bytes());
+    strings.extend_from_slice(entry_name);
+    strings.push(0);
+    let mut entry_name_offset = [0; 8];
+    entry_name_offset[4..].copy_from_slice(&2u32.to_be_bytes());
+
+    let entry_flags = if entry_symbol { xcoff::L_ENTRY } else { 0 };
+    let symbols = [
+        loader_symbol(*b"SysBeep\0", 0, 0, xcoff::L_IMPORT, xcoff::XMC_DS, 1),
+        loader_symbol(*b"sqrt\0\0\0\0", 0, 0, xcoff::L_IMPORT, xcoff::XMC_DS, 2),
+        loader_symbol(
+            *b"func\0\0\0\0",
+            TEXT_ADDRESS + 4,
+            1,
+            xcoff::L_EXPORT,
+            xcoff::XMC_PR,
+            0,
+        ),
+        loader_symbol(
+            entry_name_offset,
+            DATA_ADDRESS,
+            2,
+            xcoff::L_EXPORT | entry_flags,
+            xcoff::XMC_DS,
+            0,
+        ),
+        loader_symbol(*b"local\0\0\0", DATA_ADDRESS + 4, 2, 0, xcoff::XMC_RW, 0),
+    ];
+    let relocations = [
+        loader_relocation(DATA_ADDRESS, 0),
+        loader_relocation(DATA_ADDRESS + 4, 1),
+        loader_relocation(DATA_ADDRESS + 8, 3),
+        loader_relocation(DATA_ADDRESS + 12, 3 + 2),
+    ];
+    let import_ids = b"/usr/lib\0\0\0\0InterfaceLib\0\0\0MathLib\0\0";
+
+    let import_offset = size_of::<xcoff::LoaderHeader32>()
+        + size_of::<xcoff::LoaderSymbol32>() * symbols.len()
+        + size_of::<xcoff::LoaderRel32>() * relocations.len();
+    let strings_offset = import_offset + import_ids.len();
+    let mut loader = Vec::new();
+    loader.extend_from_slice(object::bytes_of(&xcoff::LoaderHeader32 {
+        l_version: U32::new(BE, 1),
+        l_nsyms: U32::new(BE, symbols.len() as u32),
+        l_nreloc: U32::new(BE, relocations.len() as u32),
+        l_istlen: U32::new(BE, import_ids.len() as u32),
+        l_nimpid: U32::new(BE, 3),
+        l_impoff: U32::new(BE, import_offset as u32),
+        l_stlen: U32::new(BE, strings.len() as u32),
+        l_stoff: U32::new(BE, strings_offset as u32),
+    }));
+    loader.extend_from_slice(object::bytes_of_slice(&symbols));
+    loader.extend_from_slice(object::bytes_of_slice(&relocations));
+    loader.extend_from_slice(import_ids);
+    loader.extend_from_slice(&strings);
+
+    let text_offset = size_of::<xcoff::FileHeader32>()
+        + size_of::<xcoff::AuxHeader32>()
+        + 4 * size_of::<xcoff::SectionHeader32>();
+    let data_offset = text_offset + text.len();
+    let loader_offset = data_offset + data.len();
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(object::bytes_of(&xcoff::FileHeader32 {
+        f_magic: U16::new(BE, xcoff::MAGIC_32),
+        f_nscns: U16::new(BE, 4),
+        f_timdat: U32::new(BE, 0),
+        f_symptr: U32::new(BE, 0),
+        f_nsyms: U32::new(BE, 0),
+        f_opthdr: U16::new(BE, size_of::<xcoff::AuxHeader32>() as u16),
+        f_flags: U16::new(BE, xcoff::F_EXEC),
+    }));
+    let mut aux_header = [0; size_of::<xcoff::AuxHeader32>()];
+    // o_entry
+    aux_header[16..20].copy_from_slice(&(DATA_ADDRESS + 8).to_be_bytes());
+    bytes.extend_from_slice(&aux_header);
+    for (name, address, size, offset, flags) in [
+        (
+            *b".text\0\0\0",
+            TEXT_ADDRESS,
+            text.len(),
+            text_offset,
+            xcoff::STYP_TEXT,
+        ),
+        (
+            *b".data\0\0\0",
+            DATA_ADDRESS,
+            data.len(),
+            data_offset,
+            xcoff::STYP_DATA,
+        ),
+        (*b".bss\0\0\0\0", BSS_ADDRESS, 0x20, 0, xcoff::STYP_BSS),
+        (
+            *b".loader\0",
+            0,
+            loader.len(),
+            loader_offset,
+            xcoff::STYP_LOADER,
+        ),
+    ] {
+        bytes.extend_from_slice(object::bytes_of(&xcoff::SectionHeader32 {
+            s_name: name,
+            s_paddr: U32::new(BE, address),
+            s_vaddr: U32::new(BE, address),
+            s_size: U32::new(BE, size as u32),
+            s_scnptr: U32::new(BE, offset as u32),
+            s_relptr: U32::new(BE, 0),
+            s_lnnoptr: U32::new(BE, 0),
+            s_nreloc: U16::new(BE, 0),
+            s_nlnno: U16::new(BE, 0),
+            s_flags: U32::new(BE, flags.into()),
+        }));
+    }
+    bytes.extend_from_slice(&text);
+    bytes.extend_from_slice(&data);
+    bytes.extend_from_slice(&loader);
+    bytes
+}
+
+fn section_index(file: &PefFile<'_>, name: &[u8]) -> SectionIndex {
+    file.pef_sections().section_by_name(name).unwrap().0
+}
+
+fn main_entry(file: &PefFile<'_>) -> (i32, u32) {
+    let header = file.pef_loader().unwrap().header();
+    (header.main_section.get(BE), header.main_offset.get(BE))
+}
+
+#[test]
+fn convert() {
+    let xcoff = xcoff_executable(true);
+    let pef = xcofftopef::convert(&xcoff).unwrap();
+    let file = PefFile::<&[u8]>::parse(&*pef).unwrap();
+    assert_eq!(
+        file.pef_header().architecture.get(BE),
+        pef::ARCHITECTURE_PPC
+    );
+
+    let text = section_index(&file, b".text");
+    let data = section_index(&file, b".data");
+    let bss = section_index(&file, b".bss");
+    let bss_header = file.pef_sections().section(bss).unwrap();
+    assert_eq!(bss_header.total_size.get(BE), 0x20);
+    assert_eq!(bss_header.unpacked_size.get(BE), 0);
+
+    let imports = file
+        .imports()
+        .unwrap()
+        .iter()
+        .map(|import| (import.library(), import.name()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (&b"InterfaceLib"[..], &b"SysBeep"[..]),
+            (b"MathLib", b"sqrt"),
+        ]
+    );
+
+    let data_address = file
+        .pef_sections()
+        .section(data)
+        .unwrap()
+        .default_address
+        .get(BE);
+    let mut exports = file
+        .exports()
+        .unwrap()
+        .iter()
+        .map(|export| (export.name(), export.address()))
+        .collect::<Vec<_>>();
+    exports.sort();
+    assert_eq!(
+        exports,
+        [
+            (&b"entry_point_descriptor"[..], u64::from(data_address)),
+            (b"func", 4),
+        ]
+    );
+
+    // The entry point comes from the `L_ENTRY` symbol.
+    assert_eq!(main_entry(&file), (data.0 as i32, 0));
+
+    // The relocated words are changed from addresses to section offsets.
+    let contents = file
+        .section_by_index(SectionIndex(data.0 + 1))
+        .unwrap()
+        .data()
+        .unwrap();
+    let words = contents
+        .chunks(4)
+        .map(|word| u32::from_be_bytes(word.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(words, [4, 8, 0, 4]);
+    let relocation = |offset, target| LoaderRelocation { offset, target };
+    assert_eq!(
+        file.loader_relocations(data).unwrap(),
+        [
+            relocation(0, LoaderRelocationTarget::Section(text)),
+            relocation(4, LoaderRelocationTarget::Section(data)),
+            relocation(8, LoaderRelocationTarget::Import(0)),
+            relocation(12, LoaderRelocationTarget::Section(text)),
+        ]
+    );
+}
+
+#[test]
+fn convert_aux_header_entry() {
+    let xcoff = xcoff_executable(false);
+    let pef = xcofftopef::convert(&xcoff).unwrap();
+    let file = PefFile::<&[u8]>::parse(&*pef).unwrap();
+    // The entry point comes from the auxiliary header.
+    let data = section_index(&file, b".data");
+    assert_eq!(main_entry(&file), (data.0 as i32, 8));
+}
+
+#[test]
+fn convert_unsupported_relocation() {
+    let mut xcoff = xcoff_executable(true);
+    // Change the type of the last loader relocation.
+    let relocations_end = xcoff.len()
+        - b"/usr/lib\0\0\0\0InterfaceLib\0\0\0MathLib\0\0".len()
+        - (b"entry_point_descriptor".len() + 3);
+    xcoff[relocations_end - 3] = xcoff::R_TOC;
+    let err = xcofftopef::convert(&xcoff).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Unsupported XCOFF loader relocation type"));
+}
@@ -4,7 +4,7 @@ use std::collections::{HashMap, HashSet};
 use log::info;
 use object::{build, elf};
 
-use super::{Error, Result, Rewriter};
+use super::{Builder, Error, Result, Rewriter};
 
 /// Options for modifying an ELF file.
 ///
@@ -61,10 +61,30 @@ pub struct ElfOptions {
     pub set_interpreter: Option<Vec<u8>>,
 }
 
+impl<'data> Builder<'data> {
+    fn elf(&self) -> Option<&build::elf::Builder<'data>> {
+        match self {
+            Builder::Elf(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    fn elf_mut(&mut self) -> Option<&mut build::elf::Builder<'data>> {
+        match self {
+            Builder::Elf(builder) => Some(builder),
+            _ => None,
+        }
+    }
+}
+
 impl Rewriter<'_> {
     /// Delete symbols from the symbol table.
-    pub fn elf_delete_symbols(&mut self, names: &HashSet<Vec<u8>>) {
-        for symbol in &mut self.builder.dynamic_symbols {
+    pub fn elf_delete_symbols(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't delete symbols"))?;
+        for symbol in &mut builder.dynamic_symbols {
             if names.contains(&*symbol.name) {
                 #[cfg(feature = "logging")]
                 info!("Deleting symbol {}", symbol.name);
@@ -72,11 +92,16 @@ impl Rewriter<'_> {
                 self.modified = true;
             }
         }
+        Ok(())
     }
 
     /// Delete symbols from the dynamic symbol table.
-    pub fn elf_delete_dynamic_symbols(&mut self, names: &HashSet<Vec<u8>>) {
-        for symbol in &mut self.builder.symbols {
+    pub fn elf_delete_dynamic_symbols(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't delete dynamic symbols"))?;
+        for symbol in &mut builder.symbols {
             if names.contains(&*symbol.name) {
                 #[cfg(feature = "logging")]
                 info!("Deleting dynamic symbol {}", symbol.name);
@@ -84,13 +109,18 @@ impl Rewriter<'_> {
                 self.modified = true;
             }
         }
+        Ok(())
     }
 
     /// Rename symbols in the symbol table.
     ///
     /// The `names` map is from old names to new names.
-    pub fn elf_rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        for symbol in &mut self.builder.dynamic_symbols {
+    pub fn elf_rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't rename symbols"))?;
+        for symbol in &mut builder.dynamic_symbols {
             if let Some(name) = names.get(&*symbol.name) {
                 let name = name.clone().into();
                 #[cfg(feature = "logging")]
@@ -99,13 +129,18 @@ impl Rewriter<'_> {
                 self.modified = true;
             }
         }
+        Ok(())
     }
 
     /// Rename symbols in the dynamic symbol table.
     ///
     /// The `names` map is from old names to new names.
-    pub fn elf_rename_dynamic_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        for symbol in &mut self.builder.dynamic_symbols {
+    pub fn elf_rename_dynamic_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't rename dynamic symbols"))?;
+        for symbol in &mut builder.dynamic_symbols {
             if let Some(name) = names.get(&*symbol.name) {
                 let name = name.clone().into();
                 #[cfg(feature = "logging")]
@@ -114,10 +149,15 @@ impl Rewriter<'_> {
                 self.modified = true;
             }
         }
+        Ok(())
     }
 
-    pub(crate) fn elf_delete_sections(&mut self, names: &HashSet<Vec<u8>>) {
-        for section in &mut self.builder.sections {
+    pub(crate) fn elf_delete_sections(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't delete sections"))?;
+        for section in &mut builder.sections {
             if names.contains(&*section.name) {
                 #[cfg(feature = "logging")]
                 info!("Deleting section {}", section.name);
@@ -126,10 +166,15 @@ impl Rewriter<'_> {
                 self.modified = true;
             }
         }
+        Ok(())
     }
 
-    pub(crate) fn elf_rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        for section in &mut self.builder.sections {
+    pub(crate) fn elf_rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't rename sections"))?;
+        for section in &mut builder.sections {
             if let Some(name) = names.get(&*section.name) {
                 let name = name.clone().into();
                 #[cfg(feature = "logging")]
@@ -138,6 +183,7 @@ impl Rewriter<'_> {
                 self.modified = true;
             }
         }
+        Ok(())
     }
 
     pub(crate) fn elf_modify(&mut self, options: ElfOptions) -> Result<()> {
@@ -181,6 +227,8 @@ impl Rewriter<'_> {
     pub fn elf_add_dynamic_debug(&mut self) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't add debug entry"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't add debug entry"))?;
         if dynamic.iter().any(|entry| entry.tag() == elf::DT_DEBUG) {
@@ -199,7 +247,7 @@ impl Rewriter<'_> {
 
     /// Find the first `DT_RUNPATH` or `DT_RPATH` entry in the dynamic section.
     pub fn elf_runpath(&self) -> Option<&[u8]> {
-        let dynamic = self.builder.dynamic_data()?;
+        let dynamic = self.builder.elf()?.dynamic_data()?;
         for entry in dynamic.iter() {
             let build::elf::Dynamic::String { tag, val } = entry else {
                 continue;
@@ -216,6 +264,8 @@ impl Rewriter<'_> {
     pub fn elf_delete_runpath(&mut self) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't delete runpath"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't delete runpath"))?;
         let mut modified = false;
@@ -247,6 +297,8 @@ impl Rewriter<'_> {
     pub fn elf_set_runpath(&mut self, runpath: Vec<u8>) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't set runpath"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't set runpath"))?;
         let mut found = false;
@@ -288,6 +340,8 @@ impl Rewriter<'_> {
     pub fn elf_add_runpath(&mut self, runpaths: &[Vec<u8>]) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't add runpath"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't add runpath"))?;
         let mut found = false;
@@ -334,6 +388,8 @@ impl Rewriter<'_> {
     pub fn elf_use_runpath(&mut self) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't change runpath"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't change runpath"))?;
         for entry in dynamic.iter_mut() {
@@ -356,6 +412,8 @@ impl Rewriter<'_> {
     pub fn elf_use_rpath(&mut self) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't change rpath"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't change rpath"))?;
         for entry in dynamic.iter_mut() {
@@ -376,7 +434,11 @@ impl Rewriter<'_> {
 
     /// Find the `DT_NEEDED` entries in the dynamic section.
     pub fn elf_needed(&self) -> impl Iterator<Item = &[u8]> {
-        let dynamic = self.builder.dynamic_data().unwrap_or(&[]);
+        let dynamic = self
+            .builder
+            .elf()
+            .and_then(|builder| builder.dynamic_data())
+            .unwrap_or(&[]);
         dynamic.iter().filter_map(|entry| {
             if let build::elf::Dynamic::String { tag, val } = entry {
                 if *tag == elf::DT_NEEDED {
@@ -389,9 +451,14 @@ impl Rewriter<'_> {
 
     /// Delete `DT_NEEDED` entries from the dynamic section.
     pub fn elf_delete_needed(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
-        let dynamic = self.builder.dynamic_data_mut().ok_or_else(|| {
-            Error::modify("No dynamic section found; can't delete needed library")
-        })?;
+        let dynamic = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't delete needed library"))?
+            .dynamic_data_mut()
+            .ok_or_else(|| {
+                Error::modify("No dynamic section found; can't delete needed library")
+            })?;
         let mut modified = false;
         dynamic.retain(|entry| {
             let build::elf::Dynamic::String { tag, val } = entry else {
@@ -414,9 +481,14 @@ impl Rewriter<'_> {
 
     /// Replace `DT_NEEDED` entries in the dynamic section.
     pub fn elf_replace_needed(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
-        let dynamic = self.builder.dynamic_data_mut().ok_or_else(|| {
-            Error::modify("No dynamic section found; can't replace needed library")
-        })?;
+        let dynamic = self
+            .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't replace needed library"))?
+            .dynamic_data_mut()
+            .ok_or_else(|| {
+                Error::modify("No dynamic section found; can't replace needed library")
+            })?;
         for entry in dynamic.iter_mut() {
             let build::elf::Dynamic::String { tag, val } = entry else {
                 continue;
@@ -443,6 +515,8 @@ impl Rewriter<'_> {
     pub fn elf_add_needed(&mut self, names: &[Vec<u8>]) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't add needed library"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't add needed library"))?;
         let mut found = HashSet::new();
@@ -477,8 +551,9 @@ impl Rewriter<'_> {
 
     /// Find the `DT_SONAME` entry in the dynamic section.
     pub fn elf_soname(&self) -> Option<&[u8]> {
-        let id = self.builder.dynamic_section()?;
-        let section = self.builder.sections.get(id);
+        let builder = self.builder.elf()?;
+        let id = builder.dynamic_section()?;
+        let section = builder.sections.get(id);
         let build::elf::SectionData::Dynamic(dynamic) = &section.data else {
             return None;
         };
@@ -499,6 +574,8 @@ impl Rewriter<'_> {
     pub fn elf_set_soname(&mut self, soname: Vec<u8>) -> Result<()> {
         let dynamic = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't set soname"))?
             .dynamic_data_mut()
             .ok_or_else(|| Error::modify("No dynamic section found; can't set soname"))?;
         let mut found = false;
@@ -530,7 +607,7 @@ impl Rewriter<'_> {
 
     /// Find the interpreter path in the `PT_INTERP` segment.
     pub fn elf_interpreter(&self) -> Option<&[u8]> {
-        self.builder.interp_data()
+        self.builder.elf()?.interp_data()
     }
 
     /// Set the interpreter path in the `PT_INTERP` segment.
@@ -539,6 +616,8 @@ impl Rewriter<'_> {
     pub fn elf_set_interpreter(&mut self, mut interpreter: Vec<u8>) -> Result<()> {
         let data = self
             .builder
+            .elf_mut()
+            .ok_or_else(|| Error::modify("Not an ELF file; can't set interpreter"))?
             .interp_data_mut()
             .ok_or_else(|| Error::modify("No interp section found; can't set interpreter"))?;
         #[cfg(feature = "logging")]
@@ -555,8 +634,10 @@ impl Rewriter<'_> {
     }
 
     pub(crate) fn elf_finalize(&mut self) -> Result<()> {
-        if self.modified {
-            move_sections(&mut self.builder)?;
+        if let Builder::Elf(builder) = &mut self.builder {
+            if self.modified {
+                move_sections(builder)?;
+            }
         }
         Ok(())
     }
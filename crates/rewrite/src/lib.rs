@@ -4,8 +4,8 @@
 //! Modifications can be performed using methods on the [`Rewriter`] struct, or
 //! by passing an [`Options`] struct to the [`Rewriter::modify`] method.
 //!
-//! Currently, only ELF files are supported, and not many modifications are
-//! possible yet.
+//! Currently, only ELF and PEF files are supported, and not many modifications
+//! are possible yet.
 //!
 //! # Example
 //! ```no_run
@@ -31,7 +31,11 @@ mod error;
 pub use error::{Error, ErrorKind, Result};
 
 mod rewriter;
+use rewriter::Builder;
 pub use rewriter::{Options, Rewriter};
 
 mod elf;
 pub use elf::ElfOptions;
+
+mod pef;
+pub use pef::PefOptions;
@@ -124,6 +124,59 @@ fn main() -> Result<()> {
                 .value_name("path")
                 .value_parser(clap::value_parser!(String))
                 .help("Set the interpreter path in the PT_INTERP segment"),
+            Arg::new("pef-print-libraries")
+                .long("pef-print-libraries")
+                .action(ArgAction::SetTrue)
+                .help("Print the names of the imported libraries"),
+            Arg::new("pef-rename-library")
+                .long("pef-rename-library")
+                .value_name("old=new")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Change the name of an imported library from <old> to <new>"),
+            Arg::new("pef-weak-library")
+                .long("pef-weak-library")
+                .value_name("name")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Set the weak import flag for the named library"),
+            Arg::new("pef-strong-library")
+                .long("pef-strong-library")
+                .value_name("name")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Clear the weak import flag for the named library"),
+            Arg::new("pef-weak-import")
+                .long("pef-weak-import")
+                .value_name("symbol")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Set the weak import flag for the named imported symbol"),
+            Arg::new("pef-strong-import")
+                .long("pef-strong-import")
+                .value_name("symbol")
+                .value_parser(clap::value_parser!(String))
+                .action(ArgAction::Append)
+                .help("Clear the weak import flag for the named imported symbol"),
+            Arg::new("pef-set-current-version")
+                .long("pef-set-current-version")
+                .value_name("version")
+                .value_parser(parse_u32)
+                .help("Set the current version in the container header"),
+            Arg::new("pef-set-old-def-version")
+                .long("pef-set-old-def-version")
+                .value_name("version")
+                .value_parser(parse_u32)
+                .help("Set the old definition version in the container header"),
+            Arg::new("pef-set-old-imp-version")
+                .long("pef-set-old-imp-version")
+                .value_name("version")
+                .value_parser(parse_u32)
+                .help("Set the old implementation version in the container header"),
+            Arg::new("pef-strip-sections")
+                .long("pef-strip-sections")
+                .action(ArgAction::SetTrue)
+                .help("Delete all non-instantiated sections, other than the loader section"),
             Arg::new("ignore-unknown-format")
                 .long("ignore-unknown-format")
                 .action(ArgAction::SetTrue)
@@ -153,6 +206,15 @@ fn main() -> Result<()> {
                     "elf-add-needed",
                     "elf-set-soname",
                     "elf-set-interpreter",
+                    "pef-rename-library",
+                    "pef-weak-library",
+                    "pef-strong-library",
+                    "pef-weak-import",
+                    "pef-strong-import",
+                    "pef-set-current-version",
+                    "pef-set-old-def-version",
+                    "pef-set-old-imp-version",
+                    "pef-strip-sections",
                 ])
                 .multiple(true)
                 .required(false)
@@ -179,7 +241,9 @@ fn main() -> Result<()> {
 
     if matches.get_flag("ignore-unknown-format") {
         match object::FileKind::parse(in_data) {
-            Ok(object::FileKind::Elf32) | Ok(object::FileKind::Elf64) => {}
+            Ok(object::FileKind::Elf32)
+            | Ok(object::FileKind::Elf64)
+            | Ok(object::FileKind::Pef) => {}
             _ => {
                 info!(
                     "Ignoring input file '{}' with unknown format",
@@ -213,6 +277,12 @@ fn main() -> Result<()> {
         }
     }
 
+    if matches.get_flag("pef-print-libraries") {
+        for library in rewriter.pef_libraries() {
+            println!("{}", String::from_utf8_lossy(library));
+        }
+    }
+
     // TODO: allow replacing input file
     let Some(out_path) = matches.get_one::<PathBuf>("output") else {
         return Ok(());
@@ -333,6 +403,43 @@ fn main() -> Result<()> {
         .get_one::<String>("elf-set-interpreter")
         .map(|arg| arg.clone().into_bytes());
 
+    for arg in matches
+        .get_many::<String>("pef-rename-library")
+        .unwrap_or_default()
+    {
+        let names: Vec<&[u8]> = arg.as_bytes().splitn(2, |byte| *byte == b'=').collect();
+        if names.len() != 2 {
+            return Err(
+                anyhow!(
+                "Invalid rename library: `{}`. --pef-rename-library expects argument of the form: <old>=<new>", arg)
+            );
+        }
+        options
+            .pef
+            .rename_libraries
+            .insert(names[0].to_vec(), names[1].to_vec());
+    }
+    for (arg, weak) in [("pef-weak-library", true), ("pef-strong-library", false)] {
+        for name in matches.get_many::<String>(arg).unwrap_or_default() {
+            options
+                .pef
+                .set_weak_libraries
+                .insert(name.clone().into_bytes(), weak);
+        }
+    }
+    for (arg, weak) in [("pef-weak-import", true), ("pef-strong-import", false)] {
+        for name in matches.get_many::<String>(arg).unwrap_or_default() {
+            options
+                .pef
+                .set_weak_imports
+                .insert(name.clone().into_bytes(), weak);
+        }
+    }
+    options.pef.set_current_version = matches.get_one::<u32>("pef-set-current-version").copied();
+    options.pef.set_old_def_version = matches.get_one::<u32>("pef-set-old-def-version").copied();
+    options.pef.set_old_imp_version = matches.get_one::<u32>("pef-set-old-imp-version").copied();
+    options.pef.strip_sections = matches.get_flag("pef-strip-sections");
+
     rewriter.modify(options)?;
 
     if out_path == Path::new("-") {
@@ -376,3 +483,12 @@ fn main() -> Result<()> {
     })?;
     Ok(())
 }
+
+/// Parse a decimal or `0x`-prefixed hexadecimal number.
+fn parse_u32(arg: &str) -> Result<u32> {
+    let result = match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => arg.parse(),
+    };
+    result.map_err(|_| anyhow!("Invalid number: `{}`", arg))
+}
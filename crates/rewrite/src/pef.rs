@@ -0,0 +1,335 @@
+use std::collections::{HashMap, HashSet};
+
+#[cfg(feature = "logging")]
+use log::info;
+use object::build;
+
+use super::{Builder, Error, Result, Rewriter};
+
+/// Options for modifying a PEF file.
+///
+/// This struct contains options for modifying a PEF file. It is
+/// contained in the [`Options`](super::Options) struct.
+///
+/// Options are listed in the order they are processed.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct PefOptions {
+    /// Rename imported libraries.
+    ///
+    /// See [`Rewriter::pef_rename_libraries`].
+    pub rename_libraries: HashMap<Vec<u8>, Vec<u8>>,
+    /// Set or clear the weak import flag for imported libraries.
+    ///
+    /// See [`Rewriter::pef_set_weak_libraries`].
+    pub set_weak_libraries: HashMap<Vec<u8>, bool>,
+    /// Set or clear the weak import flag for imported symbols.
+    ///
+    /// See [`Rewriter::pef_set_weak_imports`].
+    pub set_weak_imports: HashMap<Vec<u8>, bool>,
+    /// Set the `current_version` field in the container header.
+    ///
+    /// See [`Rewriter::pef_set_versions`].
+    pub set_current_version: Option<u32>,
+    /// Set the `old_def_version` field in the container header.
+    ///
+    /// See [`Rewriter::pef_set_versions`].
+    pub set_old_def_version: Option<u32>,
+    /// Set the `old_imp_version` field in the container header.
+    ///
+    /// See [`Rewriter::pef_set_versions`].
+    pub set_old_imp_version: Option<u32>,
+    /// Delete all sections that are not instantiated in memory, other than
+    /// the loader section.
+    ///
+    /// See [`Rewriter::pef_strip_sections`].
+    pub strip_sections: bool,
+}
+
+impl<'data> Builder<'data> {
+    fn pef(&self) -> Option<&build::pef::Builder<'data>> {
+        match self {
+            Builder::Pef(builder) => Some(builder),
+            _ => None,
+        }
+    }
+
+    fn pef_mut(&mut self) -> Option<&mut build::pef::Builder<'data>> {
+        match self {
+            Builder::Pef(builder) => Some(builder),
+            _ => None,
+        }
+    }
+}
+
+impl Rewriter<'_> {
+    /// Delete exported symbols.
+    pub fn pef_delete_exports(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't delete exports"))?;
+        for export in &mut builder.exports {
+            if names.contains(&*export.name) {
+                #[cfg(feature = "logging")]
+                info!("Deleting export {}", export.name);
+                export.delete = true;
+                self.modified = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename exported symbols.
+    ///
+    /// The `names` map is from old names to new names.
+    pub fn pef_rename_exports(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't rename exports"))?;
+        for export in &mut builder.exports {
+            if let Some(name) = names.get(&*export.name) {
+                let name = name.clone().into();
+                #[cfg(feature = "logging")]
+                info!("Renaming export {} to {}", export.name, name);
+                export.name = name;
+                self.modified = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rename imported symbols.
+    ///
+    /// The `names` map is from old names to new names.
+    pub fn pef_rename_imports(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't rename imports"))?;
+        for import in &mut builder.imports {
+            if let Some(name) = names.get(&*import.name) {
+                let name = name.clone().into();
+                #[cfg(feature = "logging")]
+                info!("Renaming import {} to {}", import.name, name);
+                import.name = name;
+                self.modified = true;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn pef_delete_sections(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't delete sections"))?;
+        for section in &mut builder.sections {
+            let Some(name) = &section.name else {
+                continue;
+            };
+            if !names.contains(&**name) {
+                continue;
+            }
+            if section.is_instantiated() {
+                #[cfg(feature = "logging")]
+                info!("Not deleting instantiated section {}", name);
+                continue;
+            }
+            #[cfg(feature = "logging")]
+            info!("Deleting section {}", name);
+            section.delete = true;
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn pef_rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't rename sections"))?;
+        for section in &mut builder.sections {
+            let Some(name) = section.name.as_ref().and_then(|name| names.get(&**name)) else {
+                continue;
+            };
+            let name = name.clone().into();
+            #[cfg(feature = "logging")]
+            info!(
+                "Renaming section {} to {}",
+                section.name.as_ref().unwrap(),
+                name
+            );
+            section.name = Some(name);
+            self.modified = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn pef_modify(&mut self, options: PefOptions) -> Result<()> {
+        if !options.rename_libraries.is_empty() {
+            self.pef_rename_libraries(&options.rename_libraries)?;
+        }
+        if !options.set_weak_libraries.is_empty() {
+            self.pef_set_weak_libraries(&options.set_weak_libraries)?;
+        }
+        if !options.set_weak_imports.is_empty() {
+            self.pef_set_weak_imports(&options.set_weak_imports)?;
+        }
+        if options.set_current_version.is_some()
+            || options.set_old_def_version.is_some()
+            || options.set_old_imp_version.is_some()
+        {
+            self.pef_set_versions(
+                options.set_current_version,
+                options.set_old_def_version,
+                options.set_old_imp_version,
+            )?;
+        }
+        if options.strip_sections {
+            self.pef_strip_sections()?;
+        }
+        Ok(())
+    }
+
+    /// Find the names of the imported libraries.
+    pub fn pef_libraries(&self) -> impl Iterator<Item = &[u8]> {
+        self.builder
+            .pef()
+            .into_iter()
+            .flat_map(|builder| builder.libraries.iter())
+            .map(|library| &*library.name)
+    }
+
+    /// Rename imported libraries.
+    ///
+    /// The `names` map is from old names to new names.
+    pub fn pef_rename_libraries(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't rename library"))?;
+        for library in &mut builder.libraries {
+            if let Some(name) = names.get(&*library.name) {
+                let name = name.clone().into();
+                #[cfg(feature = "logging")]
+                info!("Renaming library {} to {}", library.name, name);
+                library.name = name;
+                self.modified = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set or clear the weak import flag for imported libraries.
+    ///
+    /// The `names` map is from library names to the new value of the flag.
+    pub fn pef_set_weak_libraries(&mut self, names: &HashMap<Vec<u8>, bool>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't set weak library"))?;
+        for library in &mut builder.libraries {
+            if let Some(&weak) = names.get(&*library.name) {
+                if library.is_weak() == weak {
+                    continue;
+                }
+                #[cfg(feature = "logging")]
+                info!(
+                    "Setting library {} to {}",
+                    library.name,
+                    if weak { "weak" } else { "strong" }
+                );
+                library.set_weak(weak);
+                self.modified = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set or clear the weak import flag for imported symbols.
+    ///
+    /// The `names` map is from symbol names to the new value of the flag.
+    pub fn pef_set_weak_imports(&mut self, names: &HashMap<Vec<u8>, bool>) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't set weak import"))?;
+        for import in &mut builder.imports {
+            if let Some(&weak) = names.get(&*import.name) {
+                if import.is_weak() == weak {
+                    continue;
+                }
+                #[cfg(feature = "logging")]
+                info!(
+                    "Setting import {} to {}",
+                    import.name,
+                    if weak { "weak" } else { "strong" }
+                );
+                import.set_weak(weak);
+                self.modified = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the version fields in the container header.
+    ///
+    /// Fields that are `None` are not changed.
+    pub fn pef_set_versions(
+        &mut self,
+        current_version: Option<u32>,
+        old_def_version: Option<u32>,
+        old_imp_version: Option<u32>,
+    ) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't set versions"))?;
+        let header = &mut builder.header;
+        for (field, value, _name) in [
+            (&mut header.current_version, current_version, "current"),
+            (
+                &mut header.old_def_version,
+                old_def_version,
+                "old definition",
+            ),
+            (
+                &mut header.old_imp_version,
+                old_imp_version,
+                "old implementation",
+            ),
+        ] {
+            if let Some(value) = value {
+                #[cfg(feature = "logging")]
+                info!("Setting {} version to {:#x}", _name, value);
+                *field = value;
+                self.modified = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete all sections that are not instantiated in memory, other than
+    /// the loader section.
+    ///
+    /// This removes debug, traceback and exception sections.
+    pub fn pef_strip_sections(&mut self) -> Result<()> {
+        let builder = self
+            .builder
+            .pef_mut()
+            .ok_or_else(|| Error::modify("Not a PEF file; can't strip sections"))?;
+        for section in &mut builder.sections {
+            if section.is_instantiated() || matches!(section.data, build::pef::SectionData::Loader)
+            {
+                continue;
+            }
+            #[cfg(feature = "logging")]
+            info!("Deleting section of kind {}", section.section_kind);
+            section.delete = true;
+            self.modified = true;
+        }
+        Ok(())
+    }
+}
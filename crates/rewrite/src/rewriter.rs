@@ -30,6 +30,15 @@ pub struct Options {
     pub rename_sections: HashMap<Vec<u8>, Vec<u8>>,
     /// Options that are specific to ELF files.
     pub elf: super::ElfOptions,
+    /// Options that are specific to PEF files.
+    pub pef: super::PefOptions,
+}
+
+/// The builder for the format of the file being rewritten.
+#[derive(Debug)]
+pub(crate) enum Builder<'data> {
+    Elf(build::elf::Builder<'data>),
+    Pef(build::pef::Builder<'data>),
 }
 
 /// A rewriter for object and executable files.
@@ -37,14 +46,19 @@ pub struct Options {
 /// This struct provides a way to read a file, modify it, and write it back.
 #[derive(Debug)]
 pub struct Rewriter<'data> {
-    pub(crate) builder: build::elf::Builder<'data>,
+    pub(crate) builder: Builder<'data>,
     pub(crate) modified: bool,
 }
 
 impl<'data> Rewriter<'data> {
     /// Read a file and create a new rewriter.
     pub fn read(data: &'data [u8]) -> Result<Self> {
-        let builder = build::elf::Builder::read(data).map_err(Error::parse)?;
+        let builder = match object::FileKind::parse(data) {
+            Ok(object::FileKind::Pef) => {
+                Builder::Pef(build::pef::Builder::read(data).map_err(Error::parse)?)
+            }
+            _ => Builder::Elf(build::elf::Builder::read(data).map_err(Error::parse)?),
+        };
         Ok(Self {
             builder,
             modified: false,
@@ -55,25 +69,30 @@ impl<'data> Rewriter<'data> {
     pub fn write<W: std::io::Write>(mut self, w: W) -> Result<()> {
         self.elf_finalize()?;
         let mut buffer = object::write::StreamingBuffer::new(w);
-        self.builder.write(&mut buffer).map_err(Error::write)?;
+        match self.builder {
+            Builder::Elf(builder) => builder.write(&mut buffer),
+            Builder::Pef(builder) => builder.write(&mut buffer),
+        }
+        .map_err(Error::write)?;
         buffer.result().map_err(Error::io)
     }
 
     /// Modify the file according to the given options.
     pub fn modify(&mut self, options: Options) -> Result<()> {
         if !options.delete_symbols.is_empty() {
-            self.delete_symbols(&options.delete_symbols);
+            self.delete_symbols(&options.delete_symbols)?;
         }
         if !options.rename_symbols.is_empty() {
-            self.rename_symbols(&options.rename_symbols);
+            self.rename_symbols(&options.rename_symbols)?;
         }
         if !options.delete_sections.is_empty() {
-            self.delete_sections(&options.delete_sections);
+            self.delete_sections(&options.delete_sections)?;
         }
         if !options.rename_sections.is_empty() {
-            self.rename_sections(&options.rename_sections);
+            self.rename_sections(&options.rename_sections)?;
         }
         self.elf_modify(options.elf)?;
+        self.pef_modify(options.pef)?;
         Ok(())
     }
 
@@ -81,9 +100,16 @@ impl<'data> Rewriter<'data> {
     ///
     /// For ELF files, this deletes symbols from both the symbol table and the
     /// dynamic symbol table.
-    pub fn delete_symbols(&mut self, names: &HashSet<Vec<u8>>) {
-        self.elf_delete_symbols(names);
-        self.elf_delete_dynamic_symbols(names);
+    ///
+    /// For PEF files, this deletes exported symbols.
+    pub fn delete_symbols(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
+        match self.builder {
+            Builder::Elf(_) => {
+                self.elf_delete_symbols(names)?;
+                self.elf_delete_dynamic_symbols(names)
+            }
+            Builder::Pef(_) => self.pef_delete_exports(names),
+        }
     }
 
     /// Rename symbols in the symbol table.
@@ -91,21 +117,40 @@ impl<'data> Rewriter<'data> {
     /// For ELF files, this renames symbols in both the symbol table and the
     /// dynamic symbol table.
     ///
+    /// For PEF files, this renames both imported and exported symbols.
+    ///
     /// The `names` map is from old names to new names.
-    pub fn rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        self.elf_rename_symbols(names);
-        self.elf_rename_dynamic_symbols(names);
+    pub fn rename_symbols(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        match self.builder {
+            Builder::Elf(_) => {
+                self.elf_rename_symbols(names)?;
+                self.elf_rename_dynamic_symbols(names)
+            }
+            Builder::Pef(_) => {
+                self.pef_rename_imports(names)?;
+                self.pef_rename_exports(names)
+            }
+        }
     }
 
     /// Delete sections from the file.
-    pub fn delete_sections(&mut self, names: &HashSet<Vec<u8>>) {
-        self.elf_delete_sections(names);
+    ///
+    /// For PEF files, instantiated sections are not deleted, because they
+    /// may be referenced by index from relocations.
+    pub fn delete_sections(&mut self, names: &HashSet<Vec<u8>>) -> Result<()> {
+        match self.builder {
+            Builder::Elf(_) => self.elf_delete_sections(names),
+            Builder::Pef(_) => self.pef_delete_sections(names),
+        }
     }
 
     /// Rename sections in the file.
     ///
     /// The `names` map is from old names to new names.
-    pub fn rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) {
-        self.elf_rename_sections(names);
+    pub fn rename_sections(&mut self, names: &HashMap<Vec<u8>, Vec<u8>>) -> Result<()> {
+        match self.builder {
+            Builder::Elf(_) => self.elf_rename_sections(names),
+            Builder::Pef(_) => self.pef_rename_sections(names),
+        }
     }
 }
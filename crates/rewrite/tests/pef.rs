@@ -0,0 +1,139 @@
+use object::endian::BigEndian as BE;
+use object::read::pef::{LoaderSection, SectionTable};
+use object::read::ReadRef;
+use object::{pef, write};
+use object::{
+    Architecture, BinaryFormat, Endianness, SectionFlags, SectionKind, SymbolFlags, SymbolKind,
+    SymbolScope,
+};
+
+fn pef_object() -> Vec<u8> {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+    object.set_pef_container_info(write::PefContainerInfo {
+        date_time_stamp: 0,
+        old_def_version: 1,
+        old_imp_version: 1,
+        current_version: 1,
+    });
+
+    let text = object.section_id(write::StandardSection::Text);
+    for name in [&b"func"[..], &b"other"[..]] {
+        let value = object.append_section_data(text, &[0x4e, 0x80, 0x00, 0x20], 4);
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 4,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+    }
+
+    let traceback = object.add_section(Vec::new(), b"traceback".to_vec(), SectionKind::Debug);
+    object.section_mut(traceback).flags = SectionFlags::Pef {
+        section_kind: pef::SectionKind::Traceback as u8,
+        share_kind: pef::ShareKind::GlobalShare as u8,
+    };
+    object.append_section_data(traceback, &[0; 8], 1);
+
+    let library = object.add_pef_imported_library(write::PefImportedLibrary {
+        name: b"InterfaceLib".to_vec(),
+        old_imp_version: 0,
+        current_version: 0,
+        weak: false,
+        init_before: false,
+    });
+    for name in [&b"SysBeep"[..], &b"Gestalt"[..]] {
+        let symbol = object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Unknown,
+            weak: false,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.set_pef_symbol_library(symbol, library);
+    }
+
+    object.write().unwrap()
+}
+
+#[test]
+fn rewrite_pef() {
+    let input = pef_object();
+
+    let mut options = object_rewrite::Options::default();
+    options.delete_symbols.insert(b"other".to_vec());
+    options
+        .rename_symbols
+        .insert(b"func".to_vec(), b"renamed".to_vec());
+    options
+        .pef
+        .rename_libraries
+        .insert(b"InterfaceLib".to_vec(), b"CarbonLib".to_vec());
+    options
+        .pef
+        .set_weak_libraries
+        .insert(b"CarbonLib".to_vec(), true);
+    options
+        .pef
+        .set_weak_imports
+        .insert(b"Gestalt".to_vec(), true);
+    options.pef.set_current_version = Some(0x0110_8000);
+    options.pef.strip_sections = true;
+
+    let mut rewriter = object_rewrite::Rewriter::read(&input).unwrap();
+    rewriter.modify(options).unwrap();
+    let mut output = Vec::new();
+    rewriter.write(&mut output).unwrap();
+    let output = &*output;
+
+    let header = output.read_at::<pef::PEFContainerHeader>(0).unwrap();
+    assert_eq!(header.current_version.get(BE), 0x0110_8000);
+    assert_eq!(header.old_def_version.get(BE), 1);
+    assert_eq!(header.section_count.get(BE), 2);
+
+    let sections = SectionTable::parse(header, output).unwrap();
+    assert!(sections.section_by_name(b"traceback").is_none());
+    let loader = sections.loader().unwrap().unwrap();
+    let loader = LoaderSection::parse_section(output, loader).unwrap();
+
+    let library = &loader.libraries()[0];
+    assert_eq!(loader.library_name(library).unwrap(), b"CarbonLib");
+    assert_eq!(library.options, pef::WEAK_IMPORT_LIB);
+    let symbols = loader.library_symbols(library).unwrap();
+    assert_eq!(symbols[0].symbol_flags(), 0);
+    assert_eq!(symbols[1].symbol_flags(), pef::WEAK_IMPORT_SYMBOL);
+
+    assert_eq!(loader.exported_symbols().len(), 1);
+    assert_eq!(loader.exported_symbol_name(0).unwrap(), b"renamed");
+}
+
+#[test]
+fn rewrite_pef_noop() {
+    let input = pef_object();
+    let rewriter = object_rewrite::Rewriter::read(&input).unwrap();
+    let mut output = Vec::new();
+    rewriter.write(&mut output).unwrap();
+    assert_eq!(input, output);
+}
+
+#[test]
+fn rewrite_pef_elf_options() {
+    let input = pef_object();
+    let mut rewriter = object_rewrite::Rewriter::read(&input).unwrap();
+
+    let names = [b"func".to_vec()].into_iter().collect();
+    let err = rewriter.elf_delete_symbols(&names).unwrap_err();
+    assert_eq!(err.kind(), object_rewrite::ErrorKind::Modify);
+    assert_eq!(err.to_string(), "Not an ELF file; can't delete symbols");
+
+    let mut options = object_rewrite::Options::default();
+    options.elf.set_soname = Some(b"libfoo.so".to_vec());
+    let err = rewriter.modify(options).unwrap_err();
+    assert_eq!(err.to_string(), "Not an ELF file; can't set soname");
+}
@@ -0,0 +1,131 @@
+//! Parse arbitrary data as an object file and iterate over everything that
+//! the unified and PEF specific APIs expose.
+//!
+//! Run with `cargo fuzz run pef corpus/pef`.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use object::read::pef::{evaluate_relocations, PefFile, XsymFile};
+use object::{Object, ObjectComdat, ObjectSection, ObjectSegment, ObjectSymbol, ObjectSymbolTable};
+
+/// Don't unpack pattern data that would use an unreasonable amount of memory.
+const MAX_UNPACKED_SIZE: u32 = 1 << 20;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(file) = object::File::parse(data) {
+        iterate(&file);
+    }
+    if let Ok(file) = PefFile::parse(data) {
+        iterate(&file);
+        iterate_pef(&file, data);
+    }
+});
+
+fn iterate<'data>(file: &impl Object<'data>) {
+    let _ = file.architecture();
+    let _ = file.kind();
+    let _ = file.entry();
+    let _ = file.relative_address_base();
+    let _ = file.has_debug_symbols();
+    let _ = file.flags();
+
+    for segment in file.segments() {
+        let _ = segment.address();
+        let _ = segment.size();
+        let _ = segment.align();
+        let _ = segment.file_range();
+        let _ = segment.data();
+        let _ = segment.data_range(segment.address(), 4);
+        let _ = segment.name();
+        let _ = segment.flags();
+    }
+
+    for section in file.sections() {
+        let _ = file.section_by_index(section.index());
+        let _ = section.address();
+        let _ = section.size();
+        let _ = section.align();
+        let _ = section.file_range();
+        let _ = section.data();
+        let _ = section.data_range(section.address(), 4);
+        let _ = section.compressed_data();
+        let _ = section.name();
+        let _ = section.segment_name();
+        let _ = section.kind();
+        let _ = section.flags();
+        for _ in section.relocations() {}
+        let _ = section.relocation_map();
+    }
+
+    for comdat in file.comdats() {
+        let _ = comdat.kind();
+        let _ = comdat.name();
+        for _ in comdat.sections() {}
+    }
+
+    for symbol in file.symbols() {
+        let _ = file.symbol_by_index(symbol.index());
+        let _ = symbol.name();
+        let _ = symbol.address();
+        let _ = symbol.size();
+        let _ = symbol.kind();
+        let _ = symbol.section();
+        let _ = symbol.is_undefined();
+        let _ = symbol.is_definition();
+        let _ = symbol.is_weak();
+        let _ = symbol.scope();
+        let _ = symbol.flags();
+    }
+    if let Some(table) = file.symbol_table() {
+        for symbol in table.symbols() {
+            let _ = table.symbol_by_index(symbol.index());
+        }
+    }
+    for _ in file.dynamic_symbols() {}
+    let _ = file.symbol_map();
+    let _ = file.imports();
+    let _ = file.exports();
+}
+
+fn iterate_pef(file: &PefFile<'_>, data: &[u8]) {
+    let sections = file.pef_sections();
+    for section in sections.iter() {
+        let _ = sections.section_name(section);
+        let _ = section.pef_data(data);
+        if section.unpacked_size.get(object::BigEndian) <= MAX_UNPACKED_SIZE {
+            let _ = section.pef_unpacked_data(data);
+        }
+    }
+
+    if let Some(loader) = file.pef_loader() {
+        for library in loader.libraries() {
+            let _ = loader.library_name(library);
+            let _ = loader.library_symbols(library);
+        }
+        for symbol in loader.imported_symbols() {
+            let _ = loader.imported_symbol_name(symbol);
+        }
+        for index in 0..loader.exported_symbols().len() {
+            let _ = loader.exported_symbol_name(index);
+        }
+        for header in loader.relocation_headers() {
+            if let Ok(relocations) = loader.relocations(header) {
+                for _ in relocations {}
+            }
+            let size = sections
+                .section(object::SectionIndex(
+                    header.section_index.get(object::BigEndian).into(),
+                ))
+                .map_or(0, |section| section.total_size.get(object::BigEndian));
+            if let Ok(instructions) = loader.relocation_instructions(header) {
+                let _ = evaluate_relocations(instructions, size);
+            }
+        }
+    }
+
+    // The same data is also a plausible xSYM file for the PEF sections.
+    if let Ok(xsym) = XsymFile::parse(data) {
+        let _ = xsym.symbol_map(file);
+        let _ = xsym.statements(file);
+    }
+}
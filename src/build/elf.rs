@@ -65,6 +65,16 @@ pub struct Builder<'data> {
     pub gnu_hash_bloom_count: u32,
     /// The bucket count parameter for the GNU hash table.
     pub gnu_hash_bucket_count: u32,
+    /// Whether to write relocation sections as compact relocation sections.
+    ///
+    /// If true, then [`SectionData::Relocation`] sections that are not in a
+    /// loadable segment are written with the type [`elf::SHT_CREL`]. The section
+    /// type must still be set to [`elf::SHT_REL`] or [`elf::SHT_RELA`] to determine
+    /// whether addends are encoded, and section names beginning with `.rel` or `.rela`
+    /// are written with `.crel` instead.
+    ///
+    /// This is set when reading a file that contains `SHT_CREL` sections.
+    pub use_crel: bool,
     marker: PhantomData<()>,
 }
 
@@ -87,6 +97,7 @@ impl<'data> Builder<'data> {
             gnu_hash_bloom_shift: 0,
             gnu_hash_bloom_count: 0,
             gnu_hash_bucket_count: 0,
+            use_crel: false,
             marker: PhantomData,
         }
     }
@@ -149,6 +160,7 @@ impl<'data> Builder<'data> {
             gnu_hash_bloom_shift: 0,
             gnu_hash_bloom_count: 0,
             gnu_hash_bucket_count: 0,
+            use_crel: false,
             marker: PhantomData,
         };
 
@@ -184,6 +196,7 @@ impl<'data> Builder<'data> {
 
         for (index, section) in sections.enumerate().skip(1) {
             let id = SectionId(index.0 - 1);
+            let mut crel_is_rela = None;
             let relocations = if let Some((rels, link)) = section.rel(endian, data)? {
                 Self::read_relocations(
                     index,
@@ -206,6 +219,9 @@ impl<'data> Builder<'data> {
                     &symbols,
                     &dynamic_symbols,
                 )?
+            } else if let Some((crel, link)) = section.crel(endian, data)? {
+                crel_is_rela = Some(crel.is_rela());
+                Self::read_crel(index, endian, section, crel, link, &symbols)?
             } else {
                 SectionData::Data(Bytes::default())
             };
@@ -230,7 +246,7 @@ impl<'data> Builder<'data> {
                 | elf::SHT_LLVM_DEPENDENT_LIBRARIES => {
                     SectionData::Data(section.data(endian, data)?.into())
                 }
-                elf::SHT_REL | elf::SHT_RELA => relocations,
+                elf::SHT_REL | elf::SHT_RELA | elf::SHT_CREL => relocations,
                 elf::SHT_SYMTAB => {
                     if index == symbols.section() {
                         SectionData::Symbol
@@ -349,11 +365,22 @@ impl<'data> Builder<'data> {
                     }
                 }
             }
+            let mut sh_type = section.sh_type(endian);
+            let mut sh_addralign = section.sh_addralign(endian).into();
+            let mut sh_entsize = section.sh_entsize(endian).into();
+            if let Some(is_rela) = crel_is_rela {
+                // Compact relocations are stored in the same way as other relocations,
+                // and are converted back when writing.
+                builder.use_crel = true;
+                sh_type = if is_rela { elf::SHT_RELA } else { elf::SHT_REL };
+                sh_addralign = builder.class().align() as u64;
+                sh_entsize = builder.class().rel_size(is_rela) as u64;
+            }
             builder.sections.push(Section {
                 id,
                 delete: false,
                 name: name.into(),
-                sh_type: section.sh_type(endian),
+                sh_type,
                 sh_flags,
                 sh_addr,
                 sh_offset: section.sh_offset(endian).into(),
@@ -361,8 +388,8 @@ impl<'data> Builder<'data> {
                 sh_link_section,
                 sh_info,
                 sh_info_section,
-                sh_addralign: section.sh_addralign(endian).into(),
-                sh_entsize: section.sh_entsize(endian).into(),
+                sh_addralign,
+                sh_entsize,
                 data,
             });
         }
@@ -475,6 +502,50 @@ impl<'data> Builder<'data> {
         Ok(relocations)
     }
 
+    fn read_crel<Elf, R>(
+        index: read::SectionIndex,
+        endian: Elf::Endian,
+        section: &'data Elf::SectionHeader,
+        crel: read::elf::CrelIterator<'data, Elf>,
+        link: read::SectionIndex,
+        symbols: &read::elf::SymbolTable<'data, Elf, R>,
+    ) -> Result<SectionData<'data>>
+    where
+        Elf: FileHeader<Endian = Endianness>,
+        R: ReadRef<'data>,
+    {
+        if link != symbols.section()
+            || section.sh_flags(endian).into() & u64::from(elf::SHF_ALLOC) != 0
+        {
+            return Err(Error(format!(
+                "Unsupported SHT_CREL section at index {}",
+                index
+            )));
+        }
+        let mut relocations = Vec::new();
+        for rel in crel {
+            let rel = rel?;
+            let symbol = if let Some(symbol) = rel.symbol() {
+                if symbol.0 >= symbols.len() {
+                    return Err(Error(format!(
+                        "Invalid symbol index {} in relocation section at index {}",
+                        symbol, index,
+                    )));
+                }
+                Some(SymbolId(symbol.0 - 1))
+            } else {
+                None
+            };
+            relocations.push(Relocation {
+                r_offset: rel.r_offset,
+                symbol,
+                r_type: rel.r_type,
+                r_addend: rel.r_addend,
+            });
+        }
+        Ok(SectionData::Relocation(relocations))
+    }
+
     fn read_dynamics<Elf, R>(
         endian: Elf::Endian,
         dyns: &'data [Elf::Dyn],
@@ -743,6 +814,7 @@ impl<'data> Builder<'data> {
             name: Option<write::StringId>,
             offset: usize,
             attributes: Vec<u8>,
+            crel: Vec<u8>,
         }
 
         struct SymbolOut {
@@ -766,6 +838,23 @@ impl<'data> Builder<'data> {
         self.delete_orphans();
         self.delete_unused_versions();
 
+        // Names for sections that are written as compact relocations.
+        let mut crel_names = vec![None; self.sections.len()];
+        for section in &self.sections {
+            if !self.is_crel(section) {
+                continue;
+            }
+            let name = &section.name[..];
+            if let Some(suffix) = name
+                .strip_prefix(b".rela")
+                .or_else(|| name.strip_prefix(b".rel"))
+            {
+                let mut crel_name = b".crel".to_vec();
+                crel_name.extend_from_slice(suffix);
+                crel_names[section.id.0] = Some(crel_name);
+            }
+        }
+
         let mut writer = write::elf::Writer::new(self.endian, self.is_64, buffer);
 
         // Find metadata sections, and assign section indices.
@@ -874,7 +963,9 @@ impl<'data> Builder<'data> {
             };
             out_sections_index[section.id.0] = Some(index);
 
-            let name = if section.name.is_empty() {
+            let name = if let Some(name) = &crel_names[section.id.0] {
+                Some(writer.add_section_name(name))
+            } else if section.name.is_empty() {
                 None
             } else {
                 Some(writer.add_section_name(&section.name))
@@ -884,6 +975,7 @@ impl<'data> Builder<'data> {
                 name,
                 offset: 0,
                 attributes: Vec::new(),
+                crel: Vec::new(),
             });
         }
 
@@ -1294,8 +1386,23 @@ impl<'data> Builder<'data> {
             let SectionData::Relocation(relocations) = &section.data else {
                 continue;
             };
-            out_section.offset =
-                writer.reserve_relocations(relocations.len(), section.sh_type == elf::SHT_RELA);
+            if self.use_crel {
+                let relocations = relocations
+                    .iter()
+                    .map(|rel| write::elf::Rel {
+                        r_offset: rel.r_offset,
+                        r_sym: rel.symbol.map_or(0, |id| out_syms_index[id.0].unwrap().0),
+                        r_type: rel.r_type,
+                        r_addend: rel.r_addend,
+                    })
+                    .collect::<Vec<_>>();
+                out_section.crel =
+                    writer.encode_crel(section.sh_type == elf::SHT_RELA, &relocations);
+                out_section.offset = writer.reserve_crel(out_section.crel.len());
+            } else {
+                out_section.offset =
+                    writer.reserve_relocations(relocations.len(), section.sh_type == elf::SHT_RELA);
+            }
         }
 
         writer.reserve_shstrtab();
@@ -1600,13 +1707,18 @@ impl<'data> Builder<'data> {
         writer.write_strtab();
 
         // Write non-alloc relocations.
-        for section in &self.sections {
+        for out_section in &out_sections {
+            let section = self.sections.get(out_section.id);
             if !self.segments.is_empty() && section.is_alloc() {
                 continue;
             }
             let SectionData::Relocation(relocations) = &section.data else {
                 continue;
             };
+            if self.use_crel {
+                writer.write_crel(&out_section.crel);
+                continue;
+            }
             writer.write_align_relocation();
             for rel in relocations {
                 let r_sym = if let Some(id) = rel.symbol {
@@ -1690,6 +1802,21 @@ impl<'data> Builder<'data> {
                     } else {
                         section.sh_info
                     };
+                    if self.is_crel(section) {
+                        writer.write_section_header(&write::elf::SectionHeader {
+                            name: out_section.name,
+                            sh_type: elf::SHT_CREL,
+                            sh_flags: section.sh_flags,
+                            sh_addr: section.sh_addr,
+                            sh_offset: out_section.offset as u64,
+                            sh_size: out_section.crel.len() as u64,
+                            sh_link,
+                            sh_info,
+                            sh_addralign: 1,
+                            sh_entsize: 1,
+                        });
+                        continue;
+                    }
                     writer.write_section_header(&write::elf::SectionHeader {
                         name: out_section.name,
                         sh_type: section.sh_type,
@@ -1901,6 +2028,13 @@ impl<'data> Builder<'data> {
         write::elf::Class { is_64: self.is_64 }
     }
 
+    /// Return true if the section is written as a compact relocation section.
+    fn is_crel(&self, section: &Section<'data>) -> bool {
+        self.use_crel
+            && matches!(section.data, SectionData::Relocation(_))
+            && (self.segments.is_empty() || !section.is_alloc())
+    }
+
     /// Calculate the size of the file header.
     pub fn file_header_size(&self) -> usize {
         self.class().file_header_size()
@@ -0,0 +1,1201 @@
+//! This module provides a [`Builder`] for reading, modifying, and then writing PEF files.
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::build::{ByteString, Bytes, Error, Id, IdPrivate, Item, Result, Table};
+use crate::endian::{BigEndian as BE, I16Bytes, U32Bytes, I32, U16, U32};
+use crate::pef;
+use crate::read::pef::{LoaderSection, RelocationInstruction, SectionTable};
+use crate::read::{self, ReadRef};
+use crate::write::{self, WritableBuffer};
+
+/// A builder for reading, modifying, and then writing PEF files.
+///
+/// Public fields are available for modifying the values that will be written.
+/// Methods are available to add elements to tables, and elements can be deleted
+/// from tables by setting the `delete` field in the element.
+///
+/// The loader section is regenerated from the library, import and export tables
+/// when writing. Relocations are stored as the decoded instructions, so they
+/// refer to sections and imported symbols by index. Deleting an instantiated
+/// section or an imported symbol will change the indices of the following
+/// elements, and the relocations must be updated to match.
+#[derive(Debug)]
+pub struct Builder<'data> {
+    /// The container header.
+    pub header: Header,
+    /// The section table.
+    pub sections: Sections<'data>,
+    /// The imported libraries.
+    pub libraries: Libraries<'data>,
+    /// The imported symbols.
+    ///
+    /// The imported symbols for each library must be contiguous.
+    pub imports: Imports<'data>,
+    /// The exported symbols.
+    pub exports: Exports<'data>,
+    /// The location of the main symbol.
+    pub main: Option<EntryPoint>,
+    /// The location of the initialization function's transition vector.
+    pub init: Option<EntryPoint>,
+    /// The location of the termination routine's transition vector.
+    pub term: Option<EntryPoint>,
+    /// The power of two for the number of entries in the export hash table.
+    ///
+    /// If `None`, this is calculated from the number of exports.
+    pub export_hash_table_power: Option<u32>,
+    marker: PhantomData<()>,
+}
+
+impl<'data> Builder<'data> {
+    /// Create a new PEF builder.
+    pub fn new() -> Self {
+        Self {
+            header: Header::default(),
+            sections: Sections::new(),
+            libraries: Libraries::new(),
+            imports: Imports::new(),
+            exports: Exports::new(),
+            main: None,
+            init: None,
+            term: None,
+            export_hash_table_power: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// Read the PEF file from file data.
+    pub fn read<R: ReadRef<'data>>(data: R) -> Result<Self> {
+        let header = pef::PEFContainerHeader::parse(data)?;
+        if header.tag2.get(BE) != pef::TAG2 {
+            return Err(Error::new("Not a PEF container"));
+        }
+        let sections = SectionTable::parse(header, data)?;
+
+        let mut builder = Builder {
+            header: Header {
+                architecture: header.architecture.get(BE),
+                format_version: header.format_version.get(BE),
+                date_time_stamp: header.date_time_stamp.get(BE),
+                old_def_version: header.old_def_version.get(BE),
+                old_imp_version: header.old_imp_version.get(BE),
+                current_version: header.current_version.get(BE),
+            },
+            sections: Sections::new(),
+            libraries: Libraries::new(),
+            imports: Imports::new(),
+            exports: Exports::new(),
+            main: None,
+            init: None,
+            term: None,
+            export_hash_table_power: None,
+            marker: PhantomData,
+        };
+
+        let mut loader = None;
+        for (index, section) in sections.enumerate() {
+            let id = SectionId(index.0);
+            let section_data = if section.section_kind == pef::SectionKind::Loader as u8 {
+                if loader.is_some() {
+                    return Err(Error::new("Multiple PEF loader sections"));
+                }
+                loader = Some(LoaderSection::parse_section(data, section)?);
+                SectionData::Loader
+            } else {
+                SectionData::Data(section.pef_data(data)?.into())
+            };
+            builder.sections.push(Section {
+                id,
+                delete: false,
+                name: sections.section_name(section)?.map(ByteString::from),
+                default_address: section.default_address.get(BE),
+                total_size: section.total_size.get(BE),
+                unpacked_size: section.unpacked_size.get(BE),
+                section_kind: section.section_kind,
+                share_kind: section.share_kind,
+                alignment: section.alignment,
+                data: section_data,
+                relocations: Vec::new(),
+            });
+        }
+
+        if let Some(loader) = loader {
+            builder.read_loader(&loader)?;
+        }
+        Ok(builder)
+    }
+
+    fn read_loader(&mut self, loader: &LoaderSection<'data>) -> Result<()> {
+        let section_count = self.sections.len();
+        let section_id = |index: i32| -> Result<SectionId> {
+            if index >= 0 && (index as usize) < section_count {
+                Ok(SectionId(index as usize))
+            } else {
+                Err(Error(format!("Invalid PEF section index {}", index)))
+            }
+        };
+
+        let info = loader.header();
+        let entry = |section: &I32<BE>, offset: &U32<BE>| -> Result<Option<EntryPoint>> {
+            let section = section.get(BE);
+            if section == pef::NO_SECTION {
+                return Ok(None);
+            }
+            Ok(Some(EntryPoint {
+                section: section_id(section)?,
+                offset: offset.get(BE),
+            }))
+        };
+        self.main = entry(&info.main_section, &info.main_offset)?;
+        self.init = entry(&info.init_section, &info.init_offset)?;
+        self.term = entry(&info.term_section, &info.term_offset)?;
+        self.export_hash_table_power = Some(info.export_hash_table_power.get(BE));
+
+        let mut import_libraries = vec![None; loader.imported_symbols().len()];
+        for library in loader.libraries() {
+            let id = self.libraries.next_id();
+            let first = library.first_imported_symbol.get(BE) as usize;
+            let count = loader.library_symbols(library)?.len();
+            for import_library in &mut import_libraries[first..][..count] {
+                if import_library.is_some() {
+                    return Err(Error::new("Overlapping PEF imported library symbols"));
+                }
+                *import_library = Some(id);
+            }
+            self.libraries.push(Library {
+                id,
+                delete: false,
+                name: loader.library_name(library)?.into(),
+                old_imp_version: library.old_imp_version.get(BE),
+                current_version: library.current_version.get(BE),
+                options: library.options,
+            });
+        }
+        for (symbol, library) in loader.imported_symbols().iter().zip(import_libraries) {
+            let library =
+                library.ok_or_else(|| Error::new("PEF imported symbol has no library"))?;
+            let id = self.imports.next_id();
+            self.imports.push(Import {
+                id,
+                delete: false,
+                library,
+                name: loader.imported_symbol_name(symbol)?.into(),
+                symbol_class: (symbol.class_and_name.get(BE) >> 24) as u8,
+            });
+        }
+
+        for header in loader.relocation_headers() {
+            let id = section_id(header.section_index.get(BE).into())?;
+            let relocations = loader
+                .relocations(header)?
+                .collect::<read::Result<Vec<_>>>()?;
+            let section = self.sections.get_mut(id);
+            if !section.relocations.is_empty() {
+                return Err(Error(format!(
+                    "Multiple PEF relocation headers for section {}",
+                    id.0
+                )));
+            }
+            section.relocations = relocations;
+        }
+
+        for (index, symbol) in loader.exported_symbols().iter().enumerate() {
+            let value = symbol.symbol_value.get(BE);
+            let section = match symbol.section_index.get(BE) {
+                pef::EXPORTED_ABSOLUTE_SECTION => ExportSection::Absolute,
+                pef::EXPORTED_REEXPORTED_IMPORT_SECTION => {
+                    if value as usize >= self.imports.len() {
+                        return Err(Error(format!(
+                            "Invalid PEF re-exported import index {}",
+                            value
+                        )));
+                    }
+                    ExportSection::ReexportedImport(ImportId(value as usize))
+                }
+                index => ExportSection::Section(section_id(index.into())?),
+            };
+            let id = self.exports.next_id();
+            self.exports.push(Export {
+                id,
+                delete: false,
+                name: loader.exported_symbol_name(index)?.into(),
+                symbol_class: (symbol.class_and_name.get(BE) >> 24) as u8,
+                value,
+                section,
+            });
+        }
+        Ok(())
+    }
+
+    /// Write the PEF file to the buffer.
+    pub fn write(self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        // Assign section indices.
+        let mut section_indices = vec![None; self.sections.len()];
+        let mut section_count = 0;
+        let mut inst_section_count = 0;
+        let mut loader_id = None;
+        for section in &self.sections {
+            if section.is_instantiated() {
+                if inst_section_count != section_count {
+                    return Err(Error::new(
+                        "PEF instantiated sections must precede other sections",
+                    ));
+                }
+                inst_section_count += 1;
+            }
+            if let SectionData::Loader = section.data {
+                if loader_id.is_some() {
+                    return Err(Error::new("Multiple PEF loader sections"));
+                }
+                loader_id = Some(section.id);
+            }
+            section_indices[section.id.0] = Some(section_count as u16);
+            section_count += 1;
+        }
+        if section_count > i16::MAX as usize {
+            return Err(Error(format!("Too many PEF sections {}", section_count)));
+        }
+        let section_index = |id: SectionId| -> Result<u16> {
+            section_indices[id.0]
+                .ok_or_else(|| Error(format!("Reference to deleted PEF section {}", id.0)))
+        };
+
+        // Build the loader section.
+        let loader = match loader_id {
+            Some(_) => self.write_loader(&section_index)?,
+            None => {
+                if self.libraries.count() != 0
+                    || self.imports.count() != 0
+                    || self.exports.count() != 0
+                    || self.main.is_some()
+                    || self.init.is_some()
+                    || self.term.is_some()
+                    || self.sections.iter().any(|s| !s.relocations.is_empty())
+                {
+                    return Err(Error::new("Missing PEF loader section"));
+                }
+                Vec::new()
+            }
+        };
+
+        // Build the section name table.
+        let mut section_names = Vec::new();
+        let mut name_offsets = Vec::with_capacity(section_count);
+        for section in &self.sections {
+            name_offsets.push(match section.name {
+                Some(ref name) => {
+                    let offset = section_names.len() as i32;
+                    section_names.extend_from_slice(name);
+                    section_names.push(0);
+                    offset
+                }
+                None => pef::NO_SECTION_NAME,
+            });
+        }
+
+        // Calculate offsets of section data.
+        let mut offset = mem::size_of::<pef::PEFContainerHeader>();
+        offset += section_count * mem::size_of::<pef::PEFSectionHeader>();
+        offset += section_names.len();
+        let mut section_offsets = Vec::with_capacity(section_count);
+        for section in &self.sections {
+            let data = section.data(&loader);
+            offset = write::align(offset, section.container_align());
+            section_offsets.push(offset);
+            offset += data.len();
+        }
+        if offset > u32::MAX as usize {
+            return Err(Error::new("PEF container is too large"));
+        }
+
+        // Start writing.
+        buffer
+            .reserve(offset)
+            .map_err(|_| Error::new("Cannot allocate buffer"))?;
+
+        let header = pef::PEFContainerHeader {
+            tag1: U32::new(BE, pef::TAG1),
+            tag2: U32::new(BE, pef::TAG2),
+            architecture: U32::new(BE, self.header.architecture),
+            format_version: U32::new(BE, self.header.format_version),
+            date_time_stamp: U32::new(BE, self.header.date_time_stamp),
+            old_def_version: U32::new(BE, self.header.old_def_version),
+            old_imp_version: U32::new(BE, self.header.old_imp_version),
+            current_version: U32::new(BE, self.header.current_version),
+            section_count: U16::new(BE, section_count as u16),
+            inst_section_count: U16::new(BE, inst_section_count as u16),
+            reserved_a: U32::new(BE, 0),
+        };
+        buffer.write(&header);
+
+        for (index, section) in self.sections.iter().enumerate() {
+            let packed_size = section.data(&loader).len() as u32;
+            let section_header = pef::PEFSectionHeader {
+                name_offset: I32::new(BE, name_offsets[index]),
+                default_address: U32::new(BE, section.default_address),
+                total_size: U32::new(BE, section.total_size),
+                unpacked_size: U32::new(BE, section.unpacked_size),
+                packed_size: U32::new(BE, packed_size),
+                container_offset: U32::new(BE, section_offsets[index] as u32),
+                section_kind: section.section_kind,
+                share_kind: section.share_kind,
+                alignment: section.alignment,
+                reserved_a: 0,
+            };
+            buffer.write(&section_header);
+        }
+
+        buffer.write_bytes(&section_names);
+
+        for (index, section) in self.sections.iter().enumerate() {
+            write::write_align(buffer, section.container_align());
+            debug_assert_eq!(section_offsets[index], buffer.len());
+            buffer.write_bytes(section.data(&loader));
+        }
+
+        debug_assert_eq!(offset, buffer.len());
+        Ok(())
+    }
+
+    fn write_loader(&self, section_index: &dyn Fn(SectionId) -> Result<u16>) -> Result<Vec<u8>> {
+        let mut strings = Vec::new();
+        let mut add_string = |name: &[u8], terminate: bool| -> Result<u32> {
+            let offset = strings.len() as u32;
+            if offset > 0x00FF_FFFF {
+                return Err(Error::new("PEF loader string table is too large"));
+            }
+            strings.extend_from_slice(name);
+            if terminate {
+                strings.push(0);
+            }
+            Ok(offset)
+        };
+
+        // Assign library and import indices. The imports for each library must be
+        // contiguous, and in the same order as the libraries.
+        let mut library_indices = vec![None; self.libraries.len()];
+        for (index, library) in self.libraries.iter().enumerate() {
+            library_indices[library.id.0] = Some(index);
+        }
+        let mut import_indices = vec![None; self.imports.len()];
+        let mut library_ranges = vec![(0u32, 0u32); self.libraries.count()];
+        let mut next_library = 0;
+        for (index, import) in self.imports.iter().enumerate() {
+            import_indices[import.id.0] = Some(index as u32);
+            let library = library_indices[import.library.0].ok_or_else(|| {
+                Error(format!(
+                    "PEF imported symbol `{}` refers to a deleted library",
+                    import.name
+                ))
+            })?;
+            if library >= next_library {
+                // Empty libraries between the previous library and this one start here.
+                for range in &mut library_ranges[next_library..=library] {
+                    range.0 = index as u32;
+                }
+                next_library = library + 1;
+            } else if library + 1 != next_library {
+                return Err(Error(format!(
+                    "PEF imported symbol `{}` is not contiguous with the other imports from its library",
+                    import.name
+                )));
+            }
+            library_ranges[library].1 += 1;
+        }
+        let import_count = import_indices.iter().flatten().count() as u32;
+        for range in &mut library_ranges[next_library..] {
+            range.0 = import_count;
+        }
+
+        let mut libraries = Vec::with_capacity(library_ranges.len());
+        let mut imports = Vec::with_capacity(import_count as usize);
+        let mut import_iter = self.imports.iter().peekable();
+        for (library, &(first, count)) in self.libraries.iter().zip(&library_ranges) {
+            libraries.push(pef::PEFImportedLibrary {
+                name_offset: U32::new(BE, add_string(&library.name, true)?),
+                old_imp_version: U32::new(BE, library.old_imp_version),
+                current_version: U32::new(BE, library.current_version),
+                imported_symbol_count: U32::new(BE, count),
+                first_imported_symbol: U32::new(BE, first),
+                options: library.options,
+                reserved_a: 0,
+                reserved_b: U16::new(BE, 0),
+            });
+            for _ in 0..count {
+                let import = import_iter.next().unwrap();
+                let name_offset = add_string(&import.name, true)?;
+                imports.push(pef::PEFImportedSymbol {
+                    class_and_name: U32::new(
+                        BE,
+                        (u32::from(import.symbol_class) << 24) | name_offset,
+                    ),
+                });
+            }
+        }
+
+        // Encode relocations.
+        let mut relocation_headers = Vec::new();
+        let mut relocation_instructions = Vec::new();
+        for section in &self.sections {
+            if section.relocations.is_empty() {
+                continue;
+            }
+            let first_reloc_offset = relocation_instructions.len() * 2;
+            for instruction in &section.relocations {
+                write_relocation_instruction(instruction, &mut relocation_instructions)?;
+            }
+            relocation_headers.push(pef::PEFLoaderRelocationHeader {
+                section_index: U16::new(BE, section_index(section.id)?),
+                reserved_a: U16::new(BE, 0),
+                reloc_count: U32::new(
+                    BE,
+                    (relocation_instructions.len() - first_reloc_offset / 2) as u32,
+                ),
+                first_reloc_offset: U32::new(BE, first_reloc_offset as u32),
+            });
+        }
+
+        // Calculate exports, ordered by hash table index.
+        let mut exports = Vec::with_capacity(self.exports.count());
+        for export in &self.exports {
+            if export.name.len() > 0xFFFF {
+                return Err(Error(format!(
+                    "PEF export name `{}` is too long",
+                    export.name
+                )));
+            }
+            exports.push((export, pef::hash_word(&export.name)));
+        }
+        let hash_power = self
+            .export_hash_table_power
+            .unwrap_or_else(|| pef::hash_table_power(exports.len() as u32));
+        if hash_power > pef::HASH_TABLE_POWER_LIMIT {
+            return Err(Error(format!(
+                "Invalid PEF export hash table power {}",
+                hash_power
+            )));
+        }
+        exports.sort_by_key(|(_, hash)| pef::hash_table_index(*hash, hash_power));
+        let mut hash_slots = vec![0u32; 1 << hash_power];
+        for (export_index, (_, hash)) in exports.iter().enumerate().rev() {
+            // Iterating in reverse leaves the first index of each chain in the slot.
+            let slot = &mut hash_slots[pef::hash_table_index(*hash, hash_power) as usize];
+            *slot = (((*slot >> 18) + 1) << 18) | export_index as u32;
+        }
+        let mut exported_symbols = Vec::with_capacity(exports.len());
+        for (export, _) in &exports {
+            let (section_index, value) = match export.section {
+                ExportSection::Section(id) => (section_index(id)? as i16, export.value),
+                ExportSection::Absolute => (pef::EXPORTED_ABSOLUTE_SECTION, export.value),
+                ExportSection::ReexportedImport(id) => {
+                    let index = import_indices[id.0].ok_or_else(|| {
+                        Error(format!(
+                            "PEF export `{}` refers to a deleted import",
+                            export.name
+                        ))
+                    })?;
+                    (pef::EXPORTED_REEXPORTED_IMPORT_SECTION, index)
+                }
+            };
+            let name_offset = add_string(&export.name, false)?;
+            exported_symbols.push(pef::PEFExportedSymbol {
+                class_and_name: U32Bytes::new(
+                    BE,
+                    (u32::from(export.symbol_class) << 24) | name_offset,
+                ),
+                symbol_value: U32Bytes::new(BE, value),
+                section_index: I16Bytes::new(BE, section_index),
+            });
+        }
+
+        // Calculate entry points.
+        let entry = |entry: Option<EntryPoint>| -> Result<(i32, u32)> {
+            match entry {
+                Some(entry) => Ok((section_index(entry.section)?.into(), entry.offset)),
+                None => Ok((pef::NO_SECTION, 0)),
+            }
+        };
+        let (main_section, main_offset) = entry(self.main)?;
+        let (init_section, init_offset) = entry(self.init)?;
+        let (term_section, term_offset) = entry(self.term)?;
+
+        // Calculate offsets.
+        let mut offset = mem::size_of::<pef::PEFLoaderInfoHeader>();
+        offset += libraries.len() * mem::size_of::<pef::PEFImportedLibrary>();
+        offset += imports.len() * mem::size_of::<pef::PEFImportedSymbol>();
+        offset += relocation_headers.len() * mem::size_of::<pef::PEFLoaderRelocationHeader>();
+        let reloc_instr_offset = offset;
+        offset += relocation_instructions.len() * 2;
+        let loader_strings_offset = offset;
+        offset += strings.len();
+        offset = write::align(offset, 4);
+        let export_hash_offset = offset;
+        offset += hash_slots.len() * mem::size_of::<pef::PEFExportedSymbolHashSlot>();
+        offset += exports.len() * mem::size_of::<pef::PEFExportedSymbolKey>();
+        offset += exports.len() * mem::size_of::<pef::PEFExportedSymbol>();
+
+        // Write the loader section.
+        let mut loader = Vec::with_capacity(offset);
+        let loader_header = pef::PEFLoaderInfoHeader {
+            main_section: I32::new(BE, main_section),
+            main_offset: U32::new(BE, main_offset),
+            init_section: I32::new(BE, init_section),
+            init_offset: U32::new(BE, init_offset),
+            term_section: I32::new(BE, term_section),
+            term_offset: U32::new(BE, term_offset),
+            imported_library_count: U32::new(BE, libraries.len() as u32),
+            total_imported_symbol_count: U32::new(BE, imports.len() as u32),
+            reloc_section_count: U32::new(BE, relocation_headers.len() as u32),
+            reloc_instr_offset: U32::new(BE, reloc_instr_offset as u32),
+            loader_strings_offset: U32::new(BE, loader_strings_offset as u32),
+            export_hash_offset: U32::new(BE, export_hash_offset as u32),
+            export_hash_table_power: U32::new(BE, hash_power),
+            exported_symbol_count: U32::new(BE, exports.len() as u32),
+        };
+        loader.write_pod(&loader_header);
+        loader.write_pod_slice(&libraries);
+        loader.write_pod_slice(&imports);
+        loader.write_pod_slice(&relocation_headers);
+        debug_assert_eq!(reloc_instr_offset, loader.len());
+        for instruction in &relocation_instructions {
+            loader.write_pod(&U16::new(BE, *instruction));
+        }
+        debug_assert_eq!(loader_strings_offset, loader.len());
+        loader.write_bytes(&strings);
+        write::write_align(&mut loader, 4);
+        debug_assert_eq!(export_hash_offset, loader.len());
+        for slot in &hash_slots {
+            loader.write_pod(&U32::new(BE, *slot));
+        }
+        for (_, hash) in &exports {
+            loader.write_pod(&U32::new(BE, *hash));
+        }
+        loader.write_pod_slice(&exported_symbols);
+        debug_assert_eq!(offset, loader.len());
+        Ok(loader)
+    }
+}
+
+impl<'data> Default for Builder<'data> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encode a relocation instruction as 16-bit blocks.
+fn write_relocation_instruction(
+    instruction: &RelocationInstruction,
+    out: &mut Vec<u16>,
+) -> Result<()> {
+    let invalid = || Err(Error(format!("Invalid PEF relocation {:?}", instruction)));
+    let run = |opcode: u16, run_length: u16| -> Option<u16> {
+        if (1..=512).contains(&run_length) {
+            Some(opcode << 9 | (run_length - 1))
+        } else {
+            None
+        }
+    };
+    let small = |opcode: u16, index: u16| -> Option<u16> {
+        if index < 512 {
+            Some(opcode << 9 | index)
+        } else {
+            None
+        }
+    };
+    let large = |opcode: u16, high: u16, value: u32, bits: u32| -> Option<[u16; 2]> {
+        if value >> bits == 0 {
+            Some([opcode << 9 | high | (value >> 16) as u16, value as u16])
+        } else {
+            None
+        }
+    };
+    let lg_section = |subopcode: u16, index: u32| {
+        large(pef::RELOC_LG_SET_OR_BY_SECTION, subopcode << 6, index, 22)
+    };
+    let block = match *instruction {
+        RelocationInstruction::BySectDWithSkip {
+            skip_count,
+            reloc_count,
+        } => {
+            if reloc_count > 0x3F {
+                return invalid();
+            }
+            Some(u16::from(skip_count) << 6 | u16::from(reloc_count))
+        }
+        RelocationInstruction::BySectC { run_length } => run(pef::RELOC_BY_SECT_C, run_length),
+        RelocationInstruction::BySectD { run_length } => run(pef::RELOC_BY_SECT_D, run_length),
+        RelocationInstruction::TVector12 { run_length } => run(pef::RELOC_T_VECTOR_12, run_length),
+        RelocationInstruction::TVector8 { run_length } => run(pef::RELOC_T_VECTOR_8, run_length),
+        RelocationInstruction::VTable8 { run_length } => run(pef::RELOC_V_TABLE_8, run_length),
+        RelocationInstruction::ImportRun { run_length } => run(pef::RELOC_IMPORT_RUN, run_length),
+        RelocationInstruction::SmByImport { index } => small(pef::RELOC_SM_BY_IMPORT, index),
+        RelocationInstruction::SmSetSectC { index } => small(pef::RELOC_SM_SET_SECT_C, index),
+        RelocationInstruction::SmSetSectD { index } => small(pef::RELOC_SM_SET_SECT_D, index),
+        RelocationInstruction::SmBySection { index } => small(pef::RELOC_SM_BY_SECTION, index),
+        RelocationInstruction::IncrPosition { offset } => {
+            if (1..=0x1000).contains(&offset) {
+                Some(pef::RELOC_INCR_POSITION << 9 | (offset - 1))
+            } else {
+                None
+            }
+        }
+        RelocationInstruction::SmRepeat {
+            block_count,
+            repeat_count,
+        } => {
+            if (1..=16).contains(&block_count) && (1..=256).contains(&repeat_count) {
+                Some(
+                    pef::RELOC_SM_REPEAT << 9
+                        | u16::from(block_count - 1) << 8
+                        | (repeat_count - 1),
+                )
+            } else {
+                None
+            }
+        }
+        RelocationInstruction::SetPosition { offset } => {
+            return match large(pef::RELOC_SET_POSITION, 0, offset, 26) {
+                Some(blocks) => {
+                    out.extend_from_slice(&blocks);
+                    Ok(())
+                }
+                None => invalid(),
+            };
+        }
+        RelocationInstruction::LgByImport { index } => {
+            return match large(pef::RELOC_LG_BY_IMPORT, 0, index, 26) {
+                Some(blocks) => {
+                    out.extend_from_slice(&blocks);
+                    Ok(())
+                }
+                None => invalid(),
+            };
+        }
+        RelocationInstruction::LgRepeat {
+            block_count,
+            repeat_count,
+        } => {
+            if !(1..=16).contains(&block_count) {
+                return invalid();
+            }
+            let high = u16::from(block_count - 1) << 6;
+            return match large(pef::RELOC_LG_REPEAT, high, repeat_count, 22) {
+                Some(blocks) => {
+                    out.extend_from_slice(&blocks);
+                    Ok(())
+                }
+                None => invalid(),
+            };
+        }
+        RelocationInstruction::LgBySection { index } => {
+            return match lg_section(pef::RELOC_LG_BY_SECTION_SUBOP, index) {
+                Some(blocks) => {
+                    out.extend_from_slice(&blocks);
+                    Ok(())
+                }
+                None => invalid(),
+            };
+        }
+        RelocationInstruction::LgSetSectC { index } => {
+            return match lg_section(pef::RELOC_LG_SET_SECT_C_SUBOP, index) {
+                Some(blocks) => {
+                    out.extend_from_slice(&blocks);
+                    Ok(())
+                }
+                None => invalid(),
+            };
+        }
+        RelocationInstruction::LgSetSectD { index } => {
+            return match lg_section(pef::RELOC_LG_SET_SECT_D_SUBOP, index) {
+                Some(blocks) => {
+                    out.extend_from_slice(&blocks);
+                    Ok(())
+                }
+                None => invalid(),
+            };
+        }
+    };
+    match block {
+        Some(block) => {
+            out.push(block);
+            Ok(())
+        }
+        None => invalid(),
+    }
+}
+
+/// The container header fields that are not derived from other parts of the file.
+///
+/// This corresponds to fields in [`pef::PEFContainerHeader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    /// The `architecture` field in the container header.
+    ///
+    /// One of the `ARCHITECTURE_*` constants.
+    pub architecture: u32,
+    /// The `format_version` field in the container header.
+    pub format_version: u32,
+    /// The `date_time_stamp` field in the container header.
+    ///
+    /// This is the number of seconds since January 1, 1904.
+    pub date_time_stamp: u32,
+    /// The `old_def_version` field in the container header.
+    pub old_def_version: u32,
+    /// The `old_imp_version` field in the container header.
+    pub old_imp_version: u32,
+    /// The `current_version` field in the container header.
+    pub current_version: u32,
+}
+
+impl Default for Header {
+    fn default() -> Self {
+        Header {
+            architecture: pef::ARCHITECTURE_PPC,
+            format_version: pef::FORMAT_VERSION,
+            date_time_stamp: 0,
+            old_def_version: 0,
+            old_imp_version: 0,
+            current_version: 0,
+        }
+    }
+}
+
+/// The location of an entry point in the loader section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryPoint {
+    /// The section containing the entry point.
+    pub section: SectionId,
+    /// The offset of the entry point within the section.
+    pub offset: u32,
+}
+
+/// An ID for referring to a section in [`Sections`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SectionId(usize);
+
+impl fmt::Debug for SectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SectionId({})", self.0)
+    }
+}
+
+impl Id for SectionId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for SectionId {
+    fn new(id: usize) -> Self {
+        SectionId(id)
+    }
+}
+
+/// A section in [`Sections`].
+///
+/// This corresponds to [`pef::PEFSectionHeader`].
+#[derive(Debug)]
+pub struct Section<'data> {
+    id: SectionId,
+    /// Ignore this section when writing the PEF file.
+    pub delete: bool,
+    /// The name of the section.
+    pub name: Option<ByteString<'data>>,
+    /// The `default_address` field in the section header.
+    pub default_address: u32,
+    /// The `total_size` field in the section header.
+    pub total_size: u32,
+    /// The `unpacked_size` field in the section header.
+    ///
+    /// For pattern-initialized data, this must match the size of the unpacked data.
+    pub unpacked_size: u32,
+    /// The `section_kind` field in the section header.
+    ///
+    /// One of the [`pef::SectionKind`] values.
+    pub section_kind: u8,
+    /// The `share_kind` field in the section header.
+    ///
+    /// One of the [`pef::ShareKind`] values.
+    pub share_kind: u8,
+    /// The `alignment` field in the section header.
+    ///
+    /// This is the power of two for the alignment of the section in memory.
+    pub alignment: u8,
+    /// The contents of the section.
+    ///
+    /// This is the packed data for pattern-initialized data sections.
+    pub data: SectionData<'data>,
+    /// The relocation instructions for this section.
+    pub relocations: Vec<RelocationInstruction>,
+}
+
+impl<'data> Item for Section<'data> {
+    type Id = SectionId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Section<'data> {
+    /// The ID used for referring to this section.
+    pub fn id(&self) -> SectionId {
+        self.id
+    }
+
+    /// Returns true if the section kind is instantiated in memory.
+    ///
+    /// Unknown section kinds are treated as not instantiated.
+    pub fn is_instantiated(&self) -> bool {
+        pef::SectionKind::from_u8(self.section_kind).map_or(false, |kind| kind.is_instantiated())
+    }
+
+    fn data<'a>(&'a self, loader: &'a [u8]) -> &'a [u8] {
+        match self.data {
+            SectionData::Data(ref data) => data,
+            SectionData::Loader => loader,
+        }
+    }
+
+    fn container_align(&self) -> usize {
+        match self.data {
+            SectionData::Data(_) => 16,
+            SectionData::Loader => 4,
+        }
+    }
+}
+
+/// The contents of a [`Section`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum SectionData<'data> {
+    /// The raw section data.
+    Data(Bytes<'data>),
+    /// The loader section.
+    ///
+    /// The contents are generated when writing.
+    Loader,
+}
+
+/// A table of sections.
+pub type Sections<'data> = Table<Section<'data>>;
+
+impl<'data> Sections<'data> {
+    /// Add a new section to the table.
+    ///
+    /// The section is added after all existing sections, so it must not be
+    /// an instantiated section if there are any non-instantiated sections.
+    pub fn add(&mut self) -> &mut Section<'data> {
+        let id = self.next_id();
+        self.push(Section {
+            id,
+            delete: false,
+            name: None,
+            default_address: 0,
+            total_size: 0,
+            unpacked_size: 0,
+            section_kind: 0,
+            share_kind: 0,
+            alignment: 0,
+            data: SectionData::Data(Bytes::default()),
+            relocations: Vec::new(),
+        })
+    }
+
+    /// Return the loader section, if any.
+    pub fn loader(&self) -> Option<&Section<'data>> {
+        self.iter()
+            .find(|section| matches!(section.data, SectionData::Loader))
+    }
+}
+
+/// An ID for referring to an imported library in [`Libraries`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct LibraryId(usize);
+
+impl fmt::Debug for LibraryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LibraryId({})", self.0)
+    }
+}
+
+impl Id for LibraryId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for LibraryId {
+    fn new(id: usize) -> Self {
+        LibraryId(id)
+    }
+}
+
+/// An imported library in [`Libraries`].
+///
+/// This corresponds to [`pef::PEFImportedLibrary`].
+#[derive(Debug)]
+pub struct Library<'data> {
+    id: LibraryId,
+    /// Ignore this library when writing the PEF file.
+    ///
+    /// All imported symbols from this library must also be deleted.
+    pub delete: bool,
+    /// The name of the library.
+    pub name: ByteString<'data>,
+    /// The `old_imp_version` field in the imported library.
+    pub old_imp_version: u32,
+    /// The `current_version` field in the imported library.
+    pub current_version: u32,
+    /// The `options` field in the imported library.
+    ///
+    /// A combination of [`pef::INIT_LIB_BEFORE`] and [`pef::WEAK_IMPORT_LIB`].
+    pub options: u8,
+}
+
+impl<'data> Item for Library<'data> {
+    type Id = LibraryId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Library<'data> {
+    /// The ID used for referring to this library.
+    pub fn id(&self) -> LibraryId {
+        self.id
+    }
+
+    /// Returns true if the library is weakly imported.
+    pub fn is_weak(&self) -> bool {
+        self.options & pef::WEAK_IMPORT_LIB != 0
+    }
+
+    /// Set whether the library is weakly imported.
+    pub fn set_weak(&mut self, weak: bool) {
+        if weak {
+            self.options |= pef::WEAK_IMPORT_LIB;
+        } else {
+            self.options &= !pef::WEAK_IMPORT_LIB;
+        }
+    }
+}
+
+/// A table of imported libraries.
+pub type Libraries<'data> = Table<Library<'data>>;
+
+impl<'data> Libraries<'data> {
+    /// Add a new imported library to the table.
+    pub fn add(&mut self, name: ByteString<'data>) -> &mut Library<'data> {
+        let id = self.next_id();
+        self.push(Library {
+            id,
+            delete: false,
+            name,
+            old_imp_version: 0,
+            current_version: 0,
+            options: 0,
+        })
+    }
+
+    /// Find an imported library by name.
+    pub fn find(&self, name: &[u8]) -> Option<LibraryId> {
+        self.iter()
+            .find(|library| &*library.name == name)
+            .map(|library| library.id)
+    }
+}
+
+/// An ID for referring to an imported symbol in [`Imports`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ImportId(usize);
+
+impl fmt::Debug for ImportId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ImportId({})", self.0)
+    }
+}
+
+impl Id for ImportId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for ImportId {
+    fn new(id: usize) -> Self {
+        ImportId(id)
+    }
+}
+
+/// An imported symbol in [`Imports`].
+///
+/// This corresponds to [`pef::PEFImportedSymbol`].
+#[derive(Debug)]
+pub struct Import<'data> {
+    id: ImportId,
+    /// Ignore this symbol when writing the PEF file.
+    pub delete: bool,
+    /// The library that the symbol is imported from.
+    pub library: LibraryId,
+    /// The name of the symbol.
+    pub name: ByteString<'data>,
+    /// The symbol class and flags.
+    ///
+    /// One of the `*_SYMBOL` class constants, optionally combined with
+    /// [`pef::WEAK_IMPORT_SYMBOL`].
+    pub symbol_class: u8,
+}
+
+impl<'data> Item for Import<'data> {
+    type Id = ImportId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Import<'data> {
+    /// The ID used for referring to this imported symbol.
+    pub fn id(&self) -> ImportId {
+        self.id
+    }
+
+    /// Returns true if the symbol is weakly imported.
+    pub fn is_weak(&self) -> bool {
+        self.symbol_class & pef::WEAK_IMPORT_SYMBOL != 0
+    }
+
+    /// Set whether the symbol is weakly imported.
+    pub fn set_weak(&mut self, weak: bool) {
+        if weak {
+            self.symbol_class |= pef::WEAK_IMPORT_SYMBOL;
+        } else {
+            self.symbol_class &= !pef::WEAK_IMPORT_SYMBOL;
+        }
+    }
+}
+
+/// A table of imported symbols.
+pub type Imports<'data> = Table<Import<'data>>;
+
+impl<'data> Imports<'data> {
+    /// Add a new imported symbol to the table.
+    ///
+    /// The symbol is added after all existing symbols, so the symbols for
+    /// `library` must either be last in the table, or there must be none.
+    pub fn add(&mut self, library: LibraryId, name: ByteString<'data>) -> &mut Import<'data> {
+        let id = self.next_id();
+        self.push(Import {
+            id,
+            delete: false,
+            library,
+            name,
+            symbol_class: 0,
+        })
+    }
+}
+
+/// An ID for referring to an exported symbol in [`Exports`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ExportId(usize);
+
+impl fmt::Debug for ExportId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExportId({})", self.0)
+    }
+}
+
+impl Id for ExportId {
+    fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl IdPrivate for ExportId {
+    fn new(id: usize) -> Self {
+        ExportId(id)
+    }
+}
+
+/// An exported symbol in [`Exports`].
+///
+/// This corresponds to [`pef::PEFExportedSymbol`].
+#[derive(Debug)]
+pub struct Export<'data> {
+    id: ExportId,
+    /// Ignore this symbol when writing the PEF file.
+    pub delete: bool,
+    /// The name of the symbol.
+    pub name: ByteString<'data>,
+    /// The symbol class.
+    ///
+    /// One of the `*_SYMBOL` class constants.
+    pub symbol_class: u8,
+    /// The value of the symbol.
+    ///
+    /// This is an offset within the section, an absolute address,
+    /// or ignored for re-exported imports.
+    pub value: u32,
+    /// The location of the symbol.
+    pub section: ExportSection,
+}
+
+impl<'data> Item for Export<'data> {
+    type Id = ExportId;
+
+    fn is_deleted(&self) -> bool {
+        self.delete
+    }
+}
+
+impl<'data> Export<'data> {
+    /// The ID used for referring to this exported symbol.
+    pub fn id(&self) -> ExportId {
+        self.id
+    }
+}
+
+/// The location of an [`Export`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportSection {
+    /// The symbol value is an offset within a section.
+    Section(SectionId),
+    /// The symbol value is an absolute address.
+    Absolute,
+    /// The symbol is a re-export of an imported symbol.
+    ReexportedImport(ImportId),
+}
+
+/// A table of exported symbols.
+pub type Exports<'data> = Table<Export<'data>>;
+
+impl<'data> Exports<'data> {
+    /// Add a new exported symbol to the table.
+    pub fn add(
+        &mut self,
+        name: ByteString<'data>,
+        section: ExportSection,
+        value: u32,
+    ) -> &mut Export<'data> {
+        let id = self.next_id();
+        self.push(Export {
+            id,
+            delete: false,
+            name,
+            symbol_class: 0,
+            value,
+            section,
+        })
+    }
+
+    /// Find an exported symbol by name.
+    pub fn find(&self, name: &[u8]) -> Option<ExportId> {
+        self.iter()
+            .find(|export| &*export.name == name)
+            .map(|export| export.id)
+    }
+}
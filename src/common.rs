@@ -526,6 +526,13 @@ pub enum SectionFlags {
         /// `s_flags` field in the section header.
         s_flags: u32,
     },
+    /// PEF section flags.
+    Pef {
+        /// `section_kind` field in the section header.
+        section_kind: u8,
+        /// `share_kind` field in the section header.
+        share_kind: u8,
+    },
 }
 
 /// Symbol flags that are specific to each file format.
@@ -570,6 +577,13 @@ pub enum SymbolFlags<Section, Symbol> {
         /// Only valid if `x_smtyp` is `XTY_LD`.
         containing_csect: Option<Symbol>,
     },
+    /// PEF symbol flags.
+    Pef {
+        /// The class byte of the imported or exported symbol.
+        ///
+        /// This includes flags such as `pef::WEAK_IMPORT_SYMBOL`.
+        symbol_class: u8,
+    },
 }
 
 /// Relocation fields that are specific to each file format and architecture.
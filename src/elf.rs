@@ -8,7 +8,7 @@
 #![allow(missing_docs)]
 #![allow(clippy::identity_op)]
 
-use crate::endian::{Endian, U32Bytes, U64Bytes, I32, I64, U16, U32, U64};
+use crate::endian::{Endian, I16Bytes, I64Bytes, U32Bytes, U64Bytes, I32, I64, U16, U32, U64};
 use crate::pod::Pod;
 
 /// The header at the start of every 32-bit ELF file.
@@ -712,10 +712,20 @@ pub const SHT_GROUP: u32 = 17;
 pub const SHT_SYMTAB_SHNDX: u32 = 18;
 /// Relocation entries; only offsets.
 pub const SHT_RELR: u32 = 19;
+/// Compact relocation entries.
+pub const SHT_CREL: u32 = 0x4000_0014;
 /// Start of OS-specific section types.
 pub const SHT_LOOS: u32 = 0x6000_0000;
+/// Android packed relocation entries without explicit addends.
+pub const SHT_ANDROID_REL: u32 = 0x6000_0001;
+/// Android packed relocation entries with explicit addends.
+pub const SHT_ANDROID_RELA: u32 = 0x6000_0002;
 /// LLVM-style dependent libraries.
 pub const SHT_LLVM_DEPENDENT_LIBRARIES: u32 = 0x6fff4c04;
+/// Android relocation entries; only offsets.
+///
+/// This is the same format as `SHT_RELR`.
+pub const SHT_ANDROID_RELR: u32 = 0x6fff_ff00;
 /// Object attributes.
 pub const SHT_GNU_ATTRIBUTES: u32 = 0x6fff_fff5;
 /// GNU-style hash table.
@@ -1454,6 +1464,16 @@ pub const NT_ARM_HW_WATCH: u32 = 0x403;
 pub const NT_ARM_SYSTEM_CALL: u32 = 0x404;
 /// ARM Scalable Vector Extension registers.
 pub const NT_ARM_SVE: u32 = 0x405;
+/// ARM pointer authentication code masks.
+pub const NT_ARM_PAC_MASK: u32 = 0x406;
+/// ARM pointer authentication address keys.
+pub const NT_ARM_PACA_KEYS: u32 = 0x407;
+/// ARM pointer authentication generic key.
+pub const NT_ARM_PACG_KEYS: u32 = 0x408;
+/// ARM tagged address control register.
+pub const NT_ARM_TAGGED_ADDR_CTRL: u32 = 0x409;
+/// ARM enabled pointer authentication keys.
+pub const NT_ARM_PAC_ENABLED_KEYS: u32 = 0x40a;
 /// Vmcore Device Dump Note.
 pub const NT_VMCOREDD: u32 = 0x700;
 /// MIPS DSP ASE registers.
@@ -1469,6 +1489,259 @@ pub const NT_MIPS_FP_MODE: u32 = 0x801;
 /// uses the note name as the version string.
 pub const NT_VERSION: u32 = 1;
 
+/// A register in an [`NT_ARM_HW_BREAK`] or [`NT_ARM_HW_WATCH`] note.
+///
+/// The descriptor of these notes starts with a 32-bit debug information word,
+/// followed by 4 bytes of padding, and then an array of this type.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ArmHwdebugRegister<E: Endian> {
+    /// The breakpoint or watchpoint address.
+    pub addr: U64Bytes<E>,
+    /// The control register value.
+    pub ctrl: U32Bytes<E>,
+    /// Padding.
+    pub pad: U32Bytes<E>,
+}
+
+/// Signal information in [`Prstatus32`] and [`Prstatus64`].
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ElfSiginfo<E: Endian> {
+    /// Signal number.
+    pub si_signo: I32<E>,
+    /// Extra code.
+    pub si_code: I32<E>,
+    /// Errno.
+    pub si_errno: I32<E>,
+}
+
+/// Time value in [`Prstatus32`].
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Timeval32<E: Endian> {
+    /// Seconds.
+    pub tv_sec: I32<E>,
+    /// Microseconds.
+    pub tv_usec: I32<E>,
+}
+
+/// Time value in [`Prstatus64`].
+///
+/// Note: this type allows for misaligned values, because the descriptors of
+/// 64-bit core file notes are usually only 4 byte aligned.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Timeval64<E: Endian> {
+    /// Seconds.
+    pub tv_sec: I64Bytes<E>,
+    /// Microseconds.
+    pub tv_usec: I64Bytes<E>,
+}
+
+/// The start of the descriptor for a 32-bit [`NT_PRSTATUS`] note.
+///
+/// This is the fixed size part of `struct elf_prstatus`. It is followed by the
+/// general purpose register set (`pr_reg`), whose layout depends on the machine,
+/// and then `pr_fpvalid`.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Prstatus32<E: Endian> {
+    /// Information about the signal.
+    pub pr_info: ElfSiginfo<E>,
+    /// Current signal.
+    pub pr_cursig: I16Bytes<E>,
+    pub pr_pad: [u8; 2],
+    /// Set of pending signals.
+    pub pr_sigpend: U32<E>,
+    /// Set of held signals.
+    pub pr_sighold: U32<E>,
+    /// Process ID.
+    pub pr_pid: I32<E>,
+    /// Parent process ID.
+    pub pr_ppid: I32<E>,
+    /// Process group ID.
+    pub pr_pgrp: I32<E>,
+    /// Session ID.
+    pub pr_sid: I32<E>,
+    /// User time.
+    pub pr_utime: Timeval32<E>,
+    /// System time.
+    pub pr_stime: Timeval32<E>,
+    /// Cumulative user time.
+    pub pr_cutime: Timeval32<E>,
+    /// Cumulative system time.
+    pub pr_cstime: Timeval32<E>,
+}
+
+/// The start of the descriptor for a 64-bit [`NT_PRSTATUS`] note.
+///
+/// This is the fixed size part of `struct elf_prstatus`. It is followed by the
+/// general purpose register set (`pr_reg`), whose layout depends on the machine,
+/// and then `pr_fpvalid`.
+///
+/// Note: this type allows for misaligned values, because the descriptors of
+/// 64-bit core file notes are usually only 4 byte aligned.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Prstatus64<E: Endian> {
+    /// Information about the signal.
+    pub pr_info: ElfSiginfo<E>,
+    /// Current signal.
+    pub pr_cursig: I16Bytes<E>,
+    pub pr_pad: [u8; 2],
+    /// Set of pending signals.
+    pub pr_sigpend: U64Bytes<E>,
+    /// Set of held signals.
+    pub pr_sighold: U64Bytes<E>,
+    /// Process ID.
+    pub pr_pid: I32<E>,
+    /// Parent process ID.
+    pub pr_ppid: I32<E>,
+    /// Process group ID.
+    pub pr_pgrp: I32<E>,
+    /// Session ID.
+    pub pr_sid: I32<E>,
+    /// User time.
+    pub pr_utime: Timeval64<E>,
+    /// System time.
+    pub pr_stime: Timeval64<E>,
+    /// Cumulative user time.
+    pub pr_cutime: Timeval64<E>,
+    /// Cumulative system time.
+    pub pr_cstime: Timeval64<E>,
+}
+
+/// Number of bytes in [`Prpsinfo32::pr_fname`] and [`Prpsinfo64::pr_fname`].
+pub const ELF_PRFNAMESZ: usize = 16;
+/// Number of bytes in [`Prpsinfo32::pr_psargs`] and [`Prpsinfo64::pr_psargs`].
+pub const ELF_PRARGSZ: usize = 80;
+
+/// The descriptor for a 32-bit [`NT_PRPSINFO`] note.
+///
+/// This is the layout used by i386 and ARM, which have 16-bit user and group IDs.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Prpsinfo32<E: Endian> {
+    /// Numeric process state.
+    pub pr_state: u8,
+    /// Character for `pr_state`.
+    pub pr_sname: u8,
+    /// Zombie.
+    pub pr_zomb: u8,
+    /// Nice value.
+    pub pr_nice: u8,
+    /// Process flags.
+    pub pr_flag: U32<E>,
+    /// User ID.
+    pub pr_uid: U16<E>,
+    /// Group ID.
+    pub pr_gid: U16<E>,
+    /// Process ID.
+    pub pr_pid: I32<E>,
+    /// Parent process ID.
+    pub pr_ppid: I32<E>,
+    /// Process group ID.
+    pub pr_pgrp: I32<E>,
+    /// Session ID.
+    pub pr_sid: I32<E>,
+    /// Filename of executable.
+    pub pr_fname: [u8; ELF_PRFNAMESZ],
+    /// Initial part of argument list.
+    pub pr_psargs: [u8; ELF_PRARGSZ],
+}
+
+/// The descriptor for a 64-bit [`NT_PRPSINFO`] note.
+///
+/// Note: this type allows for misaligned values, because the descriptors of
+/// 64-bit core file notes are usually only 4 byte aligned.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct Prpsinfo64<E: Endian> {
+    /// Numeric process state.
+    pub pr_state: u8,
+    /// Character for `pr_state`.
+    pub pr_sname: u8,
+    /// Zombie.
+    pub pr_zomb: u8,
+    /// Nice value.
+    pub pr_nice: u8,
+    pub pr_pad: [u8; 4],
+    /// Process flags.
+    pub pr_flag: U64Bytes<E>,
+    /// User ID.
+    pub pr_uid: U32<E>,
+    /// Group ID.
+    pub pr_gid: U32<E>,
+    /// Process ID.
+    pub pr_pid: I32<E>,
+    /// Parent process ID.
+    pub pr_ppid: I32<E>,
+    /// Process group ID.
+    pub pr_pgrp: I32<E>,
+    /// Session ID.
+    pub pr_sid: I32<E>,
+    /// Filename of executable.
+    pub pr_fname: [u8; ELF_PRFNAMESZ],
+    /// Initial part of argument list.
+    pub pr_psargs: [u8; ELF_PRARGSZ],
+}
+
+// Values for the type of an auxiliary vector entry in a `NT_AUXV` note.
+//
+/// End of vector.
+pub const AT_NULL: u64 = 0;
+/// Entry should be ignored.
+pub const AT_IGNORE: u64 = 1;
+/// File descriptor of program.
+pub const AT_EXECFD: u64 = 2;
+/// Program headers for program.
+pub const AT_PHDR: u64 = 3;
+/// Size of program header entry.
+pub const AT_PHENT: u64 = 4;
+/// Number of program headers.
+pub const AT_PHNUM: u64 = 5;
+/// System page size.
+pub const AT_PAGESZ: u64 = 6;
+/// Base address of interpreter.
+pub const AT_BASE: u64 = 7;
+/// Flags.
+pub const AT_FLAGS: u64 = 8;
+/// Entry point of program.
+pub const AT_ENTRY: u64 = 9;
+/// Program is not ELF.
+pub const AT_NOTELF: u64 = 10;
+/// Real uid.
+pub const AT_UID: u64 = 11;
+/// Effective uid.
+pub const AT_EUID: u64 = 12;
+/// Real gid.
+pub const AT_GID: u64 = 13;
+/// Effective gid.
+pub const AT_EGID: u64 = 14;
+/// String identifying platform.
+pub const AT_PLATFORM: u64 = 15;
+/// Machine-dependent hints about processor capabilities.
+pub const AT_HWCAP: u64 = 16;
+/// Frequency of `times()`.
+pub const AT_CLKTCK: u64 = 17;
+/// Secure mode boolean.
+pub const AT_SECURE: u64 = 23;
+/// String identifying real platform.
+pub const AT_BASE_PLATFORM: u64 = 24;
+/// Address of 16 random bytes.
+pub const AT_RANDOM: u64 = 25;
+/// More machine-dependent hints about processor capabilities.
+pub const AT_HWCAP2: u64 = 26;
+/// Filename of program.
+pub const AT_EXECFN: u64 = 31;
+/// Pointer to the global system page used for system calls.
+pub const AT_SYSINFO: u64 = 32;
+/// Address of the vDSO.
+pub const AT_SYSINFO_EHDR: u64 = 33;
+/// Minimal stack size for signal delivery.
+pub const AT_MINSIGSTKSZ: u64 = 51;
+
 /// Dynamic section entry.
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -1561,8 +1834,28 @@ pub const DT_PREINIT_ARRAY: u32 = 32;
 pub const DT_PREINIT_ARRAYSZ: u32 = 33;
 /// Address of SYMTAB_SHNDX section
 pub const DT_SYMTAB_SHNDX: u32 = 34;
+/// Total size of RELR relative relocations
+pub const DT_RELRSZ: u32 = 35;
+/// Address of RELR relative relocations
+pub const DT_RELR: u32 = 36;
+/// Size of one RELR relative relocation
+pub const DT_RELRENT: u32 = 37;
 /// Start of OS-specific
 pub const DT_LOOS: u32 = 0x6000_000d;
+/// Address of Android packed relocations without explicit addends
+pub const DT_ANDROID_REL: u32 = 0x6000_000f;
+/// Total size of Android packed relocations without explicit addends
+pub const DT_ANDROID_RELSZ: u32 = 0x6000_0010;
+/// Address of Android packed relocations with explicit addends
+pub const DT_ANDROID_RELA: u32 = 0x6000_0011;
+/// Total size of Android packed relocations with explicit addends
+pub const DT_ANDROID_RELASZ: u32 = 0x6000_0012;
+/// Address of Android RELR relative relocations
+pub const DT_ANDROID_RELR: u32 = 0x6fff_e000;
+/// Total size of Android RELR relative relocations
+pub const DT_ANDROID_RELRSZ: u32 = 0x6fff_e001;
+/// Size of one Android RELR relative relocation
+pub const DT_ANDROID_RELRENT: u32 = 0x6fff_e003;
 /// End of OS-specific
 pub const DT_HIOS: u32 = 0x6fff_f000;
 /// Start of processor-specific
@@ -1714,6 +2007,36 @@ pub const VERSYM_HIDDEN: u16 = 0x8000;
 /// Symbol version index.
 pub const VERSYM_VERSION: u16 = 0x7fff;
 
+/// The magic bytes at the start of an `SHT_ANDROID_REL` or `SHT_ANDROID_RELA` section.
+pub const ANDROID_APS2_MAGIC: [u8; 4] = *b"APS2";
+
+// Values for the flags of a group in an Android packed relocation section.
+/// All relocations in the group use the same `r_info`.
+pub const RELOCATION_GROUPED_BY_INFO_FLAG: u64 = 1;
+/// All relocations in the group use the same offset delta.
+pub const RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG: u64 = 2;
+/// All relocations in the group use the same addend.
+pub const RELOCATION_GROUPED_BY_ADDEND_FLAG: u64 = 4;
+/// The relocations in the group have addends.
+pub const RELOCATION_GROUP_HAS_ADDEND_FLAG: u64 = 8;
+
+// Values for the header of an `SHT_CREL` section.
+/// The relocations have explicit addends.
+pub const CREL_HDR_ADDEND: u64 = 4;
+/// Mask for the shift applied to all relocation offsets.
+pub const CREL_HDR_SHIFT_MASK: u64 = 3;
+
+/// The magic string at the end of a Linux kernel module that has an appended signature.
+pub const MODULE_SIG_STRING: &[u8] = b"~Module signature appended~\n";
+
+// Values for the `id_type` field of a Linux kernel module signature.
+/// OpenPGP signature.
+pub const PKEY_ID_PGP: u8 = 0;
+/// X.509 certificate.
+pub const PKEY_ID_X509: u8 = 1;
+/// PKCS#7 signed data.
+pub const PKEY_ID_PKCS7: u8 = 2;
+
 /// Version definition sections
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
@@ -1857,6 +2180,35 @@ pub const ELF_NOTE_GNU: &[u8] = b"GNU";
 // See https://go-review.googlesource.com/9520 and https://go-review.googlesource.com/10704.
 pub const ELF_NOTE_GO: &[u8] = b"Go";
 
+/// FreeDesktop.org entries in the note section have this name.
+// See https://uapi-group.org/specifications/specs/elf_package_metadata/.
+pub const ELF_NOTE_FDO: &[u8] = b"FDO";
+
+// Note types for `ELF_NOTE_FDO`.
+/// Package metadata, usually in a `.note.package` section.
+///
+/// The descriptor is a null-terminated JSON object.
+pub const NT_FDO_PACKAGING_METADATA: u32 = 0xcafe_1a7e;
+/// Libraries that may be loaded with `dlopen`, usually in a `.note.dlopen` section.
+///
+/// The descriptor is a null-terminated JSON array.
+pub const NT_FDO_DLOPEN_METADATA: u32 = 0x407c_0c0a;
+
+/// SystemTap entries in the note section have this name.
+pub const ELF_NOTE_STAPSDT: &[u8] = b"stapsdt";
+
+// Note types for `ELF_NOTE_STAPSDT`.
+/// SystemTap probe descriptor, usually in a `.note.stapsdt` section.
+///
+/// The descriptor consists of:
+/// - the probe address
+/// - the link time address of the `.stapsdt.base` section
+/// - the semaphore address, or 0 if there is no semaphore
+/// - the null-terminated provider name, probe name, and argument format
+///
+/// The addresses are 4 bytes for 32-bit files and 8 bytes for 64-bit files.
+pub const NT_STAPSDT: u32 = 3;
+
 // Note types for `ELF_NOTE_GNU`.
 
 /// ABI information.
@@ -3845,6 +4197,10 @@ pub const SHT_ARM_PREEMPTMAP: u32 = SHT_LOPROC + 2;
 /// ARM attributes section.
 pub const SHT_ARM_ATTRIBUTES: u32 = SHT_LOPROC + 3;
 
+// ARM values for the second word of an entry in a `SHT_ARM_EXIDX` section.
+/// The function cannot be unwound.
+pub const EXIDX_CANTUNWIND: u32 = 1;
+
 // AArch64 values for `SectionHeader*::sh_type`.
 /// AArch64 attributes section.
 pub const SHT_AARCH64_ATTRIBUTES: u32 = SHT_LOPROC + 3;
@@ -3979,6 +4335,8 @@ pub const R_AARCH64_ADR_GOT_PAGE: u32 = 311;
 pub const R_AARCH64_LD64_GOT_LO12_NC: u32 = 312;
 /// GOT-page-rel. GOT off. LD/ST 14:3
 pub const R_AARCH64_LD64_GOTPAGE_LO15: u32 = 313;
+/// PC-relative 32-bit procedure linkage table entry.
+pub const R_AARCH64_PLT32: u32 = 314;
 /// PC-relative ADR imm. 20:0.
 pub const R_AARCH64_TLSGD_ADR_PREL21: u32 = 512;
 /// page-rel. ADRP imm. 32:12.
@@ -4952,6 +5310,14 @@ pub const R_390_GOTPLT20: u32 = 59;
 pub const R_390_TLS_GOTIE20: u32 = 60;
 /// STT_GNU_IFUNC relocation.
 pub const R_390_IRELATIVE: u32 = 61;
+/// PC relative 12 bit shifted by 1.
+pub const R_390_PC12DBL: u32 = 62;
+/// 12 bit PC rel. PLT shifted by 1.
+pub const R_390_PLT12DBL: u32 = 63;
+/// PC relative 24 bit shifted by 1.
+pub const R_390_PC24DBL: u32 = 64;
+/// 24 bit PC rel. PLT shifted by 1.
+pub const R_390_PLT24DBL: u32 = 65;
 
 // CRIS values `Rel*::r_type`.
 pub const R_CRIS_NONE: u32 = 0;
@@ -6485,6 +6851,14 @@ unsafe_impl_endian_pod!(
     Vernaux,
     NoteHeader32,
     NoteHeader64,
+    ArmHwdebugRegister,
+    ElfSiginfo,
+    Timeval32,
+    Timeval64,
+    Prstatus32,
+    Prstatus64,
+    Prpsinfo32,
+    Prpsinfo64,
     HashHeader,
     GnuHashHeader,
 );
@@ -108,4 +108,6 @@ pub mod pe;
 #[cfg(feature = "xcoff")]
 pub mod xcoff;
 #[cfg(feature = "pef")]
-pub mod pef;
\ No newline at end of file
+pub mod pef;
+#[cfg(feature = "pef")]
+pub mod xsym;
\ No newline at end of file
@@ -4,69 +4,67 @@
 
 #![allow(missing_docs)]
 
-use crate::endian::{ BigEndian as BE, I32, U16, U32};
+use crate::endian::{BigEndian as BE, I16Bytes, U16Bytes, U32Bytes, I32, U16, U32};
 use crate::pod::Pod;
 
 /// Joy!
-pub const TAG1 : u32 = 0x4A6F_7921;
+pub const TAG1: u32 = 0x4A6F_7921;
 /// peff
-pub const TAG2 : u32= 0x7065_6666;
+pub const TAG2: u32 = 0x7065_6666;
 
 /// pwpc
-pub const ARCHITECTURE_PPC : u32 = 0x7077_7063;
+pub const ARCHITECTURE_PPC: u32 = 0x7077_7063;
 /// m68k
-pub const ARCHITECTURE_68K : u32 = 0x6D36_386B;
+pub const ARCHITECTURE_68K: u32 = 0x6D36_386B;
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
-pub struct PEFContainerHeader
-{
+pub struct PEFContainerHeader {
     /// Designates that the container uses an Apple-defined format. This field must be set to "Joy!" in ASCII
-    pub tag1 : U32<BE>, 
+    pub tag1: U32<BE>,
     /// Identifies the type of container (currently set to peff in ASCII")
-    pub tag2 : U32<BE>,
+    pub tag2: U32<BE>,
     /// Indicates the architecture type that the container was generated for. This field holds the ASCII value pwpc for the PowerPC CFM implementation or m68k for CFM-68K.
-    pub architecture : U32<BE>,
+    pub architecture: U32<BE>,
     /// Indicates the version of PEF used in the container. The current version is 1.
-    pub format_version : U32<BE>,
+    pub format_version: U32<BE>,
     /// Indicates when the PEF container was created. The stamp follows the Macintosh time-measurement scheme (that is, the number of seconds measured from January 1, 1904)
-    pub date_time_stamp : U32<BE>,
+    pub date_time_stamp: U32<BE>,
     /// Contain version information that the Code Fragment Manager uses to check shared library compatibility
-    pub old_def_version : U32<BE>,
+    pub old_def_version: U32<BE>,
     /// Contain version information that the Code Fragment Manager uses to check shared library compatibility
-    pub old_imp_version : U32<BE>,
+    pub old_imp_version: U32<BE>,
     /// Contain version information that the Code Fragment Manager uses to check shared library compatibility
-    pub current_version : U32<BE>,
+    pub current_version: U32<BE>,
     /// Indicates the total number of sections contained in the container
-    pub section_count : U16<BE>,
+    pub section_count: U16<BE>,
     /// Indicates the number of instantiated sections. Instantiated sections contain code or data that are required for execution
-    pub inst_section_count : U16<BE>,
+    pub inst_section_count: U16<BE>,
     /// Reserved for future use
-    pub reserved_a : U32<BE>,
+    pub reserved_a: U32<BE>,
 }
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum SectionKind 
-{
+pub enum SectionKind {
     /// Contains read-only executable code in an uncompressed binary format.
     /// A container can have any number of code sections. Code sections are always shared.
     Code = 0,
     /// Contains uncompressed, initialized, read/write data followed by zero-initialized read/write data
     UnpackedData = 1,
-    /// Contains read/write data initialized by a pattern specification contained in the section’s contents. 
+    /// Contains read/write data initialized by a pattern specification contained in the section’s contents.
     /// The contents essentially contain a small program that tells the Code Fragment Manager how to initialize the raw data in memory.
     PatternInitializedData = 2,
-    /// Contains uncompressed, initialized, read-only data. 
+    /// Contains uncompressed, initialized, read-only data.
     /// A container can have any number of constant sections, and they are implicitly shared.
     Constant = 3,
-    /// Contains information about imports, exports, and entry points. 
+    /// Contains information about imports, exports, and entry points.
     /// A container can have only one loader section.
     Loader = 4,
     /// Reserved for future use
     Debug = 5,
-    /// Contains information that is both executable and modifiable. 
-    /// For example, this section can store code that contains embedded data. 
+    /// Contains information that is both executable and modifiable.
+    /// For example, this section can store code that contains embedded data.
     /// A container can have any number of executable data sections, each with a different sharing option.
     ExecutableData = 6,
     /// Reserved for future use
@@ -75,107 +73,832 @@ pub enum SectionKind
     Traceback = 8,
 }
 
+impl SectionKind {
+    /// Convert a raw `PEFSectionHeader::section_kind` value.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            0 => SectionKind::Code,
+            1 => SectionKind::UnpackedData,
+            2 => SectionKind::PatternInitializedData,
+            3 => SectionKind::Constant,
+            4 => SectionKind::Loader,
+            5 => SectionKind::Debug,
+            6 => SectionKind::ExecutableData,
+            7 => SectionKind::Exception,
+            8 => SectionKind::Traceback,
+            _ => return None,
+        })
+    }
+
+    /// Return true if sections of this kind are instantiated in memory.
+    pub fn is_instantiated(self) -> bool {
+        match self {
+            SectionKind::Code
+            | SectionKind::UnpackedData
+            | SectionKind::PatternInitializedData
+            | SectionKind::Constant
+            | SectionKind::ExecutableData => true,
+            SectionKind::Loader
+            | SectionKind::Debug
+            | SectionKind::Exception
+            | SectionKind::Traceback => false,
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ShareKind 
-{
+pub enum ShareKind {
     /// Indicates that the section is shared within a process, but a fresh copy is created for different processes.
-    ProcessShare = 1, 
+    ProcessShare = 1,
     /// Indicates that the section is shared between all processes in the system.
-    GlobalShare = 4, 
-    /// Indicates that the section is shared between all processes, but is protected. 
+    GlobalShare = 4,
+    /// Indicates that the section is shared between all processes, but is protected.
     /// Protected sections are read/write in privileged mode and read-only in user mode. This option is not available in System 7.
     ProtectedShare = 5,
 }
 
+impl ShareKind {
+    /// Convert a raw `PEFSectionHeader::share_kind` value.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Some(match value {
+            1 => ShareKind::ProcessShare,
+            4 => ShareKind::GlobalShare,
+            5 => ShareKind::ProtectedShare,
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
-pub struct PEFSectionHeader
-{
-    /// Holds the offset from the start of the section name table to the location of the section name. 
+pub struct PEFSectionHeader {
+    /// Holds the offset from the start of the section name table to the location of the section name.
     /// The name of the section is stored as a C-style null-terminated character string.
-    pub name_offset : I32<BE>,
-    /// Indicates the preferred address (as designated by the linker) at which to place the section’s instance. 
-    /// If the Code Fragment Manager can place the instance in the preferred memory location, 
+    pub name_offset: I32<BE>,
+    /// Indicates the preferred address (as designated by the linker) at which to place the section’s instance.
+    /// If the Code Fragment Manager can place the instance in the preferred memory location,
     /// the load-time and link-time addresses are identical and no internal relocations need to be performed.
-    pub default_address : U32<BE>,
-    /// Indicates the size, in bytes, required by the section’s contents at execution time. 
-    /// For a code section, this size is merely the size of the executable code. 
-    /// For a data section, this size indicates the sum of the size of the initialized data plus the size of any zero-initialized data. 
+    pub default_address: U32<BE>,
+    /// Indicates the size, in bytes, required by the section’s contents at execution time.
+    /// For a code section, this size is merely the size of the executable code.
+    /// For a data section, this size indicates the sum of the size of the initialized data plus the size of any zero-initialized data.
     /// Zero-initialized data appears at the end of a section’s contents and its length is exactly
     /// the difference of the totalSize and unpackedSize values. For noninstantiated sections, this field is ignored.
-    pub total_size : U32<BE>,
-    /// Indicates the size of the section’s contents that is explicitly initialized from the container. 
-    /// For code sections, this field is the size of the executable code. 
-    /// For an unpacked data section, this field indicates only the size of the initialized data. 
-    /// For packed data this is the size to which the compressed contents expand. 
+    pub total_size: U32<BE>,
+    /// Indicates the size of the section’s contents that is explicitly initialized from the container.
+    /// For code sections, this field is the size of the executable code.
+    /// For an unpacked data section, this field indicates only the size of the initialized data.
+    /// For packed data this is the size to which the compressed contents expand.
     /// The unpackedSize value also defines the boundary between the explicitly initialized portion and the zero-initialized portion.
-    pub unpacked_size : U32<BE>,
-    /// Indicates the size, in bytes, of a section’s contents in the container. 
-    /// For code sections, this field is the size of the executable code. 
-    /// For an unpacked data section, this field indicates only the size of the initialized data. 
+    pub unpacked_size: U32<BE>,
+    /// Indicates the size, in bytes, of a section’s contents in the container.
+    /// For code sections, this field is the size of the executable code.
+    /// For an unpacked data section, this field indicates only the size of the initialized data.
     /// For a packed data section this field is the size of the pattern description contained in the section.
-    pub packed_size : U32<BE>,
-    /// Contains the offset from the beginning of the container to the start of the section’s contents. 
+    pub packed_size: U32<BE>,
+    /// Contains the offset from the beginning of the container to the start of the section’s contents.
     /// Packed data sections and the loader section should be 4-byte aligned.
     /// Code sections and data sections that are not packed should be at least 16-byte aligned.
-    pub container_offset : U32<BE>,
-    /// Indicates the type of section as well as any special attributes. 
+    pub container_offset: U32<BE>,
+    /// Indicates the type of section as well as any special attributes.
     /// Note that instantiated read-only sections cannot have zero-initialized extensions.
-    pub section_kind : SectionKind, 
+    ///
+    /// One of the [`SectionKind`] values.
+    pub section_kind: u8,
     /// Controls how the section information is shared among processes by the Code Fragment Manager.
-    pub share_kind : ShareKind,
-    /// Indicates the desired alignment for instantiated sections in memory as a power of 2. 
-    /// A value of 0 indicates 1-byte alignment, 1 indicates 2-byte (halfword) alignment, 2 indicates 4-byte (word) alignment, and so on. 
-    /// Note that this field does not indicate the alignment of raw data relative to a container. 
+    ///
+    /// One of the [`ShareKind`] values.
+    pub share_kind: u8,
+    /// Indicates the desired alignment for instantiated sections in memory as a power of 2.
+    /// A value of 0 indicates 1-byte alignment, 1 indicates 2-byte (halfword) alignment, 2 indicates 4-byte (word) alignment, and so on.
+    /// Note that this field does not indicate the alignment of raw data relative to a container.
     /// The Code Fragment Manager does not support this field under System 7.
-    pub alignment : u8, 
+    pub alignment: u8,
     /// Reserved for future use
-    pub reserved_a : u8,
+    pub reserved_a: u8,
 }
 
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
-pub struct PEFLoaderInfoHeader 
-{
-    /// Specifies the number of the section in this container that contains the main symbol. 
+pub struct PEFLoaderInfoHeader {
+    /// Specifies the number of the section in this container that contains the main symbol.
     /// If the fragment does not have a main symbol, this field is set to -1.
-    pub main_section : I32<BE>,
+    pub main_section: I32<BE>,
     /// Indicates the offset (in bytes) from the beginning of the section to the main symbol.
-    pub main_offset : U32<BE>,
-    /// Contains the number of the section containing the initialization function’s transition vector. 
+    pub main_offset: U32<BE>,
+    /// Contains the number of the section containing the initialization function’s transition vector.
     /// If no initialization function exists, this field is set to -1.
-    pub init_section : I32<BE>,
+    pub init_section: I32<BE>,
     /// Indicates the offset (in bytes) from the beginning of the section to the initialization function’s transition vector.
-    pub init_offset : U32<BE>,
-    /// Contains the number of the section containing the termination routine’s transition vector. 
+    pub init_offset: U32<BE>,
+    /// Contains the number of the section containing the termination routine’s transition vector.
     /// If no termination routine exists, this field is set to -1.
-    pub term_section : I32<BE>,
+    pub term_section: I32<BE>,
     /// Indicates the offset (in bytes) from the beginning of the section to the termination routine’s transition vector.
-    pub term_offset : U32<BE>,
+    pub term_offset: U32<BE>,
     /// The importedLibraryCount field (4 bytes) indicates the number of imported libraries.
-    pub imported_library_count : U32<BE>,
+    pub imported_library_count: U32<BE>,
     /// Indicates the total number of imported symbols.
-    pub total_imported_symbol_count : U32<BE>,
+    pub total_imported_symbol_count: U32<BE>,
     /// Indicates the number of sections containing load-time relocations.
-    pub reloc_section_count : U32<BE>,
+    pub reloc_section_count: U32<BE>,
     /// Indicates the offset (in bytes) from the beginning of the loader section to the start of the relocations area.
-    pub reloc_instr_offset : U32<BE>,
+    pub reloc_instr_offset: U32<BE>,
     /// Indicates the offset (in bytes) from the beginning of the loader section to the start of the loader string table.
-    pub loader_strings_offset : U32<BE>,
-    /// Indicates the offset (in bytes) from the beginning of the loader section to the start of the export hash table. 
+    pub loader_strings_offset: U32<BE>,
+    /// Indicates the offset (in bytes) from the beginning of the loader section to the start of the export hash table.
     /// The hash table should be 4-byte aligned with padding added if necessary.
-    pub export_hash_offset : U32<BE>,
-    /// Indicates the number of hash index values (that is, the number of entries in the hash table). 
-    /// The number of entries is specified as a power of two. 
-    /// For example, a value of 0 indicates one entry, while a value of 2 indicates four entries. 
+    pub export_hash_offset: U32<BE>,
+    /// Indicates the number of hash index values (that is, the number of entries in the hash table).
+    /// The number of entries is specified as a power of two.
+    /// For example, a value of 0 indicates one entry, while a value of 2 indicates four entries.
     /// If no exports exist, the hash table still contains one entry, and the value of this field is 0.
-    pub export_hash_table_power : U32<BE>,
+    pub export_hash_table_power: U32<BE>,
     /// Indicates the number of symbols exported from this container.
-    pub exported_symbol_count : U32<BE>,
+    pub exported_symbol_count: U32<BE>,
+}
+
+/// The current PEF container format version.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The value of `PEFSectionHeader::name_offset` for a section without a name.
+pub const NO_SECTION_NAME: i32 = -1;
+
+/// The value of the `main_section`, `init_section` and `term_section` fields of
+/// `PEFLoaderInfoHeader` when the corresponding symbol does not exist.
+pub const NO_SECTION: i32 = -1;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PEFImportedLibrary {
+    /// Indicates the offset (in bytes) from the beginning of the loader string table to the start
+    /// of the null-terminated library name.
+    pub name_offset: U32<BE>,
+    /// Provides version information for checking the compatibility of the imported library.
+    pub old_imp_version: U32<BE>,
+    /// Provides version information for checking the compatibility of the imported library.
+    pub current_version: U32<BE>,
+    /// Indicates the number of symbols imported from this library.
+    pub imported_symbol_count: U32<BE>,
+    /// Holds the (zero-based) index of the first entry in the imported symbol table for this library.
+    pub first_imported_symbol: U32<BE>,
+    /// Contains bit flag information as defined by the `PEF_INIT_LIB_BEFORE` and
+    /// `PEF_WEAK_IMPORT_LIB` masks.
+    pub options: u8,
+    /// Reserved for future use
+    pub reserved_a: u8,
+    /// Reserved for future use
+    pub reserved_b: U16<BE>,
+}
+
+/// The imported library must be initialized before the client fragment.
+pub const INIT_LIB_BEFORE: u8 = 0x80;
+/// The imported library is weak, and the fragment can be prepared even if it is missing.
+pub const WEAK_IMPORT_LIB: u8 = 0x40;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PEFImportedSymbol {
+    /// The high-order 8 bits contain the symbol class (including the `WEAK_IMPORT_SYMBOL` flag),
+    /// and the remaining 24 bits contain the offset from the beginning of the loader string
+    /// table to the null-terminated symbol name.
+    pub class_and_name: U32<BE>,
+}
+
+impl PEFImportedSymbol {
+    /// Return the symbol class, excluding any flags.
+    #[inline]
+    pub fn symbol_class(&self) -> u8 {
+        (self.class_and_name.get(BE) >> 24) as u8 & SYMBOL_CLASS_MASK
+    }
+
+    /// Return the symbol flags.
+    #[inline]
+    pub fn symbol_flags(&self) -> u8 {
+        (self.class_and_name.get(BE) >> 24) as u8 & !SYMBOL_CLASS_MASK
+    }
+
+    /// Return the offset of the symbol name in the loader string table.
+    #[inline]
+    pub fn name_offset(&self) -> u32 {
+        self.class_and_name.get(BE) & 0x00FF_FFFF
+    }
+}
+
+/// Mask for the symbol class in the class byte of an imported or exported symbol.
+pub const SYMBOL_CLASS_MASK: u8 = 0x0F;
+/// The imported symbol is weak and need not be resolved.
+pub const WEAK_IMPORT_SYMBOL: u8 = 0x80;
+
+/// A code address.
+pub const CODE_SYMBOL: u8 = 0x00;
+/// A data address.
+pub const DATA_SYMBOL: u8 = 0x01;
+/// A standard procedure pointer (transition vector).
+pub const TVECT_SYMBOL: u8 = 0x02;
+/// A direct data area (table of contents) symbol.
+pub const TOC_SYMBOL: u8 = 0x03;
+/// A linker-inserted glue symbol.
+pub const GLUE_SYMBOL: u8 = 0x04;
+/// An undefined symbol.
+pub const UNDEFINED_SYMBOL: u8 = 0x0F;
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PEFLoaderRelocationHeader {
+    /// Holds the section index of the section whose contents are to be relocated.
+    pub section_index: U16<BE>,
+    /// Reserved for future use
+    pub reserved_a: U16<BE>,
+    /// Indicates the number of 16-bit relocation blocks for this section.
+    pub reloc_count: U32<BE>,
+    /// Indicates the byte offset from the start of the relocations area to the first
+    /// relocation instruction for this section.
+    pub first_reloc_offset: U32<BE>,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PEFExportedSymbolHashSlot {
+    /// The high-order 14 bits contain the number of symbols in this hash chain,
+    /// and the low-order 18 bits contain the index of the first export key in the chain.
+    pub count_and_start: U32<BE>,
+}
+
+impl PEFExportedSymbolHashSlot {
+    /// Return the number of symbols in the hash chain.
+    #[inline]
+    pub fn symbol_count(&self) -> u32 {
+        self.count_and_start.get(BE) >> 18
+    }
+
+    /// Return the index of the first export key in the hash chain.
+    #[inline]
+    pub fn first_symbol(&self) -> u32 {
+        self.count_and_start.get(BE) & 0x3_FFFF
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PEFExportedSymbolKey {
+    /// The high-order 16 bits contain the length of the symbol name,
+    /// and the low-order 16 bits contain the encoded hash value.
+    ///
+    /// See `hash_word`.
+    pub full_hash_word: U32<BE>,
+}
+
+impl PEFExportedSymbolKey {
+    /// Return the length of the symbol name.
+    #[inline]
+    pub fn name_length(&self) -> u32 {
+        self.full_hash_word.get(BE) >> 16
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PEFExportedSymbol {
+    /// The high-order 8 bits contain the symbol class, and the remaining 24 bits contain the
+    /// offset from the beginning of the loader string table to the symbol name.
+    ///
+    /// The name is not null-terminated; its length is given by the export key.
+    pub class_and_name: U32Bytes<BE>,
+    /// Typically indicates the offset from the beginning of the symbol's section to the
+    /// exported symbol.
+    pub symbol_value: U32Bytes<BE>,
+    /// Indicates the section index of the section containing this symbol,
+    /// or one of the `EXPORTED_*_SECTION` values.
+    pub section_index: I16Bytes<BE>,
+}
+
+impl PEFExportedSymbol {
+    /// Return the symbol class, excluding any flags.
+    #[inline]
+    pub fn symbol_class(&self) -> u8 {
+        (self.class_and_name.get(BE) >> 24) as u8 & SYMBOL_CLASS_MASK
+    }
+
+    /// Return the offset of the symbol name in the loader string table.
+    #[inline]
+    pub fn name_offset(&self) -> u32 {
+        self.class_and_name.get(BE) & 0x00FF_FFFF
+    }
+}
+
+/// The exported symbol has an absolute value.
+pub const EXPORTED_ABSOLUTE_SECTION: i16 = -2;
+/// The exported symbol is a re-exported import, and its value is the imported symbol index.
+pub const EXPORTED_REEXPORTED_IMPORT_SECTION: i16 = -3;
+
+/// The largest hash table exponent produced by `hash_table_power`.
+pub const HASH_TABLE_POWER_LIMIT: u32 = 16;
+/// The average hash chain length that `hash_table_power` aims to stay below.
+pub const HASH_AVERAGE_CHAIN_LIMIT: u32 = 10;
+
+/// Compute the full hash word of an exported symbol name.
+///
+/// The name length is stored in the high-order 16 bits and the encoded hash
+/// value in the low-order 16 bits.
+pub fn hash_word(name: &[u8]) -> u32 {
+    let mut hash: i32 = 0;
+    let mut length: u32 = 0;
+    for &c in name {
+        if c == 0 {
+            break;
+        }
+        length += 1;
+        hash = (hash << 1).wrapping_sub(hash >> 16) ^ i32::from(c);
+    }
+    (length << 16) | ((hash ^ (hash >> 16)) as u32 & 0xFFFF)
+}
+
+/// Compute the export hash table index for a full hash word.
+#[inline]
+pub fn hash_table_index(full_hash_word: u32, power: u32) -> u32 {
+    (full_hash_word ^ (full_hash_word >> power)) & ((1 << power) - 1)
 }
 
+/// Compute the export hash table exponent for the given number of exports.
+pub fn hash_table_power(export_count: u32) -> u32 {
+    let mut power = 0;
+    while power < HASH_TABLE_POWER_LIMIT {
+        if export_count >> power < HASH_AVERAGE_CHAIN_LIMIT {
+            break;
+        }
+        power += 1;
+    }
+    power
+}
+
+// Relocation instruction opcodes.
+//
+// The opcode is contained in the high-order 7 bits of the first 16-bit block
+// of each instruction. Some opcodes only use some of these bits.
+
+/// `00xxxxx`: add `sectionD` to `relocCount` words after skipping `skipCount` words.
+pub const RELOC_BY_SECT_D_WITH_SKIP: u16 = 0x00;
+/// `0100000`: add `sectionC` to each of `runLength` words.
+pub const RELOC_BY_SECT_C: u16 = 0x20;
+/// `0100001`: add `sectionD` to each of `runLength` words.
+pub const RELOC_BY_SECT_D: u16 = 0x21;
+/// `0100010`: relocate `runLength` 12 byte transition vectors.
+pub const RELOC_T_VECTOR_12: u16 = 0x22;
+/// `0100011`: relocate `runLength` 8 byte transition vectors.
+pub const RELOC_T_VECTOR_8: u16 = 0x23;
+/// `0100100`: relocate `runLength` 8 byte virtual table entries.
+pub const RELOC_V_TABLE_8: u16 = 0x24;
+/// `0100101`: add the addresses of `runLength` consecutive imported symbols.
+pub const RELOC_IMPORT_RUN: u16 = 0x25;
+/// `0110000`: add the address of an imported symbol.
+pub const RELOC_SM_BY_IMPORT: u16 = 0x30;
+/// `0110001`: set `sectionC` to the address of a section.
+pub const RELOC_SM_SET_SECT_C: u16 = 0x31;
+/// `0110010`: set `sectionD` to the address of a section.
+pub const RELOC_SM_SET_SECT_D: u16 = 0x32;
+/// `0110011`: add the address of a section.
+pub const RELOC_SM_BY_SECTION: u16 = 0x33;
+/// `1000xxx`: increment the relocation address.
+pub const RELOC_INCR_POSITION: u16 = 0x40;
+/// `1001xxx`: repeat preceding blocks.
+pub const RELOC_SM_REPEAT: u16 = 0x48;
+/// `101000x`: set the relocation address.
+pub const RELOC_SET_POSITION: u16 = 0x50;
+/// `101001x`: add the address of an imported symbol with a large index.
+pub const RELOC_LG_BY_IMPORT: u16 = 0x52;
+/// `101100x`: repeat preceding blocks with a large count.
+pub const RELOC_LG_REPEAT: u16 = 0x58;
+/// `101101x`: large section index variants of the `RELOC_SM_*` section opcodes.
+pub const RELOC_LG_SET_OR_BY_SECTION: u16 = 0x5A;
+
+/// `RELOC_LG_SET_OR_BY_SECTION` subopcode: add the address of a section.
+pub const RELOC_LG_BY_SECTION_SUBOP: u16 = 0x00;
+/// `RELOC_LG_SET_OR_BY_SECTION` subopcode: set `sectionC` to the address of a section.
+pub const RELOC_LG_SET_SECT_C_SUBOP: u16 = 0x01;
+/// `RELOC_LG_SET_OR_BY_SECTION` subopcode: set `sectionD` to the address of a section.
+pub const RELOC_LG_SET_SECT_D_SUBOP: u16 = 0x02;
+
+// Pattern-initialized data opcodes.
+//
+// Each instruction starts with a byte containing a 3-bit opcode and a 5-bit count.
+// If the count is zero, then the actual count follows as an argument.
+// Arguments are big-endian with 7 bits per byte, and the high bit set in all bytes
+// except the last.
+
+/// Shift for the opcode in the first byte of a pattern-initialized data instruction.
+pub const PK_DATA_OPCODE_SHIFT: u8 = 5;
+/// Mask for the count in the first byte of a pattern-initialized data instruction.
+pub const PK_DATA_COUNT5_MASK: u8 = 0x1F;
+/// The largest count that can be stored in the first byte of an instruction.
+pub const PK_DATA_MAX_COUNT5: u32 = 31;
+/// Number of value bits in each byte of an argument.
+pub const PK_DATA_VCOUNT_SHIFT: u32 = 7;
+/// Mask for the value bits in each byte of an argument.
+pub const PK_DATA_VCOUNT_MASK: u8 = 0x7F;
+/// Set in each byte of an argument except the last.
+pub const PK_DATA_VCOUNT_END_MASK: u8 = 0x80;
+
+/// Initialize `count` bytes to zero.
+pub const PK_DATA_ZERO: u8 = 0;
+/// Copy `count` bytes of raw data.
+pub const PK_DATA_BLOCK: u8 = 1;
+/// Copy a `count` byte block of raw data `repeatCount + 1` times.
+pub const PK_DATA_REPEAT: u8 = 2;
+/// Interleave a `count` byte common block with `repeatCount` custom blocks of
+/// `customSize` bytes, starting and ending with the common block.
+pub const PK_DATA_REPEAT_BLOCK: u8 = 3;
+/// Interleave `count` zero bytes with `repeatCount` custom blocks of
+/// `customSize` bytes, starting and ending with the zero bytes.
+pub const PK_DATA_REPEAT_ZERO: u8 = 4;
+
+// CFM-68K jump tables.
+//
+// Segmented CFM-68K code is loaded by the segment loader, so calls between code
+// sections go through 8 byte jump table entries in the classic 68K format.
+
+/// The size of a jump table entry.
+pub const JT_ENTRY_SIZE: usize = 8;
+/// `MOVE.W #imm,-(SP)`, which pushes the segment number in an unloaded entry.
+pub const JT_MOVE_W_IMM_SP: u16 = 0x3F3C;
+/// The `_LoadSeg` trap in an unloaded entry.
+pub const JT_LOAD_SEG: u16 = 0xA9F0;
+/// `JMP (xxx).L`, which jumps to the routine in a loaded entry.
+pub const JT_JMP_ABS_L: u16 = 0x4EF9;
+
+// Resource forks.
+//
+// A Mac OS file has a data fork and a resource fork. PEF containers are normally
+// in the data fork, and the 'cfrg' resource in the resource fork describes where
+// each fragment is. The layout is described in "Inside Macintosh: More Macintosh Toolbox".
+//
+// Resource forks have no alignment requirements, so these use unaligned types.
+
+/// The header at the start of a resource fork.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ResourceForkHeader {
+    /// Offset from the start of the resource fork to the resource data.
+    pub data_offset: U32Bytes<BE>,
+    /// Offset from the start of the resource fork to the resource map.
+    pub map_offset: U32Bytes<BE>,
+    /// Length of the resource data.
+    pub data_length: U32Bytes<BE>,
+    /// Length of the resource map.
+    pub map_length: U32Bytes<BE>,
+}
+
+/// The header at the start of the resource map.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ResourceMapHeader {
+    /// Reserved for a copy of the resource fork header.
+    pub reserved_header: ResourceForkHeader,
+    /// Reserved for a handle to the next resource map.
+    pub reserved_next_map: U32Bytes<BE>,
+    /// Reserved for the file reference number.
+    pub reserved_file_ref: U16Bytes<BE>,
+    /// Resource fork attributes.
+    pub attributes: U16Bytes<BE>,
+    /// Offset from the start of the resource map to the type list.
+    pub type_list_offset: U16Bytes<BE>,
+    /// Offset from the start of the resource map to the name list.
+    pub name_list_offset: U16Bytes<BE>,
+}
+
+/// An entry in the resource type list.
+///
+/// The type list starts with a `U16` giving the number of types minus one.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ResourceType {
+    /// The resource type, such as 'cfrg'.
+    pub res_type: U32Bytes<BE>,
+    /// The number of resources of this type minus one.
+    pub count_minus_one: U16Bytes<BE>,
+    /// Offset from the start of the type list to the reference list for this type.
+    pub reference_list_offset: U16Bytes<BE>,
+}
+
+/// An entry in a resource reference list.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ResourceReference {
+    /// The resource ID.
+    pub id: I16Bytes<BE>,
+    /// Offset from the start of the name list to the resource name, or -1 if there is no name.
+    pub name_offset: I16Bytes<BE>,
+    /// The resource attributes.
+    pub attributes: u8,
+    /// Offset from the start of the resource data to the data for this resource.
+    ///
+    /// The data starts with a `U32` giving its length.
+    pub data_offset: [u8; 3],
+    /// Reserved for a handle to the resource.
+    pub reserved_handle: U32Bytes<BE>,
+}
+
+impl ResourceReference {
+    /// Return the offset of the data for this resource.
+    #[inline]
+    pub fn data_offset(&self) -> u32 {
+        let [a, b, c] = self.data_offset;
+        u32::from_be_bytes([0, a, b, c])
+    }
+}
+
+/// 'cfrg'
+pub const RESOURCE_TYPE_CFRG: u32 = 0x6366_7267;
+/// The resource ID of the 'cfrg' resource.
+pub const CFRG_RESOURCE_ID: i16 = 0;
+/// The current version of the 'cfrg' resource.
+pub const CFRG_RESOURCE_VERSION: u16 = 1;
+
+/// The header of the 'cfrg' resource, which describes the code fragments in a file.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CFragResource {
+    pub reserved_a: U32Bytes<BE>,
+    pub reserved_b: U32Bytes<BE>,
+    pub reserved_c: U16Bytes<BE>,
+    /// The resource version, which must be `CFRG_RESOURCE_VERSION`.
+    pub version: U16Bytes<BE>,
+    pub reserved_d: U32Bytes<BE>,
+    pub reserved_e: U32Bytes<BE>,
+    pub reserved_f: U32Bytes<BE>,
+    pub reserved_g: U32Bytes<BE>,
+    pub reserved_h: U16Bytes<BE>,
+    /// The number of members that follow.
+    pub member_count: U16Bytes<BE>,
+}
+
+/// A member of the 'cfrg' resource, describing one code fragment.
+///
+/// The member is followed by the fragment name as a Pascal string.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct CFragResourceMember {
+    /// The architecture of the fragment, such as `ARCHITECTURE_PPC`.
+    pub architecture: U32Bytes<BE>,
+    pub reserved_a: U16Bytes<BE>,
+    pub reserved_b: u8,
+    /// The update level, used to choose between duplicate fragments.
+    pub update_level: u8,
+    /// The current version of the fragment.
+    pub current_version: U32Bytes<BE>,
+    /// The oldest version of the fragment that is compatible with the current version.
+    pub old_def_version: U32Bytes<BE>,
+    /// For applications, the stack size.
+    pub usage_1: U32Bytes<BE>,
+    /// For applications, the directory ID of the application subdirectory.
+    pub usage_2: U16Bytes<BE>,
+    /// One of the `CFRG_USAGE_*` values.
+    pub usage: u8,
+    /// One of the `CFRG_*_LOCATOR` values.
+    pub location: u8,
+    /// The offset of the fragment within its location.
+    pub offset: U32Bytes<BE>,
+    /// The length of the fragment, or `CFRG_GOES_TO_EOF`.
+    pub length: U32Bytes<BE>,
+    /// Location specific information, such as the resource type for `CFRG_RESOURCE_LOCATOR`.
+    pub where_1: U32Bytes<BE>,
+    /// Location specific information, such as the resource ID for `CFRG_RESOURCE_LOCATOR`.
+    pub where_2: U16Bytes<BE>,
+    /// The number of extensions following the name.
+    pub extension_count: U16Bytes<BE>,
+    /// The total size of this member, including the name and extensions.
+    pub member_size: U16Bytes<BE>,
+}
+
+/// The fragment is an import library.
+pub const CFRG_USAGE_IMPORT_LIBRARY: u8 = 0;
+/// The fragment is an application.
+pub const CFRG_USAGE_APPLICATION: u8 = 1;
+/// The fragment is a drop-in additions (plug-in).
+pub const CFRG_USAGE_DROP_IN: u8 = 2;
+/// The fragment is a stub library, used only for linking.
+pub const CFRG_USAGE_STUB_LIBRARY: u8 = 3;
+/// The fragment is a weak stub library, used only for linking.
+pub const CFRG_USAGE_WEAK_STUB_LIBRARY: u8 = 4;
+
+/// The fragment is in memory.
+pub const CFRG_MEMORY_LOCATOR: u8 = 0;
+/// The fragment is in the data fork.
+pub const CFRG_DATA_FORK_LOCATOR: u8 = 1;
+/// The fragment is in a resource.
+pub const CFRG_RESOURCE_LOCATOR: u8 = 2;
+/// The fragment is in a byte stream.
+pub const CFRG_BYTE_STREAM_LOCATOR: u8 = 3;
+/// The fragment is named by another fragment.
+pub const CFRG_NAMED_FRAGMENT_LOCATOR: u8 = 4;
+
+/// The fragment extends to the end of its fork.
+pub const CFRG_GOES_TO_EOF: u32 = 0;
+
+/// 'vers'
+pub const RESOURCE_TYPE_VERS: u32 = 0x7665_7273;
+/// The resource ID of the 'vers' resource for the file version.
+pub const VERS_FILE_ID: i16 = 1;
+/// The resource ID of the 'vers' resource for the version of the product containing the file.
+pub const VERS_PRODUCT_ID: i16 = 2;
+
+/// The header of a 'vers' resource.
+///
+/// The header is followed by the short version string and the long version string,
+/// both as Pascal strings.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct VersResource {
+    /// The major version, in BCD.
+    pub major: u8,
+    /// The minor version in the high nibble and the bug fix version in the low nibble, in BCD.
+    pub minor_and_bug_fix: u8,
+    /// The release stage, which is one of the `VERS_STAGE_*` values.
+    pub stage: u8,
+    /// The revision for a non-final release.
+    pub non_release: u8,
+    /// The region code, such as 0 for the United States.
+    pub region: U16Bytes<BE>,
+}
+
+pub const VERS_STAGE_DEVELOPMENT: u8 = 0x20;
+pub const VERS_STAGE_ALPHA: u8 = 0x40;
+pub const VERS_STAGE_BETA: u8 = 0x60;
+pub const VERS_STAGE_FINAL: u8 = 0x80;
+
+/// 'SIZE'
+pub const RESOURCE_TYPE_SIZE: u32 = 0x5349_5A45;
+/// The resource ID of the 'SIZE' resource written by the developer.
+///
+/// If the user changes the partition size in the Finder, the Finder writes
+/// a `SIZE_USER_ID` resource, which takes precedence.
+pub const SIZE_DEFAULT_ID: i16 = -1;
+/// The resource ID of the 'SIZE' resource written by the Finder.
+pub const SIZE_USER_ID: i16 = 0;
+
+/// The 'SIZE' resource, which gives the flags and memory partition for an application.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct SizeResource {
+    /// Combination of the `SIZE_*` flags.
+    pub flags: U16Bytes<BE>,
+    /// The preferred partition size in bytes.
+    pub preferred_size: U32Bytes<BE>,
+    /// The minimum partition size in bytes.
+    pub minimum_size: U32Bytes<BE>,
+}
+
+// Values for `SizeResource::flags`.
+pub const SIZE_ACCEPT_SUSPEND_RESUME_EVENTS: u16 = 0x4000;
+pub const SIZE_CAN_BACKGROUND: u16 = 0x1000;
+pub const SIZE_DOES_ACTIVATE_ON_FG_SWITCH: u16 = 0x0800;
+pub const SIZE_ONLY_BACKGROUND: u16 = 0x0400;
+pub const SIZE_GET_FRONT_CLICKS: u16 = 0x0200;
+pub const SIZE_ACCEPT_APP_DIED_EVENTS: u16 = 0x0100;
+pub const SIZE_IS_32_BIT_COMPATIBLE: u16 = 0x0080;
+pub const SIZE_IS_HIGH_LEVEL_EVENT_AWARE: u16 = 0x0040;
+pub const SIZE_LOCAL_AND_REMOTE_HL_EVENTS: u16 = 0x0020;
+pub const SIZE_IS_STATIONERY_AWARE: u16 = 0x0010;
+pub const SIZE_USE_TEXT_EDIT_SERVICES: u16 = 0x0008;
+
+// File wrappers.
+//
+// Files copied off a Mac OS volume keep their resource fork by wrapping both forks
+// in a MacBinary or AppleSingle file, or by storing the resource fork in a
+// separate AppleDouble file.
+
+/// The header of a MacBinary file.
+///
+/// The header is followed by the data fork and then the resource fork,
+/// each padded to a multiple of 128 bytes.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct MacBinaryHeader {
+    /// Must be 0.
+    pub old_version: u8,
+    /// The length of the file name.
+    pub name_length: u8,
+    /// The file name.
+    pub name: [u8; 63],
+    /// The file type.
+    pub file_type: U32Bytes<BE>,
+    /// The file creator.
+    pub creator: U32Bytes<BE>,
+    /// The high byte of the Finder flags.
+    pub finder_flags: u8,
+    /// Must be 0.
+    pub zero_a: u8,
+    pub vertical_position: U16Bytes<BE>,
+    pub horizontal_position: U16Bytes<BE>,
+    pub folder_id: U16Bytes<BE>,
+    pub protected: u8,
+    /// Must be 0.
+    pub zero_b: u8,
+    /// The length of the data fork.
+    pub data_fork_length: U32Bytes<BE>,
+    /// The length of the resource fork.
+    pub resource_fork_length: U32Bytes<BE>,
+    pub creation_date: U32Bytes<BE>,
+    pub modification_date: U32Bytes<BE>,
+    pub comment_length: U16Bytes<BE>,
+    /// The low byte of the Finder flags (MacBinary II).
+    pub finder_flags_2: u8,
+    /// `MACBINARY_SIGNATURE` for MacBinary III.
+    pub signature: U32Bytes<BE>,
+    pub script: u8,
+    pub extended_finder_flags: u8,
+    pub reserved: [u8; 8],
+    pub unpacked_length: U32Bytes<BE>,
+    /// The length of a secondary header following this header (MacBinary II).
+    pub secondary_header_length: U16Bytes<BE>,
+    /// The MacBinary version used to write the file (MacBinary II).
+    pub version: u8,
+    /// The MacBinary version needed to read the file (MacBinary II).
+    pub min_version: u8,
+    /// CRC of the first 124 bytes (MacBinary II).
+    pub crc: U16Bytes<BE>,
+    pub reserved_2: [u8; 2],
+}
+
+/// 'mBIN'
+pub const MACBINARY_SIGNATURE: u32 = 0x6D42_494E;
+
+/// The magic number of an AppleSingle file.
+pub const APPLE_SINGLE_MAGIC: u32 = 0x0005_1600;
+/// The magic number of an AppleDouble header file.
+pub const APPLE_DOUBLE_MAGIC: u32 = 0x0005_1607;
+
+/// The header of an AppleSingle or AppleDouble file.
+///
+/// The header is followed by `entry_count` entries.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct AppleSingleHeader {
+    /// `APPLE_SINGLE_MAGIC` or `APPLE_DOUBLE_MAGIC`.
+    pub magic: U32Bytes<BE>,
+    /// The format version.
+    pub version: U32Bytes<BE>,
+    pub filler: [u8; 16],
+    /// The number of entries.
+    pub entry_count: U16Bytes<BE>,
+}
+
+/// An entry in an AppleSingle or AppleDouble file.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct AppleSingleEntry {
+    /// One of the `APPLE_SINGLE_ENTRY_*` values.
+    pub entry_id: U32Bytes<BE>,
+    /// The offset of the entry data from the start of the file.
+    pub offset: U32Bytes<BE>,
+    /// The length of the entry data.
+    pub length: U32Bytes<BE>,
+}
+
+/// The data fork.
+pub const APPLE_SINGLE_ENTRY_DATA_FORK: u32 = 1;
+/// The resource fork.
+pub const APPLE_SINGLE_ENTRY_RESOURCE_FORK: u32 = 2;
+/// The file name.
+pub const APPLE_SINGLE_ENTRY_REAL_NAME: u32 = 3;
+/// The Finder info, starting with the file type and creator.
+pub const APPLE_SINGLE_ENTRY_FINDER_INFO: u32 = 9;
+
+// Traceback tables.
+//
+// PowerPC compilers place a traceback table after the code of each function.
+// The table starts with a zero word, followed by an 8 byte fixed part and then
+// optional fields whose presence is indicated by flags in the fixed part.
+
+/// The traceback table has a `tb_offset` field giving the length of the function code.
+pub const TB_HAS_TBOFF: u8 = 0x20;
+/// The traceback table has `ctl_info` fields.
+pub const TB_HAS_CTL: u8 = 0x08;
+/// The traceback table has a `hand_mask` field.
+pub const TB_INT_HNDL: u8 = 0x80;
+/// The traceback table has a function name.
+pub const TB_NAME_PRESENT: u8 = 0x40;
+
 unsafe_impl_pod!(
     PEFContainerHeader,
-    PEFSectionHeader
-);
\ No newline at end of file
+    PEFSectionHeader,
+    PEFLoaderInfoHeader,
+    PEFImportedLibrary,
+    PEFImportedSymbol,
+    PEFLoaderRelocationHeader,
+    PEFExportedSymbolHashSlot,
+    PEFExportedSymbolKey,
+    PEFExportedSymbol,
+    ResourceForkHeader,
+    ResourceMapHeader,
+    ResourceType,
+    ResourceReference,
+    CFragResource,
+    CFragResourceMember,
+    VersResource,
+    SizeResource,
+    MacBinaryHeader,
+    AppleSingleHeader,
+    AppleSingleEntry,
+);
@@ -24,6 +24,7 @@ use crate::read::{
     ObjectSection, ObjectSegment, ObjectSymbol, ObjectSymbolTable, ReadRef, Relocation,
     RelocationMap, Result, SectionFlags, SectionIndex, SectionKind, SegmentFlags, SubArchitecture,
     SymbolFlags, SymbolIndex, SymbolKind, SymbolMap, SymbolMapName, SymbolScope, SymbolSection,
+    SymbolVersion,
 };
 
 /// Evaluate an expression on the contents of a file format enum.
@@ -1296,6 +1297,10 @@ impl<'data, 'file, R: ReadRef<'data>> ObjectSymbol<'data> for Symbol<'data, 'fil
         with_inner!(self.inner, SymbolInternal, |x| x.0.size())
     }
 
+    fn is_size_exact(&self) -> bool {
+        with_inner!(self.inner, SymbolInternal, |x| x.0.is_size_exact())
+    }
+
     fn kind(&self) -> SymbolKind {
         with_inner!(self.inner, SymbolInternal, |x| x.0.kind())
     }
@@ -1335,6 +1340,10 @@ impl<'data, 'file, R: ReadRef<'data>> ObjectSymbol<'data> for Symbol<'data, 'fil
     fn flags(&self) -> SymbolFlags<SectionIndex, SymbolIndex> {
         with_inner!(self.inner, SymbolInternal, |x| x.0.flags())
     }
+
+    fn version(&self) -> Result<Option<SymbolVersion<'data>>> {
+        with_inner!(self.inner, SymbolInternal, |x| x.0.version())
+    }
 }
 
 /// An iterator for the dynamic relocation entries in a [`File`].
@@ -1412,6 +1421,6 @@ impl<'data, 'file, R: ReadRef<'data>> Iterator for SectionRelocationIterator<'da
     type Item = (u64, Relocation);
 
     fn next(&mut self) -> Option<Self::Item> {
-        todo!();
+        with_inner_mut!(self.inner, SectionRelocationIteratorInternal, |x| x.next())
     }
 }
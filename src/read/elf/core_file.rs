@@ -0,0 +1,714 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::time::Duration;
+
+use crate::elf;
+use crate::endian::{self, U32Bytes, U64Bytes};
+use crate::read::{self, Bytes, Error, ReadError, ReadRef};
+
+use super::{ElfFile, FileHeader, Note, ProgramHeader};
+
+/// A view of the notes in an ELF core file.
+///
+/// This decodes the notes written by Linux and other systems for the crashed process,
+/// such as the status of each thread, the process information, the auxiliary vector
+/// and the mapped files.
+///
+/// Notes with other names or types can be accessed with [`Self::notes`].
+#[derive(Debug)]
+pub struct ElfCore<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    endian: Elf::Endian,
+    is_64: bool,
+    machine: u16,
+    notes: Vec<Note<'data, Elf>>,
+}
+
+impl<'data, Elf> ElfCore<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    /// Parse the notes in the `PT_NOTE` segments of a core file.
+    ///
+    /// Returns an error if the file type is not [`elf::ET_CORE`].
+    pub fn parse<R: ReadRef<'data>>(file: &ElfFile<'data, Elf, R>) -> read::Result<Self> {
+        let endian = file.endian();
+        let header = file.elf_header();
+        if header.e_type(endian) != elf::ET_CORE {
+            return Err(Error("ELF file is not a core file"));
+        }
+        let mut notes = Vec::new();
+        for segment in file.elf_program_headers() {
+            if let Some(mut iter) = segment.notes(endian, file.data())? {
+                while let Some(note) = iter.next()? {
+                    notes.push(note);
+                }
+            }
+        }
+        Ok(ElfCore {
+            endian,
+            is_64: header.is_type_64(),
+            machine: header.e_machine(endian),
+            notes,
+        })
+    }
+
+    /// Return all of the notes in the core file.
+    pub fn notes(&self) -> &[Note<'data, Elf>] {
+        &self.notes
+    }
+
+    fn core_notes(&self, n_type: u32) -> impl Iterator<Item = &Note<'data, Elf>> {
+        let endian = self.endian;
+        self.notes
+            .iter()
+            .filter(move |note| note.name() == elf::ELF_NOTE_CORE && note.n_type(endian) == n_type)
+    }
+
+    /// Return the status of each thread from the [`elf::NT_PRSTATUS`] notes.
+    ///
+    /// The first thread is usually the one that received the signal.
+    pub fn threads(&self) -> read::Result<Vec<CoreThread<'data, Elf>>> {
+        self.core_notes(elf::NT_PRSTATUS)
+            .map(|note| CoreThread::parse(self.endian, self.is_64, self.machine, note.desc()))
+            .collect()
+    }
+
+    /// Return the process information from the [`elf::NT_PRPSINFO`] note.
+    pub fn process_info(&self) -> read::Result<Option<CoreProcessInfo<'data>>> {
+        self.core_notes(elf::NT_PRPSINFO)
+            .next()
+            .map(|note| CoreProcessInfo::parse(self.endian, self.is_64, note.desc()))
+            .transpose()
+    }
+
+    /// Return an iterator for the auxiliary vector from the [`elf::NT_AUXV`] note.
+    pub fn auxv(&self) -> Option<AuxvIterator<'data, Elf>> {
+        self.core_notes(elf::NT_AUXV)
+            .next()
+            .map(|note| AuxvIterator {
+                endian: self.endian,
+                is_64: self.is_64,
+                data: Bytes(note.desc()),
+            })
+    }
+
+    /// Return the value of the first auxiliary vector entry with the given type.
+    ///
+    /// `a_type` is one of the `AT_*` constants.
+    pub fn auxv_value(&self, a_type: u64) -> read::Result<Option<u64>> {
+        if let Some(auxv) = self.auxv() {
+            for entry in auxv {
+                let entry = entry?;
+                if entry.a_type() == a_type {
+                    return Ok(Some(entry.a_val()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the mapped files from the [`elf::NT_FILE`] note.
+    pub fn mapped_files(&self) -> read::Result<Vec<CoreMappedFile<'data>>> {
+        match self.core_notes(elf::NT_FILE).next() {
+            Some(note) => parse_mapped_files(self.endian, self.is_64, note.desc()),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Read a word from `data`.
+///
+/// Core file note descriptors may be only 4 byte aligned, so this allows misaligned values.
+fn read_word<E: endian::Endian>(endian: E, is_64: bool, data: &mut Bytes<'_>) -> Result<u64, ()> {
+    if is_64 {
+        Ok(data.read::<U64Bytes<E>>()?.get(endian))
+    } else {
+        Ok(data.read::<U32Bytes<E>>()?.get(endian).into())
+    }
+}
+
+/// The layout of the general purpose registers in a [`elf::NT_PRSTATUS`] note.
+#[derive(Debug)]
+struct RegisterSet {
+    names: &'static [&'static str],
+    pc: usize,
+    sp: usize,
+}
+
+static REGISTERS_X86_64: RegisterSet = RegisterSet {
+    names: &[
+        "r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax", "rcx", "rdx",
+        "rsi", "rdi", "orig_rax", "rip", "cs", "eflags", "rsp", "ss", "fs_base", "gs_base", "ds",
+        "es", "fs", "gs",
+    ],
+    pc: 16,
+    sp: 19,
+};
+
+static REGISTERS_I386: RegisterSet = RegisterSet {
+    names: &[
+        "ebx", "ecx", "edx", "esi", "edi", "ebp", "eax", "ds", "es", "fs", "gs", "orig_eax", "eip",
+        "cs", "eflags", "esp", "ss",
+    ],
+    pc: 12,
+    sp: 15,
+};
+
+static REGISTERS_AARCH64: RegisterSet = RegisterSet {
+    names: &[
+        "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13",
+        "x14", "x15", "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26",
+        "x27", "x28", "x29", "x30", "sp", "pc", "pstate",
+    ],
+    pc: 32,
+    sp: 31,
+};
+
+static REGISTERS_RISCV64: RegisterSet = RegisterSet {
+    names: &[
+        "pc", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+        "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+        "t5", "t6",
+    ],
+    pc: 0,
+    sp: 2,
+};
+
+impl RegisterSet {
+    fn get(machine: u16, is_64: bool) -> Option<&'static RegisterSet> {
+        match (machine, is_64) {
+            (elf::EM_X86_64, true) => Some(&REGISTERS_X86_64),
+            (elf::EM_386, false) => Some(&REGISTERS_I386),
+            (elf::EM_AARCH64, true) => Some(&REGISTERS_AARCH64),
+            (elf::EM_RISCV, true) => Some(&REGISTERS_RISCV64),
+            _ => None,
+        }
+    }
+}
+
+/// The status of a thread in a core file.
+///
+/// This is parsed from an [`elf::NT_PRSTATUS`] note.
+///
+/// Returned by [`ElfCore::threads`].
+#[derive(Debug)]
+pub struct CoreThread<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    endian: Elf::Endian,
+    is_64: bool,
+    signal: i32,
+    signal_code: i32,
+    signal_errno: i32,
+    current_signal: i16,
+    pending_signals: u64,
+    held_signals: u64,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    user_time: (i64, i64),
+    system_time: (i64, i64),
+    registers: &'data [u8],
+    register_set: Option<&'static RegisterSet>,
+}
+
+impl<'data, Elf> CoreThread<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    fn parse(
+        endian: Elf::Endian,
+        is_64: bool,
+        machine: u16,
+        desc: &'data [u8],
+    ) -> read::Result<Self> {
+        let mut data = Bytes(desc);
+        let mut thread = if is_64 {
+            let status = data
+                .read::<elf::Prstatus64<Elf::Endian>>()
+                .read_error("ELF NT_PRSTATUS note is too short")?;
+            CoreThread {
+                endian,
+                is_64,
+                signal: status.pr_info.si_signo.get(endian),
+                signal_code: status.pr_info.si_code.get(endian),
+                signal_errno: status.pr_info.si_errno.get(endian),
+                current_signal: status.pr_cursig.get(endian),
+                pending_signals: status.pr_sigpend.get(endian),
+                held_signals: status.pr_sighold.get(endian),
+                pid: status.pr_pid.get(endian),
+                ppid: status.pr_ppid.get(endian),
+                pgrp: status.pr_pgrp.get(endian),
+                sid: status.pr_sid.get(endian),
+                user_time: (
+                    status.pr_utime.tv_sec.get(endian),
+                    status.pr_utime.tv_usec.get(endian),
+                ),
+                system_time: (
+                    status.pr_stime.tv_sec.get(endian),
+                    status.pr_stime.tv_usec.get(endian),
+                ),
+                registers: &[],
+                register_set: None,
+            }
+        } else {
+            let status = data
+                .read::<elf::Prstatus32<Elf::Endian>>()
+                .read_error("ELF NT_PRSTATUS note is too short")?;
+            CoreThread {
+                endian,
+                is_64,
+                signal: status.pr_info.si_signo.get(endian),
+                signal_code: status.pr_info.si_code.get(endian),
+                signal_errno: status.pr_info.si_errno.get(endian),
+                current_signal: status.pr_cursig.get(endian),
+                pending_signals: status.pr_sigpend.get(endian).into(),
+                held_signals: status.pr_sighold.get(endian).into(),
+                pid: status.pr_pid.get(endian),
+                ppid: status.pr_ppid.get(endian),
+                pgrp: status.pr_pgrp.get(endian),
+                sid: status.pr_sid.get(endian),
+                user_time: (
+                    status.pr_utime.tv_sec.get(endian).into(),
+                    status.pr_utime.tv_usec.get(endian).into(),
+                ),
+                system_time: (
+                    status.pr_stime.tv_sec.get(endian).into(),
+                    status.pr_stime.tv_usec.get(endian).into(),
+                ),
+                registers: &[],
+                register_set: None,
+            }
+        };
+
+        // The register set is followed by `pr_fpvalid`, which is an int that
+        // is padded to the word size.
+        let word_size = if is_64 { 8 } else { 4 };
+        let len = data
+            .len()
+            .checked_sub(word_size)
+            .read_error("ELF NT_PRSTATUS note is too short")?;
+        let len = len - len % word_size;
+        thread.registers = data.read_bytes(len).map(|bytes| bytes.0).unwrap_or(&[]);
+        thread.register_set = RegisterSet::get(machine, is_64)
+            .filter(|set| set.names.len() * word_size == thread.registers.len());
+        Ok(thread)
+    }
+
+    /// Return the signal number from the signal information.
+    pub fn signal(&self) -> i32 {
+        self.signal
+    }
+
+    /// Return the signal code from the signal information.
+    pub fn signal_code(&self) -> i32 {
+        self.signal_code
+    }
+
+    /// Return the errno value from the signal information.
+    pub fn signal_errno(&self) -> i32 {
+        self.signal_errno
+    }
+
+    /// Return the current signal.
+    pub fn current_signal(&self) -> i16 {
+        self.current_signal
+    }
+
+    /// Return the set of pending signals.
+    pub fn pending_signals(&self) -> u64 {
+        self.pending_signals
+    }
+
+    /// Return the set of held signals.
+    pub fn held_signals(&self) -> u64 {
+        self.held_signals
+    }
+
+    /// Return the thread ID.
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// Return the parent process ID.
+    pub fn ppid(&self) -> i32 {
+        self.ppid
+    }
+
+    /// Return the process group ID.
+    pub fn pgrp(&self) -> i32 {
+        self.pgrp
+    }
+
+    /// Return the session ID.
+    pub fn sid(&self) -> i32 {
+        self.sid
+    }
+
+    /// Return the user time.
+    ///
+    /// Negative times are treated as zero.
+    pub fn user_time(&self) -> Duration {
+        timeval_duration(self.user_time)
+    }
+
+    /// Return the system time.
+    ///
+    /// Negative times are treated as zero.
+    pub fn system_time(&self) -> Duration {
+        timeval_duration(self.system_time)
+    }
+
+    /// Return the raw bytes of the general purpose register set.
+    pub fn register_data(&self) -> &'data [u8] {
+        self.registers
+    }
+
+    /// Return the number of general purpose registers.
+    ///
+    /// Each register is the size of a word.
+    pub fn register_count(&self) -> usize {
+        self.registers.len() / self.word_size()
+    }
+
+    /// Return the value of the general purpose register at the given index.
+    pub fn register(&self, index: usize) -> Option<u64> {
+        let offset = index.checked_mul(self.word_size())?;
+        let mut data = Bytes(self.registers);
+        data.skip(offset).ok()?;
+        read_word(self.endian, self.is_64, &mut data).ok()
+    }
+
+    /// Return the name of the general purpose register at the given index.
+    ///
+    /// This is the name used by the Linux `user_regs_struct` for the machine.
+    /// Returns `None` if the register set of the machine is not known.
+    /// x86-64, i386, AArch64 and 64-bit RISC-V are supported.
+    pub fn register_name(&self, index: usize) -> Option<&'static str> {
+        self.register_set?.names.get(index).copied()
+    }
+
+    /// Return the program counter.
+    ///
+    /// Returns `None` if the register set of the machine is not known.
+    pub fn pc(&self) -> Option<u64> {
+        self.register(self.register_set?.pc)
+    }
+
+    /// Return the stack pointer.
+    ///
+    /// Returns `None` if the register set of the machine is not known.
+    pub fn sp(&self) -> Option<u64> {
+        self.register(self.register_set?.sp)
+    }
+
+    fn word_size(&self) -> usize {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+}
+
+fn timeval_duration((sec, usec): (i64, i64)) -> Duration {
+    let sec = u64::try_from(sec).unwrap_or(0);
+    let usec = u64::try_from(usec).unwrap_or(0);
+    Duration::from_secs(sec).saturating_add(Duration::from_micros(usec))
+}
+
+/// The process information in a core file.
+///
+/// This is parsed from an [`elf::NT_PRPSINFO`] note.
+///
+/// Returned by [`ElfCore::process_info`].
+#[derive(Debug)]
+pub struct CoreProcessInfo<'data> {
+    state: u8,
+    state_name: u8,
+    zombie: u8,
+    nice: i8,
+    flags: u64,
+    uid: u32,
+    gid: u32,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    file_name: &'data [u8],
+    arguments: &'data [u8],
+}
+
+impl<'data> CoreProcessInfo<'data> {
+    fn parse<E: endian::Endian>(endian: E, is_64: bool, desc: &'data [u8]) -> read::Result<Self> {
+        let data = Bytes(desc);
+        if is_64 {
+            let info = data
+                .read_at::<elf::Prpsinfo64<E>>(0)
+                .read_error("ELF NT_PRPSINFO note is too short")?;
+            Ok(CoreProcessInfo {
+                state: info.pr_state,
+                state_name: info.pr_sname,
+                zombie: info.pr_zomb,
+                nice: info.pr_nice as i8,
+                flags: info.pr_flag.get(endian),
+                uid: info.pr_uid.get(endian),
+                gid: info.pr_gid.get(endian),
+                pid: info.pr_pid.get(endian),
+                ppid: info.pr_ppid.get(endian),
+                pgrp: info.pr_pgrp.get(endian),
+                sid: info.pr_sid.get(endian),
+                file_name: until_null(&info.pr_fname),
+                arguments: until_null(&info.pr_psargs),
+            })
+        } else {
+            let info = data
+                .read_at::<elf::Prpsinfo32<E>>(0)
+                .read_error("ELF NT_PRPSINFO note is too short")?;
+            Ok(CoreProcessInfo {
+                state: info.pr_state,
+                state_name: info.pr_sname,
+                zombie: info.pr_zomb,
+                nice: info.pr_nice as i8,
+                flags: info.pr_flag.get(endian).into(),
+                uid: info.pr_uid.get(endian).into(),
+                gid: info.pr_gid.get(endian).into(),
+                pid: info.pr_pid.get(endian),
+                ppid: info.pr_ppid.get(endian),
+                pgrp: info.pr_pgrp.get(endian),
+                sid: info.pr_sid.get(endian),
+                file_name: until_null(&info.pr_fname),
+                arguments: until_null(&info.pr_psargs),
+            })
+        }
+    }
+
+    /// Return the numeric process state.
+    pub fn state(&self) -> u8 {
+        self.state
+    }
+
+    /// Return the character for the process state, such as `b'R'` or `b'S'`.
+    pub fn state_name(&self) -> u8 {
+        self.state_name
+    }
+
+    /// Return true if the process is a zombie.
+    pub fn is_zombie(&self) -> bool {
+        self.zombie != 0
+    }
+
+    /// Return the nice value.
+    pub fn nice(&self) -> i8 {
+        self.nice
+    }
+
+    /// Return the process flags.
+    pub fn flags(&self) -> u64 {
+        self.flags
+    }
+
+    /// Return the user ID.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Return the group ID.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Return the process ID.
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// Return the parent process ID.
+    pub fn ppid(&self) -> i32 {
+        self.ppid
+    }
+
+    /// Return the process group ID.
+    pub fn pgrp(&self) -> i32 {
+        self.pgrp
+    }
+
+    /// Return the session ID.
+    pub fn sid(&self) -> i32 {
+        self.sid
+    }
+
+    /// Return the file name of the executable.
+    ///
+    /// This is truncated to 15 bytes.
+    pub fn file_name(&self) -> &'data [u8] {
+        self.file_name
+    }
+
+    /// Return the initial part of the argument list.
+    ///
+    /// The arguments are separated by spaces, and truncated to 79 bytes.
+    pub fn arguments(&self) -> &'data [u8] {
+        self.arguments
+    }
+}
+
+fn until_null(data: &[u8]) -> &[u8] {
+    match memchr::memchr(0, data) {
+        Some(end) => &data[..end],
+        None => data,
+    }
+}
+
+/// An iterator for the entries in an [`elf::NT_AUXV`] note.
+///
+/// Iteration stops at the [`elf::AT_NULL`] entry.
+///
+/// Returned by [`ElfCore::auxv`].
+#[derive(Debug)]
+pub struct AuxvIterator<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    endian: Elf::Endian,
+    is_64: bool,
+    data: Bytes<'data>,
+}
+
+impl<'data, Elf> AuxvIterator<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    /// Returns the next entry.
+    pub fn next(&mut self) -> read::Result<Option<AuxvEntry>> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let result = self.parse();
+        if !matches!(result, Ok(Some(_))) {
+            self.data = Bytes(&[]);
+        }
+        result
+    }
+
+    fn parse(&mut self) -> read::Result<Option<AuxvEntry>> {
+        let a_type = read_word(self.endian, self.is_64, &mut self.data)
+            .read_error("Invalid ELF auxiliary vector entry")?;
+        let a_val = read_word(self.endian, self.is_64, &mut self.data)
+            .read_error("Invalid ELF auxiliary vector entry")?;
+        if a_type == elf::AT_NULL {
+            return Ok(None);
+        }
+        Ok(Some(AuxvEntry { a_type, a_val }))
+    }
+}
+
+impl<'data, Elf: FileHeader> Iterator for AuxvIterator<'data, Elf> {
+    type Item = read::Result<AuxvEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// An entry in an [`elf::NT_AUXV`] note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuxvEntry {
+    a_type: u64,
+    a_val: u64,
+}
+
+impl AuxvEntry {
+    /// Return the entry type.
+    ///
+    /// This is one of the `AT_*` constants.
+    pub fn a_type(&self) -> u64 {
+        self.a_type
+    }
+
+    /// Return the entry value.
+    pub fn a_val(&self) -> u64 {
+        self.a_val
+    }
+}
+
+/// A file mapped into memory in a core file.
+///
+/// Returned by [`ElfCore::mapped_files`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoreMappedFile<'data> {
+    start: u64,
+    end: u64,
+    file_offset: u64,
+    path: &'data [u8],
+}
+
+impl<'data> CoreMappedFile<'data> {
+    /// Return the start address of the mapping.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Return the end address of the mapping.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Return the offset in bytes of the start of the mapping in the file.
+    pub fn file_offset(&self) -> u64 {
+        self.file_offset
+    }
+
+    /// Return the path of the file.
+    pub fn path(&self) -> &'data [u8] {
+        self.path
+    }
+}
+
+/// Parse the descriptor of an [`elf::NT_FILE`] note.
+///
+/// This consists of the number of mappings and the page size, followed by the
+/// start, end and page offset of each mapping, and then the null terminated paths.
+fn parse_mapped_files<E: endian::Endian>(
+    endian: E,
+    is_64: bool,
+    desc: &[u8],
+) -> read::Result<Vec<CoreMappedFile<'_>>> {
+    let mut data = Bytes(desc);
+    let count = read_word(endian, is_64, &mut data).read_error("ELF NT_FILE note is too short")?;
+    let page_size =
+        read_word(endian, is_64, &mut data).read_error("ELF NT_FILE note is too short")?;
+    let word_size = if is_64 { 8 } else { 4 };
+    let ranges_size = usize::try_from(count)
+        .ok()
+        .and_then(|count| count.checked_mul(3 * word_size))
+        .read_error("Invalid ELF NT_FILE count")?;
+    let mut ranges = data
+        .read_bytes(ranges_size)
+        .read_error("Invalid ELF NT_FILE count")?;
+    let mut files = Vec::with_capacity(ranges_size / (3 * word_size));
+    for _ in 0..count {
+        let start =
+            read_word(endian, is_64, &mut ranges).read_error("Invalid ELF NT_FILE entry")?;
+        let end = read_word(endian, is_64, &mut ranges).read_error("Invalid ELF NT_FILE entry")?;
+        let page_offset =
+            read_word(endian, is_64, &mut ranges).read_error("Invalid ELF NT_FILE entry")?;
+        let file_offset = page_offset
+            .checked_mul(page_size)
+            .read_error("Invalid ELF NT_FILE page offset")?;
+        let path = data.read_string().read_error("Invalid ELF NT_FILE path")?;
+        files.push(CoreMappedFile {
+            start,
+            end,
+            file_offset,
+            path,
+        });
+    }
+    Ok(files)
+}
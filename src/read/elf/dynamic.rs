@@ -1,10 +1,16 @@
-use core::convert::TryInto;
+use core::convert::{TryFrom, TryInto};
 use core::fmt::Debug;
+use core::mem;
 
 use crate::elf;
 use crate::endian;
-use crate::pod::Pod;
-use crate::read::{ReadError, Result, StringTable};
+use crate::pod::{self, Pod};
+use crate::read::{Error, ReadError, ReadRef, Result, StringTable};
+
+use super::{
+    AndroidRelocationIterator, DynamicRelocations, ElfRelaIterator, FileHeader, GnuHashTable,
+    HashTable, ProgramHeader, SymbolTable, VerdefIterator, VerneedIterator, VersionTable,
+};
 
 /// A trait for generic access to [`elf::Dyn32`] and [`elf::Dyn64`].
 #[allow(missing_docs)]
@@ -74,6 +80,10 @@ pub trait Dyn: Debug + Pod {
                 | elf::DT_INIT_ARRAY
                 | elf::DT_PREINIT_ARRAY
                 | elf::DT_SYMTAB_SHNDX
+                | elf::DT_RELR
+                | elf::DT_ANDROID_REL
+                | elf::DT_ANDROID_RELA
+                | elf::DT_ANDROID_RELR
                 | elf::DT_VERDEF
                 | elf::DT_VERNEED
                 | elf::DT_VERSYM
@@ -115,3 +125,441 @@ impl<Endian: endian::Endian> Dyn for elf::Dyn64<Endian> {
         self.d_val.get(endian)
     }
 }
+
+/// The dynamic linking tables located via the entries of a [`elf::PT_DYNAMIC`] segment.
+///
+/// This is used to access the dynamic symbols, versions and relocations of
+/// files that have no section headers.
+///
+/// The addresses in the dynamic entries are mapped to file offsets using the
+/// [`elf::PT_LOAD`] segments, so the entries must contain unrelocated addresses.
+#[derive(Debug)]
+pub struct DynamicTable<'data, Elf: FileHeader, R = &'data [u8]>
+where
+    R: ReadRef<'data>,
+{
+    dynamic: &'data [Elf::Dyn],
+    symbols: SymbolTable<'data, Elf, R>,
+    hash: Option<HashTable<'data, Elf>>,
+    gnu_hash: Option<GnuHashTable<'data, Elf>>,
+    versyms: &'data [elf::Versym<Elf::Endian>],
+    verdefs: Option<VerdefIterator<'data, Elf>>,
+    verneeds: Option<VerneedIterator<'data, Elf>>,
+    rel: &'data [Elf::Rel],
+    rela: &'data [Elf::Rela],
+    plt_rel: &'data [Elf::Rel],
+    plt_rela: &'data [Elf::Rela],
+    relr: &'data [Elf::Relr],
+    android_rel: &'data [u8],
+    android_rela: &'data [u8],
+}
+
+impl<'data, Elf: FileHeader, R: ReadRef<'data>> Default for DynamicTable<'data, Elf, R> {
+    fn default() -> Self {
+        DynamicTable {
+            dynamic: &[],
+            symbols: SymbolTable::default(),
+            hash: None,
+            gnu_hash: None,
+            versyms: &[],
+            verdefs: None,
+            verneeds: None,
+            rel: &[],
+            rela: &[],
+            plt_rel: &[],
+            plt_rela: &[],
+            relr: &[],
+            android_rel: &[],
+            android_rela: &[],
+        }
+    }
+}
+
+impl<'data, Elf: FileHeader, R: ReadRef<'data>> DynamicTable<'data, Elf, R> {
+    /// Parse the dynamic tables using the given program headers.
+    ///
+    /// Returns an empty table if there is no [`elf::PT_DYNAMIC`] segment.
+    ///
+    /// The number of dynamic symbols is determined from the [`elf::DT_HASH`]
+    /// or [`elf::DT_GNU_HASH`] table. If neither is present, then the symbol
+    /// table is assumed to end at the start of the string table.
+    pub fn parse(
+        endian: Elf::Endian,
+        data: R,
+        segments: &'data [Elf::ProgramHeader],
+    ) -> Result<Self> {
+        let mut dynamic = None;
+        for segment in segments {
+            if let Some(entries) = segment.dynamic(endian, data)? {
+                dynamic = Some(entries);
+                break;
+            }
+        }
+        let dynamic = match dynamic {
+            Some(dynamic) => dynamic,
+            None => return Ok(DynamicTable::default()),
+        };
+
+        let mut symtab = None;
+        let mut strtab = None;
+        let mut strsz = 0;
+        let mut hash = None;
+        let mut gnu_hash = None;
+        let mut versym = None;
+        let mut verdef = None;
+        let mut verneed = None;
+        let mut rel = None;
+        let mut relsz = 0;
+        let mut rela = None;
+        let mut relasz = 0;
+        let mut jmprel = None;
+        let mut pltrelsz = 0;
+        let mut pltrel = 0;
+        let mut relr = None;
+        let mut relrsz = 0;
+        let mut android_rel = None;
+        let mut android_relsz = 0;
+        let mut android_rela = None;
+        let mut android_relasz = 0;
+        for d in dynamic {
+            let tag = match d.tag32(endian) {
+                Some(tag) => tag,
+                None => continue,
+            };
+            let val: u64 = d.d_val(endian).into();
+            match tag {
+                elf::DT_NULL => break,
+                elf::DT_SYMTAB => symtab = Some(val),
+                elf::DT_STRTAB => strtab = Some(val),
+                elf::DT_STRSZ => strsz = val,
+                elf::DT_HASH => hash = Some(val),
+                elf::DT_GNU_HASH => gnu_hash = Some(val),
+                elf::DT_VERSYM => versym = Some(val),
+                elf::DT_VERDEF => verdef = Some(val),
+                elf::DT_VERNEED => verneed = Some(val),
+                elf::DT_REL => rel = Some(val),
+                elf::DT_RELSZ => relsz = val,
+                elf::DT_RELA => rela = Some(val),
+                elf::DT_RELASZ => relasz = val,
+                elf::DT_JMPREL => jmprel = Some(val),
+                elf::DT_PLTRELSZ => pltrelsz = val,
+                elf::DT_PLTREL => pltrel = val,
+                elf::DT_RELR | elf::DT_ANDROID_RELR => relr = Some(val),
+                elf::DT_RELRSZ | elf::DT_ANDROID_RELRSZ => relrsz = val,
+                elf::DT_ANDROID_REL => android_rel = Some(val),
+                elf::DT_ANDROID_RELSZ => android_relsz = val,
+                elf::DT_ANDROID_RELA => android_rela = Some(val),
+                elf::DT_ANDROID_RELASZ => android_relasz = val,
+                _ => {}
+            }
+        }
+
+        let range = |address| address_range::<Elf>(endian, segments, address);
+        let read_slice = |address, size| -> Result<&'data [u8]> {
+            let (offset, _) = range(address).read_error("Invalid ELF dynamic table address")?;
+            data.read_bytes_at(offset, size)
+                .read_error("Invalid ELF dynamic table size")
+        };
+
+        let hash = match hash {
+            Some(address) => {
+                let (offset, size) = range(address).read_error("Invalid ELF DT_HASH address")?;
+                let hash_data = data
+                    .read_bytes_at(offset, size)
+                    .read_error("Invalid ELF DT_HASH address")?;
+                Some(HashTable::parse(endian, hash_data)?)
+            }
+            None => None,
+        };
+        let gnu_hash = match gnu_hash {
+            Some(address) => {
+                let (offset, size) =
+                    range(address).read_error("Invalid ELF DT_GNU_HASH address")?;
+                let hash_data = data
+                    .read_bytes_at(offset, size)
+                    .read_error("Invalid ELF DT_GNU_HASH address")?;
+                Some(GnuHashTable::parse(endian, hash_data)?)
+            }
+            None => None,
+        };
+
+        let strings = match strtab {
+            Some(address) => {
+                let (offset, _) = range(address).read_error("Invalid ELF DT_STRTAB address")?;
+                let end = offset
+                    .checked_add(strsz)
+                    .read_error("Invalid ELF DT_STRSZ value")?;
+                StringTable::new(data, offset, end)
+            }
+            None => StringTable::default(),
+        };
+
+        let symbols = match symtab {
+            Some(address) => {
+                let count = if let Some(hash) = &hash {
+                    hash.symbol_table_length() as usize
+                } else if let Some(gnu_hash) = &gnu_hash {
+                    gnu_hash
+                        .symbol_table_length(endian)
+                        .unwrap_or_else(|| gnu_hash.symbol_base()) as usize
+                } else {
+                    // Linkers usually place the string table immediately after
+                    // the symbol table.
+                    let end = strtab
+                        .filter(|end| *end > address)
+                        .read_error("Missing ELF DT_HASH and DT_GNU_HASH")?;
+                    ((end - address) / mem::size_of::<Elf::Sym>() as u64) as usize
+                };
+                let (offset, _) = range(address).read_error("Invalid ELF DT_SYMTAB address")?;
+                let symbols = data
+                    .read_slice_at(offset, count)
+                    .read_error("Invalid ELF dynamic symbol table size")?;
+                SymbolTable::from_dynamic(symbols, strings)
+            }
+            None => SymbolTable::default(),
+        };
+
+        let versyms = match versym {
+            Some(address) => {
+                let size = (symbols.len() * mem::size_of::<elf::Versym<Elf::Endian>>()) as u64;
+                let versyms = read_slice(address, size)?;
+                pod::slice_from_all_bytes(versyms)
+                    .ok()
+                    .read_error("Invalid ELF DT_VERSYM data")?
+            }
+            None => &[],
+        };
+        let verdefs = match verdef {
+            Some(address) => {
+                let (offset, size) = range(address).read_error("Invalid ELF DT_VERDEF address")?;
+                let verdefs = data
+                    .read_bytes_at(offset, size)
+                    .read_error("Invalid ELF DT_VERDEF address")?;
+                Some(VerdefIterator::new(endian, verdefs))
+            }
+            None => None,
+        };
+        let verneeds = match verneed {
+            Some(address) => {
+                let (offset, size) = range(address).read_error("Invalid ELF DT_VERNEED address")?;
+                let verneeds = data
+                    .read_bytes_at(offset, size)
+                    .read_error("Invalid ELF DT_VERNEED address")?;
+                Some(VerneedIterator::new(endian, verneeds))
+            }
+            None => None,
+        };
+
+        fn table<'data, T: Pod>(data: &'data [u8]) -> Result<&'data [T]> {
+            pod::slice_from_all_bytes(data)
+                .ok()
+                .read_error("Invalid ELF dynamic relocation table size")
+        }
+        let rel = match rel {
+            Some(address) => table(read_slice(address, relsz)?)?,
+            None => &[],
+        };
+        let rela = match rela {
+            Some(address) => table(read_slice(address, relasz)?)?,
+            None => &[],
+        };
+        let (plt_rel, plt_rela) = match jmprel {
+            Some(address) => {
+                let plt = read_slice(address, pltrelsz)?;
+                match u32::try_from(pltrel) {
+                    Ok(elf::DT_REL) => (table(plt)?, &[][..]),
+                    Ok(elf::DT_RELA) => (&[][..], table(plt)?),
+                    _ => return Err(Error("Invalid ELF DT_PLTREL value")),
+                }
+            }
+            None => (&[][..], &[][..]),
+        };
+        let relr = match relr {
+            Some(address) => table(read_slice(address, relrsz)?)?,
+            None => &[],
+        };
+        let android_rel = match android_rel {
+            Some(address) => read_slice(address, android_relsz)?,
+            None => &[],
+        };
+        let android_rela = match android_rela {
+            Some(address) => read_slice(address, android_relasz)?,
+            None => &[],
+        };
+
+        Ok(DynamicTable {
+            dynamic,
+            symbols,
+            hash,
+            gnu_hash,
+            versyms,
+            verdefs,
+            verneeds,
+            rel,
+            rela,
+            plt_rel,
+            plt_rela,
+            relr,
+            android_rel,
+            android_rela,
+        })
+    }
+
+    /// Return true if there was no dynamic segment.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dynamic.is_empty()
+    }
+
+    /// Return the entries of the dynamic segment.
+    #[inline]
+    pub fn dynamic(&self) -> &'data [Elf::Dyn] {
+        self.dynamic
+    }
+
+    /// Return the table located by [`elf::DT_SYMTAB`] and [`elf::DT_STRTAB`].
+    ///
+    /// The section indices of this table are always 0.
+    #[inline]
+    pub fn symbols(&self) -> &SymbolTable<'data, Elf, R> {
+        &self.symbols
+    }
+
+    /// Return the table located by [`elf::DT_HASH`].
+    #[inline]
+    pub fn hash(&self) -> Option<&HashTable<'data, Elf>> {
+        self.hash.as_ref()
+    }
+
+    /// Return the table located by [`elf::DT_GNU_HASH`].
+    #[inline]
+    pub fn gnu_hash(&self) -> Option<&GnuHashTable<'data, Elf>> {
+        self.gnu_hash.as_ref()
+    }
+
+    /// Return the entries located by [`elf::DT_VERSYM`].
+    #[inline]
+    pub fn gnu_versym(&self) -> &'data [elf::Versym<Elf::Endian>] {
+        self.versyms
+    }
+
+    /// Return an iterator for the entries located by [`elf::DT_VERDEF`].
+    #[inline]
+    pub fn gnu_verdef(&self) -> Option<VerdefIterator<'data, Elf>> {
+        self.verdefs.clone()
+    }
+
+    /// Return an iterator for the entries located by [`elf::DT_VERNEED`].
+    #[inline]
+    pub fn gnu_verneed(&self) -> Option<VerneedIterator<'data, Elf>> {
+        self.verneeds.clone()
+    }
+
+    /// Parse the version tables.
+    ///
+    /// Returns `Ok(None)` if there is no [`elf::DT_VERSYM`] entry.
+    /// Returns `Err` for invalid values.
+    pub fn versions(&self, endian: Elf::Endian) -> Result<Option<VersionTable<'data, Elf>>> {
+        if self.versyms.is_empty() {
+            return Ok(None);
+        }
+        VersionTable::parse(
+            endian,
+            self.versyms,
+            self.gnu_verdef(),
+            self.gnu_verneed(),
+            self.symbols.strings(),
+        )
+        .map(Some)
+    }
+
+    /// Return the relocations located by [`elf::DT_REL`].
+    #[inline]
+    pub fn rel(&self) -> &'data [Elf::Rel] {
+        self.rel
+    }
+
+    /// Return the relocations located by [`elf::DT_RELA`].
+    #[inline]
+    pub fn rela(&self) -> &'data [Elf::Rela] {
+        self.rela
+    }
+
+    /// Return the relocations located by [`elf::DT_JMPREL`] if [`elf::DT_PLTREL`]
+    /// is [`elf::DT_REL`].
+    #[inline]
+    pub fn plt_rel(&self) -> &'data [Elf::Rel] {
+        self.plt_rel
+    }
+
+    /// Return the relocations located by [`elf::DT_JMPREL`] if [`elf::DT_PLTREL`]
+    /// is [`elf::DT_RELA`].
+    #[inline]
+    pub fn plt_rela(&self) -> &'data [Elf::Rela] {
+        self.plt_rela
+    }
+
+    /// Return the relocations located by [`elf::DT_RELR`] or [`elf::DT_ANDROID_RELR`].
+    #[inline]
+    pub fn relr(&self) -> &'data [Elf::Relr] {
+        self.relr
+    }
+
+    /// Return the packed relocation data located by [`elf::DT_ANDROID_REL`].
+    ///
+    /// Use [`AndroidRelocationIterator`] to decode this.
+    #[inline]
+    pub fn android_rel(&self) -> &'data [u8] {
+        self.android_rel
+    }
+
+    /// Return the packed relocation data located by [`elf::DT_ANDROID_RELA`].
+    ///
+    /// Use [`AndroidRelocationIterator`] to decode this.
+    #[inline]
+    pub fn android_rela(&self) -> &'data [u8] {
+        self.android_rela
+    }
+
+    /// Return the `index`th relocation table, in the order `DT_REL`, `DT_RELA`,
+    /// `DT_JMPREL`, `DT_ANDROID_REL`, `DT_ANDROID_RELA`.
+    ///
+    /// Invalid packed relocations are skipped.
+    pub(super) fn relocations(&self, index: usize) -> Option<DynamicRelocations<'data, Elf>> {
+        let android =
+            |data: &'data [u8], is_rela| AndroidRelocationIterator::new(data, is_rela).ok();
+        Some(match index {
+            0 => DynamicRelocations::Rela(ElfRelaIterator::Rel(self.rel.iter())),
+            1 => DynamicRelocations::Rela(ElfRelaIterator::Rela(self.rela.iter())),
+            2 if !self.plt_rel.is_empty() => {
+                DynamicRelocations::Rela(ElfRelaIterator::Rel(self.plt_rel.iter()))
+            }
+            2 => DynamicRelocations::Rela(ElfRelaIterator::Rela(self.plt_rela.iter())),
+            3 => DynamicRelocations::Android(android(self.android_rel, false)),
+            4 => DynamicRelocations::Android(android(self.android_rela, true)),
+            _ => return None,
+        })
+    }
+}
+
+/// Find the file offset of the given address, and the size of the remaining
+/// file data in the segment containing it.
+fn address_range<'data, Elf: FileHeader>(
+    endian: Elf::Endian,
+    segments: &'data [Elf::ProgramHeader],
+    address: u64,
+) -> Option<(u64, u64)> {
+    for segment in segments {
+        if segment.p_type(endian) != elf::PT_LOAD {
+            continue;
+        }
+        let vaddr = segment.p_vaddr(endian).into();
+        let (offset, size) = segment.file_range(endian);
+        if let Some(delta) = address.checked_sub(vaddr) {
+            if delta < size {
+                return Some((offset + delta, size - delta));
+            }
+        }
+    }
+    None
+}
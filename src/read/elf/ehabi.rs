@@ -0,0 +1,674 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::elf;
+use crate::endian::U32Bytes;
+use crate::read::{
+    self, Bytes, Error, Object, ObjectSection, ObjectSymbol, ReadError, ReadRef, RelocationFlags,
+    RelocationTarget, SectionIndex, SymbolIndex, SymbolSection,
+};
+
+use super::{ElfFile, ElfSection, FileHeader, SectionHeader};
+
+/// An ARM EHABI exception index table.
+///
+/// This is read from a `.ARM.exidx` section, which contains one entry for each
+/// function. Each entry gives the start address of the function, and either
+/// the unwind instructions, or a reference to them in a `.ARM.extab` section.
+///
+/// Relocations are applied when reading a table in a relocatable object.
+///
+/// Returned by [`ElfSection::arm_exidx`].
+#[derive(Debug)]
+pub struct ArmExidxTable<'data, 'file, Elf, R = &'data [u8]>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    file: &'file ElfFile<'data, Elf, R>,
+    index: SectionIndex,
+    address: u64,
+    data: Bytes<'data>,
+    relocations: Option<Vec<Prel31Relocation>>,
+    /// The address, end address, and index of the sections that may contain
+    /// unwind tables, sorted by address.
+    sections: Vec<(u64, u64, SectionIndex)>,
+}
+
+/// A `R_ARM_PREL31` relocation in a relocatable object.
+#[derive(Debug, Clone, Copy)]
+struct Prel31Relocation {
+    section: SectionIndex,
+    offset: u64,
+    symbol: SymbolIndex,
+    addend: Option<i64>,
+}
+
+impl<'data, 'file, Elf, R> ArmExidxTable<'data, 'file, Elf, R>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    /// Parse the exception index table in the given section.
+    pub(super) fn parse(section: &ElfSection<'data, 'file, Elf, R>) -> read::Result<Self> {
+        let file = section.file;
+        let endian = file.endian;
+        let data = section
+            .section
+            .data(endian, file.data)
+            .read_error("Invalid ARM EHABI index table offset or size")?;
+        if data.len() % 8 != 0 {
+            return Err(Error("Invalid ARM EHABI index table size"));
+        }
+
+        let relocations = if file.header.e_type(endian) == elf::ET_REL {
+            // The unwind tables may contain relocations for personality routines,
+            // so also read the relocations for the sections that they are in.
+            let mut relocations = Vec::new();
+            prel31_relocations(section, &mut relocations);
+            let mut sections = relocations
+                .iter()
+                .filter(|relocation| relocation.offset % 8 == 4)
+                .filter_map(|relocation| {
+                    let symbol = file.symbol_by_index(relocation.symbol).ok()?;
+                    match symbol.section() {
+                        SymbolSection::Section(index) if index != section.index => Some(index),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            sections.sort_unstable_by_key(|index| index.0);
+            sections.dedup();
+            for index in sections {
+                prel31_relocations(&file.section_by_index(index)?, &mut relocations);
+            }
+            relocations
+                .sort_unstable_by_key(|relocation| (relocation.section.0, relocation.offset));
+            Some(relocations)
+        } else {
+            None
+        };
+
+        let mut sections = file
+            .sections
+            .enumerate()
+            .filter(|(_, section)| {
+                section.sh_flags(endian).into() & u64::from(elf::SHF_ALLOC) != 0
+                    && section.sh_type(endian) != elf::SHT_NOBITS
+            })
+            .filter_map(|(index, section)| {
+                let address = section.sh_addr(endian).into();
+                let end = address.checked_add(section.sh_size(endian).into())?;
+                (end > address).then_some((address, end, index))
+            })
+            .collect::<Vec<_>>();
+        sections.sort_unstable_by_key(|(address, _, index)| (*address, index.0));
+
+        Ok(ArmExidxTable {
+            file,
+            index: section.index,
+            address: section.section.sh_addr(endian).into(),
+            data: Bytes(data),
+            relocations,
+            sections,
+        })
+    }
+
+    /// Return the number of entries in the table.
+    pub fn len(&self) -> usize {
+        self.data.len() / 8
+    }
+
+    /// Return true if the table has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Return an iterator over the entries in the table.
+    pub fn entries<'table>(&'table self) -> ArmExidxIterator<'data, 'file, 'table, Elf, R> {
+        ArmExidxIterator {
+            table: self,
+            index: 0,
+        }
+    }
+
+    /// Return the entry at the given index.
+    pub fn entry(&self, index: usize) -> read::Result<ArmExidxEntry<'data, Elf>> {
+        let offset = index
+            .checked_mul(8)
+            .read_error("Invalid ARM EHABI index table index")?;
+        let function = self.word(offset)?;
+        let unwind = self.word(offset + 4)?;
+        let function = self.target(self.index, self.address, offset as u64, function);
+        let unwind = self.unwind(offset as u64 + 4, unwind)?;
+        Ok(ArmExidxEntry { function, unwind })
+    }
+
+    /// Find the entry for the function containing the given address.
+    ///
+    /// This requires the entries to be sorted by address, which is normally done
+    /// by the linker. Returns an error for relocatable objects.
+    pub fn find(&self, address: u64) -> read::Result<Option<ArmExidxEntry<'data, Elf>>> {
+        if self.relocations.is_some() {
+            return Err(Error(
+                "Unsupported ARM EHABI index table search in relocatable object",
+            ));
+        }
+        let mut low = 0;
+        let mut high = self.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let offset = mid * 8;
+            let word = self.word(offset)?;
+            let function = self
+                .address
+                .wrapping_add(offset as u64)
+                .wrapping_add(prel31(word) as u64);
+            if function <= address {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        if low == 0 {
+            return Ok(None);
+        }
+        self.entry(low - 1).map(Some)
+    }
+
+    fn word(&self, offset: usize) -> read::Result<u32> {
+        self.data
+            .read_at::<U32Bytes<Elf::Endian>>(offset)
+            .read_error("Invalid ARM EHABI index table index")
+            .map(|word| word.get(self.file.endian))
+    }
+
+    fn relocation(&self, section: SectionIndex, offset: u64) -> Option<&Prel31Relocation> {
+        let relocations = self.relocations.as_ref()?;
+        let index = relocations
+            .binary_search_by_key(&(section.0, offset), |relocation| {
+                (relocation.section.0, relocation.offset)
+            })
+            .ok()?;
+        relocations.get(index)
+    }
+
+    /// Resolve the prel31 value `word` at `offset` in a section with the given address.
+    fn target(
+        &self,
+        section: SectionIndex,
+        address: u64,
+        offset: u64,
+        word: u32,
+    ) -> ArmEhabiTarget {
+        if let Some(relocation) = self.relocation(section, offset) {
+            let addend = relocation.addend.unwrap_or_else(|| prel31(word));
+            if let Ok(symbol) = self.file.symbol_by_index(relocation.symbol) {
+                if let SymbolSection::Section(section) = symbol.section() {
+                    return ArmEhabiTarget::Section {
+                        section,
+                        offset: symbol.address().wrapping_add(addend as u64),
+                    };
+                }
+            }
+            return ArmEhabiTarget::Symbol {
+                symbol: relocation.symbol,
+                addend,
+            };
+        }
+        ArmEhabiTarget::Address(
+            address
+                .wrapping_add(offset)
+                .wrapping_add(prel31(word) as u64),
+        )
+    }
+
+    /// Decode the second word of an index table entry.
+    fn unwind(&self, offset: u64, word: u32) -> read::Result<ArmExidxUnwind<'data, Elf>> {
+        let endian = self.file.endian;
+        if self.relocation(self.index, offset).is_none() {
+            if word == elf::EXIDX_CANTUNWIND {
+                return Ok(ArmExidxUnwind::CantUnwind);
+            }
+            if word & 0x8000_0000 != 0 {
+                return ArmExidxUnwind::compact(endian, word, Bytes(&[]));
+            }
+        }
+
+        let target = self.target(self.index, self.address, offset, word);
+        let (section, address, offset, mut data) = self.unwind_table(target)?;
+        let word = data
+            .read::<U32Bytes<Elf::Endian>>()
+            .read_error("Invalid ARM EHABI unwind table offset")?
+            .get(endian);
+        if self.relocation(section, offset).is_none() && word & 0x8000_0000 != 0 {
+            return ArmExidxUnwind::compact(endian, word, data);
+        }
+        let personality = self.target(section, address, offset, word);
+        Ok(ArmExidxUnwind::Generic {
+            personality,
+            data: data.0,
+        })
+    }
+
+    /// Find the unwind table data for a reference from the index table.
+    ///
+    /// Returns the section index, section address, offset within the section,
+    /// and the section data starting at that offset.
+    fn unwind_table(
+        &self,
+        target: ArmEhabiTarget,
+    ) -> read::Result<(SectionIndex, u64, u64, Bytes<'data>)> {
+        let endian = self.file.endian;
+        let (index, section, offset) = match target {
+            ArmEhabiTarget::Address(address) => {
+                let count = self
+                    .sections
+                    .partition_point(|(start, _, _)| *start <= address);
+                let (start, _, index) = count
+                    .checked_sub(1)
+                    .map(|i| self.sections[i])
+                    .filter(|(_, end, _)| address < *end)
+                    .read_error("Invalid ARM EHABI unwind table address")?;
+                (index, self.file.sections.section(index)?, address - start)
+            }
+            ArmEhabiTarget::Section { section, offset } => {
+                (section, self.file.sections.section(section)?, offset)
+            }
+            ArmEhabiTarget::Symbol { .. } => {
+                return Err(Error("Unsupported ARM EHABI unwind table symbol"));
+            }
+        };
+        let mut data = section
+            .data(endian, self.file.data)
+            .map(Bytes)
+            .read_error("Invalid ARM EHABI unwind table section offset or size")?;
+        data.skip(offset as usize)
+            .read_error("Invalid ARM EHABI unwind table offset")?;
+        Ok((index, section.sh_addr(endian).into(), offset, data))
+    }
+}
+
+/// Add the `R_ARM_PREL31` relocations for a section.
+fn prel31_relocations<'data, 'file, Elf, R>(
+    section: &ElfSection<'data, 'file, Elf, R>,
+    relocations: &mut Vec<Prel31Relocation>,
+) where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    for (offset, relocation) in section.relocations() {
+        if relocation.flags()
+            != (RelocationFlags::Elf {
+                r_type: elf::R_ARM_PREL31,
+            })
+        {
+            continue;
+        }
+        let RelocationTarget::Symbol(symbol) = relocation.target() else {
+            continue;
+        };
+        let addend = if relocation.has_implicit_addend() {
+            None
+        } else {
+            Some(relocation.addend())
+        };
+        relocations.push(Prel31Relocation {
+            section: section.index,
+            offset,
+            symbol,
+            addend,
+        });
+    }
+}
+
+/// Sign extend a prel31 value.
+fn prel31(word: u32) -> i64 {
+    i64::from(((word << 1) as i32) >> 1)
+}
+
+/// An iterator over the entries in an [`ArmExidxTable`].
+#[derive(Debug)]
+pub struct ArmExidxIterator<'data, 'file, 'table, Elf, R = &'data [u8]>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    table: &'table ArmExidxTable<'data, 'file, Elf, R>,
+    index: usize,
+}
+
+impl<'data, 'file, 'table, Elf, R> ArmExidxIterator<'data, 'file, 'table, Elf, R>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    /// Return the next entry.
+    pub fn next(&mut self) -> read::Result<Option<ArmExidxEntry<'data, Elf>>> {
+        if self.index >= self.table.len() {
+            return Ok(None);
+        }
+        let result = self.table.entry(self.index).map(Some);
+        self.index += 1;
+        result
+    }
+}
+
+impl<'data, 'file, 'table, Elf, R> Iterator for ArmExidxIterator<'data, 'file, 'table, Elf, R>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    type Item = read::Result<ArmExidxEntry<'data, Elf>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// An address referenced by an ARM EHABI table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmEhabiTarget {
+    /// An absolute address.
+    Address(u64),
+    /// An offset within a section.
+    ///
+    /// This is used for relocatable objects.
+    Section {
+        /// The index of the section.
+        section: SectionIndex,
+        /// The offset within the section.
+        offset: u64,
+    },
+    /// An offset from a symbol that is not defined in a section.
+    ///
+    /// This is used for relocatable objects.
+    Symbol {
+        /// The index of the symbol.
+        symbol: SymbolIndex,
+        /// The offset from the symbol value.
+        addend: i64,
+    },
+}
+
+/// An entry in an [`ArmExidxTable`].
+#[derive(Debug, Clone)]
+pub struct ArmExidxEntry<'data, Elf: FileHeader> {
+    function: ArmEhabiTarget,
+    unwind: ArmExidxUnwind<'data, Elf>,
+}
+
+impl<'data, Elf: FileHeader> ArmExidxEntry<'data, Elf> {
+    /// Return the start address of the function.
+    ///
+    /// The entry applies until the start address of the next entry.
+    pub fn function(&self) -> ArmEhabiTarget {
+        self.function
+    }
+
+    /// Return the unwind information for the function.
+    pub fn unwind(&self) -> &ArmExidxUnwind<'data, Elf> {
+        &self.unwind
+    }
+}
+
+/// The unwind information for an entry in an [`ArmExidxTable`].
+#[derive(Debug, Clone)]
+pub enum ArmExidxUnwind<'data, Elf: FileHeader> {
+    /// The function cannot be unwound.
+    ///
+    /// This is the [`elf::EXIDX_CANTUNWIND`] value.
+    CantUnwind,
+    /// The compact model, which uses one of the ARM defined personality routines.
+    Compact {
+        /// The index of the personality routine.
+        ///
+        /// 0 is `__aeabi_unwind_cpp_pr0`, which has up to 3 unwind opcodes.
+        /// 1 and 2 are `__aeabi_unwind_cpp_pr1` and `__aeabi_unwind_cpp_pr2`,
+        /// which may have more unwind opcodes and are followed by descriptors.
+        personality: u8,
+        /// The unwind opcodes.
+        opcodes: ArmUnwindOpcodeIterator<'data, Elf>,
+        /// The remaining `.ARM.extab` section data after the unwind opcodes.
+        ///
+        /// This is empty if the opcodes are inline in the index table.
+        data: &'data [u8],
+    },
+    /// The generic model, which uses a personality routine defined by the toolchain.
+    Generic {
+        /// The address of the personality routine.
+        personality: ArmEhabiTarget,
+        /// The remaining `.ARM.extab` section data after the personality routine.
+        ///
+        /// The format of this data is defined by the personality routine.
+        data: &'data [u8],
+    },
+}
+
+impl<'data, Elf: FileHeader> ArmExidxUnwind<'data, Elf> {
+    /// Decode a word for the compact model, which may be followed by more opcodes.
+    fn compact(endian: Elf::Endian, word: u32, mut data: Bytes<'data>) -> read::Result<Self> {
+        let personality = ((word >> 24) & 0xf) as u8;
+        let opcodes = match personality {
+            0 => ArmUnwindOpcodeIterator {
+                endian,
+                word: word << 8,
+                bytes: 3,
+                data: Bytes(&[]),
+            },
+            1 | 2 => {
+                let count = ((word >> 16) & 0xff) as usize;
+                let words = data
+                    .read_bytes(count * 4)
+                    .read_error("Invalid ARM unwind opcode count")?;
+                ArmUnwindOpcodeIterator {
+                    endian,
+                    word: word << 16,
+                    bytes: 2,
+                    data: words,
+                }
+            }
+            _ => return Err(Error("Unsupported ARM EHABI personality routine index")),
+        };
+        Ok(ArmExidxUnwind::Compact {
+            personality,
+            opcodes,
+            data: data.0,
+        })
+    }
+}
+
+/// An iterator over ARM EHABI unwind opcodes.
+///
+/// The iterator stops after a [`ArmUnwindOpcode::Finish`] opcode.
+#[derive(Debug, Clone)]
+pub struct ArmUnwindOpcodeIterator<'data, Elf: FileHeader> {
+    endian: Elf::Endian,
+    word: u32,
+    bytes: u8,
+    data: Bytes<'data>,
+}
+
+impl<'data, Elf: FileHeader> ArmUnwindOpcodeIterator<'data, Elf> {
+    /// Return the next opcode.
+    pub fn next(&mut self) -> read::Result<Option<ArmUnwindOpcode>> {
+        if self.bytes == 0 && self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let result = self.parse();
+        if matches!(result, Ok(ArmUnwindOpcode::Finish) | Err(_)) {
+            self.bytes = 0;
+            self.data = Bytes(&[]);
+        }
+        result.map(Some)
+    }
+
+    fn byte(&mut self) -> read::Result<u8> {
+        if self.bytes == 0 {
+            self.word = self
+                .data
+                .read::<U32Bytes<Elf::Endian>>()
+                .read_error("Truncated ARM unwind opcode")?
+                .get(self.endian);
+            self.bytes = 4;
+        }
+        let byte = (self.word >> 24) as u8;
+        self.word <<= 8;
+        self.bytes -= 1;
+        Ok(byte)
+    }
+
+    fn parse(&mut self) -> read::Result<ArmUnwindOpcode> {
+        let spare = Error("Unsupported ARM unwind opcode");
+        let op = self.byte()?;
+        let opcode = match op {
+            0x00..=0x3f => ArmUnwindOpcode::IncrementVsp((u32::from(op & 0x3f) << 2) + 4),
+            0x40..=0x7f => ArmUnwindOpcode::DecrementVsp((u32::from(op & 0x3f) << 2) + 4),
+            0x80..=0x8f => {
+                let mask = (u16::from(op & 0xf) << 12) | (u16::from(self.byte()?) << 4);
+                if mask == 0 {
+                    ArmUnwindOpcode::RefuseToUnwind
+                } else {
+                    ArmUnwindOpcode::PopRegisters(mask)
+                }
+            }
+            0x9d | 0x9f => return Err(spare),
+            0x90..=0x9f => ArmUnwindOpcode::SetVsp(op & 0xf),
+            0xa0..=0xaf => {
+                let mask = ((2u16 << (op & 0x7)) - 1) << 4;
+                if op & 0x8 != 0 {
+                    ArmUnwindOpcode::PopRegisters(mask | (1 << 14))
+                } else {
+                    ArmUnwindOpcode::PopRegisters(mask)
+                }
+            }
+            0xb0 => ArmUnwindOpcode::Finish,
+            0xb1 => {
+                let mask = self.byte()?;
+                if mask == 0 || mask & 0xf0 != 0 {
+                    return Err(spare);
+                }
+                ArmUnwindOpcode::PopRegisters(mask.into())
+            }
+            0xb2 => {
+                let err = "Invalid ARM unwind opcode offset";
+                let mut value = 0u64;
+                let mut shift = 0;
+                loop {
+                    let byte = self.byte()?;
+                    if shift >= 32 {
+                        return Err(Error(err));
+                    }
+                    value |= u64::from(byte & 0x7f) << shift;
+                    if byte & 0x80 == 0 {
+                        break;
+                    }
+                    shift += 7;
+                }
+                let offset = u32::try_from(0x204 + (value << 2))
+                    .map_err(|_| ())
+                    .read_error(err)?;
+                ArmUnwindOpcode::IncrementVsp(offset)
+            }
+            0xb3 => {
+                let (first, count) = register_range(self.byte()?);
+                ArmUnwindOpcode::PopVfpFstmfdx { first, count }
+            }
+            0xb8..=0xbf => ArmUnwindOpcode::PopVfpFstmfdx {
+                first: 8,
+                count: (op & 0x7) + 1,
+            },
+            0xc0..=0xc5 => ArmUnwindOpcode::PopWmmxData {
+                first: 10,
+                count: (op & 0x7) + 1,
+            },
+            0xc6 => {
+                let (first, count) = register_range(self.byte()?);
+                ArmUnwindOpcode::PopWmmxData { first, count }
+            }
+            0xc7 => {
+                let mask = self.byte()?;
+                if mask == 0 || mask & 0xf0 != 0 {
+                    return Err(spare);
+                }
+                ArmUnwindOpcode::PopWmmxControl(mask)
+            }
+            0xc8 => {
+                let (first, count) = register_range(self.byte()?);
+                ArmUnwindOpcode::PopVfp {
+                    first: first + 16,
+                    count,
+                }
+            }
+            0xc9 => {
+                let (first, count) = register_range(self.byte()?);
+                ArmUnwindOpcode::PopVfp { first, count }
+            }
+            0xd0..=0xd7 => ArmUnwindOpcode::PopVfp {
+                first: 8,
+                count: (op & 0x7) + 1,
+            },
+            _ => return Err(spare),
+        };
+        Ok(opcode)
+    }
+}
+
+impl<'data, Elf: FileHeader> Iterator for ArmUnwindOpcodeIterator<'data, Elf> {
+    type Item = read::Result<ArmUnwindOpcode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// Decode the `sssscccc` operand of an unwind opcode.
+fn register_range(byte: u8) -> (u8, u8) {
+    (byte >> 4, (byte & 0xf) + 1)
+}
+
+/// An ARM EHABI unwind opcode.
+///
+/// `vsp` is the virtual stack pointer that is used while unwinding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArmUnwindOpcode {
+    /// Add the given value to `vsp`.
+    IncrementVsp(u32),
+    /// Subtract the given value from `vsp`.
+    DecrementVsp(u32),
+    /// The function cannot be unwound.
+    RefuseToUnwind,
+    /// Pop the integer registers in the mask, where bit `n` is `r[n]`.
+    PopRegisters(u16),
+    /// Set `vsp` to the value of the given integer register.
+    SetVsp(u8),
+    /// Pop VFP double-precision registers that were saved by `FSTMFDX`.
+    PopVfpFstmfdx {
+        /// The first register number.
+        first: u8,
+        /// The number of registers.
+        count: u8,
+    },
+    /// Pop VFP double-precision registers that were saved by `VPUSH`.
+    PopVfp {
+        /// The first register number.
+        first: u8,
+        /// The number of registers.
+        count: u8,
+    },
+    /// Pop Intel Wireless MMX data registers.
+    PopWmmxData {
+        /// The first register number.
+        first: u8,
+        /// The number of registers.
+        count: u8,
+    },
+    /// Pop the Intel Wireless MMX control registers in the mask, where bit `n` is `wCGR[n]`.
+    PopWmmxControl(u8),
+    /// Finish unwinding.
+    ///
+    /// If `r15` was not popped, then it is set to the value of `r14`.
+    Finish,
+}
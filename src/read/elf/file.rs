@@ -8,14 +8,16 @@ use crate::endian::{self, Endian, Endianness, U32};
 use crate::pod::Pod;
 use crate::read::{
     self, util, Architecture, ByteString, Bytes, Error, Export, FileFlags, Import, Object,
-    ObjectKind, ReadError, ReadRef, SectionIndex, StringTable, SymbolIndex,
+    ObjectKind, ObjectSymbolTable, ReadError, ReadRef, SectionIndex, StringTable, SymbolIndex,
+    SymbolMap, SymbolMapName,
 };
 
 use super::{
-    CompressionHeader, Dyn, ElfComdat, ElfComdatIterator, ElfDynamicRelocationIterator, ElfSection,
-    ElfSectionIterator, ElfSegment, ElfSegmentIterator, ElfSymbol, ElfSymbolIterator,
-    ElfSymbolTable, NoteHeader, ProgramHeader, Rel, Rela, RelocationSections, Relr, SectionHeader,
-    SectionTable, Sym, SymbolTable,
+    CompressionHeader, Dyn, DynamicTable, ElfComdat, ElfComdatIterator,
+    ElfDynamicRelocationIterator, ElfSection, ElfSectionIterator, ElfSegment, ElfSegmentIterator,
+    ElfSymbol, ElfSymbolIterator, ElfSymbolTable, FunctionDescriptors, NoteHeader, ProgramHeader,
+    Rel, Rela, RelocationSections, Relr, SectionHeader, SectionTable, Sym, SymbolTable,
+    VersionTable,
 };
 
 /// A 32-bit ELF object file.
@@ -48,6 +50,9 @@ where
     pub(super) relocations: RelocationSections,
     pub(super) symbols: SymbolTable<'data, Elf, R>,
     pub(super) dynamic_symbols: SymbolTable<'data, Elf, R>,
+    pub(super) dynamic: DynamicTable<'data, Elf, R>,
+    pub(super) versions: VersionTable<'data, Elf>,
+    pub(super) descriptors: FunctionDescriptors<'data>,
 }
 
 impl<'data, Elf, R> ElfFile<'data, Elf, R>
@@ -61,12 +66,36 @@ where
         let endian = header.endian()?;
         let segments = header.program_headers(endian, data)?;
         let sections = header.sections(endian, data)?;
-        let symbols = sections.symbols(endian, data, elf::SHT_SYMTAB)?;
-        // TODO: get dynamic symbols from DT_SYMTAB if there are no sections
-        let dynamic_symbols = sections.symbols(endian, data, elf::SHT_DYNSYM)?;
+        let mut symbols = sections.symbols(endian, data, elf::SHT_SYMTAB)?;
+        let mut dynamic_symbols = sections.symbols(endian, data, elf::SHT_DYNSYM)?;
         // The API we provide requires a mapping from section to relocations, so build it now.
         let relocations = sections.relocation_sections(endian, symbols.section())?;
 
+        // Without section headers, the dynamic segment is the only source of symbols.
+        // This is best effort, since the segment may contain relocated addresses
+        // (e.g. for images dumped from memory), so ignore errors.
+        let mut dynamic = DynamicTable::default();
+        if sections.is_empty() {
+            dynamic = DynamicTable::parse(endian, data, segments).unwrap_or_default();
+            dynamic_symbols = *dynamic.symbols();
+            symbols = dynamic_symbols;
+        }
+
+        // Symbol versions are optional information for symbols, so ignore errors.
+        // `imports` parses them again to report errors.
+        let versions = if sections.is_empty() {
+            dynamic.versions(endian)
+        } else {
+            sections.versions(endian, data)
+        }
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
+        // Function descriptors are only used for symbol entry addresses, so ignore errors.
+        let descriptors =
+            FunctionDescriptors::parse(header, endian, data, &sections).unwrap_or_default();
+
         Ok(ElfFile {
             endian,
             data,
@@ -76,6 +105,9 @@ where
             relocations,
             symbols,
             dynamic_symbols,
+            dynamic,
+            versions,
+            descriptors,
         })
     }
 
@@ -134,6 +166,35 @@ where
         &self.dynamic_symbols
     }
 
+    /// Get the tables located via the dynamic segment.
+    ///
+    /// This is only parsed for files that have no section headers, and
+    /// returns an empty table otherwise, or if the dynamic segment is invalid.
+    /// Use [`DynamicTable::parse`] directly if you need the error.
+    pub fn elf_dynamic_table(&self) -> &DynamicTable<'data, Elf, R> {
+        &self.dynamic
+    }
+
+    /// Get the GNU symbol version table for the dynamic symbols.
+    ///
+    /// Returns an empty table if the file has no version information, or if
+    /// the version sections are invalid.
+    /// Use [`SectionTable::versions`] directly if you need the error.
+    pub fn elf_versions(&self) -> &VersionTable<'data, Elf> {
+        &self.versions
+    }
+
+    /// The version table to use for `self.symbols`.
+    ///
+    /// This is only the dynamic symbol table if there are no section headers.
+    fn symbol_versions(&self) -> Option<&VersionTable<'data, Elf>> {
+        if self.sections.is_empty() {
+            Some(&self.versions)
+        } else {
+            None
+        }
+    }
+
     /// Get a mapping for linked relocation sections.
     pub fn elf_relocation_sections(&self) -> &RelocationSections {
         &self.relocations
@@ -342,13 +403,20 @@ where
         Ok(ElfSymbol {
             endian: self.endian,
             symbols: &self.symbols,
+            versions: self.symbol_versions(),
+            descriptors: &self.descriptors,
             index,
             symbol,
         })
     }
 
     fn symbols(&self) -> ElfSymbolIterator<'data, '_, Elf, R> {
-        ElfSymbolIterator::new(self.endian, &self.symbols)
+        ElfSymbolIterator::new(
+            self.endian,
+            &self.symbols,
+            self.symbol_versions(),
+            &self.descriptors,
+        )
     }
 
     fn symbol_table(&self) -> Option<ElfSymbolTable<'data, '_, Elf, R>> {
@@ -358,11 +426,18 @@ where
         Some(ElfSymbolTable {
             endian: self.endian,
             symbols: &self.symbols,
+            versions: self.symbol_versions(),
+            descriptors: &self.descriptors,
         })
     }
 
     fn dynamic_symbols(&self) -> ElfSymbolIterator<'data, '_, Elf, R> {
-        ElfSymbolIterator::new(self.endian, &self.dynamic_symbols)
+        ElfSymbolIterator::new(
+            self.endian,
+            &self.dynamic_symbols,
+            Some(&self.versions),
+            &self.descriptors,
+        )
     }
 
     fn dynamic_symbol_table(&self) -> Option<ElfSymbolTable<'data, '_, Elf, R>> {
@@ -372,37 +447,71 @@ where
         Some(ElfSymbolTable {
             endian: self.endian,
             symbols: &self.dynamic_symbols,
+            versions: Some(&self.versions),
+            descriptors: &self.descriptors,
         })
     }
 
+    fn symbol_map(&self) -> SymbolMap<SymbolMapName<'data>> {
+        // Use the entry address so that PPC64 ELFv1 function symbols map to code.
+        let mut symbols = Vec::new();
+        if let Some(table) = self.symbol_table().or_else(|| self.dynamic_symbol_table()) {
+            let mut all_symbols = Vec::new();
+            for symbol in table.symbols() {
+                if let Some((priority, index, name)) = read::symbol_map_key(&symbol) {
+                    all_symbols.push((symbol.entry_address(), priority, index, name));
+                }
+            }
+            all_symbols.sort_unstable();
+            all_symbols.dedup_by_key(|(address, ..)| *address);
+            symbols.extend(
+                all_symbols
+                    .into_iter()
+                    .map(|(address, _priority, _index, name)| SymbolMapName::new(address, name)),
+            );
+        }
+        SymbolMap::new(symbols)
+    }
+
     fn dynamic_relocations<'file>(
         &'file self,
     ) -> Option<ElfDynamicRelocationIterator<'data, 'file, Elf, R>> {
         Some(ElfDynamicRelocationIterator {
-            section_index: SectionIndex(1),
+            section_index: if self.sections.is_empty() {
+                SectionIndex(0)
+            } else {
+                SectionIndex(1)
+            },
             file: self,
             relocations: None,
+            android: None,
         })
     }
 
     fn imports(&self) -> read::Result<Vec<Import<'data>>> {
-        let versions = self.sections.versions(self.endian, self.data)?;
+        // Unlike `self.versions`, report errors for invalid version sections,
+        // since they are needed to find the library for each import.
+        let versions = if self.sections.is_empty() {
+            self.dynamic.versions(self.endian)?
+        } else {
+            self.sections.versions(self.endian, self.data)?
+        }
+        .unwrap_or_default();
 
         let mut imports = Vec::new();
         for (index, symbol) in self.dynamic_symbols.enumerate() {
             if symbol.is_undefined(self.endian) {
                 let name = symbol.name(self.endian, self.dynamic_symbols.strings())?;
                 if !name.is_empty() {
-                    let library = if let Some(svt) = versions.as_ref() {
-                        let vi = svt.version_index(self.endian, index);
-                        svt.version(vi)?.and_then(|v| v.file())
-                    } else {
-                        None
-                    }
-                    .unwrap_or(&[]);
+                    let version_index = versions.version_index(self.endian, index);
+                    let library = versions
+                        .version(version_index)?
+                        .and_then(|v| v.file())
+                        .unwrap_or(&[]);
                     imports.push(Import {
                         name: ByteString(name),
                         library: ByteString(library),
+                        version: versions.symbol_version(self.endian, index)?,
                     });
                 }
             }
@@ -412,13 +521,14 @@ where
 
     fn exports(&self) -> read::Result<Vec<Export<'data>>> {
         let mut exports = Vec::new();
-        for symbol in self.dynamic_symbols.iter() {
+        for (index, symbol) in self.dynamic_symbols.enumerate() {
             if symbol.is_definition(self.endian) {
                 let name = symbol.name(self.endian, self.dynamic_symbols.strings())?;
                 let address = symbol.st_value(self.endian).into();
                 exports.push(Export {
                     name: ByteString(name),
                     address,
+                    version: self.versions.symbol_version(self.endian, index)?,
                 });
             }
         }
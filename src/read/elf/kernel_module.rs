@@ -0,0 +1,510 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::elf;
+use crate::endian::{BigEndian, U32Bytes, U64Bytes};
+use crate::read::{
+    self, Bytes, Error, Object, ObjectSection, ObjectSymbol, ReadError, ReadRef, Relocation,
+    RelocationKind, RelocationTarget, SymbolSection,
+};
+
+use super::{ElfFile, ElfSection, FileHeader, SectionHeader};
+
+/// A view of the metadata in a Linux kernel module.
+///
+/// Kernel modules are relocatable objects, usually with a `.ko` extension.
+/// This decodes the module information strings, the symbol versions that the
+/// module depends on, the module name, the symbols that the module exports,
+/// and the signature that may be appended to the file.
+#[derive(Debug)]
+pub struct ElfKernelModule<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    endian: Elf::Endian,
+    is_64: bool,
+    modinfo: &'data [u8],
+    versions: &'data [u8],
+    this_module: &'data [u8],
+    exports: Vec<KernelModuleExport<'data>>,
+    signature: Option<ModuleSignature<'data>>,
+}
+
+impl<'data, Elf> ElfKernelModule<'data, Elf>
+where
+    Elf: FileHeader,
+{
+    /// Parse the metadata of a kernel module.
+    ///
+    /// Returns an error if the file type is not [`elf::ET_REL`], or if there is
+    /// no `.modinfo` section.
+    pub fn parse<R: ReadRef<'data>>(file: &ElfFile<'data, Elf, R>) -> read::Result<Self> {
+        let endian = file.endian();
+        let header = file.elf_header();
+        if header.e_type(endian) != elf::ET_REL {
+            return Err(Error("ELF file is not a relocatable object"));
+        }
+        let section_data = |name: &[u8]| -> read::Result<Option<&'data [u8]>> {
+            let Some((_, section)) = file.elf_section_table().section_by_name(endian, name) else {
+                return Ok(None);
+            };
+            section
+                .data(endian, file.data())
+                .read_error("Invalid ELF kernel module section offset or size")
+                .map(Some)
+        };
+        let modinfo =
+            section_data(b".modinfo")?.read_error("ELF file is not a Linux kernel module")?;
+        let versions = section_data(b"__versions")?.unwrap_or(&[]);
+        let this_module = section_data(b".gnu.linkonce.this_module")?.unwrap_or(&[]);
+
+        let mut exports = Vec::new();
+        for section in file.sections() {
+            let gpl = match section.name_bytes()? {
+                b"__ksymtab" => false,
+                b"__ksymtab_gpl" => true,
+                _ => continue,
+            };
+            parse_exports(file, &section, gpl, &mut exports)?;
+        }
+
+        let signature = ModuleSignature::parse(file.data())?;
+
+        Ok(ElfKernelModule {
+            endian,
+            is_64: header.is_type_64(),
+            modinfo,
+            versions,
+            this_module,
+            exports,
+            signature,
+        })
+    }
+
+    /// Return an iterator for the key and value pairs in the `.modinfo` section.
+    pub fn modinfo(&self) -> ModinfoIterator<'data> {
+        ModinfoIterator {
+            data: Bytes(self.modinfo),
+        }
+    }
+
+    /// Return the value of the first `.modinfo` entry with the given key.
+    ///
+    /// Common keys include `license`, `vermagic`, `depends` and `srcversion`.
+    pub fn modinfo_value(&self, key: &[u8]) -> Option<&'data [u8]> {
+        self.modinfo()
+            .find(|(entry_key, _)| *entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Return an iterator for the symbol versions in the `__versions` section.
+    ///
+    /// These are the CRCs of the symbols that the module imports, which are
+    /// checked when the module is loaded by a kernel with `CONFIG_MODVERSIONS`.
+    pub fn versions(&self) -> ModuleVersionIterator<'data, Elf> {
+        ModuleVersionIterator {
+            endian: self.endian,
+            is_64: self.is_64,
+            data: Bytes(self.versions),
+        }
+    }
+
+    /// Return the module name from the `.gnu.linkonce.this_module` section.
+    ///
+    /// This is the `name` field of `struct module`.
+    pub fn name(&self) -> Option<&'data [u8]> {
+        // The name follows an `enum module_state` and a `struct list_head`.
+        let (offset, len) = if self.is_64 { (24, 56) } else { (12, 60) };
+        let name = Bytes(self.this_module).read_bytes_at(offset, len).ok()?;
+        Some(trim_nul(name.0))
+    }
+
+    /// Return the symbols exported from the `__ksymtab` and `__ksymtab_gpl` sections.
+    pub fn exports(&self) -> &[KernelModuleExport<'data>] {
+        &self.exports
+    }
+
+    /// Return the signature appended to the module, if any.
+    pub fn signature(&self) -> Option<&ModuleSignature<'data>> {
+        self.signature.as_ref()
+    }
+}
+
+/// Return the bytes before the first null byte.
+fn trim_nul(data: &[u8]) -> &[u8] {
+    match data.iter().position(|&c| c == 0) {
+        Some(end) => &data[..end],
+        None => data,
+    }
+}
+
+/// Add the exports defined by the `__ksymtab_<name>` symbols in a ksymtab section.
+fn parse_exports<'data, 'file, Elf, R>(
+    file: &'file ElfFile<'data, Elf, R>,
+    section: &ElfSection<'data, 'file, Elf, R>,
+    gpl: bool,
+    exports: &mut Vec<KernelModuleExport<'data>>,
+) -> read::Result<()>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    let data = section.data()?;
+    let mut relocations = section.relocations().collect::<Vec<_>>();
+    relocations.sort_by_key(|(offset, _)| *offset);
+    let relocation_at = |offset: u64| {
+        relocations
+            .binary_search_by_key(&offset, |(offset, _)| *offset)
+            .ok()
+            .map(|index| &relocations[index].1)
+    };
+
+    for symbol in file.symbols() {
+        if symbol.section() != SymbolSection::Section(section.index()) {
+            continue;
+        }
+        let Some(name) = symbol.name_bytes()?.strip_prefix(b"__ksymtab_") else {
+            continue;
+        };
+        let offset = symbol.address().wrapping_sub(section.address());
+
+        let namespace = export_namespace(file, data, offset, relocation_at)?;
+        exports.push(KernelModuleExport {
+            name,
+            gpl,
+            namespace,
+        });
+    }
+    Ok(())
+}
+
+/// Read the namespace of the `struct kernel_symbol` at `offset` in a ksymtab section.
+fn export_namespace<'data, 'rel, Elf, R>(
+    file: &ElfFile<'data, Elf, R>,
+    data: &'data [u8],
+    offset: u64,
+    relocation_at: impl Fn(u64) -> Option<&'rel Relocation>,
+) -> read::Result<Option<&'data [u8]>>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    // Each field is either a 32-bit relative offset or a pointer.
+    // The namespace is the third field.
+    let Some(relocation) = relocation_at(offset) else {
+        return Ok(None);
+    };
+    let size = if relocation.kind() == RelocationKind::Relative || !Elf::is_type_64_sized() {
+        4
+    } else {
+        8
+    };
+    let offset = offset.wrapping_add(2 * size);
+    let Some(relocation) = relocation_at(offset) else {
+        return Ok(None);
+    };
+    let mut addend = relocation.addend();
+    if relocation.has_implicit_addend() {
+        let err = "Invalid ELF kernel symbol offset";
+        let endian = file.endian();
+        let offset = usize::try_from(offset).ok().read_error(err)?;
+        addend = if size == 8 {
+            Bytes(data)
+                .read_at::<U64Bytes<Elf::Endian>>(offset)
+                .read_error(err)?
+                .get(endian) as i64
+        } else {
+            i64::from(
+                Bytes(data)
+                    .read_at::<U32Bytes<Elf::Endian>>(offset)
+                    .read_error(err)?
+                    .get(endian) as i32,
+            )
+        };
+    }
+    let namespace = kernel_string(file, relocation.target(), addend)?;
+    Ok(namespace.filter(|namespace| !namespace.is_empty()))
+}
+
+/// Read a string referenced by a relocation in a ksymtab section.
+fn kernel_string<'data, Elf, R>(
+    file: &ElfFile<'data, Elf, R>,
+    target: RelocationTarget,
+    addend: i64,
+) -> read::Result<Option<&'data [u8]>>
+where
+    Elf: FileHeader,
+    R: ReadRef<'data>,
+{
+    let RelocationTarget::Symbol(index) = target else {
+        return Ok(None);
+    };
+    let symbol = file.symbol_by_index(index)?;
+    let Some(section_index) = symbol.section_index() else {
+        return Ok(None);
+    };
+    let section = file.section_by_index(section_index)?;
+    let offset = symbol
+        .address()
+        .wrapping_add(addend as u64)
+        .wrapping_sub(section.address());
+    let offset = usize::try_from(offset)
+        .ok()
+        .read_error("Invalid ELF kernel symbol string offset")?;
+    Bytes(section.data()?)
+        .read_string_at(offset)
+        .read_error("Invalid ELF kernel symbol string offset")
+        .map(Some)
+}
+
+/// An iterator for the entries in the `.modinfo` section of a kernel module.
+///
+/// Each entry is a null-terminated `key=value` string. The iterator returns
+/// the key and value, or the whole string as the key if there is no `=`.
+///
+/// Returned by [`ElfKernelModule::modinfo`].
+#[derive(Debug, Clone)]
+pub struct ModinfoIterator<'data> {
+    data: Bytes<'data>,
+}
+
+impl<'data> Iterator for ModinfoIterator<'data> {
+    type Item = (&'data [u8], &'data [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.data.is_empty() {
+                return None;
+            }
+            let entry = match self.data.read_string() {
+                Ok(entry) => entry,
+                Err(()) => {
+                    // The last entry is not null-terminated.
+                    let entry = self.data.0;
+                    self.data = Bytes(&[]);
+                    entry
+                }
+            };
+            // Entries may be separated by padding.
+            if entry.is_empty() {
+                continue;
+            }
+            return Some(match entry.iter().position(|&c| c == b'=') {
+                Some(index) => (&entry[..index], &entry[index + 1..]),
+                None => (entry, &[]),
+            });
+        }
+    }
+}
+
+/// An iterator for the entries in the `__versions` section of a kernel module.
+///
+/// Returned by [`ElfKernelModule::versions`].
+#[derive(Debug, Clone)]
+pub struct ModuleVersionIterator<'data, Elf: FileHeader> {
+    endian: Elf::Endian,
+    is_64: bool,
+    data: Bytes<'data>,
+}
+
+impl<'data, Elf: FileHeader> ModuleVersionIterator<'data, Elf> {
+    /// Return the next symbol version.
+    pub fn next(&mut self) -> read::Result<Option<ModuleVersion<'data>>> {
+        if self.data.is_empty() {
+            return Ok(None);
+        }
+
+        let result = self.parse().map(Some);
+        if result.is_err() {
+            self.data = Bytes(&[]);
+        }
+        result
+    }
+
+    fn parse(&mut self) -> read::Result<ModuleVersion<'data>> {
+        // Each entry is a `struct modversion_info`, which is 64 bytes long.
+        let mut entry = self
+            .data
+            .read_bytes(64)
+            .read_error("Invalid ELF kernel module version size")?;
+        let crc = if self.is_64 {
+            entry
+                .read::<U64Bytes<Elf::Endian>>()
+                .map(|crc| crc.get(self.endian))
+        } else {
+            entry
+                .read::<U32Bytes<Elf::Endian>>()
+                .map(|crc| crc.get(self.endian).into())
+        }
+        .read_error("Invalid ELF kernel module version size")?;
+        Ok(ModuleVersion {
+            crc,
+            name: trim_nul(entry.0),
+        })
+    }
+}
+
+impl<'data, Elf: FileHeader> Iterator for ModuleVersionIterator<'data, Elf> {
+    type Item = read::Result<ModuleVersion<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// A symbol version in the `__versions` section of a kernel module.
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleVersion<'data> {
+    crc: u64,
+    name: &'data [u8],
+}
+
+impl<'data> ModuleVersion<'data> {
+    /// Return the CRC of the symbol.
+    pub fn crc(&self) -> u64 {
+        self.crc
+    }
+
+    /// Return the name of the symbol.
+    pub fn name(&self) -> &'data [u8] {
+        self.name
+    }
+}
+
+/// A symbol exported by a kernel module.
+#[derive(Debug, Clone, Copy)]
+pub struct KernelModuleExport<'data> {
+    name: &'data [u8],
+    gpl: bool,
+    namespace: Option<&'data [u8]>,
+}
+
+impl<'data> KernelModuleExport<'data> {
+    /// Return the name of the symbol.
+    pub fn name(&self) -> &'data [u8] {
+        self.name
+    }
+
+    /// Return true if the symbol is only exported to GPL compatible modules.
+    pub fn is_gpl(&self) -> bool {
+        self.gpl
+    }
+
+    /// Return the namespace that the symbol is exported in, if any.
+    pub fn namespace(&self) -> Option<&'data [u8]> {
+        self.namespace
+    }
+}
+
+/// A signature appended to a kernel module.
+///
+/// The signature is followed by a `struct module_signature` and the
+/// [`elf::MODULE_SIG_STRING`] magic string.
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleSignature<'data> {
+    algo: u8,
+    hash: u8,
+    id_type: u8,
+    signer: &'data [u8],
+    key_id: &'data [u8],
+    signature: &'data [u8],
+}
+
+impl<'data> ModuleSignature<'data> {
+    /// Parse the signature at the end of the file data.
+    ///
+    /// Returns `Ok(None)` if the data does not end with [`elf::MODULE_SIG_STRING`].
+    fn parse<R: ReadRef<'data>>(data: R) -> read::Result<Option<Self>> {
+        let err = "Invalid ELF kernel module signature";
+        let len = data.len().read_error(err)?;
+        let magic_len = elf::MODULE_SIG_STRING.len() as u64;
+        let Some(magic_offset) = len.checked_sub(magic_len) else {
+            return Ok(None);
+        };
+        if data
+            .read_bytes_at(magic_offset, magic_len)
+            .read_error(err)?
+            != elf::MODULE_SIG_STRING
+        {
+            return Ok(None);
+        }
+
+        // `struct module_signature` is 12 bytes long.
+        let info_offset = magic_offset.checked_sub(12).read_error(err)?;
+        let mut info = Bytes(data.read_bytes_at(info_offset, 12).read_error(err)?);
+        let (algo, hash, id_type, signer_len, key_id_len) = (|| -> Result<_, ()> {
+            let fields = info.read_bytes(8)?.0;
+            Ok((fields[0], fields[1], fields[2], fields[3], fields[4]))
+        })()
+        .read_error(err)?;
+        let sig_len = info
+            .read::<U32Bytes<BigEndian>>()
+            .read_error(err)?
+            .get(BigEndian);
+
+        // The signer name and key ID precede the signature.
+        let signature_offset = info_offset.checked_sub(sig_len.into()).read_error(err)?;
+        let key_id_offset = signature_offset
+            .checked_sub(key_id_len.into())
+            .read_error(err)?;
+        let signer_offset = key_id_offset
+            .checked_sub(signer_len.into())
+            .read_error(err)?;
+        Ok(Some(ModuleSignature {
+            algo,
+            hash,
+            id_type,
+            signer: data
+                .read_bytes_at(signer_offset, signer_len.into())
+                .read_error(err)?,
+            key_id: data
+                .read_bytes_at(key_id_offset, key_id_len.into())
+                .read_error(err)?,
+            signature: data
+                .read_bytes_at(signature_offset, sig_len.into())
+                .read_error(err)?,
+        }))
+    }
+
+    /// Return the public-key crypto algorithm.
+    ///
+    /// This is 0 for PKCS#7 signatures, which contain this information.
+    pub fn algo(&self) -> u8 {
+        self.algo
+    }
+
+    /// Return the digest algorithm.
+    ///
+    /// This is 0 for PKCS#7 signatures, which contain this information.
+    pub fn hash(&self) -> u8 {
+        self.hash
+    }
+
+    /// Return the key identifier type.
+    ///
+    /// This is one of the `PKEY_ID_*` constants, and is usually [`elf::PKEY_ID_PKCS7`].
+    pub fn id_type(&self) -> u8 {
+        self.id_type
+    }
+
+    /// Return the signer's name.
+    ///
+    /// This is empty for PKCS#7 signatures.
+    pub fn signer(&self) -> &'data [u8] {
+        self.signer
+    }
+
+    /// Return the key identifier.
+    ///
+    /// This is empty for PKCS#7 signatures.
+    pub fn key_id(&self) -> &'data [u8] {
+        self.key_id
+    }
+
+    /// Return the signature data.
+    ///
+    /// For PKCS#7 signatures, this is DER encoded signed data.
+    pub fn signature(&self) -> &'data [u8] {
+        self.signature
+    }
+}
@@ -68,6 +68,12 @@ pub use compression::*;
 mod note;
 pub use note::*;
 
+mod core_file;
+pub use core_file::*;
+
+mod kernel_module;
+pub use kernel_module::*;
+
 mod hash;
 pub use hash::*;
 
@@ -76,3 +82,6 @@ pub use version::*;
 
 mod attributes;
 pub use attributes::*;
+
+mod ehabi;
+pub use ehabi::*;
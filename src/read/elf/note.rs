@@ -2,7 +2,7 @@ use core::fmt::Debug;
 use core::mem;
 
 use crate::elf;
-use crate::endian::{self, U32};
+use crate::endian::{self, I32Bytes, U32Bytes, U64Bytes, U32};
 use crate::pod::Pod;
 use crate::read::util;
 use crate::read::{self, Bytes, Error, ReadError};
@@ -178,6 +178,229 @@ impl<'data, Elf: FileHeader> Note<'data, Elf> {
             data: Bytes(self.desc),
         })
     }
+
+    /// Parse the descriptor of a note with a well-known name and type.
+    ///
+    /// Returns `Ok(None)` if the name and type are not recognised.
+    /// Returns an error if the descriptor is invalid for the name and type.
+    pub fn parse_known(&self, endian: Elf::Endian) -> read::Result<Option<KnownNote<'data, Elf>>> {
+        let desc = Bytes(self.desc);
+        let u32_at = |offset| {
+            desc.read_at::<U32Bytes<Elf::Endian>>(offset)
+                .map(|val| val.get(endian))
+        };
+        let u64_at = |offset| {
+            desc.read_at::<U64Bytes<Elf::Endian>>(offset)
+                .map(|val| val.get(endian))
+        };
+        let address_at = |offset| {
+            if Elf::is_type_64_sized() {
+                u64_at(offset)
+            } else {
+                u32_at(offset).map(u64::from)
+            }
+        };
+        let note = match (self.name(), self.n_type(endian)) {
+            (elf::ELF_NOTE_GNU, elf::NT_GNU_ABI_TAG) => (|| -> Result<_, ()> {
+                Ok(KnownNote::GnuAbiTag {
+                    os: u32_at(0)?,
+                    version: [u32_at(4)?, u32_at(8)?, u32_at(12)?],
+                })
+            })()
+            .read_error("Invalid ELF NT_GNU_ABI_TAG note")?,
+            (elf::ELF_NOTE_GNU, elf::NT_GNU_BUILD_ID) => KnownNote::GnuBuildId(self.desc),
+            (elf::ELF_NOTE_GNU, elf::NT_GNU_GOLD_VERSION) => {
+                KnownNote::GnuGoldVersion(trim_nul(self.desc))
+            }
+            (elf::ELF_NOTE_GNU, elf::NT_GNU_PROPERTY_TYPE_0) => {
+                return Ok(self.gnu_properties(endian).map(KnownNote::GnuProperties));
+            }
+            (elf::ELF_NOTE_GO, elf::NT_GO_BUILD_ID) => KnownNote::GoBuildId(trim_nul(self.desc)),
+            (elf::ELF_NOTE_FDO, elf::NT_FDO_PACKAGING_METADATA) => {
+                KnownNote::FdoPackagingMetadata(trim_nul(self.desc))
+            }
+            (elf::ELF_NOTE_FDO, elf::NT_FDO_DLOPEN_METADATA) => {
+                KnownNote::FdoDlopenMetadata(trim_nul(self.desc))
+            }
+            (elf::ELF_NOTE_STAPSDT, elf::NT_STAPSDT) => (|| -> Result<_, ()> {
+                let size = if Elf::is_type_64_sized() { 8 } else { 4 };
+                let pc = address_at(0)?;
+                let base = address_at(size)?;
+                let semaphore = address_at(2 * size)?;
+                let mut strings = desc;
+                strings.skip(3 * size)?;
+                let provider = strings.read_string()?;
+                let name = strings.read_string()?;
+                let args = strings.read_string()?;
+                Ok(KnownNote::Stapsdt(StapsdtProbe {
+                    pc,
+                    base,
+                    semaphore,
+                    provider,
+                    name,
+                    args,
+                }))
+            })()
+            .read_error("Invalid ELF NT_STAPSDT note")?,
+            (elf::ELF_NOTE_LINUX, elf::NT_ARM_TLS) => {
+                KnownNote::ArmTls(address_at(0).read_error("Invalid ELF NT_ARM_TLS note")?)
+            }
+            (elf::ELF_NOTE_LINUX, elf::NT_ARM_SYSTEM_CALL) => KnownNote::ArmSystemCall(
+                desc.read_at::<I32Bytes<Elf::Endian>>(0)
+                    .read_error("Invalid ELF NT_ARM_SYSTEM_CALL note")?
+                    .get(endian),
+            ),
+            (elf::ELF_NOTE_LINUX, n_type @ (elf::NT_ARM_HW_BREAK | elf::NT_ARM_HW_WATCH)) => {
+                (|| -> Result<_, ()> {
+                    let info = u32_at(0)?;
+                    let mut registers = desc;
+                    registers.skip(8)?;
+                    let count =
+                        registers.len() / mem::size_of::<elf::ArmHwdebugRegister<Elf::Endian>>();
+                    let registers = registers.read_slice(count)?;
+                    Ok(if n_type == elf::NT_ARM_HW_BREAK {
+                        KnownNote::ArmHwBreak { info, registers }
+                    } else {
+                        KnownNote::ArmHwWatch { info, registers }
+                    })
+                })()
+                .read_error("Invalid ELF NT_ARM_HW_BREAK or NT_ARM_HW_WATCH note")?
+            }
+            (elf::ELF_NOTE_LINUX, elf::NT_ARM_PAC_MASK) => (|| -> Result<_, ()> {
+                Ok(KnownNote::ArmPacMask {
+                    data_mask: u64_at(0)?,
+                    insn_mask: u64_at(8)?,
+                })
+            })()
+            .read_error("Invalid ELF NT_ARM_PAC_MASK note")?,
+            (elf::ELF_NOTE_LINUX, elf::NT_ARM_TAGGED_ADDR_CTRL) => KnownNote::ArmTaggedAddrCtrl(
+                u64_at(0).read_error("Invalid ELF NT_ARM_TAGGED_ADDR_CTRL note")?,
+            ),
+            (elf::ELF_NOTE_LINUX, elf::NT_ARM_PAC_ENABLED_KEYS) => KnownNote::ArmPacEnabledKeys(
+                u64_at(0).read_error("Invalid ELF NT_ARM_PAC_ENABLED_KEYS note")?,
+            ),
+            _ => return Ok(None),
+        };
+        Ok(Some(note))
+    }
+}
+
+/// Remove trailing null bytes from a note descriptor.
+fn trim_nul(mut data: &[u8]) -> &[u8] {
+    while let [rest @ .., 0] = data {
+        data = rest;
+    }
+    data
+}
+
+/// A note with a well-known name and type.
+///
+/// Returned by [`Note::parse_known`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum KnownNote<'data, Elf: FileHeader> {
+    /// An [`elf::NT_GNU_ABI_TAG`] note.
+    GnuAbiTag {
+        /// The OS descriptor.
+        ///
+        /// This is one of the `ELF_NOTE_OS_*` constants.
+        os: u32,
+        /// The major, minor and subminor version of the ABI.
+        version: [u32; 3],
+    },
+    /// An [`elf::NT_GNU_BUILD_ID`] note.
+    GnuBuildId(&'data [u8]),
+    /// An [`elf::NT_GNU_GOLD_VERSION`] note containing a version string.
+    GnuGoldVersion(&'data [u8]),
+    /// An [`elf::NT_GNU_PROPERTY_TYPE_0`] note.
+    GnuProperties(GnuPropertyIterator<'data, Elf::Endian>),
+    /// An [`elf::NT_GO_BUILD_ID`] note.
+    GoBuildId(&'data [u8]),
+    /// An [`elf::NT_FDO_PACKAGING_METADATA`] note.
+    ///
+    /// This is a JSON object, excluding the null terminator.
+    FdoPackagingMetadata(&'data [u8]),
+    /// An [`elf::NT_FDO_DLOPEN_METADATA`] note.
+    ///
+    /// This is a JSON array, excluding the null terminator.
+    FdoDlopenMetadata(&'data [u8]),
+    /// An [`elf::NT_STAPSDT`] note.
+    Stapsdt(StapsdtProbe<'data>),
+    /// An [`elf::NT_ARM_TLS`] note containing the thread pointer register.
+    ArmTls(u64),
+    /// An [`elf::NT_ARM_SYSTEM_CALL`] note containing the system call number.
+    ArmSystemCall(i32),
+    /// An [`elf::NT_ARM_HW_BREAK`] note.
+    ArmHwBreak {
+        /// The debug information, which includes the number of registers.
+        info: u32,
+        /// The breakpoint registers.
+        registers: &'data [elf::ArmHwdebugRegister<Elf::Endian>],
+    },
+    /// An [`elf::NT_ARM_HW_WATCH`] note.
+    ArmHwWatch {
+        /// The debug information, which includes the number of registers.
+        info: u32,
+        /// The watchpoint registers.
+        registers: &'data [elf::ArmHwdebugRegister<Elf::Endian>],
+    },
+    /// An [`elf::NT_ARM_PAC_MASK`] note.
+    ArmPacMask {
+        /// The mask of the pointer authentication code bits for data pointers.
+        data_mask: u64,
+        /// The mask of the pointer authentication code bits for instruction pointers.
+        insn_mask: u64,
+    },
+    /// An [`elf::NT_ARM_TAGGED_ADDR_CTRL`] note.
+    ArmTaggedAddrCtrl(u64),
+    /// An [`elf::NT_ARM_PAC_ENABLED_KEYS`] note.
+    ArmPacEnabledKeys(u64),
+}
+
+/// A SystemTap probe descriptor in an [`elf::NT_STAPSDT`] note.
+#[derive(Debug, Clone, Copy)]
+pub struct StapsdtProbe<'data> {
+    pc: u64,
+    base: u64,
+    semaphore: u64,
+    provider: &'data [u8],
+    name: &'data [u8],
+    args: &'data [u8],
+}
+
+impl<'data> StapsdtProbe<'data> {
+    /// Return the link time address of the probe.
+    pub fn pc(&self) -> u64 {
+        self.pc
+    }
+
+    /// Return the link time address of the `.stapsdt.base` section.
+    ///
+    /// If the section has a different address, then `pc` and `semaphore`
+    /// must be adjusted by the difference.
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Return the link time address of the semaphore, or 0 if there is no semaphore.
+    pub fn semaphore(&self) -> u64 {
+        self.semaphore
+    }
+
+    /// Return the provider name.
+    pub fn provider(&self) -> &'data [u8] {
+        self.provider
+    }
+
+    /// Return the probe name.
+    pub fn name(&self) -> &'data [u8] {
+        self.name
+    }
+
+    /// Return the argument format string.
+    pub fn args(&self) -> &'data [u8] {
+        self.args
+    }
 }
 
 /// A trait for generic access to [`elf::NoteHeader32`] and [`elf::NoteHeader64`].
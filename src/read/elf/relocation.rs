@@ -1,14 +1,16 @@
 use alloc::fmt;
 use alloc::vec::Vec;
+use core::convert::TryFrom;
 use core::fmt::Debug;
+use core::marker::PhantomData;
 use core::slice;
 
 use crate::elf;
 use crate::endian::{self, Endianness};
 use crate::pod::Pod;
 use crate::read::{
-    self, Error, ReadRef, Relocation, RelocationEncoding, RelocationFlags, RelocationKind,
-    RelocationTarget, SectionIndex, SymbolIndex,
+    self, Bytes, Error, ReadError, ReadRef, Relocation, RelocationEncoding, RelocationFlags,
+    RelocationKind, RelocationTarget, SectionIndex, SymbolIndex,
 };
 
 use super::{ElfFile, FileHeader, SectionHeader, SectionTable};
@@ -31,7 +33,7 @@ impl RelocationSections {
         let mut relocations = vec![0; sections.len()];
         for (index, section) in sections.iter().enumerate().rev() {
             let sh_type = section.sh_type(endian);
-            if sh_type == elf::SHT_REL || sh_type == elf::SHT_RELA {
+            if sh_type == elf::SHT_REL || sh_type == elf::SHT_RELA || sh_type == elf::SHT_CREL {
                 // The symbol indices used in relocations must be for the symbol table
                 // we are expecting to use.
                 let sh_link = section.link(endian);
@@ -51,7 +53,10 @@ impl RelocationSections {
                 // We don't support relocations that apply to other relocation sections
                 // because it interferes with the chaining of relocation sections below.
                 let sh_info_type = sections.section(sh_info)?.sh_type(endian);
-                if sh_info_type == elf::SHT_REL || sh_info_type == elf::SHT_RELA {
+                if sh_info_type == elf::SHT_REL
+                    || sh_info_type == elf::SHT_RELA
+                    || sh_info_type == elf::SHT_CREL
+                {
                     return Err(Error("Unsupported ELF sh_info for relocation section"));
                 }
 
@@ -91,6 +96,12 @@ impl<'data, Elf: FileHeader> ElfRelaIterator<'data, Elf> {
     }
 }
 
+/// One of the relocation tables located via the dynamic segment.
+pub(super) enum DynamicRelocations<'data, Elf: FileHeader> {
+    Rela(ElfRelaIterator<'data, Elf>),
+    Android(Option<AndroidRelocationIterator<'data, Elf>>),
+}
+
 impl<'data, Elf: FileHeader> Iterator for ElfRelaIterator<'data, Elf> {
     type Item = Elf::Rela;
 
@@ -116,9 +127,13 @@ where
     R: ReadRef<'data>,
 {
     /// The current relocation section index.
+    ///
+    /// If the file has no sections, then this is the index of the next table
+    /// in the dynamic segment instead.
     pub(super) section_index: SectionIndex,
     pub(super) file: &'file ElfFile<'data, Elf, R>,
     pub(super) relocations: Option<ElfRelaIterator<'data, Elf>>,
+    pub(super) android: Option<AndroidRelocationIterator<'data, Elf>>,
 }
 
 impl<'data, 'file, Elf, R> Iterator for ElfDynamicRelocationIterator<'data, 'file, Elf, R>
@@ -140,6 +155,31 @@ where
                 self.relocations = None;
             }
 
+            if let Some(ref mut relocations) = self.android {
+                // Stop at the first invalid relocation.
+                if let Some(Ok(reloc)) = relocations.next().transpose() {
+                    let relocation = parse_relocation_parts(
+                        self.file.header,
+                        endian,
+                        reloc.r_type,
+                        reloc.symbol(),
+                        reloc.r_addend,
+                        !relocations.is_rela(),
+                    );
+                    return Some((reloc.r_offset, relocation));
+                }
+                self.android = None;
+            }
+
+            if self.file.sections.is_empty() {
+                match self.file.dynamic.relocations(self.section_index.0)? {
+                    DynamicRelocations::Rela(relocations) => self.relocations = Some(relocations),
+                    DynamicRelocations::Android(relocations) => self.android = relocations,
+                }
+                self.section_index.0 += 1;
+                continue;
+            }
+
             let section = self.file.sections.section(self.section_index).ok()?;
             self.section_index.0 += 1;
 
@@ -158,6 +198,13 @@ where
                         self.relocations = Some(ElfRelaIterator::Rela(relocations.iter()));
                     }
                 }
+                elf::SHT_ANDROID_REL | elf::SHT_ANDROID_RELA => {
+                    if let Ok(Some((relocations, _))) =
+                        section.android_relocations(endian, self.file.data)
+                    {
+                        self.android = Some(relocations);
+                    }
+                }
                 _ => {}
             }
         }
@@ -191,6 +238,7 @@ where
     pub(super) section_index: SectionIndex,
     pub(super) file: &'file ElfFile<'data, Elf, R>,
     pub(super) relocations: Option<ElfRelaIterator<'data, Elf>>,
+    pub(super) crel: Option<CrelIterator<'data, Elf>>,
 }
 
 impl<'data, 'file, Elf, R> Iterator for ElfSectionRelocationIterator<'data, 'file, Elf, R>
@@ -211,6 +259,23 @@ where
                 }
                 self.relocations = None;
             }
+
+            if let Some(ref mut relocations) = self.crel {
+                // Stop at the first invalid relocation.
+                if let Some(Ok(reloc)) = relocations.next().transpose() {
+                    let relocation = parse_relocation_parts(
+                        self.file.header,
+                        endian,
+                        reloc.r_type,
+                        reloc.symbol(),
+                        reloc.r_addend,
+                        !relocations.is_rela(),
+                    );
+                    return Some((reloc.r_offset, relocation));
+                }
+                self.crel = None;
+            }
+
             self.section_index = self.file.relocations.get(self.section_index)?;
             // The construction of RelocationSections ensures section_index is valid.
             let section = self.file.sections.section(self.section_index).unwrap();
@@ -225,6 +290,11 @@ where
                         self.relocations = Some(ElfRelaIterator::Rela(relocations.iter()));
                     }
                 }
+                elf::SHT_CREL => {
+                    if let Ok(Some((relocations, _))) = section.crel(endian, self.file.data) {
+                        self.crel = Some(relocations);
+                    }
+                }
                 _ => {}
             }
         }
@@ -246,12 +316,29 @@ fn parse_relocation<Elf: FileHeader>(
     endian: Elf::Endian,
     reloc: Elf::Rela,
     implicit_addend: bool,
+) -> Relocation {
+    let is_mips64el = header.is_mips64el(endian);
+    parse_relocation_parts(
+        header,
+        endian,
+        reloc.r_type(endian, is_mips64el),
+        reloc.symbol(endian, is_mips64el),
+        reloc.r_addend(endian).into(),
+        implicit_addend,
+    )
+}
+
+fn parse_relocation_parts<Elf: FileHeader>(
+    header: &Elf,
+    endian: Elf::Endian,
+    r_type: u32,
+    symbol: Option<SymbolIndex>,
+    addend: i64,
+    implicit_addend: bool,
 ) -> Relocation {
     use RelocationEncoding as E;
     use RelocationKind as K;
 
-    let is_mips64el = header.is_mips64el(endian);
-    let r_type = reloc.r_type(endian, is_mips64el);
     let flags = RelocationFlags::Elf { r_type };
     let g = E::Generic;
     let unknown = (K::Unknown, E::Generic, 0);
@@ -446,7 +533,7 @@ fn parse_relocation<Elf: FileHeader>(
         },
         _ => unknown,
     };
-    let target = match reloc.symbol(endian, is_mips64el) {
+    let target = match symbol {
         None => RelocationTarget::Absolute,
         Some(symbol) => RelocationTarget::Symbol(symbol),
     };
@@ -455,7 +542,7 @@ fn parse_relocation<Elf: FileHeader>(
         encoding,
         size,
         target,
-        addend: reloc.r_addend(endian).into(),
+        addend,
         implicit_addend,
         flags,
     }
@@ -736,3 +823,366 @@ impl<Endian: endian::Endian> Relr for elf::Relr64<Endian> {
         }
     }
 }
+
+/// A relocation decoded from an ELF `SHT_ANDROID_REL` or `SHT_ANDROID_RELA` section.
+///
+/// Returned by [`AndroidRelocationIterator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AndroidRelocation {
+    /// The offset of the location to be relocated.
+    pub r_offset: u64,
+    /// The symbol index.
+    pub r_sym: u32,
+    /// The relocation type.
+    pub r_type: u32,
+    /// The addend.
+    ///
+    /// This is always 0 for `SHT_ANDROID_REL`.
+    pub r_addend: i64,
+}
+
+impl AndroidRelocation {
+    /// Get the symbol index referenced by the relocation.
+    ///
+    /// Returns `None` for the null symbol index.
+    pub fn symbol(&self) -> Option<SymbolIndex> {
+        if self.r_sym == 0 {
+            None
+        } else {
+            Some(SymbolIndex(self.r_sym as usize))
+        }
+    }
+}
+
+/// An iterator over the relocations in an ELF `SHT_ANDROID_REL` or `SHT_ANDROID_RELA` section.
+///
+/// These sections use the "APS2" packed format, which encodes groups of
+/// relocations as SLEB128 deltas.
+///
+/// Returned by [`SectionHeader::android_relocations`](super::SectionHeader::android_relocations).
+#[derive(Debug, Clone)]
+pub struct AndroidRelocationIterator<'data, Elf: FileHeader> {
+    data: Bytes<'data>,
+    is_rela: bool,
+    remaining: u64,
+    group_remaining: u64,
+    group_flags: u64,
+    group_offset_delta: u64,
+    r_offset: u64,
+    r_info: u64,
+    r_addend: i64,
+    marker: PhantomData<Elf>,
+}
+
+impl<'data, Elf: FileHeader> AndroidRelocationIterator<'data, Elf> {
+    /// Create a new iterator given the section data.
+    ///
+    /// `is_rela` should be true for `SHT_ANDROID_RELA` or `DT_ANDROID_RELA`.
+    pub fn new(data: &'data [u8], is_rela: bool) -> read::Result<Self> {
+        let mut data = Bytes(data);
+        if data.read_bytes(4).ok().map(|magic| magic.0) != Some(&elf::ANDROID_APS2_MAGIC[..]) {
+            return Err(Error("Invalid ELF Android relocation magic"));
+        }
+        let remaining = data
+            .read_sleb128()
+            .ok()
+            .and_then(|count| u64::try_from(count).ok())
+            .read_error("Invalid ELF Android relocation count")?;
+        let r_offset = data
+            .read_sleb128()
+            .read_error("Invalid ELF Android relocation offset")? as u64;
+        Ok(AndroidRelocationIterator {
+            data,
+            is_rela,
+            remaining,
+            group_remaining: 0,
+            group_flags: 0,
+            group_offset_delta: 0,
+            r_offset,
+            r_info: 0,
+            r_addend: 0,
+            marker: PhantomData,
+        })
+    }
+
+    /// Return true if the relocations have explicit addends.
+    pub fn is_rela(&self) -> bool {
+        self.is_rela
+    }
+
+    /// Return the next relocation.
+    pub fn next(&mut self) -> read::Result<Option<AndroidRelocation>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let result = self.parse().map(Some);
+        if result.is_err() {
+            self.remaining = 0;
+        }
+        result
+    }
+
+    fn parse(&mut self) -> read::Result<AndroidRelocation> {
+        while self.group_remaining == 0 {
+            self.parse_group()?;
+        }
+
+        let flags = self.group_flags;
+        if flags & elf::RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG != 0 {
+            self.r_offset = self.r_offset.wrapping_add(self.group_offset_delta);
+        } else {
+            let delta = self
+                .data
+                .read_sleb128()
+                .read_error("Invalid ELF Android relocation offset")?;
+            self.r_offset = self.r_offset.wrapping_add(delta as u64);
+        }
+        if flags & elf::RELOCATION_GROUPED_BY_INFO_FLAG == 0 {
+            self.r_info =
+                self.data
+                    .read_sleb128()
+                    .read_error("Invalid ELF Android relocation info")? as u64;
+        }
+        if flags & elf::RELOCATION_GROUP_HAS_ADDEND_FLAG != 0
+            && flags & elf::RELOCATION_GROUPED_BY_ADDEND_FLAG == 0
+        {
+            let delta = self
+                .data
+                .read_sleb128()
+                .read_error("Invalid ELF Android relocation addend")?;
+            self.r_addend = self.r_addend.wrapping_add(delta);
+        }
+        self.group_remaining -= 1;
+        self.remaining -= 1;
+
+        Ok(if Elf::is_type_64_sized() {
+            AndroidRelocation {
+                r_offset: self.r_offset,
+                r_sym: (self.r_info >> 32) as u32,
+                r_type: self.r_info as u32,
+                r_addend: self.r_addend,
+            }
+        } else {
+            AndroidRelocation {
+                r_offset: u64::from(self.r_offset as u32),
+                r_sym: (self.r_info as u32) >> 8,
+                r_type: self.r_info as u32 & 0xff,
+                r_addend: i64::from(self.r_addend as i32),
+            }
+        })
+    }
+
+    fn parse_group(&mut self) -> read::Result<()> {
+        let size = self
+            .data
+            .read_sleb128()
+            .ok()
+            .and_then(|size| u64::try_from(size).ok())
+            .filter(|size| *size <= self.remaining)
+            .read_error("Invalid ELF Android relocation group size")?;
+        let flags = self
+            .data
+            .read_sleb128()
+            .read_error("Invalid ELF Android relocation group flags")? as u64;
+        if flags & elf::RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG != 0 {
+            self.group_offset_delta = self
+                .data
+                .read_sleb128()
+                .read_error("Invalid ELF Android relocation group offset")?
+                as u64;
+        }
+        if flags & elf::RELOCATION_GROUPED_BY_INFO_FLAG != 0 {
+            self.r_info = self
+                .data
+                .read_sleb128()
+                .read_error("Invalid ELF Android relocation group info")?
+                as u64;
+        }
+        if flags & elf::RELOCATION_GROUP_HAS_ADDEND_FLAG != 0 {
+            if !self.is_rela {
+                return Err(Error("Unexpected ELF Android relocation addend"));
+            }
+            if flags & elf::RELOCATION_GROUPED_BY_ADDEND_FLAG != 0 {
+                let delta = self
+                    .data
+                    .read_sleb128()
+                    .read_error("Invalid ELF Android relocation group addend")?;
+                self.r_addend = self.r_addend.wrapping_add(delta);
+            }
+        } else {
+            self.r_addend = 0;
+        }
+        self.group_remaining = size;
+        self.group_flags = flags;
+        Ok(())
+    }
+}
+
+impl<'data, Elf: FileHeader> Iterator for AndroidRelocationIterator<'data, Elf> {
+    type Item = read::Result<AndroidRelocation>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
+
+/// A relocation decoded from an ELF `SHT_CREL` section.
+///
+/// Returned by [`CrelIterator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crel {
+    /// The offset of the location to be relocated.
+    pub r_offset: u64,
+    /// The symbol index.
+    pub r_sym: u32,
+    /// The relocation type.
+    pub r_type: u32,
+    /// The addend.
+    ///
+    /// This is always 0 if the section does not have explicit addends.
+    pub r_addend: i64,
+}
+
+impl Crel {
+    /// Get the symbol index referenced by the relocation.
+    ///
+    /// Returns `None` for the null symbol index.
+    pub fn symbol(&self) -> Option<SymbolIndex> {
+        if self.r_sym == 0 {
+            None
+        } else {
+            Some(SymbolIndex(self.r_sym as usize))
+        }
+    }
+}
+
+/// An iterator over the relocations in an ELF `SHT_CREL` section.
+///
+/// These sections use a compact encoding of the offset, symbol, type and addend
+/// deltas between consecutive relocations.
+///
+/// Returned by [`SectionHeader::crel`](super::SectionHeader::crel).
+#[derive(Debug, Clone)]
+pub struct CrelIterator<'data, Elf: FileHeader> {
+    data: Bytes<'data>,
+    is_rela: bool,
+    shift: u64,
+    remaining: u64,
+    r_offset: u64,
+    r_sym: u32,
+    r_type: u32,
+    r_addend: i64,
+    marker: PhantomData<Elf>,
+}
+
+impl<'data, Elf: FileHeader> CrelIterator<'data, Elf> {
+    /// Create a new iterator given the section data.
+    pub fn new(data: &'data [u8]) -> read::Result<Self> {
+        let mut data = Bytes(data);
+        let header = data.read_uleb128().read_error("Invalid ELF CREL header")?;
+        Ok(CrelIterator {
+            data,
+            is_rela: header & elf::CREL_HDR_ADDEND != 0,
+            shift: header & elf::CREL_HDR_SHIFT_MASK,
+            remaining: header >> 3,
+            r_offset: 0,
+            r_sym: 0,
+            r_type: 0,
+            r_addend: 0,
+            marker: PhantomData,
+        })
+    }
+
+    /// Return true if the relocations have explicit addends.
+    pub fn is_rela(&self) -> bool {
+        self.is_rela
+    }
+
+    /// Return the number of relocations that remain.
+    pub fn len(&self) -> usize {
+        self.remaining as usize
+    }
+
+    /// Return true if there are no more relocations.
+    pub fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Return the next relocation.
+    pub fn next(&mut self) -> read::Result<Option<Crel>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        let result = self.parse().map(Some);
+        if result.is_err() {
+            self.remaining = 0;
+        }
+        result
+    }
+
+    fn parse(&mut self) -> read::Result<Crel> {
+        let flag_bits = if self.is_rela { 3 } else { 2 };
+        let b = *self
+            .data
+            .read::<u8>()
+            .read_error("Invalid ELF CREL offset")?;
+        let mut delta = u64::from(b & 0x7f) >> flag_bits;
+        if b & 0x80 != 0 {
+            let high = self
+                .data
+                .read_uleb128()
+                .read_error("Invalid ELF CREL offset")?;
+            delta |= high.wrapping_shl(7 - flag_bits);
+        }
+        self.r_offset = self.r_offset.wrapping_add(delta);
+        if b & 1 != 0 {
+            let delta = self
+                .data
+                .read_sleb128()
+                .read_error("Invalid ELF CREL symbol")?;
+            self.r_sym = self.r_sym.wrapping_add(delta as u32);
+        }
+        if b & 2 != 0 {
+            let delta = self
+                .data
+                .read_sleb128()
+                .read_error("Invalid ELF CREL type")?;
+            self.r_type = self.r_type.wrapping_add(delta as u32);
+        }
+        if b & 4 != 0 && self.is_rela {
+            let delta = self
+                .data
+                .read_sleb128()
+                .read_error("Invalid ELF CREL addend")?;
+            self.r_addend = self.r_addend.wrapping_add(delta);
+        }
+        self.remaining -= 1;
+
+        let r_offset = self.r_offset.wrapping_shl(self.shift as u32);
+        Ok(if Elf::is_type_64_sized() {
+            Crel {
+                r_offset,
+                r_sym: self.r_sym,
+                r_type: self.r_type,
+                r_addend: self.r_addend,
+            }
+        } else {
+            Crel {
+                r_offset: u64::from(r_offset as u32),
+                r_sym: self.r_sym,
+                r_type: self.r_type,
+                r_addend: i64::from(self.r_addend as i32),
+            }
+        })
+    }
+}
+
+impl<'data, Elf: FileHeader> Iterator for CrelIterator<'data, Elf> {
+    type Item = read::Result<Crel>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next().transpose()
+    }
+}
@@ -11,9 +11,9 @@ use crate::read::{
 };
 
 use super::{
-    AttributesSection, CompressionHeader, ElfFile, ElfSectionRelocationIterator, FileHeader,
-    GnuHashTable, HashTable, NoteIterator, RelocationSections, RelrIterator, SymbolTable,
-    VerdefIterator, VerneedIterator, VersionTable,
+    AndroidRelocationIterator, ArmExidxTable, AttributesSection, CompressionHeader, CrelIterator,
+    ElfFile, ElfSectionRelocationIterator, FileHeader, GnuHashTable, HashTable, NoteIterator,
+    RelocationSections, RelrIterator, SymbolTable, VerdefIterator, VerneedIterator, VersionTable,
 };
 
 /// The table of section headers in an ELF file.
@@ -481,6 +481,19 @@ impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> ElfSection<'data, 'file,
         Ok(rela)
     }
 
+    /// Parse the ARM EHABI exception index table in this section.
+    ///
+    /// Returns `None` if this is not an ARM `SHT_ARM_EXIDX` section.
+    pub fn arm_exidx(&self) -> read::Result<Option<ArmExidxTable<'data, 'file, Elf, R>>> {
+        let endian = self.file.endian;
+        if self.file.header.e_machine(endian) != elf::EM_ARM
+            || self.section.sh_type(endian) != elf::SHT_ARM_EXIDX
+        {
+            return Ok(None);
+        }
+        ArmExidxTable::parse(self).map(Some)
+    }
+
     fn bytes(&self) -> read::Result<&'data [u8]> {
         self.section
             .data(self.file.endian, self.file.data)
@@ -656,7 +669,11 @@ where
             | elf::SHT_DYNSYM
             | elf::SHT_GROUP
             | elf::SHT_SYMTAB_SHNDX
-            | elf::SHT_RELR => SectionKind::Metadata,
+            | elf::SHT_RELR
+            | elf::SHT_CREL
+            | elf::SHT_ANDROID_REL
+            | elf::SHT_ANDROID_RELA
+            | elf::SHT_ANDROID_RELR => SectionKind::Metadata,
             _ => SectionKind::Elf(sh_type),
         }
     }
@@ -666,6 +683,7 @@ where
             section_index: self.index,
             file: self.file,
             relocations: None,
+            crel: None,
         }
     }
 
@@ -856,6 +874,8 @@ pub trait SectionHeader: Debug + Pod {
 
     /// Return the `Elf::Relr` entries in the section.
     ///
+    /// This supports both `SHT_RELR` and `SHT_ANDROID_RELR`.
+    ///
     /// Returns `Ok(None)` if the section does not contain relative relocations.
     /// Returns `Err` for invalid values.
     fn relr<'data, R: ReadRef<'data>>(
@@ -863,7 +883,8 @@ pub trait SectionHeader: Debug + Pod {
         endian: Self::Endian,
         data: R,
     ) -> read::Result<Option<RelrIterator<'data, Self::Elf>>> {
-        if self.sh_type(endian) != elf::SHT_RELR {
+        let sh_type = self.sh_type(endian);
+        if sh_type != elf::SHT_RELR && sh_type != elf::SHT_ANDROID_RELR {
             return Ok(None);
         }
         let data = self
@@ -873,6 +894,50 @@ pub trait SectionHeader: Debug + Pod {
         Ok(Some(relrs))
     }
 
+    /// Return an iterator for the packed relocations in the section.
+    ///
+    /// Also returns the linked symbol table index.
+    ///
+    /// Returns `Ok(None)` if the section type is not `SHT_ANDROID_REL` or `SHT_ANDROID_RELA`.
+    /// Returns `Err` for invalid values.
+    fn android_relocations<'data, R: ReadRef<'data>>(
+        &self,
+        endian: Self::Endian,
+        data: R,
+    ) -> read::Result<Option<(AndroidRelocationIterator<'data, Self::Elf>, SectionIndex)>> {
+        let is_rela = match self.sh_type(endian) {
+            elf::SHT_ANDROID_REL => false,
+            elf::SHT_ANDROID_RELA => true,
+            _ => return Ok(None),
+        };
+        let data = self
+            .data(endian, data)
+            .read_error("Invalid ELF relocation section offset or size")?;
+        let relocations = AndroidRelocationIterator::new(data, is_rela)?;
+        Ok(Some((relocations, self.link(endian))))
+    }
+
+    /// Return an iterator for the compact relocations in the section.
+    ///
+    /// Also returns the linked symbol table index.
+    ///
+    /// Returns `Ok(None)` if the section type is not `SHT_CREL`.
+    /// Returns `Err` for invalid values.
+    fn crel<'data, R: ReadRef<'data>>(
+        &self,
+        endian: Self::Endian,
+        data: R,
+    ) -> read::Result<Option<(CrelIterator<'data, Self::Elf>, SectionIndex)>> {
+        if self.sh_type(endian) != elf::SHT_CREL {
+            return Ok(None);
+        }
+        let data = self
+            .data(endian, data)
+            .read_error("Invalid ELF relocation section offset or size")?;
+        let relocations = CrelIterator::new(data)?;
+        Ok(Some((relocations, self.link(endian))))
+    }
+
     /// Return entries in a dynamic section.
     ///
     /// Also returns the linked string table index.
@@ -1,19 +1,20 @@
 use alloc::fmt;
 use alloc::vec::Vec;
+use core::convert::TryFrom;
 use core::fmt::Debug;
 use core::slice;
 use core::str;
 
 use crate::elf;
-use crate::endian::{self, Endianness, U32};
+use crate::endian::{self, Endianness, U32, U64};
 use crate::pod::Pod;
 use crate::read::util::StringTable;
 use crate::read::{
-    self, ObjectSymbol, ObjectSymbolTable, ReadError, ReadRef, SectionIndex, SymbolFlags,
-    SymbolIndex, SymbolKind, SymbolMap, SymbolMapEntry, SymbolScope, SymbolSection,
+    self, Bytes, ObjectSymbol, ObjectSymbolTable, ReadError, ReadRef, SectionIndex, SymbolFlags,
+    SymbolIndex, SymbolKind, SymbolMap, SymbolMapEntry, SymbolScope, SymbolSection, SymbolVersion,
 };
 
-use super::{FileHeader, SectionHeader, SectionTable};
+use super::{FileHeader, SectionHeader, SectionTable, VersionTable};
 
 /// A table of symbol entries in an ELF file.
 ///
@@ -88,6 +89,18 @@ impl<'data, Elf: FileHeader, R: ReadRef<'data>> SymbolTable<'data, Elf, R> {
         })
     }
 
+    /// Create a symbol table that is not associated with any sections.
+    pub(super) fn from_dynamic(
+        symbols: &'data [Elf::Sym],
+        strings: StringTable<'data, R>,
+    ) -> SymbolTable<'data, Elf, R> {
+        SymbolTable {
+            symbols,
+            strings,
+            ..Default::default()
+        }
+    }
+
     /// Return the section index of this symbol table.
     #[inline]
     pub fn section(&self) -> SectionIndex {
@@ -217,6 +230,54 @@ impl<'data, Elf: FileHeader, R: ReadRef<'data>> SymbolTable<'data, Elf, R> {
     }
 }
 
+/// The function descriptors in the `.opd` section of a PPC64 ELFv1 file.
+#[derive(Debug, Default, Clone, Copy)]
+pub(super) struct FunctionDescriptors<'data> {
+    address: u64,
+    data: Bytes<'data>,
+}
+
+impl<'data> FunctionDescriptors<'data> {
+    /// Find the function descriptors if the file uses the PPC64 ELFv1 ABI.
+    ///
+    /// Returns an empty table for all other files.
+    pub(super) fn parse<Elf: FileHeader, R: ReadRef<'data>>(
+        header: &Elf,
+        endian: Elf::Endian,
+        data: R,
+        sections: &SectionTable<'data, Elf, R>,
+    ) -> read::Result<Self> {
+        // Descriptors in relocatable objects are filled in by relocations.
+        if !header.is_type_64()
+            || header.e_machine(endian) != elf::EM_PPC64
+            || header.e_type(endian) == elf::ET_REL
+            || header.e_flags(endian) & elf::EF_PPC64_ABI == 2
+        {
+            return Ok(FunctionDescriptors::default());
+        }
+        let Some((_, section)) = sections.section_by_name(endian, b".opd") else {
+            return Ok(FunctionDescriptors::default());
+        };
+        let data = section
+            .data(endian, data)
+            .read_error("Invalid ELF .opd section offset or size")?;
+        Ok(FunctionDescriptors {
+            address: section.sh_addr(endian).into(),
+            data: Bytes(data),
+        })
+    }
+
+    /// Return the entry point address of the descriptor at the given address.
+    ///
+    /// Returns `None` if the address is not within the `.opd` section.
+    pub(super) fn entry_address<E: endian::Endian>(&self, endian: E, address: u64) -> Option<u64> {
+        let offset = address.checked_sub(self.address)?;
+        let offset = usize::try_from(offset).ok()?;
+        let entry = self.data.read_at::<U64<E>>(offset).ok()?;
+        Some(entry.get(endian))
+    }
+}
+
 /// A symbol table in an [`ElfFile32`](super::ElfFile32).
 pub type ElfSymbolTable32<'data, 'file, Endian = Endianness, R = &'data [u8]> =
     ElfSymbolTable<'data, 'file, elf::FileHeader32<Endian>, R>;
@@ -233,6 +294,8 @@ where
 {
     pub(super) endian: Elf::Endian,
     pub(super) symbols: &'file SymbolTable<'data, Elf, R>,
+    pub(super) versions: Option<&'file VersionTable<'data, Elf>>,
+    pub(super) descriptors: &'file FunctionDescriptors<'data>,
 }
 
 impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> read::private::Sealed
@@ -247,7 +310,7 @@ impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> ObjectSymbolTable<'data>
     type SymbolIterator = ElfSymbolIterator<'data, 'file, Elf, R>;
 
     fn symbols(&self) -> Self::SymbolIterator {
-        ElfSymbolIterator::new(self.endian, self.symbols)
+        ElfSymbolIterator::new(self.endian, self.symbols, self.versions, self.descriptors)
     }
 
     fn symbol_by_index(&self, index: SymbolIndex) -> read::Result<Self::Symbol> {
@@ -255,6 +318,8 @@ impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> ObjectSymbolTable<'data>
         Ok(ElfSymbol {
             endian: self.endian,
             symbols: self.symbols,
+            versions: self.versions,
+            descriptors: self.descriptors,
             index,
             symbol,
         })
@@ -276,6 +341,8 @@ where
 {
     endian: Elf::Endian,
     symbols: &'file SymbolTable<'data, Elf, R>,
+    versions: Option<&'file VersionTable<'data, Elf>>,
+    descriptors: &'file FunctionDescriptors<'data>,
     index: SymbolIndex,
 }
 
@@ -284,10 +351,17 @@ where
     Elf: FileHeader,
     R: ReadRef<'data>,
 {
-    pub(super) fn new(endian: Elf::Endian, symbols: &'file SymbolTable<'data, Elf, R>) -> Self {
+    pub(super) fn new(
+        endian: Elf::Endian,
+        symbols: &'file SymbolTable<'data, Elf, R>,
+        versions: Option<&'file VersionTable<'data, Elf>>,
+        descriptors: &'file FunctionDescriptors<'data>,
+    ) -> Self {
         ElfSymbolIterator {
             endian,
             symbols,
+            versions,
+            descriptors,
             index: SymbolIndex(1),
         }
     }
@@ -313,6 +387,8 @@ impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> Iterator
         Some(ElfSymbol {
             endian: self.endian,
             symbols: self.symbols,
+            versions: self.versions,
+            descriptors: self.descriptors,
             index,
             symbol,
         })
@@ -337,6 +413,8 @@ where
 {
     pub(super) endian: Elf::Endian,
     pub(super) symbols: &'file SymbolTable<'data, Elf, R>,
+    pub(super) versions: Option<&'file VersionTable<'data, Elf>>,
+    pub(super) descriptors: &'file FunctionDescriptors<'data>,
     pub(super) index: SymbolIndex,
     pub(super) symbol: &'data Elf::Sym,
 }
@@ -358,6 +436,24 @@ impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> ElfSymbol<'data, 'file, E
     pub fn elf_symbol(&self) -> &'data Elf::Sym {
         self.symbol
     }
+
+    /// Get the entry point address of the symbol.
+    ///
+    /// For function symbols in PPC64 ELFv1 files, [`ObjectSymbol::address`] is the
+    /// address of a function descriptor in the `.opd` section. This returns the
+    /// code address that is stored in that descriptor.
+    ///
+    /// For all other symbols, this is the same as [`ObjectSymbol::address`].
+    pub fn entry_address(&self) -> u64 {
+        let address = self.address();
+        match self.symbol.st_type() {
+            elf::STT_FUNC | elf::STT_GNU_IFUNC => self
+                .descriptors
+                .entry_address(self.endian, address)
+                .unwrap_or(address),
+            _ => address,
+        }
+    }
 }
 
 impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> read::private::Sealed
@@ -484,6 +580,13 @@ impl<'data, 'file, Elf: FileHeader, R: ReadRef<'data>> ObjectSymbol<'data>
             st_other: self.symbol.st_other(),
         }
     }
+
+    fn version(&self) -> read::Result<Option<SymbolVersion<'data>>> {
+        match self.versions {
+            Some(versions) => versions.symbol_version(self.endian, self.index),
+            None => Ok(None),
+        }
+    }
 }
 
 /// A trait for generic access to [`elf::Sym32`] and [`elf::Sym64`].
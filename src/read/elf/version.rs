@@ -1,6 +1,8 @@
 use alloc::vec::Vec;
 
-use crate::read::{Bytes, ReadError, ReadRef, Result, StringTable, SymbolIndex};
+use crate::read::{
+    ByteString, Bytes, ReadError, ReadRef, Result, StringTable, SymbolIndex, SymbolVersion,
+};
 use crate::{elf, endian};
 
 use super::FileHeader;
@@ -197,6 +199,27 @@ impl<'data, Elf: FileHeader> VersionTable<'data, Elf> {
             .map(Some)
     }
 
+    /// Return the version of a symbol for the unified read API.
+    ///
+    /// Returns `Ok(None)` for local and global versions.
+    /// Returns `Err(_)` if the version index is invalid.
+    pub(super) fn symbol_version(
+        &self,
+        endian: Elf::Endian,
+        index: SymbolIndex,
+    ) -> Result<Option<SymbolVersion<'data>>> {
+        let version_index = self.version_index(endian, index);
+        let Some(version) = self.version(version_index)? else {
+            return Ok(None);
+        };
+        let hidden = version_index.is_hidden();
+        Ok(Some(SymbolVersion {
+            name: ByteString(version.name()),
+            hidden,
+            default: !hidden && version.file().is_none(),
+        }))
+    }
+
     /// Return true if the given symbol index satisfies the requirements of `need`.
     ///
     /// Returns false for any error.
@@ -440,6 +440,7 @@ where
                 imports.push(Import {
                     name: ByteString(name),
                     library: ByteString(library),
+                    version: None,
                 });
             }
         }
@@ -469,6 +470,7 @@ where
                 exports.push(Export {
                     name: ByteString(name),
                     address,
+                    version: None,
                 });
             }
         }
@@ -85,6 +85,11 @@ mod any;
 ))]
 pub use any::*;
 
+#[cfg(feature = "elf")]
+mod relocate;
+#[cfg(feature = "elf")]
+pub use relocate::*;
+
 #[cfg(feature = "archive")]
 pub mod archive;
 
@@ -368,7 +373,7 @@ impl FileKind {
             #[cfg(feature = "xcoff")]
             [0x01, 0xf7, ..] => FileKind::Xcoff64,
             #[cfg(feature = "pef")]
-            [0x4A, 0x6F, 0x79, 0x21, ..] => FileKind::Pef,
+            [b'J', b'o', b'y', b'!', b'p', b'e', b'f', b'f'] => FileKind::Pef,
             _ => return Err(Error("Unknown file magic")),
         };
         Ok(kind)
@@ -630,6 +635,44 @@ impl<'data> ObjectMapFile<'data> {
     }
 }
 
+/// The version of a symbol.
+///
+/// Currently this is only used for ELF files with GNU symbol versioning.
+///
+/// Returned by [`ObjectSymbol::version`], [`Import::version`] and [`Export::version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolVersion<'data> {
+    name: ByteString<'data>,
+    hidden: bool,
+    default: bool,
+}
+
+impl<'data> SymbolVersion<'data> {
+    /// The version name.
+    #[inline]
+    pub fn name(&self) -> &'data [u8] {
+        self.name.0
+    }
+
+    /// Return true if the version is hidden.
+    ///
+    /// A symbol with a hidden version can only be referenced by specifying the version.
+    #[inline]
+    pub fn is_hidden(&self) -> bool {
+        self.hidden
+    }
+
+    /// Return true if this is the default version of a symbol definition.
+    ///
+    /// This is false for references to versions in other files.
+    ///
+    /// Tools such as `nm` display the default version using `@@` instead of `@`.
+    #[inline]
+    pub fn is_default(&self) -> bool {
+        self.default
+    }
+}
+
 /// An imported symbol.
 ///
 /// Returned by [`Object::imports`].
@@ -638,6 +681,7 @@ pub struct Import<'data> {
     library: ByteString<'data>,
     // TODO: or ordinal
     name: ByteString<'data>,
+    version: Option<SymbolVersion<'data>>,
 }
 
 impl<'data> Import<'data> {
@@ -652,6 +696,12 @@ impl<'data> Import<'data> {
     pub fn library(&self) -> &'data [u8] {
         self.library.0
     }
+
+    /// The version of the symbol to import.
+    #[inline]
+    pub fn version(&self) -> Option<SymbolVersion<'data>> {
+        self.version
+    }
 }
 
 /// An exported symbol.
@@ -662,6 +712,7 @@ pub struct Export<'data> {
     // TODO: and ordinal?
     name: ByteString<'data>,
     address: u64,
+    version: Option<SymbolVersion<'data>>,
 }
 
 impl<'data> Export<'data> {
@@ -676,6 +727,12 @@ impl<'data> Export<'data> {
     pub fn address(&self) -> u64 {
         self.address
     }
+
+    /// The version of the exported symbol.
+    #[inline]
+    pub fn version(&self) -> Option<SymbolVersion<'data>> {
+        self.version
+    }
 }
 
 /// PDB information from the debug directory in a PE file.
@@ -320,6 +320,7 @@ where
                         imports.push(Import {
                             library: ByteString(library),
                             name: ByteString(name),
+                            version: None,
                         });
                     }
                 }
@@ -338,6 +339,7 @@ where
                     exports.push(Export {
                         name: ByteString(name),
                         address: self.common.image_base.wrapping_add(address.into()),
+                        version: None,
                     })
                 }
             }
@@ -0,0 +1,245 @@
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+use crate::read::{Object, ReadRef, Result};
+
+use super::PefFile;
+
+/// The name of the Mac OS 8/9 Toolbox library.
+pub const INTERFACE_LIB: &[u8] = b"InterfaceLib";
+/// The name of the Carbon library.
+pub const CARBON_LIB: &[u8] = b"CarbonLib";
+
+/// The InterfaceLib routines that are also supported by Carbon.
+///
+/// The names follow a header of comment lines, one per line and sorted by byte value.
+static CARBON_SYMBOLS: &str = include_str!("carbon.txt");
+
+/// InterfaceLib routines that are known to be unsupported by Carbon.
+///
+/// These are the Toolbox initialization routines, which Carbon performs
+/// automatically, and the Standard File Package, which is replaced by
+/// Navigation Services. Sorted by byte value.
+static UNSUPPORTED_CARBON_SYMBOLS: &[&[u8]] = &[
+    b"CustomGetFile",
+    b"CustomPutFile",
+    b"InitDialogs",
+    b"InitFonts",
+    b"InitGraf",
+    b"InitMenus",
+    b"InitWindows",
+    b"SFGetFile",
+    b"SFPGetFile",
+    b"SFPPutFile",
+    b"SFPutFile",
+    b"StandardGetFile",
+    b"StandardPutFile",
+    b"TEInit",
+];
+
+/// Whether an InterfaceLib routine is supported by Carbon.
+///
+/// Returned by [`carbon_support`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CarbonSupport {
+    /// The routine is in the table of routines that are supported by Carbon.
+    Supported,
+    /// The routine is known to be unsupported by Carbon.
+    Unsupported,
+    /// The routine is in neither table.
+    ///
+    /// The table of supported routines only contains the commonly used
+    /// routines, so many of these are also supported.
+    Unknown,
+}
+
+/// Return whether the InterfaceLib routine with the given name is supported by Carbon.
+pub fn carbon_support(name: &[u8]) -> CarbonSupport {
+    if is_carbon_symbol(name) {
+        CarbonSupport::Supported
+    } else if UNSUPPORTED_CARBON_SYMBOLS.binary_search(&name).is_ok() {
+        CarbonSupport::Unsupported
+    } else {
+        CarbonSupport::Unknown
+    }
+}
+
+/// Return true if the InterfaceLib routine with the given name is known to be
+/// supported by Carbon.
+///
+/// This uses a table of the commonly used Toolbox routines in the Carbon
+/// specification, so a return value of false does not mean that the routine is
+/// unsupported. Use [`carbon_support`] to distinguish unsupported routines.
+pub fn is_carbon_symbol(name: &[u8]) -> bool {
+    // Binary search the lines of the table without splitting it first.
+    let mut table = carbon_table();
+    while !table.is_empty() {
+        let middle = table.len() / 2;
+        let start = table[..middle]
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |index| index + 1);
+        let end = table[middle..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(table.len(), |index| middle + index);
+        match table[start..end].cmp(name) {
+            Ordering::Less => table = table.get(end + 1..).unwrap_or(&[]),
+            Ordering::Greater => table = &table[..start],
+            Ordering::Equal => return true,
+        }
+    }
+    false
+}
+
+/// Return the sorted names in [`CARBON_SYMBOLS`], without the header.
+fn carbon_table() -> &'static [u8] {
+    let mut table = CARBON_SYMBOLS.as_bytes();
+    while table.is_empty() || table[0] == b'#' || table[0] == b'\n' {
+        match table.iter().position(|&byte| byte == b'\n') {
+            Some(index) => table = &table[index + 1..],
+            None => return &[],
+        }
+    }
+    table
+}
+
+/// How a PEF fragment uses the Mac OS Toolbox.
+///
+/// Returned by [`CarbonReport::compatibility`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CarbonCompatibility {
+    /// The fragment imports neither InterfaceLib nor CarbonLib.
+    None,
+    /// The fragment imports InterfaceLib but not CarbonLib, so it requires the Classic environment.
+    ClassicOnly,
+    /// The fragment imports CarbonLib but not InterfaceLib.
+    Carbon,
+    /// The fragment imports both InterfaceLib and CarbonLib.
+    Mixed,
+}
+
+/// The result of checking the imports of a PEF fragment for Carbon compatibility.
+///
+/// Returned by [`PefFile::carbon_report`].
+#[derive(Debug, Clone)]
+pub struct CarbonReport<'data> {
+    compatibility: CarbonCompatibility,
+    unsupported: Vec<&'data [u8]>,
+    unknown: Vec<&'data [u8]>,
+}
+
+impl<'data> CarbonReport<'data> {
+    /// Return how the fragment uses the Toolbox.
+    #[inline]
+    pub fn compatibility(&self) -> CarbonCompatibility {
+        self.compatibility
+    }
+
+    /// Return the names of the InterfaceLib imports that are known to be
+    /// unsupported by Carbon.
+    ///
+    /// These must be removed before the fragment can be relinked against CarbonLib.
+    #[inline]
+    pub fn unsupported_imports(&self) -> &[&'data [u8]] {
+        &self.unsupported
+    }
+
+    /// Return the names of the InterfaceLib imports that are not in either table.
+    ///
+    /// These may or may not be supported by Carbon, and need to be checked
+    /// against the Carbon specification.
+    #[inline]
+    pub fn unknown_imports(&self) -> &[&'data [u8]] {
+        &self.unknown
+    }
+}
+
+impl<'data, R> PefFile<'data, R>
+where
+    R: ReadRef<'data>,
+{
+    /// Check the imports of the fragment for Carbon compatibility.
+    ///
+    /// A fragment is Carbonized if it imports the Toolbox from CarbonLib rather than
+    /// InterfaceLib. The InterfaceLib imports are also checked against tables of
+    /// the routines that are supported and unsupported by Carbon (see [`carbon_support`]).
+    pub fn carbon_report(&self) -> Result<CarbonReport<'data>> {
+        let mut interface_lib = false;
+        let mut carbon_lib = false;
+        let mut unsupported = Vec::new();
+        let mut unknown = Vec::new();
+        for import in self.imports()? {
+            let name = import.name();
+            match import.library() {
+                INTERFACE_LIB => {
+                    interface_lib = true;
+                    match carbon_support(name) {
+                        CarbonSupport::Supported => {}
+                        CarbonSupport::Unsupported => unsupported.push(name),
+                        CarbonSupport::Unknown => unknown.push(name),
+                    }
+                }
+                CARBON_LIB => carbon_lib = true,
+                _ => {}
+            }
+        }
+        let compatibility = match (interface_lib, carbon_lib) {
+            (false, false) => CarbonCompatibility::None,
+            (true, false) => CarbonCompatibility::ClassicOnly,
+            (false, true) => CarbonCompatibility::Carbon,
+            (true, true) => CarbonCompatibility::Mixed,
+        };
+        Ok(CarbonReport {
+            compatibility,
+            unsupported,
+            unknown,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn carbon_symbols() -> impl Iterator<Item = &'static [u8]> {
+        let table = carbon_table();
+        table
+            .strip_suffix(b"\n")
+            .unwrap_or(table)
+            .split(|&byte| byte == b'\n')
+    }
+
+    #[test]
+    fn carbon_table_sorted() {
+        let symbols: Vec<_> = carbon_symbols().collect();
+        assert!(!symbols[0].is_empty());
+        for pair in symbols.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} is not sorted", pair);
+        }
+    }
+
+    #[test]
+    fn carbon_table_search() {
+        for symbol in carbon_symbols() {
+            assert!(is_carbon_symbol(symbol));
+            let mut missing = symbol.to_vec();
+            missing.push(b'_');
+            assert!(!is_carbon_symbol(&missing));
+            assert!(!is_carbon_symbol(&symbol[..symbol.len() - 1]));
+        }
+        assert!(!is_carbon_symbol(b""));
+        assert!(!is_carbon_symbol(b"#"));
+    }
+
+    #[test]
+    fn unsupported_table_sorted() {
+        for pair in UNSUPPORTED_CARBON_SYMBOLS.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} is not sorted", pair);
+        }
+        for symbol in UNSUPPORTED_CARBON_SYMBOLS {
+            assert!(!is_carbon_symbol(symbol));
+            assert_eq!(carbon_support(symbol), CarbonSupport::Unsupported);
+        }
+    }
+}
@@ -1,22 +1,20 @@
-use core::fmt::Debug;
-use crate::pef::{self};
-use crate::endian::BigEndian as BE;
-use core::{slice, str};
-use core::marker::PhantomData;
 use alloc::vec::Vec;
-use crate::pef::PEFSectionHeader;
+use core::convert::{TryFrom, TryInto};
+use core::{slice, str};
+
+use crate::endian::BigEndian as BE;
+use crate::pef;
 use crate::read::{
-    self, Architecture, ComdatKind, CompressedData, CompressedFileRange, Error, Export, FileFlags,
-    Import, NoDynamicRelocationIterator, Object, ObjectComdat, ObjectKind, ObjectSection,
-    ObjectSegment, ObjectSymbol, ObjectSymbolTable, ReadError, ReadRef, Relocation, RelocationMap,
-    Result, SectionFlags, SectionIndex, SectionKind, SegmentFlags, SymbolFlags, SymbolIndex,
+    self, Architecture, ByteString, ComdatKind, Error, Export, FileFlags, Import,
+    NoDynamicRelocationIterator, Object, ObjectComdat, ObjectKind, ObjectSection, ObjectSymbol,
+    ObjectSymbolTable, ReadError, ReadRef, Result, SectionIndex, SymbolFlags, SymbolIndex,
     SymbolKind, SymbolScope, SymbolSection,
 };
 
 use super::{
-    PefSection, PefSectionIterator,
-    PefSegment, PefSegmentIterator, SectionTable,
-    PefRelocationIterator
+    evaluate_relocations, JumpTableEntry, LoaderRelocation, LoaderRelocationTarget, LoaderSection,
+    PefSection, PefSectionIterator, PefSegment, PefSegmentIterator, SectionTable,
+    TracebackTableIterator, TransitionVector,
 };
 
 /// A PEF image file.
@@ -24,10 +22,13 @@ use super::{
 /// Most functionality is provided by the [`Object`] trait implementation.
 #[derive(Debug)]
 pub struct PefFile<'data, R = &'data [u8]>
+where
+    R: ReadRef<'data>,
 {
     pub(super) header: &'data pef::PEFContainerHeader,
-    //pub(super) sections: Vec<SectionTable<'data>>,
-    //pub(super) symbols: PefSymbolTable<'data>,
+    pub(super) sections: SectionTable<'data, R>,
+    pub(super) loader: Option<LoaderSection<'data>>,
+    symbols: Vec<PefSymbolInternal<'data>>,
     pub(super) data: R,
 }
 
@@ -38,15 +39,356 @@ where
     /// Parse the raw PEF file data.
     pub fn parse(data: R) -> Result<Self> {
         let header = pef::PEFContainerHeader::parse(data)?;
-        //let sections = Vec::new();
-        //let symbols = Vec::new();
+        let sections = SectionTable::parse(header, data)?;
+        let loader = match sections.loader()? {
+            Some(section) => Some(LoaderSection::parse_section(data, section)?),
+            None => None,
+        };
+        let architecture = header.architecture.get(BE);
+        let mut symbols = match loader {
+            Some(ref loader) => parse_symbols(&sections, loader, data, architecture)?,
+            None => Vec::new(),
+        };
+        infer_symbol_sizes(&sections, data, architecture, &mut symbols);
         Ok(PefFile {
             header,
-            //sections,
-            //symbols,
+            sections,
+            loader,
+            symbols,
             data,
         })
     }
+
+    /// Return the container header.
+    #[inline]
+    pub fn pef_header(&self) -> &'data pef::PEFContainerHeader {
+        self.header
+    }
+
+    /// Return the section table.
+    #[inline]
+    pub fn pef_sections(&self) -> &SectionTable<'data, R> {
+        &self.sections
+    }
+
+    /// Return the loader section, if any.
+    #[inline]
+    pub fn pef_loader(&self) -> Option<&LoaderSection<'data>> {
+        self.loader.as_ref()
+    }
+
+    /// Evaluate the loader relocations for a section.
+    ///
+    /// `index` is the zero-based index used by the loader section.
+    /// Returns an empty list if there is no loader section.
+    pub fn loader_relocations(&self, index: SectionIndex) -> Result<Vec<LoaderRelocation>> {
+        match &self.loader {
+            Some(loader) => loader_relocations(&self.sections, loader, index),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Read the transition vector at the given offset in a section.
+    ///
+    /// `index` is the zero-based index used by the loader section.
+    /// The code and data pointers are resolved using the loader relocations.
+    /// Returns `Ok(None)` if the code pointer is not relocated relative to a section,
+    /// such as when it refers to an imported symbol.
+    pub fn transition_vector(
+        &self,
+        index: SectionIndex,
+        offset: u32,
+    ) -> Result<Option<TransitionVector>> {
+        let Some(loader) = &self.loader else {
+            return Ok(None);
+        };
+        let section = self.sections.section(index)?;
+        let contents = section.pef_unpacked_data(self.data)?;
+        let relocations = loader_relocations(&self.sections, loader, index)?;
+        read_transition_vector(&self.sections, &contents, offset, &relocations)
+    }
+
+    /// Read the jump table entry that a CFM-68K transition vector refers to.
+    ///
+    /// In segmented CFM-68K code, the code pointer of a transition vector refers
+    /// to the routine's entry in the jump table rather than to the routine itself.
+    /// Use [`JumpTableIterator`](super::JumpTableIterator) on the section data
+    /// starting at this entry to read the rest of the table.
+    ///
+    /// Returns `Ok(None)` if the code pointer does not refer to a jump table entry.
+    pub fn jump_table_entry(&self, tvector: &TransitionVector) -> Result<Option<JumpTableEntry>> {
+        // Section indices in the unified API start at 1.
+        let index = tvector
+            .code_section
+            .0
+            .checked_sub(1)
+            .read_error("Invalid PEF section index")?;
+        let section = self.sections.section(SectionIndex(index))?;
+        let contents = section.pef_unpacked_data(self.data)?;
+        let entry = tvector
+            .code
+            .checked_sub(section.default_address.get(BE).into())
+            .and_then(|offset| usize::try_from(offset).ok())
+            .and_then(|offset| contents.get(offset..))
+            .read_error("Invalid PEF jump table entry offset")?;
+        Ok(entry
+            .get(..pef::JT_ENTRY_SIZE)
+            .and_then(|entry| entry.try_into().ok())
+            .and_then(JumpTableEntry::parse))
+    }
+}
+
+fn loader_relocations<'data, R: ReadRef<'data>>(
+    sections: &SectionTable<'data, R>,
+    loader: &LoaderSection<'data>,
+    index: SectionIndex,
+) -> Result<Vec<LoaderRelocation>> {
+    let section = sections.section(index)?;
+    let mut relocations = Vec::new();
+    for header in loader.relocation_headers() {
+        if usize::from(header.section_index.get(BE)) == index.0 {
+            let blocks = loader.relocation_instructions(header)?;
+            relocations.extend(evaluate_relocations(blocks, section.total_size.get(BE))?);
+        }
+    }
+    Ok(relocations)
+}
+
+/// Read a transition vector at `offset` in `section`.
+///
+/// `contents` must be the unpacked data of the section, and `relocations` must be
+/// the loader relocations for the section.
+fn read_transition_vector<'data, R: ReadRef<'data>>(
+    sections: &SectionTable<'data, R>,
+    contents: &[u8],
+    offset: u32,
+    relocations: &[LoaderRelocation],
+) -> Result<Option<TransitionVector>> {
+    let offset = u64::from(offset);
+    let word = |offset: u64| -> Result<(u64, Option<SectionIndex>)> {
+        let value = usize::try_from(offset)
+            .ok()
+            .and_then(|offset| contents.get(offset..)?.get(..4))
+            .read_error("Invalid PEF transition vector offset")?;
+        let value = u32::from_be_bytes([value[0], value[1], value[2], value[3]]);
+        let target = relocations
+            .iter()
+            .find(|relocation| u64::from(relocation.offset) == offset)
+            .and_then(|relocation| match relocation.target {
+                LoaderRelocationTarget::Section(index) => Some(index),
+                LoaderRelocationTarget::Import(_) => None,
+            });
+        let Some(index) = target else {
+            return Ok((value.into(), None));
+        };
+        let base = sections.section(index)?.default_address.get(BE);
+        Ok((u64::from(value.wrapping_add(base)), Some(index)))
+    };
+    let (code, code_section) = word(offset)?;
+    let Some(code_section) = code_section else {
+        return Ok(None);
+    };
+    let (data, _) = word(offset + 4)?;
+    Ok(Some(TransitionVector {
+        code,
+        // Section indices in the unified API start at 1.
+        code_section: SectionIndex(code_section.0 + 1),
+        data,
+    }))
+}
+
+/// Build the symbols for the imports and exports in the loader section.
+///
+/// Imported symbols come first, so that the symbol index of an import
+/// matches its index in the loader relocations.
+///
+/// For CFM-68K, exported transition vectors are resolved to the code they refer to,
+/// since there are no function descriptors in conventional 68K code.
+fn parse_symbols<'data, R: ReadRef<'data>>(
+    sections: &SectionTable<'data, R>,
+    loader: &LoaderSection<'data>,
+    data: R,
+    architecture: u32,
+) -> Result<Vec<PefSymbolInternal<'data>>> {
+    let m68k = architecture == pef::ARCHITECTURE_68K;
+    // The unpacked data and evaluated relocations for each section, for resolving
+    // transition vectors.
+    let mut contents = Vec::new();
+    contents.resize_with(sections.len(), || None);
+    let mut relocations = Vec::new();
+    relocations.resize_with(sections.len(), || None);
+    let mut symbols = Vec::new();
+    for symbol in loader.imported_symbols() {
+        symbols.push(PefSymbolInternal {
+            name: loader.imported_symbol_name(symbol)?,
+            address: 0,
+            size: 0,
+            kind: symbol_kind(symbol.symbol_class(), architecture),
+            section: SymbolSection::Undefined,
+            scope: SymbolScope::Dynamic,
+            weak: symbol.symbol_flags() & pef::WEAK_IMPORT_SYMBOL != 0,
+            exact_size: false,
+        });
+    }
+    for library in loader.libraries() {
+        let library_symbols = loader.library_symbols(library)?;
+        if library.options & pef::WEAK_IMPORT_LIB != 0 {
+            let first = library.first_imported_symbol.get(BE) as usize;
+            let range = first..first + library_symbols.len();
+            for symbol in symbols.get_mut(range).unwrap_or(&mut []) {
+                symbol.weak = true;
+            }
+        }
+    }
+    for (index, symbol) in loader.exported_symbols().iter().enumerate() {
+        let value = symbol.symbol_value.get(BE);
+        let mut kind = symbol_kind(symbol.symbol_class(), architecture);
+        let (section, address) = match symbol.section_index.get(BE) {
+            pef::EXPORTED_ABSOLUTE_SECTION => (SymbolSection::Absolute, value.into()),
+            pef::EXPORTED_REEXPORTED_IMPORT_SECTION => (SymbolSection::Undefined, 0),
+            section_index => {
+                let section_index = usize::try_from(section_index)
+                    .ok()
+                    .read_error("Invalid PEF exported symbol section index")?;
+                let section = sections.section(SectionIndex(section_index))?;
+                let address = u64::from(section.default_address.get(BE)) + u64::from(value);
+                // Section indices in the unified API start at 1.
+                let mut resolved = (
+                    SymbolSection::Section(SectionIndex(section_index + 1)),
+                    address,
+                );
+                if m68k && symbol.symbol_class() == pef::TVECT_SYMBOL {
+                    // Malformed data or relocations leave the symbol at the transition vector.
+                    let contents = contents[section_index]
+                        .get_or_insert_with(|| section.pef_unpacked_data(data).unwrap_or_default());
+                    let relocations = relocations[section_index].get_or_insert_with(|| {
+                        loader_relocations(sections, loader, SectionIndex(section_index))
+                            .unwrap_or_default()
+                    });
+                    match read_transition_vector(sections, contents, value, relocations) {
+                        Ok(Some(tvector)) => {
+                            resolved = (SymbolSection::Section(tvector.code_section), tvector.code);
+                        }
+                        _ => kind = SymbolKind::Data,
+                    }
+                }
+                resolved
+            }
+        };
+        symbols.push(PefSymbolInternal {
+            name: loader.exported_symbol_name(index)?,
+            address,
+            size: 0,
+            kind,
+            section,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            exact_size: false,
+        });
+    }
+    Ok(symbols)
+}
+
+/// Add symbols for the named functions in traceback tables, and set the size
+/// of every symbol that is defined in a section.
+///
+/// The size is the function length from the traceback table if the symbol is
+/// at the start of a function. Otherwise it is the gap to the next symbol or
+/// function in the same section, or the gap to the end of the section.
+fn infer_symbol_sizes<'data, R: ReadRef<'data>>(
+    sections: &SectionTable<'data, R>,
+    data: R,
+    architecture: u32,
+    symbols: &mut Vec<PefSymbolInternal<'data>>,
+) {
+    let mut boundaries = symbols
+        .iter()
+        .filter_map(|symbol| match symbol.section {
+            SymbolSection::Section(index) => Some((index.0, symbol.address)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+
+    let mut functions = Vec::new();
+    for (index, section) in sections.enumerate() {
+        // Traceback tables are only used for PowerPC.
+        if section.kind() != Some(pef::SectionKind::Code) || architecture != pef::ARCHITECTURE_PPC {
+            continue;
+        }
+        let Ok(section_data) = section.pef_data(data) else {
+            continue;
+        };
+        let section_address = u64::from(section.default_address.get(BE));
+        // Section indices in the unified API start at 1.
+        let index = index.0 + 1;
+        for table in TracebackTableIterator::new(section_data) {
+            let address = section_address + u64::from(table.function_offset);
+            functions.push((index, address, u64::from(table.function_size)));
+            let Some(name) = table.name.filter(|name| !name.is_empty()) else {
+                continue;
+            };
+            if boundaries.binary_search(&(index, address)).is_ok() {
+                continue;
+            }
+            symbols.push(PefSymbolInternal {
+                name,
+                address,
+                size: 0,
+                kind: SymbolKind::Text,
+                section: SymbolSection::Section(SectionIndex(index)),
+                scope: SymbolScope::Compilation,
+                weak: false,
+                exact_size: false,
+            });
+        }
+    }
+    functions.sort_unstable();
+    boundaries.extend(
+        functions
+            .iter()
+            .map(|&(index, address, _)| (index, address)),
+    );
+    boundaries.sort_unstable();
+
+    for symbol in symbols {
+        let SymbolSection::Section(index) = symbol.section else {
+            continue;
+        };
+        let key = (index.0, symbol.address);
+        if let Ok(function) =
+            functions.binary_search_by_key(&key, |&(index, address, _)| (index, address))
+        {
+            symbol.size = functions[function].2;
+            symbol.exact_size = true;
+            continue;
+        }
+        let next = boundaries.partition_point(|&boundary| boundary <= key);
+        let end = match boundaries.get(next) {
+            Some(&(next_index, address)) if next_index == index.0 => address,
+            _ => index
+                .0
+                .checked_sub(1)
+                .and_then(|index| sections.section(SectionIndex(index)).ok())
+                .map_or(0, |section| {
+                    u64::from(section.default_address.get(BE)) + u64::from(section.pef_size())
+                }),
+        };
+        symbol.size = end.saturating_sub(symbol.address);
+    }
+}
+
+/// Return the symbol kind for a symbol class.
+///
+/// PowerPC transition vectors are function descriptors in the data section,
+/// but CFM-68K transition vectors are treated as functions.
+fn symbol_kind(class: u8, architecture: u32) -> SymbolKind {
+    match class {
+        pef::CODE_SYMBOL | pef::GLUE_SYMBOL => SymbolKind::Text,
+        pef::TVECT_SYMBOL if architecture == pef::ARCHITECTURE_68K => SymbolKind::Text,
+        pef::DATA_SYMBOL | pef::TVECT_SYMBOL | pef::TOC_SYMBOL => SymbolKind::Data,
+        _ => SymbolKind::Unknown,
+    }
 }
 
 impl pef::PEFContainerHeader {
@@ -64,11 +406,7 @@ impl pef::PEFContainerHeader {
     }
 }
 
-impl<'data, R> read::private::Sealed for PefFile<'data, R>
-where
-    R: ReadRef<'data>,
-{
-}
+impl<'data, R> read::private::Sealed for PefFile<'data, R> where R: ReadRef<'data> {}
 
 impl<'data, R: ReadRef<'data>> Object<'data> for PefFile<'data, R> {
     type Segment<'file>
@@ -146,7 +484,10 @@ impl<'data, R: ReadRef<'data>> Object<'data> for PefFile<'data, R> {
     }
 
     fn segments(&self) -> Self::SegmentIterator<'_> {
-        todo!();
+        PefSegmentIterator {
+            file: self,
+            iter: self.sections.iter(),
+        }
     }
 
     fn section_by_name_bytes<'file>(
@@ -158,32 +499,57 @@ impl<'data, R: ReadRef<'data>> Object<'data> for PefFile<'data, R> {
     }
 
     fn section_by_index(&self, index: SectionIndex) -> Result<PefSection<'data, '_, R>> {
-        todo!()
+        let section = index
+            .0
+            .checked_sub(1)
+            .read_error("Invalid PEF section index")
+            .and_then(|index| self.sections.section(SectionIndex(index)))?;
+        Ok(PefSection {
+            file: self,
+            index,
+            section,
+        })
     }
 
     fn sections(&self) -> Self::SectionIterator<'_> {
-        todo!()
+        PefSectionIterator {
+            file: self,
+            iter: self.sections.iter().enumerate(),
+        }
     }
 
     fn comdats(&self) -> Self::ComdatIterator<'_> {
-        todo!()
+        PefComdatIterator { file: self }
     }
 
     #[inline]
     fn symbol_by_index(&self, index: SymbolIndex) -> Result<PefSymbol<'data, '_>> {
-        todo!()
+        let symbol = self
+            .symbols
+            .get(index.0)
+            .read_error("Invalid PEF symbol index")?;
+        Ok(PefSymbol { index, symbol })
     }
 
     fn symbols(&self) -> Self::SymbolIterator<'_> {
-        todo!()
+        PefSymbolIterator {
+            symbols: self.symbols.iter().enumerate(),
+        }
     }
 
     fn symbol_table(&self) -> Option<PefSymbolTable<'data, '_>> {
-        todo!()
+        if self.loader.is_none() && self.symbols.is_empty() {
+            return None;
+        }
+        Some(PefSymbolTable {
+            symbols: &self.symbols,
+        })
     }
 
     fn dynamic_symbols(&self) -> Self::SymbolIterator<'_> {
-        todo!()
+        PefSymbolIterator {
+            symbols: [].iter().enumerate(),
+        }
     }
 
     #[inline]
@@ -197,24 +563,78 @@ impl<'data, R: ReadRef<'data>> Object<'data> for PefFile<'data, R> {
     }
 
     fn imports(&self) -> Result<Vec<Import<'data>>> {
-        todo!()
+        let mut imports = Vec::new();
+        if let Some(loader) = &self.loader {
+            for library in loader.libraries() {
+                let library_name = loader.library_name(library)?;
+                for symbol in loader.library_symbols(library)? {
+                    imports.push(Import {
+                        library: ByteString(library_name),
+                        name: ByteString(loader.imported_symbol_name(symbol)?),
+                        version: None,
+                    });
+                }
+            }
+        }
+        Ok(imports)
     }
 
+    /// Return the exports in the loader section.
+    ///
+    /// Re-exported imports are included, with an address of 0.
     fn exports(&self) -> Result<Vec<Export<'data>>> {
-        todo!()
+        let imports = self
+            .loader
+            .as_ref()
+            .map_or(0, |loader| loader.imported_symbols().len());
+        Ok(self
+            .symbols
+            .iter()
+            // Imported symbols come first. Functions found from traceback tables are local.
+            .skip(imports)
+            .filter(|symbol| symbol.scope == SymbolScope::Dynamic)
+            .map(|symbol| Export {
+                name: ByteString(symbol.name),
+                address: symbol.address,
+                version: None,
+            })
+            .collect())
     }
 
     fn has_debug_symbols(&self) -> bool {
-        todo!()
+        // Source-level debug information is normally in a separate xSYM file,
+        // see `XsymFile`.
+        self.sections
+            .iter()
+            .any(|section| section.kind() == Some(pef::SectionKind::Debug))
     }
 
     fn relative_address_base(&self) -> u64 {
-        todo!()
+        0
     }
 
-    #[inline]
+    /// Return the address of the main symbol, or 0 if there is none.
+    ///
+    /// For PowerPC this is the address of the transition vector.
+    /// For CFM-68K this is the address of the code that the transition vector refers to.
     fn entry(&self) -> u64 {
-        todo!()
+        let Some(loader) = &self.loader else {
+            return 0;
+        };
+        let header = loader.header();
+        let Ok(index) = usize::try_from(header.main_section.get(BE)) else {
+            return 0;
+        };
+        if self.header.architecture.get(BE) == pef::ARCHITECTURE_68K {
+            let offset = header.main_offset.get(BE);
+            if let Ok(Some(tvector)) = self.transition_vector(SectionIndex(index), offset) {
+                return tvector.code;
+            }
+        }
+        let Ok(section) = self.sections.section(SectionIndex(index)) else {
+            return 0;
+        };
+        u64::from(section.default_address.get(BE)) + u64::from(header.main_offset.get(BE))
     }
 
     #[inline]
@@ -227,12 +647,15 @@ impl<'data, R: ReadRef<'data>> Object<'data> for PefFile<'data, R> {
 ///
 /// This is a stub that doesn't implement any functionality.
 #[derive(Debug)]
-pub struct PefComdatIterator<'data, 'file, R = &'data [u8]> {
+pub struct PefComdatIterator<'data, 'file, R = &'data [u8]>
+where
+    R: ReadRef<'data>,
+{
     #[allow(unused)]
     file: &'file PefFile<'data, R>,
 }
 
-impl<'data, 'file, R> Iterator for PefComdatIterator<'data, 'file, R> {
+impl<'data, 'file, R: ReadRef<'data>> Iterator for PefComdatIterator<'data, 'file, R> {
     type Item = PefComdat<'data, 'file, R>;
 
     #[inline]
@@ -245,39 +668,41 @@ impl<'data, 'file, R> Iterator for PefComdatIterator<'data, 'file, R> {
 ///
 /// This is a stub that doesn't implement any functionality.
 #[derive(Debug)]
-pub struct PefComdat<'data, 'file, R = &'data [u8]> {
-    #[allow(unused)]
+pub struct PefComdat<'data, 'file, R = &'data [u8]>
+where
+    R: ReadRef<'data>,
+{
     file: &'file PefFile<'data, R>,
 }
 
-impl<'data, 'file, R> read::private::Sealed for PefComdat<'data, 'file, R> {}
+impl<'data, 'file, R: ReadRef<'data>> read::private::Sealed for PefComdat<'data, 'file, R> {}
 
-impl<'data, 'file, R> ObjectComdat<'data> for PefComdat<'data, 'file, R> {
+impl<'data, 'file, R: ReadRef<'data>> ObjectComdat<'data> for PefComdat<'data, 'file, R> {
     type SectionIterator = PefComdatSectionIterator<'data, 'file, R>;
 
     #[inline]
     fn kind(&self) -> ComdatKind {
-        unreachable!();
+        ComdatKind::Unknown
     }
 
     #[inline]
     fn symbol(&self) -> SymbolIndex {
-        unreachable!();
+        SymbolIndex(0)
     }
 
     #[inline]
     fn name_bytes(&self) -> Result<&'data [u8]> {
-        unreachable!();
+        Err(Error("PEF does not support COMDAT section groups"))
     }
 
     #[inline]
     fn name(&self) -> Result<&'data str> {
-        unreachable!();
+        Err(Error("PEF does not support COMDAT section groups"))
     }
 
     #[inline]
     fn sections(&self) -> Self::SectionIterator {
-        unreachable!();
+        PefComdatSectionIterator { file: self.file }
     }
 }
 
@@ -285,12 +710,15 @@ impl<'data, 'file, R> ObjectComdat<'data> for PefComdat<'data, 'file, R> {
 ///
 /// This is a stub that doesn't implement any functionality.
 #[derive(Debug)]
-pub struct PefComdatSectionIterator<'data, 'file, R = &'data [u8]> {
+pub struct PefComdatSectionIterator<'data, 'file, R = &'data [u8]>
+where
+    R: ReadRef<'data>,
+{
     #[allow(unused)]
     file: &'file PefFile<'data, R>,
 }
 
-impl<'data, 'file, R> Iterator for PefComdatSectionIterator<'data, 'file, R> {
+impl<'data, 'file, R: ReadRef<'data>> Iterator for PefComdatSectionIterator<'data, 'file, R> {
     type Item = SectionIndex;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -354,12 +782,14 @@ pub struct PefSymbol<'data, 'file> {
 
 #[derive(Clone, Debug)]
 struct PefSymbolInternal<'data> {
-    name: &'data str,
+    name: &'data [u8],
     address: u64,
     size: u64,
     kind: SymbolKind,
     section: SymbolSection,
     scope: SymbolScope,
+    weak: bool,
+    exact_size: bool,
 }
 
 impl<'data, 'file> read::private::Sealed for PefSymbol<'data, 'file> {}
@@ -372,12 +802,14 @@ impl<'data, 'file> ObjectSymbol<'data> for PefSymbol<'data, 'file> {
 
     #[inline]
     fn name_bytes(&self) -> read::Result<&'data [u8]> {
-        Ok(self.symbol.name.as_bytes())
+        Ok(self.symbol.name)
     }
 
     #[inline]
     fn name(&self) -> read::Result<&'data str> {
-        Ok(self.symbol.name)
+        str::from_utf8(self.symbol.name)
+            .ok()
+            .read_error("Non UTF-8 PEF symbol name")
     }
 
     #[inline]
@@ -390,6 +822,15 @@ impl<'data, 'file> ObjectSymbol<'data> for PefSymbol<'data, 'file> {
         self.symbol.size
     }
 
+    /// Return true if the symbol size was read from a traceback table.
+    ///
+    /// PEF exports don't record a size, so otherwise the size is inferred from
+    /// the address of the next symbol or the end of the section.
+    #[inline]
+    fn is_size_exact(&self) -> bool {
+        self.symbol.exact_size
+    }
+
     #[inline]
     fn kind(&self) -> SymbolKind {
         self.symbol.kind
@@ -418,7 +859,7 @@ impl<'data, 'file> ObjectSymbol<'data> for PefSymbol<'data, 'file> {
 
     #[inline]
     fn is_weak(&self) -> bool {
-        false
+        self.symbol.weak
     }
 
     #[inline]
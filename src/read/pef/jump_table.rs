@@ -0,0 +1,85 @@
+use core::convert::TryInto;
+
+use crate::pef;
+
+/// An entry in a CFM-68K jump table.
+///
+/// Segmented CFM-68K code uses jump table entries in the same format as the
+/// classic 68K segment loader. Each entry is either unloaded, in which case it
+/// calls `_LoadSeg` to load the segment, or loaded, in which case it jumps
+/// directly to the routine.
+///
+/// The code pointer in the transition vector of a segmented routine refers to
+/// the routine's jump table entry. Use
+/// [`PefFile::jump_table_entry`](super::PefFile::jump_table_entry) to read it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpTableEntry {
+    /// The segment containing the routine has not been loaded.
+    Unloaded {
+        /// The offset of the routine within its segment.
+        offset: u16,
+        /// The segment number.
+        segment: u16,
+    },
+    /// The segment containing the routine has been loaded.
+    Loaded {
+        /// The segment number.
+        segment: u16,
+        /// The address of the routine.
+        address: u32,
+    },
+}
+
+impl JumpTableEntry {
+    /// Parse a jump table entry.
+    ///
+    /// Returns `None` if `data` is not a jump table entry.
+    pub fn parse(data: &[u8; pef::JT_ENTRY_SIZE]) -> Option<Self> {
+        let word = |index: usize| u16::from_be_bytes([data[index], data[index + 1]]);
+        if word(2) == pef::JT_MOVE_W_IMM_SP && word(6) == pef::JT_LOAD_SEG {
+            Some(JumpTableEntry::Unloaded {
+                offset: word(0),
+                segment: word(4),
+            })
+        } else if word(2) == pef::JT_JMP_ABS_L {
+            Some(JumpTableEntry::Loaded {
+                segment: word(0),
+                address: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// An iterator over the entries in a CFM-68K jump table.
+///
+/// Iteration stops at the first entry that is not a valid jump table entry.
+///
+/// The jump table is in a code section. The code pointer of a transition vector
+/// for a segmented routine gives the offset of an entry within the table.
+#[derive(Debug, Clone)]
+pub struct JumpTableIterator<'data> {
+    data: &'data [u8],
+}
+
+impl<'data> JumpTableIterator<'data> {
+    /// Construct a new iterator for the given jump table data.
+    pub fn new(data: &'data [u8]) -> Self {
+        JumpTableIterator { data }
+    }
+}
+
+impl<'data> Iterator for JumpTableIterator<'data> {
+    type Item = JumpTableEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.data.get(..pef::JT_ENTRY_SIZE)?;
+        let entry = JumpTableEntry::parse(entry.try_into().ok()?);
+        self.data = match entry {
+            Some(_) => &self.data[pef::JT_ENTRY_SIZE..],
+            None => &[],
+        };
+        entry
+    }
+}
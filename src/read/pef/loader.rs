@@ -0,0 +1,218 @@
+use crate::endian::{BigEndian as BE, U16};
+use crate::pef;
+use crate::read::{Bytes, Error, ReadError, ReadRef, Result};
+
+use super::RelocationInstructionIterator;
+
+/// The contents of the loader section in a PEF container.
+///
+/// This contains the imported libraries and symbols, the relocations,
+/// the exported symbols, and the entry points.
+#[derive(Debug, Clone, Copy)]
+pub struct LoaderSection<'data> {
+    data: Bytes<'data>,
+    header: &'data pef::PEFLoaderInfoHeader,
+    libraries: &'data [pef::PEFImportedLibrary],
+    imported_symbols: &'data [pef::PEFImportedSymbol],
+    relocation_headers: &'data [pef::PEFLoaderRelocationHeader],
+    strings: Bytes<'data>,
+    hash_slots: &'data [pef::PEFExportedSymbolHashSlot],
+    export_keys: &'data [pef::PEFExportedSymbolKey],
+    exported_symbols: &'data [pef::PEFExportedSymbol],
+}
+
+impl<'data> LoaderSection<'data> {
+    /// Parse the loader section.
+    ///
+    /// `data` must be the contents of the loader section.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let data = Bytes(data);
+        let mut tail = data;
+        let header = tail
+            .read::<pef::PEFLoaderInfoHeader>()
+            .read_error("Invalid PEF loader info header size or alignment")?;
+        let libraries = tail
+            .read_slice(header.imported_library_count.get(BE) as usize)
+            .read_error("Invalid PEF imported library count")?;
+        let imported_symbols = tail
+            .read_slice(header.total_imported_symbol_count.get(BE) as usize)
+            .read_error("Invalid PEF imported symbol count")?;
+        let relocation_headers = tail
+            .read_slice(header.reloc_section_count.get(BE) as usize)
+            .read_error("Invalid PEF relocation section count")?;
+
+        // The string table has no explicit size, so bound it by the end of the section.
+        let mut strings = data;
+        strings
+            .skip(header.loader_strings_offset.get(BE) as usize)
+            .read_error("Invalid PEF loader strings offset")?;
+
+        let mut tail = data;
+        tail.skip(header.export_hash_offset.get(BE) as usize)
+            .read_error("Invalid PEF export hash offset")?;
+        let power = header.export_hash_table_power.get(BE);
+        if power > 31 {
+            return Err(Error("Invalid PEF export hash table power"));
+        }
+        let hash_slots = tail
+            .read_slice(1 << power)
+            .read_error("Invalid PEF export hash table size")?;
+        let export_count = header.exported_symbol_count.get(BE) as usize;
+        let export_keys = tail
+            .read_slice(export_count)
+            .read_error("Invalid PEF exported symbol count")?;
+        let exported_symbols = tail
+            .read_slice(export_count)
+            .read_error("Invalid PEF exported symbol count")?;
+
+        Ok(LoaderSection {
+            data,
+            header,
+            libraries,
+            imported_symbols,
+            relocation_headers,
+            strings,
+            hash_slots,
+            export_keys,
+            exported_symbols,
+        })
+    }
+
+    /// Parse the loader section of a PEF container.
+    ///
+    /// `data` must be the entire container data.
+    /// `section` must be the loader section header.
+    pub fn parse_section<R: ReadRef<'data>>(
+        data: R,
+        section: &pef::PEFSectionHeader,
+    ) -> Result<Self> {
+        Self::parse(section.pef_data(data)?)
+    }
+
+    /// Return the loader info header.
+    #[inline]
+    pub fn header(&self) -> &'data pef::PEFLoaderInfoHeader {
+        self.header
+    }
+
+    /// Return the imported libraries.
+    #[inline]
+    pub fn libraries(&self) -> &'data [pef::PEFImportedLibrary] {
+        self.libraries
+    }
+
+    /// Return all of the imported symbols.
+    #[inline]
+    pub fn imported_symbols(&self) -> &'data [pef::PEFImportedSymbol] {
+        self.imported_symbols
+    }
+
+    /// Return the imported symbols for the given library.
+    pub fn library_symbols(
+        &self,
+        library: &pef::PEFImportedLibrary,
+    ) -> Result<&'data [pef::PEFImportedSymbol]> {
+        let first = library.first_imported_symbol.get(BE) as usize;
+        let count = library.imported_symbol_count.get(BE) as usize;
+        first
+            .checked_add(count)
+            .and_then(|end| self.imported_symbols.get(first..end))
+            .read_error("Invalid PEF imported library symbol range")
+    }
+
+    /// Return the relocation headers.
+    #[inline]
+    pub fn relocation_headers(&self) -> &'data [pef::PEFLoaderRelocationHeader] {
+        self.relocation_headers
+    }
+
+    /// Return the raw relocation instructions for a relocation header.
+    pub fn relocation_instructions(
+        &self,
+        header: &pef::PEFLoaderRelocationHeader,
+    ) -> Result<&'data [U16<BE>]> {
+        let offset = u64::from(self.header.reloc_instr_offset.get(BE))
+            + u64::from(header.first_reloc_offset.get(BE));
+        self.data
+            .0
+            .read_slice_at(offset, header.reloc_count.get(BE) as usize)
+            .read_error("Invalid PEF relocation instruction offset or count")
+    }
+
+    /// Return an iterator for the decoded relocation instructions for a relocation header.
+    pub fn relocations(
+        &self,
+        header: &pef::PEFLoaderRelocationHeader,
+    ) -> Result<RelocationInstructionIterator<'data>> {
+        self.relocation_instructions(header)
+            .map(RelocationInstructionIterator::new)
+    }
+
+    /// Return the export hash table.
+    #[inline]
+    pub fn hash_slots(&self) -> &'data [pef::PEFExportedSymbolHashSlot] {
+        self.hash_slots
+    }
+
+    /// Return the export key table.
+    #[inline]
+    pub fn export_keys(&self) -> &'data [pef::PEFExportedSymbolKey] {
+        self.export_keys
+    }
+
+    /// Return the exported symbol table.
+    #[inline]
+    pub fn exported_symbols(&self) -> &'data [pef::PEFExportedSymbol] {
+        self.exported_symbols
+    }
+
+    /// Return the null-terminated string at the given offset in the loader string table.
+    pub fn string(&self, offset: u32) -> Result<&'data [u8]> {
+        self.strings
+            .read_string_at(offset as usize)
+            .read_error("Invalid PEF loader string offset")
+    }
+
+    /// Return the name of an imported library.
+    #[inline]
+    pub fn library_name(&self, library: &pef::PEFImportedLibrary) -> Result<&'data [u8]> {
+        self.string(library.name_offset.get(BE))
+    }
+
+    /// Return the name of an imported symbol.
+    #[inline]
+    pub fn imported_symbol_name(&self, symbol: &pef::PEFImportedSymbol) -> Result<&'data [u8]> {
+        self.string(symbol.name_offset())
+    }
+
+    /// Return the name of the exported symbol at the given index.
+    ///
+    /// Exported symbol names are not null-terminated, so the length is taken
+    /// from the corresponding export key.
+    pub fn exported_symbol_name(&self, index: usize) -> Result<&'data [u8]> {
+        let symbol = self
+            .exported_symbols
+            .get(index)
+            .read_error("Invalid PEF exported symbol index")?;
+        let key = self
+            .export_keys
+            .get(index)
+            .read_error("Invalid PEF exported symbol index")?;
+        self.strings
+            .read_bytes_at(symbol.name_offset() as usize, key.name_length() as usize)
+            .read_error("Invalid PEF exported symbol name")
+            .map(|name| name.0)
+    }
+
+    /// Return the size of the loader section data.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Return true if the loader section data is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
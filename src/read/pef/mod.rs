@@ -6,4 +6,31 @@ mod file;
 pub use file::*;
 
 mod section;
-pub use section::*;
\ No newline at end of file
+pub use section::*;
+
+mod loader;
+pub use loader::*;
+
+mod relocation;
+pub use relocation::*;
+
+mod pattern;
+pub use pattern::*;
+
+mod xsym;
+pub use xsym::*;
+
+mod traceback;
+pub use traceback::*;
+
+mod resource;
+pub use resource::*;
+
+mod jump_table;
+pub use jump_table::*;
+
+mod carbon;
+pub use carbon::*;
+
+mod scan;
+pub use scan::*;
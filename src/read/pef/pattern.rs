@@ -0,0 +1,317 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::pef;
+use crate::read::{Bytes, Error, ReadError, Result};
+
+/// An iterator over the instructions in a pattern-initialized data section.
+///
+/// Returned by [`PatternDataIterator::new`].
+#[derive(Debug, Clone)]
+pub struct PatternDataIterator<'data> {
+    data: Bytes<'data>,
+}
+
+impl<'data> PatternDataIterator<'data> {
+    /// Construct a new iterator for the contents of a pattern-initialized data section.
+    pub fn new(data: &'data [u8]) -> Self {
+        PatternDataIterator { data: Bytes(data) }
+    }
+
+    fn read_argument(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        loop {
+            let byte = *self
+                .data
+                .read::<u8>()
+                .read_error("Invalid PEF pattern data argument")?;
+            if value >> (32 - pef::PK_DATA_VCOUNT_SHIFT) != 0 {
+                return Err(Error("Invalid PEF pattern data argument"));
+            }
+            value =
+                (value << pef::PK_DATA_VCOUNT_SHIFT) | u32::from(byte & pef::PK_DATA_VCOUNT_MASK);
+            if byte & pef::PK_DATA_VCOUNT_END_MASK == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn read_data(&mut self, size: u32, count: u32) -> Result<&'data [u8]> {
+        let len = size
+            .checked_mul(count)
+            .read_error("Invalid PEF pattern data size")?;
+        self.data
+            .read_bytes(len as usize)
+            .read_error("Invalid PEF pattern data size")
+            .map(|bytes| bytes.0)
+    }
+
+    fn parse(&mut self) -> Result<PatternDataInstruction<'data>> {
+        let byte = *self
+            .data
+            .read::<u8>()
+            .read_error("Invalid PEF pattern data opcode")?;
+        let opcode = byte >> pef::PK_DATA_OPCODE_SHIFT;
+        let mut count = u32::from(byte & pef::PK_DATA_COUNT5_MASK);
+        if count == 0 {
+            count = self.read_argument()?;
+        }
+        Ok(match opcode {
+            pef::PK_DATA_ZERO => PatternDataInstruction::Zero { size: count },
+            pef::PK_DATA_BLOCK => PatternDataInstruction::Block {
+                data: self.read_data(count, 1)?,
+            },
+            pef::PK_DATA_REPEAT => {
+                let repeat_count = self
+                    .read_argument()?
+                    .checked_add(1)
+                    .read_error("Invalid PEF pattern data repeat count")?;
+                PatternDataInstruction::Repeat {
+                    data: self.read_data(count, 1)?,
+                    repeat_count,
+                }
+            }
+            pef::PK_DATA_REPEAT_BLOCK => {
+                let custom_size = self.read_argument()?;
+                let repeat_count = self.read_argument()?;
+                PatternDataInstruction::RepeatBlock {
+                    common: self.read_data(count, 1)?,
+                    custom_size,
+                    repeat_count,
+                    custom: self.read_data(custom_size, repeat_count)?,
+                }
+            }
+            pef::PK_DATA_REPEAT_ZERO => {
+                let custom_size = self.read_argument()?;
+                let repeat_count = self.read_argument()?;
+                PatternDataInstruction::RepeatZero {
+                    common_size: count,
+                    custom_size,
+                    repeat_count,
+                    custom: self.read_data(custom_size, repeat_count)?,
+                }
+            }
+            _ => return Err(Error("Unknown PEF pattern data opcode")),
+        })
+    }
+}
+
+impl<'data> Iterator for PatternDataIterator<'data> {
+    type Item = Result<PatternDataInstruction<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let result = self.parse();
+        if result.is_err() {
+            self.data = Bytes(&[]);
+        }
+        Some(result)
+    }
+}
+
+/// An instruction in a pattern-initialized data section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternDataInstruction<'data> {
+    /// Initialize `size` bytes to zero.
+    Zero {
+        /// The number of zero bytes.
+        size: u32,
+    },
+    /// Copy a block of raw data.
+    Block {
+        /// The raw data.
+        data: &'data [u8],
+    },
+    /// Copy a block of raw data multiple times.
+    Repeat {
+        /// The raw data.
+        data: &'data [u8],
+        /// The number of copies of the data.
+        repeat_count: u32,
+    },
+    /// Interleave a common block with custom blocks.
+    ///
+    /// The common block is copied first, and then each custom block is copied
+    /// followed by the common block.
+    RepeatBlock {
+        /// The common block.
+        common: &'data [u8],
+        /// The size of each custom block.
+        custom_size: u32,
+        /// The number of custom blocks.
+        repeat_count: u32,
+        /// The custom blocks.
+        custom: &'data [u8],
+    },
+    /// Interleave zero bytes with custom blocks.
+    ///
+    /// The zero bytes are initialized first, and then each custom block is copied
+    /// followed by the zero bytes.
+    RepeatZero {
+        /// The number of zero bytes.
+        common_size: u32,
+        /// The size of each custom block.
+        custom_size: u32,
+        /// The number of custom blocks.
+        repeat_count: u32,
+        /// The custom blocks.
+        custom: &'data [u8],
+    },
+}
+
+impl<'data> PatternDataInstruction<'data> {
+    /// Return the number of bytes produced by this instruction.
+    ///
+    /// Returns `None` if the size overflows.
+    pub fn unpacked_size(&self) -> Option<u64> {
+        match *self {
+            PatternDataInstruction::Zero { size } => Some(size.into()),
+            PatternDataInstruction::Block { data } => Some(data.len() as u64),
+            PatternDataInstruction::Repeat { data, repeat_count } => {
+                (data.len() as u64).checked_mul(repeat_count.into())
+            }
+            PatternDataInstruction::RepeatBlock {
+                common,
+                repeat_count,
+                custom,
+                ..
+            } => Self::interleaved_size(common.len() as u64, repeat_count, custom),
+            PatternDataInstruction::RepeatZero {
+                common_size,
+                repeat_count,
+                custom,
+                ..
+            } => Self::interleaved_size(common_size.into(), repeat_count, custom),
+        }
+    }
+
+    fn interleaved_size(common_size: u64, repeat_count: u32, custom: &[u8]) -> Option<u64> {
+        (u64::from(repeat_count) + 1)
+            .checked_mul(common_size)?
+            .checked_add(custom.len() as u64)
+    }
+
+    fn custom_block(custom: &[u8], custom_size: u32, index: u32) -> &[u8] {
+        let start = index as usize * custom_size as usize;
+        custom
+            .get(start..)
+            .and_then(|custom| custom.get(..custom_size as usize))
+            .unwrap_or(&[])
+    }
+
+    /// Return all of the complete custom blocks, without any common data.
+    fn custom_blocks(custom: &[u8], custom_size: u32, repeat_count: u32) -> &[u8] {
+        let custom_size = custom_size as usize;
+        let count = (repeat_count as usize).min(custom.len().checked_div(custom_size).unwrap_or(0));
+        &custom[..count * custom_size]
+    }
+
+    /// Append the bytes produced by this instruction to `out`.
+    pub fn unpack_into(&self, out: &mut Vec<u8>) {
+        match *self {
+            PatternDataInstruction::Zero { size } => {
+                out.resize(out.len() + size as usize, 0);
+            }
+            PatternDataInstruction::Block { data } => out.extend_from_slice(data),
+            PatternDataInstruction::Repeat { data, repeat_count } => {
+                if data.is_empty() {
+                    return;
+                }
+                for _ in 0..repeat_count {
+                    out.extend_from_slice(data);
+                }
+            }
+            PatternDataInstruction::RepeatBlock {
+                common,
+                custom_size,
+                repeat_count,
+                custom,
+            } => {
+                out.extend_from_slice(common);
+                if common.is_empty() {
+                    // Don't iterate over the repeat count when there is nothing to interleave.
+                    out.extend_from_slice(Self::custom_blocks(custom, custom_size, repeat_count));
+                    return;
+                }
+                for i in 0..repeat_count {
+                    out.extend_from_slice(Self::custom_block(custom, custom_size, i));
+                    out.extend_from_slice(common);
+                }
+            }
+            PatternDataInstruction::RepeatZero {
+                common_size,
+                custom_size,
+                repeat_count,
+                custom,
+            } => {
+                if common_size == 0 {
+                    out.extend_from_slice(Self::custom_blocks(custom, custom_size, repeat_count));
+                    return;
+                }
+                out.resize(out.len() + common_size as usize, 0);
+                for i in 0..repeat_count {
+                    out.extend_from_slice(Self::custom_block(custom, custom_size, i));
+                    out.resize(out.len() + common_size as usize, 0);
+                }
+            }
+        }
+    }
+}
+
+/// Return the default limit on the unpacked size of pattern data.
+///
+/// `packed_size` is the size of the pattern data. The limit is 16 MiB, or 256
+/// times the packed size if that is larger. This prevents a few bytes of `Zero`
+/// or repeat instructions from allocating gigabytes of memory.
+///
+/// This is the limit used by
+/// [`PEFSectionHeader::pef_unpacked_data`](crate::pef::PEFSectionHeader::pef_unpacked_data).
+pub fn default_pattern_data_limit(packed_size: usize) -> u32 {
+    let ratio = u32::try_from(packed_size)
+        .unwrap_or(u32::MAX)
+        .saturating_mul(256);
+    ratio.max(1 << 24)
+}
+
+/// Unpack the contents of a pattern-initialized data section.
+///
+/// `unpacked_size` is the `unpacked_size` field of the section header.
+/// `max_size` is the largest unpacked size that is allowed; see
+/// [`default_pattern_data_limit`].
+///
+/// Returns an error if `unpacked_size` is larger than `max_size`, if the
+/// instructions produce a different number of bytes, or if the memory for the
+/// unpacked data cannot be allocated.
+pub fn unpack_pattern_data(data: &[u8], unpacked_size: u32, max_size: u32) -> Result<Vec<u8>> {
+    if unpacked_size > max_size {
+        return Err(Error("PEF unpacked size exceeds limit"));
+    }
+
+    // Check the instructions before allocating, so that the allocation size
+    // is never taken from the header alone.
+    let mut size = 0u64;
+    for instruction in PatternDataIterator::new(data) {
+        size = instruction?
+            .unpacked_size()
+            .and_then(|instruction_size| instruction_size.checked_add(size))
+            .read_error("Invalid PEF pattern data size")?;
+        if size > u64::from(unpacked_size) {
+            return Err(Error("PEF pattern data exceeds unpacked size"));
+        }
+    }
+    if size != u64::from(unpacked_size) {
+        return Err(Error("PEF pattern data is smaller than unpacked size"));
+    }
+
+    let mut out = Vec::new();
+    out.try_reserve_exact(unpacked_size as usize)
+        .ok()
+        .read_error("PEF unpacked size is too large")?;
+    for instruction in PatternDataIterator::new(data) {
+        instruction?.unpack_into(&mut out);
+    }
+    debug_assert_eq!(out.len(), unpacked_size as usize);
+    Ok(out)
+}
@@ -0,0 +1,447 @@
+use alloc::vec::Vec;
+use core::slice;
+
+use crate::endian::{BigEndian as BE, U16};
+use crate::pef;
+use crate::read::{Error, Result, SectionIndex};
+
+/// A decoded relocation instruction in a PEF loader section.
+///
+/// Counts and offsets are stored as their actual values, rather than the
+/// biased values used in the encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelocationInstruction {
+    /// Skip `skip_count` words, and then add `sectionD` to `reloc_count` words.
+    BySectDWithSkip {
+        /// The number of words to skip.
+        skip_count: u8,
+        /// The number of words to relocate.
+        reloc_count: u8,
+    },
+    /// Add `sectionC` to `run_length` words.
+    BySectC {
+        /// The number of words to relocate, from 1 to 512.
+        run_length: u16,
+    },
+    /// Add `sectionD` to `run_length` words.
+    BySectD {
+        /// The number of words to relocate, from 1 to 512.
+        run_length: u16,
+    },
+    /// Relocate `run_length` 12 byte transition vectors.
+    ///
+    /// `sectionC` is added to the first word, `sectionD` is added to the second word,
+    /// and the third word is skipped.
+    TVector12 {
+        /// The number of transition vectors, from 1 to 512.
+        run_length: u16,
+    },
+    /// Relocate `run_length` 8 byte transition vectors.
+    ///
+    /// `sectionC` is added to the first word, and `sectionD` is added to the second word.
+    TVector8 {
+        /// The number of transition vectors, from 1 to 512.
+        run_length: u16,
+    },
+    /// Relocate `run_length` 8 byte virtual table entries.
+    ///
+    /// `sectionD` is added to the first word, and the second word is skipped.
+    VTable8 {
+        /// The number of entries, from 1 to 512.
+        run_length: u16,
+    },
+    /// Add the addresses of `run_length` consecutive imported symbols, starting at `importIndex`.
+    ImportRun {
+        /// The number of words to relocate, from 1 to 512.
+        run_length: u16,
+    },
+    /// Add the address of an imported symbol, and set `importIndex` to the following symbol.
+    SmByImport {
+        /// The imported symbol index.
+        index: u16,
+    },
+    /// Set `sectionC` to the address of a section.
+    SmSetSectC {
+        /// The section index.
+        index: u16,
+    },
+    /// Set `sectionD` to the address of a section.
+    SmSetSectD {
+        /// The section index.
+        index: u16,
+    },
+    /// Add the address of a section.
+    SmBySection {
+        /// The section index.
+        index: u16,
+    },
+    /// Increment the relocation address.
+    IncrPosition {
+        /// The number of bytes to add, from 1 to 4096.
+        offset: u16,
+    },
+    /// Repeat the preceding instruction blocks.
+    SmRepeat {
+        /// The number of preceding 16-bit instruction blocks, from 1 to 16.
+        block_count: u8,
+        /// The number of repetitions, from 1 to 256.
+        repeat_count: u16,
+    },
+    /// Set the relocation address.
+    SetPosition {
+        /// The offset from the start of the section, up to 26 bits.
+        offset: u32,
+    },
+    /// Add the address of an imported symbol with a large index, and set `importIndex`
+    /// to the following symbol.
+    LgByImport {
+        /// The imported symbol index, up to 26 bits.
+        index: u32,
+    },
+    /// Repeat the preceding instruction blocks with a large count.
+    LgRepeat {
+        /// The number of preceding 16-bit instruction blocks, from 1 to 16.
+        block_count: u8,
+        /// The number of repetitions, up to 22 bits.
+        repeat_count: u32,
+    },
+    /// Add the address of a section with a large index.
+    LgBySection {
+        /// The section index, up to 22 bits.
+        index: u32,
+    },
+    /// Set `sectionC` to the address of a section with a large index.
+    LgSetSectC {
+        /// The section index, up to 22 bits.
+        index: u32,
+    },
+    /// Set `sectionD` to the address of a section with a large index.
+    LgSetSectD {
+        /// The section index, up to 22 bits.
+        index: u32,
+    },
+}
+
+impl RelocationInstruction {
+    /// Return the number of 16-bit blocks used to encode this instruction.
+    pub fn block_count(&self) -> usize {
+        match self {
+            RelocationInstruction::SetPosition { .. }
+            | RelocationInstruction::LgByImport { .. }
+            | RelocationInstruction::LgRepeat { .. }
+            | RelocationInstruction::LgBySection { .. }
+            | RelocationInstruction::LgSetSectC { .. }
+            | RelocationInstruction::LgSetSectD { .. } => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// An iterator over the relocation instructions for a section in a PEF loader section.
+///
+/// Returned by [`LoaderSection::relocations`](super::LoaderSection::relocations).
+#[derive(Debug, Clone)]
+pub struct RelocationInstructionIterator<'data> {
+    blocks: slice::Iter<'data, U16<BE>>,
+}
+
+impl<'data> RelocationInstructionIterator<'data> {
+    /// Construct a new iterator for the given 16-bit instruction blocks.
+    pub fn new(blocks: &'data [U16<BE>]) -> Self {
+        RelocationInstructionIterator {
+            blocks: blocks.iter(),
+        }
+    }
+
+    fn parse(&mut self, block: u16) -> Result<RelocationInstruction> {
+        let opcode = block >> 9;
+        let run_length = (block & 0x1FF) + 1;
+        let index = block & 0x1FF;
+        Ok(match opcode {
+            0x00..=0x1F => RelocationInstruction::BySectDWithSkip {
+                skip_count: (block >> 6) as u8,
+                reloc_count: (block & 0x3F) as u8,
+            },
+            pef::RELOC_BY_SECT_C => RelocationInstruction::BySectC { run_length },
+            pef::RELOC_BY_SECT_D => RelocationInstruction::BySectD { run_length },
+            pef::RELOC_T_VECTOR_12 => RelocationInstruction::TVector12 { run_length },
+            pef::RELOC_T_VECTOR_8 => RelocationInstruction::TVector8 { run_length },
+            pef::RELOC_V_TABLE_8 => RelocationInstruction::VTable8 { run_length },
+            pef::RELOC_IMPORT_RUN => RelocationInstruction::ImportRun { run_length },
+            pef::RELOC_SM_BY_IMPORT => RelocationInstruction::SmByImport { index },
+            pef::RELOC_SM_SET_SECT_C => RelocationInstruction::SmSetSectC { index },
+            pef::RELOC_SM_SET_SECT_D => RelocationInstruction::SmSetSectD { index },
+            pef::RELOC_SM_BY_SECTION => RelocationInstruction::SmBySection { index },
+            0x40..=0x47 => RelocationInstruction::IncrPosition {
+                offset: (block & 0xFFF) + 1,
+            },
+            0x48..=0x4F => RelocationInstruction::SmRepeat {
+                block_count: ((block >> 8) & 0xF) as u8 + 1,
+                repeat_count: (block & 0xFF) + 1,
+            },
+            0x50..=0x53 | 0x58..=0x5B => {
+                let low = self
+                    .blocks
+                    .next()
+                    .ok_or(Error("Missing PEF relocation instruction block"))?
+                    .get(BE);
+                let value = (u32::from(block & 0x3FF) << 16) | u32::from(low);
+                let index = value & 0x3F_FFFF;
+                let subopcode = (block >> 6) & 0xF;
+                match opcode & !1 {
+                    pef::RELOC_SET_POSITION => RelocationInstruction::SetPosition { offset: value },
+                    pef::RELOC_LG_BY_IMPORT => RelocationInstruction::LgByImport { index: value },
+                    pef::RELOC_LG_REPEAT => RelocationInstruction::LgRepeat {
+                        block_count: subopcode as u8 + 1,
+                        repeat_count: index,
+                    },
+                    _ => match subopcode {
+                        pef::RELOC_LG_BY_SECTION_SUBOP => {
+                            RelocationInstruction::LgBySection { index }
+                        }
+                        pef::RELOC_LG_SET_SECT_C_SUBOP => {
+                            RelocationInstruction::LgSetSectC { index }
+                        }
+                        pef::RELOC_LG_SET_SECT_D_SUBOP => {
+                            RelocationInstruction::LgSetSectD { index }
+                        }
+                        _ => return Err(Error("Unknown PEF relocation subopcode")),
+                    },
+                }
+            }
+            _ => return Err(Error("Unknown PEF relocation opcode")),
+        })
+    }
+}
+
+impl<'data> Iterator for RelocationInstructionIterator<'data> {
+    type Item = Result<RelocationInstruction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = self.blocks.next()?.get(BE);
+        let result = self.parse(block);
+        if result.is_err() {
+            self.blocks = [].iter();
+        }
+        Some(result)
+    }
+}
+
+/// The address that a [`LoaderRelocation`] adds to a word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoaderRelocationTarget {
+    /// The address of a section.
+    Section(SectionIndex),
+    /// The address of an imported symbol, given by its index in the imported symbol table.
+    Import(u32),
+}
+
+/// A relocation produced by evaluating relocation instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoaderRelocation {
+    /// The offset of the relocated 32-bit word within the section.
+    pub offset: u32,
+    /// The address that is added to the word.
+    pub target: LoaderRelocationTarget,
+}
+
+/// A transition vector, resolved using the loader relocations.
+///
+/// Function pointers and exported function symbols refer to a transition vector
+/// rather than directly to code. The vector holds the address of the code and the
+/// data pointer for the fragment containing it, which is loaded into the TOC register
+/// (r2) for PowerPC, or into A5 for CFM-68K.
+///
+/// Returned by [`PefFile::transition_vector`](super::PefFile::transition_vector).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransitionVector {
+    /// The address of the code.
+    pub code: u64,
+    /// The index of the section containing the code.
+    ///
+    /// This is an index for [`Object::section_by_index`](crate::read::Object::section_by_index),
+    /// so it starts at 1.
+    pub code_section: SectionIndex,
+    /// The data pointer.
+    pub data: u64,
+}
+
+/// Evaluate the relocation instructions for a section.
+///
+/// This performs the same steps as the Code Fragment Manager, but returns
+/// the relocations instead of applying them. `sectionC` and `sectionD`
+/// are initially sections 0 and 1.
+///
+/// `size` is the size of the section in memory. Returns an error if a
+/// relocation is outside the section, if there are more relocations than
+/// words in the section, or if repeat instructions execute too many
+/// instructions without relocating anything.
+pub fn evaluate_relocations(blocks: &[U16<BE>], size: u32) -> Result<Vec<LoaderRelocation>> {
+    let max_relocations = size as usize / 4;
+    let mut relocations = Vec::new();
+    let mut position = 0u32;
+    let mut section_c = 0;
+    let mut section_d = 1;
+    let mut import_index = 0u32;
+    // The remaining count for each repeat instruction that is in progress.
+    let mut repeats = Vec::new();
+    let mut block = 0;
+    // Each repeated group of blocks should relocate at least one word.
+    let mut steps = (max_relocations + 1)
+        .saturating_mul(17)
+        .saturating_add(blocks.len());
+    let mut relocate = |position: &mut u32, target| -> Result<()> {
+        if position.checked_add(4).map_or(true, |end| end > size) {
+            return Err(Error("Invalid PEF relocation position"));
+        }
+        if relocations.len() >= max_relocations {
+            return Err(Error("Too many PEF relocations"));
+        }
+        relocations.push(LoaderRelocation {
+            offset: *position,
+            target,
+        });
+        *position += 4;
+        Ok(())
+    };
+    let section = |index: u32| LoaderRelocationTarget::Section(SectionIndex(index as usize));
+    while let Some(instruction) = blocks
+        .get(block..)
+        .and_then(|blocks| RelocationInstructionIterator::new(blocks).next())
+    {
+        let instruction = instruction?;
+        let start = block;
+        block += instruction.block_count();
+        steps = steps
+            .checked_sub(1)
+            .ok_or(Error("Too many PEF relocation instructions"))?;
+        match instruction {
+            RelocationInstruction::BySectDWithSkip {
+                skip_count,
+                reloc_count,
+            } => {
+                position = position
+                    .checked_add(u32::from(skip_count) * 4)
+                    .ok_or(Error("Invalid PEF relocation position"))?;
+                for _ in 0..reloc_count {
+                    relocate(&mut position, section(section_d))?;
+                }
+            }
+            RelocationInstruction::BySectC { run_length } => {
+                for _ in 0..run_length {
+                    relocate(&mut position, section(section_c))?;
+                }
+            }
+            RelocationInstruction::BySectD { run_length } => {
+                for _ in 0..run_length {
+                    relocate(&mut position, section(section_d))?;
+                }
+            }
+            RelocationInstruction::TVector12 { run_length } => {
+                for _ in 0..run_length {
+                    relocate(&mut position, section(section_c))?;
+                    relocate(&mut position, section(section_d))?;
+                    position = position
+                        .checked_add(4)
+                        .ok_or(Error("Invalid PEF relocation position"))?;
+                }
+            }
+            RelocationInstruction::TVector8 { run_length } => {
+                for _ in 0..run_length {
+                    relocate(&mut position, section(section_c))?;
+                    relocate(&mut position, section(section_d))?;
+                }
+            }
+            RelocationInstruction::VTable8 { run_length } => {
+                for _ in 0..run_length {
+                    relocate(&mut position, section(section_d))?;
+                    position = position
+                        .checked_add(4)
+                        .ok_or(Error("Invalid PEF relocation position"))?;
+                }
+            }
+            RelocationInstruction::ImportRun { run_length } => {
+                for _ in 0..run_length {
+                    relocate(&mut position, LoaderRelocationTarget::Import(import_index))?;
+                    import_index = import_index.wrapping_add(1);
+                }
+            }
+            RelocationInstruction::SmByImport { index } => {
+                let index = u32::from(index);
+                relocate(&mut position, LoaderRelocationTarget::Import(index))?;
+                import_index = index + 1;
+            }
+            RelocationInstruction::LgByImport { index } => {
+                relocate(&mut position, LoaderRelocationTarget::Import(index))?;
+                import_index = index + 1;
+            }
+            RelocationInstruction::SmSetSectC { index } => section_c = u32::from(index),
+            RelocationInstruction::SmSetSectD { index } => section_d = u32::from(index),
+            RelocationInstruction::LgSetSectC { index } => section_c = index,
+            RelocationInstruction::LgSetSectD { index } => section_d = index,
+            RelocationInstruction::SmBySection { index } => {
+                relocate(&mut position, section(u32::from(index)))?;
+            }
+            RelocationInstruction::LgBySection { index } => {
+                relocate(&mut position, section(index))?;
+            }
+            RelocationInstruction::IncrPosition { offset } => {
+                position = position
+                    .checked_add(u32::from(offset))
+                    .filter(|position| *position <= size)
+                    .ok_or(Error("Invalid PEF relocation position"))?;
+            }
+            RelocationInstruction::SetPosition { offset } => {
+                if offset > size {
+                    return Err(Error("Invalid PEF relocation position"));
+                }
+                position = offset;
+            }
+            RelocationInstruction::SmRepeat {
+                block_count,
+                repeat_count,
+            } => {
+                let repeat_count = u32::from(repeat_count);
+                repeat_blocks(&mut repeats, &mut block, start, block_count, repeat_count)?;
+            }
+            RelocationInstruction::LgRepeat {
+                block_count,
+                repeat_count,
+            } => {
+                repeat_blocks(&mut repeats, &mut block, start, block_count, repeat_count)?;
+            }
+        }
+    }
+    Ok(relocations)
+}
+
+/// Handle a repeat instruction at block `start`.
+///
+/// The first time the instruction is reached, its remaining count is set
+/// to `repeat_count`. Each time it is reached with a non-zero remaining count,
+/// the remaining count is decremented and `block` is moved back by `block_count`.
+fn repeat_blocks(
+    repeats: &mut Vec<(usize, u32)>,
+    block: &mut usize,
+    start: usize,
+    block_count: u8,
+    repeat_count: u32,
+) -> Result<()> {
+    let index = match repeats.iter().position(|(block, _)| *block == start) {
+        Some(index) => index,
+        None => {
+            repeats.push((start, repeat_count));
+            repeats.len() - 1
+        }
+    };
+    let remaining = &mut repeats[index].1;
+    if *remaining == 0 {
+        repeats.swap_remove(index);
+        return Ok(());
+    }
+    *remaining -= 1;
+    *block = start
+        .checked_sub(block_count.into())
+        .ok_or(Error("Invalid PEF relocation repeat block count"))?;
+    Ok(())
+}
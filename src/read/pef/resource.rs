@@ -0,0 +1,828 @@
+use alloc::vec::Vec;
+use core::{fmt, mem};
+
+use crate::endian::{BigEndian as BE, U16Bytes, U32Bytes};
+use crate::pef;
+use crate::read::{Bytes, Error, ReadError, Result};
+
+/// The kind of wrapper that a [`MacFile`] was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MacFileKind {
+    /// The file had no wrapper, and contains only the data fork.
+    Raw,
+    /// A MacBinary file containing both forks.
+    MacBinary,
+    /// An AppleSingle file containing both forks.
+    AppleSingle,
+    /// An AppleDouble header file containing the resource fork.
+    ///
+    /// The data fork is stored in a separate file.
+    AppleDouble,
+}
+
+/// A Mac OS file, consisting of a data fork and a resource fork.
+///
+/// Use [`Self::fragments`] to find the PEF containers in the file.
+#[derive(Debug, Clone, Copy)]
+pub struct MacFile<'data> {
+    kind: MacFileKind,
+    name: Option<&'data [u8]>,
+    file_type: Option<u32>,
+    creator: Option<u32>,
+    data_fork: &'data [u8],
+    resource_fork: &'data [u8],
+}
+
+impl<'data> MacFile<'data> {
+    /// Parse a file that may be wrapped in a MacBinary, AppleSingle or AppleDouble file.
+    ///
+    /// Data that is not recognized as a wrapper is treated as a data fork with an
+    /// empty resource fork.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        if let Ok(header) = Bytes(data).read_at::<pef::AppleSingleHeader>(0) {
+            let magic = header.magic.get(BE);
+            if magic == pef::APPLE_SINGLE_MAGIC || magic == pef::APPLE_DOUBLE_MAGIC {
+                return Self::parse_apple_single(data, header);
+            }
+        }
+        if let Some(file) = Self::parse_macbinary(data) {
+            return Ok(file);
+        }
+        Ok(Self::from_forks(data, &[]))
+    }
+
+    /// Construct a file from separately stored forks.
+    ///
+    /// This is useful for files on volumes that support resource forks directly,
+    /// or for combining a data fork with the resource fork from an AppleDouble file.
+    pub fn from_forks(data_fork: &'data [u8], resource_fork: &'data [u8]) -> Self {
+        MacFile {
+            kind: MacFileKind::Raw,
+            name: None,
+            file_type: None,
+            creator: None,
+            data_fork,
+            resource_fork,
+        }
+    }
+
+    fn parse_apple_single(
+        data: &'data [u8],
+        header: &'data pef::AppleSingleHeader,
+    ) -> Result<Self> {
+        let kind = if header.magic.get(BE) == pef::APPLE_DOUBLE_MAGIC {
+            MacFileKind::AppleDouble
+        } else {
+            MacFileKind::AppleSingle
+        };
+        let entries = Bytes(data)
+            .read_slice_at::<pef::AppleSingleEntry>(
+                mem::size_of::<pef::AppleSingleHeader>(),
+                header.entry_count.get(BE).into(),
+            )
+            .read_error("Invalid AppleSingle entry count")?;
+        let mut file = Self::from_forks(&[], &[]);
+        file.kind = kind;
+        for entry in entries {
+            let entry_data = Bytes(data)
+                .read_bytes_at(entry.offset.get(BE) as usize, entry.length.get(BE) as usize)
+                .read_error("Invalid AppleSingle entry offset or length")?
+                .0;
+            match entry.entry_id.get(BE) {
+                pef::APPLE_SINGLE_ENTRY_DATA_FORK => file.data_fork = entry_data,
+                pef::APPLE_SINGLE_ENTRY_RESOURCE_FORK => file.resource_fork = entry_data,
+                pef::APPLE_SINGLE_ENTRY_REAL_NAME => file.name = Some(entry_data),
+                pef::APPLE_SINGLE_ENTRY_FINDER_INFO => {
+                    let mut info = Bytes(entry_data);
+                    if let (Ok(file_type), Ok(creator)) =
+                        (info.read::<U32Bytes<BE>>(), info.read::<U32Bytes<BE>>())
+                    {
+                        file.file_type = Some(file_type.get(BE));
+                        file.creator = Some(creator.get(BE));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(file)
+    }
+
+    /// Parse a MacBinary file.
+    ///
+    /// MacBinary has no magic number, so this checks the fields that must be zero,
+    /// and either the MacBinary III signature or the MacBinary II CRC.
+    fn parse_macbinary(data: &'data [u8]) -> Option<Self> {
+        let header = Bytes(data).read_at::<pef::MacBinaryHeader>(0).ok()?;
+        let name_length = usize::from(header.name_length);
+        if header.old_version != 0
+            || header.zero_a != 0
+            || header.zero_b != 0
+            || name_length == 0
+            || name_length > header.name.len()
+        {
+            return None;
+        }
+        let header_bytes = &data[..mem::size_of::<pef::MacBinaryHeader>()];
+        if header.signature.get(BE) != pef::MACBINARY_SIGNATURE
+            && macbinary_crc(&header_bytes[..124]) != header.crc.get(BE)
+            && header_bytes[99..126].iter().any(|&byte| byte != 0)
+        {
+            return None;
+        }
+        let data_length = header.data_fork_length.get(BE) as usize;
+        let resource_length = header.resource_fork_length.get(BE) as usize;
+        let secondary_length = usize::from(header.secondary_header_length.get(BE));
+        let data_offset = 128 + align_128(secondary_length)?;
+        let resource_offset = data_offset.checked_add(align_128(data_length)?)?;
+        let data_fork = data.get(data_offset..data_offset.checked_add(data_length)?)?;
+        let resource_fork =
+            data.get(resource_offset..resource_offset.checked_add(resource_length)?)?;
+        Some(MacFile {
+            kind: MacFileKind::MacBinary,
+            name: Some(&header.name[..name_length]),
+            file_type: Some(header.file_type.get(BE)),
+            creator: Some(header.creator.get(BE)),
+            data_fork,
+            resource_fork,
+        })
+    }
+
+    /// Return the kind of wrapper the file was read from.
+    #[inline]
+    pub fn kind(&self) -> MacFileKind {
+        self.kind
+    }
+
+    /// Return the file name stored in the wrapper, if any.
+    #[inline]
+    pub fn name(&self) -> Option<&'data [u8]> {
+        self.name
+    }
+
+    /// Return the file type stored in the wrapper, if any.
+    #[inline]
+    pub fn file_type(&self) -> Option<u32> {
+        self.file_type
+    }
+
+    /// Return the file creator stored in the wrapper, if any.
+    #[inline]
+    pub fn creator(&self) -> Option<u32> {
+        self.creator
+    }
+
+    /// Return the contents of the data fork.
+    #[inline]
+    pub fn data_fork(&self) -> &'data [u8] {
+        self.data_fork
+    }
+
+    /// Return the contents of the resource fork.
+    #[inline]
+    pub fn resource_fork(&self) -> &'data [u8] {
+        self.resource_fork
+    }
+
+    /// Parse the resource fork.
+    ///
+    /// Returns `Ok(None)` if the resource fork is empty.
+    pub fn resources(&self) -> Result<Option<ResourceFork<'data>>> {
+        if self.resource_fork.is_empty() {
+            return Ok(None);
+        }
+        ResourceFork::parse(self.resource_fork).map(Some)
+    }
+
+    /// Parse the 'cfrg' resource.
+    ///
+    /// Returns `Ok(None)` if there is no 'cfrg' resource.
+    pub fn cfrg(&self) -> Result<Option<CfrgResource<'data>>> {
+        let Some(resources) = self.resources()? else {
+            return Ok(None);
+        };
+        match resources.resource(pef::RESOURCE_TYPE_CFRG, pef::CFRG_RESOURCE_ID)? {
+            Some(resource) => CfrgResource::parse(resource.data()?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Parse the 'vers' resource with the given ID.
+    ///
+    /// Use [`pef::VERS_FILE_ID`] for the version of the file, or [`pef::VERS_PRODUCT_ID`]
+    /// for the version of the product that it is part of.
+    /// Returns `Ok(None)` if there is no such resource.
+    pub fn version(&self, id: i16) -> Result<Option<VersionResource<'data>>> {
+        let Some(resources) = self.resources()? else {
+            return Ok(None);
+        };
+        match resources.resource(pef::RESOURCE_TYPE_VERS, id)? {
+            Some(resource) => VersionResource::parse(resource.data()?).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Parse the 'SIZE' resource, which gives the memory partition of an application.
+    ///
+    /// This prefers the resource written by the Finder when the user changes the
+    /// partition size. Returns `Ok(None)` if there is no 'SIZE' resource.
+    pub fn size_resource(&self) -> Result<Option<&'data pef::SizeResource>> {
+        let Some(resources) = self.resources()? else {
+            return Ok(None);
+        };
+        for id in [pef::SIZE_USER_ID, pef::SIZE_DEFAULT_ID] {
+            if let Some(resource) = resources.resource(pef::RESOURCE_TYPE_SIZE, id)? {
+                return pef::SizeResource::parse(resource.data()?).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the code fragments in the file.
+    ///
+    /// If there is a 'cfrg' resource, then this returns the fragments of its members
+    /// that are stored in the data fork or in a resource. Otherwise, if the data
+    /// fork is a PEF container, then this returns the data fork.
+    pub fn fragments(&self) -> Result<Vec<MacFragment<'data>>> {
+        let mut fragments = Vec::new();
+        if let Some(cfrg) = self.cfrg()? {
+            for member in cfrg.members() {
+                let member = member?;
+                if let Some(data) = member.container(self)? {
+                    fragments.push(MacFragment {
+                        member: Some(member),
+                        data,
+                    });
+                }
+            }
+        } else if self.data_fork.starts_with(&pef::TAG1.to_be_bytes()) {
+            fragments.push(MacFragment {
+                member: None,
+                data: self.data_fork,
+            });
+        }
+        Ok(fragments)
+    }
+}
+
+fn align_128(size: usize) -> Option<usize> {
+    Some(size.checked_add(127)? & !127)
+}
+
+/// The CRC-16/XMODEM used by MacBinary II.
+fn macbinary_crc(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// A code fragment in a [`MacFile`].
+///
+/// Returned by [`MacFile::fragments`].
+#[derive(Debug, Clone, Copy)]
+pub struct MacFragment<'data> {
+    member: Option<CfrgMember<'data>>,
+    data: &'data [u8],
+}
+
+impl<'data> MacFragment<'data> {
+    /// Return the 'cfrg' member describing the fragment, if any.
+    #[inline]
+    pub fn member(&self) -> Option<&CfrgMember<'data>> {
+        self.member.as_ref()
+    }
+
+    /// Return the fragment name from the 'cfrg' member, if any.
+    #[inline]
+    pub fn name(&self) -> Option<&'data [u8]> {
+        self.member.map(|member| member.name())
+    }
+
+    /// Return the data of the PEF container.
+    ///
+    /// This can be parsed with [`super::PefFile::parse`].
+    #[inline]
+    pub fn data(&self) -> &'data [u8] {
+        self.data
+    }
+}
+
+/// A parsed resource fork.
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceFork<'data> {
+    resource_data: Bytes<'data>,
+    type_list: Bytes<'data>,
+    types: &'data [pef::ResourceType],
+    names: Bytes<'data>,
+}
+
+impl<'data> ResourceFork<'data> {
+    /// Parse the contents of a resource fork.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let data = Bytes(data);
+        let header = data
+            .read_at::<pef::ResourceForkHeader>(0)
+            .read_error("Invalid resource fork header size")?;
+        let resource_data = data
+            .read_bytes_at(
+                header.data_offset.get(BE) as usize,
+                header.data_length.get(BE) as usize,
+            )
+            .read_error("Invalid resource data offset or length")?;
+        let map = data
+            .read_bytes_at(
+                header.map_offset.get(BE) as usize,
+                header.map_length.get(BE) as usize,
+            )
+            .read_error("Invalid resource map offset or length")?;
+        let map_header = map
+            .read_at::<pef::ResourceMapHeader>(0)
+            .read_error("Invalid resource map header size")?;
+
+        let mut type_list = map;
+        type_list
+            .skip(map_header.type_list_offset.get(BE).into())
+            .read_error("Invalid resource type list offset")?;
+        let mut tail = type_list;
+        let count = tail
+            .read::<U16Bytes<BE>>()
+            .read_error("Invalid resource type list offset")?;
+        // The count is stored minus one, so 0xffff means an empty list.
+        let count = count.get(BE).wrapping_add(1);
+        let types = tail
+            .read_slice(count.into())
+            .read_error("Invalid resource type count")?;
+
+        let mut names = map;
+        names
+            .skip(map_header.name_list_offset.get(BE).into())
+            .read_error("Invalid resource name list offset")?;
+
+        Ok(ResourceFork {
+            resource_data,
+            type_list,
+            types,
+            names,
+        })
+    }
+
+    /// Return the resource types.
+    #[inline]
+    pub fn types(&self) -> &'data [pef::ResourceType] {
+        self.types
+    }
+
+    /// Return the resource references for a resource type.
+    pub fn references(
+        &self,
+        res_type: &pef::ResourceType,
+    ) -> Result<&'data [pef::ResourceReference]> {
+        self.type_list
+            .read_slice_at(
+                res_type.reference_list_offset.get(BE).into(),
+                usize::from(res_type.count_minus_one.get(BE)) + 1,
+            )
+            .read_error("Invalid resource reference list offset or count")
+    }
+
+    /// Iterate over all resources.
+    pub fn resources(&self) -> ResourceIterator<'data> {
+        ResourceIterator {
+            fork: *self,
+            types: self.types.iter(),
+            res_type: 0,
+            references: [].iter(),
+        }
+    }
+
+    /// Return the resource with the given type and ID.
+    pub fn resource(&self, res_type: u32, id: i16) -> Result<Option<Resource<'data>>> {
+        for entry in self.types {
+            if entry.res_type.get(BE) != res_type {
+                continue;
+            }
+            for reference in self.references(entry)? {
+                if reference.id.get(BE) == id {
+                    return Ok(Some(Resource {
+                        fork: *self,
+                        res_type,
+                        reference,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// An iterator over the resources in a [`ResourceFork`].
+#[derive(Debug)]
+pub struct ResourceIterator<'data> {
+    fork: ResourceFork<'data>,
+    types: core::slice::Iter<'data, pef::ResourceType>,
+    res_type: u32,
+    references: core::slice::Iter<'data, pef::ResourceReference>,
+}
+
+impl<'data> Iterator for ResourceIterator<'data> {
+    type Item = Result<Resource<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(reference) = self.references.next() {
+                return Some(Ok(Resource {
+                    fork: self.fork,
+                    res_type: self.res_type,
+                    reference,
+                }));
+            }
+            let res_type = self.types.next()?;
+            match self.fork.references(res_type) {
+                Ok(references) => {
+                    self.res_type = res_type.res_type.get(BE);
+                    self.references = references.iter();
+                }
+                Err(e) => {
+                    self.types = [].iter();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// A resource in a [`ResourceFork`].
+#[derive(Debug, Clone, Copy)]
+pub struct Resource<'data> {
+    fork: ResourceFork<'data>,
+    res_type: u32,
+    reference: &'data pef::ResourceReference,
+}
+
+impl<'data> Resource<'data> {
+    /// Return the resource type.
+    #[inline]
+    pub fn res_type(&self) -> u32 {
+        self.res_type
+    }
+
+    /// Return the resource ID.
+    #[inline]
+    pub fn id(&self) -> i16 {
+        self.reference.id.get(BE)
+    }
+
+    /// Return the resource attributes.
+    #[inline]
+    pub fn attributes(&self) -> u8 {
+        self.reference.attributes
+    }
+
+    /// Return the resource name, if any.
+    pub fn name(&self) -> Result<Option<&'data [u8]>> {
+        let offset = self.reference.name_offset.get(BE);
+        if offset < 0 {
+            return Ok(None);
+        }
+        let mut names = self.fork.names;
+        names
+            .skip(offset as usize)
+            .read_error("Invalid resource name offset")?;
+        let len = names
+            .read::<u8>()
+            .read_error("Invalid resource name offset")?;
+        names
+            .read_bytes(usize::from(*len))
+            .read_error("Invalid resource name length")
+            .map(|name| Some(name.0))
+    }
+
+    /// Return the resource data.
+    pub fn data(&self) -> Result<&'data [u8]> {
+        let mut data = self.fork.resource_data;
+        data.skip(self.reference.data_offset() as usize)
+            .read_error("Invalid resource data offset")?;
+        let len = data
+            .read::<U32Bytes<BE>>()
+            .read_error("Invalid resource data offset")?;
+        data.read_bytes(len.get(BE) as usize)
+            .read_error("Invalid resource data length")
+            .map(|data| data.0)
+    }
+}
+
+/// A parsed 'cfrg' resource.
+///
+/// This describes the code fragments in a file.
+#[derive(Debug, Clone, Copy)]
+pub struct CfrgResource<'data> {
+    header: &'data pef::CFragResource,
+    members: Bytes<'data>,
+}
+
+impl<'data> CfrgResource<'data> {
+    /// Parse the data of a 'cfrg' resource.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let mut members = Bytes(data);
+        let header = members
+            .read::<pef::CFragResource>()
+            .read_error("Invalid cfrg resource header size")?;
+        if header.version.get(BE) != pef::CFRG_RESOURCE_VERSION {
+            return Err(Error("Unsupported cfrg resource version"));
+        }
+        Ok(CfrgResource { header, members })
+    }
+
+    /// Return the resource header.
+    #[inline]
+    pub fn header(&self) -> &'data pef::CFragResource {
+        self.header
+    }
+
+    /// Iterate over the members of the resource.
+    pub fn members(&self) -> CfrgMemberIterator<'data> {
+        CfrgMemberIterator {
+            data: self.members,
+            remaining: self.header.member_count.get(BE),
+        }
+    }
+}
+
+/// An iterator over the members of a [`CfrgResource`].
+#[derive(Debug)]
+pub struct CfrgMemberIterator<'data> {
+    data: Bytes<'data>,
+    remaining: u16,
+}
+
+impl<'data> CfrgMemberIterator<'data> {
+    fn parse(&mut self) -> Result<CfrgMember<'data>> {
+        let member = self
+            .data
+            .read_at::<pef::CFragResourceMember>(0)
+            .read_error("Invalid cfrg member size")?;
+        let member_size = usize::from(member.member_size.get(BE));
+        let mut tail = self
+            .data
+            .read_bytes(member_size)
+            .read_error("Invalid cfrg member size")?;
+        tail.skip(mem::size_of::<pef::CFragResourceMember>())
+            .read_error("Invalid cfrg member size")?;
+        let len = tail.read::<u8>().read_error("Invalid cfrg member size")?;
+        let name = tail
+            .read_bytes(usize::from(*len))
+            .read_error("Invalid cfrg member name length")?;
+        Ok(CfrgMember {
+            member,
+            name: name.0,
+        })
+    }
+}
+
+impl<'data> Iterator for CfrgMemberIterator<'data> {
+    type Item = Result<CfrgMember<'data>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let result = self.parse();
+        if result.is_err() {
+            self.remaining = 0;
+        }
+        Some(result)
+    }
+}
+
+/// A member of a [`CfrgResource`], describing one code fragment.
+#[derive(Debug, Clone, Copy)]
+pub struct CfrgMember<'data> {
+    member: &'data pef::CFragResourceMember,
+    name: &'data [u8],
+}
+
+impl<'data> CfrgMember<'data> {
+    /// Return the raw member.
+    #[inline]
+    pub fn member(&self) -> &'data pef::CFragResourceMember {
+        self.member
+    }
+
+    /// Return the fragment name.
+    ///
+    /// This is the name that other fragments use to import this fragment.
+    #[inline]
+    pub fn name(&self) -> &'data [u8] {
+        self.name
+    }
+
+    /// Return the architecture, such as [`pef::ARCHITECTURE_PPC`].
+    #[inline]
+    pub fn architecture(&self) -> u32 {
+        self.member.architecture.get(BE)
+    }
+
+    /// Return the usage, which is one of the `CFRG_USAGE_*` values.
+    #[inline]
+    pub fn usage(&self) -> u8 {
+        self.member.usage
+    }
+
+    /// Return the location, which is one of the `CFRG_*_LOCATOR` values.
+    #[inline]
+    pub fn location(&self) -> u8 {
+        self.member.location
+    }
+
+    /// Return the current version of the fragment.
+    #[inline]
+    pub fn current_version(&self) -> u32 {
+        self.member.current_version.get(BE)
+    }
+
+    /// Return the oldest version that is compatible with the current version.
+    #[inline]
+    pub fn old_def_version(&self) -> u32 {
+        self.member.old_def_version.get(BE)
+    }
+
+    /// Return the data of the fragment within `file`.
+    ///
+    /// Returns `Ok(None)` if the fragment is not stored in the data fork or a resource.
+    pub fn container(&self, file: &MacFile<'data>) -> Result<Option<&'data [u8]>> {
+        let data = match self.member.location {
+            pef::CFRG_DATA_FORK_LOCATOR => file.data_fork,
+            pef::CFRG_RESOURCE_LOCATOR => {
+                let resources = file
+                    .resources()?
+                    .read_error("Missing resource fork for cfrg member")?;
+                let res_type = self.member.where_1.get(BE);
+                let id = self.member.where_2.get(BE) as i16;
+                resources
+                    .resource(res_type, id)?
+                    .read_error("Missing resource for cfrg member")?
+                    .data()?
+            }
+            _ => return Ok(None),
+        };
+        let offset = self.member.offset.get(BE) as usize;
+        let length = self.member.length.get(BE);
+        let data = if length == pef::CFRG_GOES_TO_EOF {
+            data.get(offset..)
+        } else {
+            Bytes(data)
+                .read_bytes_at(offset, length as usize)
+                .ok()
+                .map(|data| data.0)
+        };
+        data.read_error("Invalid cfrg member offset or length")
+            .map(Some)
+    }
+}
+
+/// A parsed 'vers' resource.
+///
+/// The [`Display`](fmt::Display) implementation formats the version number in the
+/// same way as the Finder, such as "2.1.3" or "1.0b2".
+#[derive(Debug, Clone, Copy)]
+pub struct VersionResource<'data> {
+    header: &'data pef::VersResource,
+    short_version: &'data [u8],
+    long_version: &'data [u8],
+}
+
+impl<'data> VersionResource<'data> {
+    /// Parse the data of a 'vers' resource.
+    pub fn parse(data: &'data [u8]) -> Result<Self> {
+        let mut data = Bytes(data);
+        let header = data
+            .read::<pef::VersResource>()
+            .read_error("Invalid vers resource size")?;
+        let mut read_string = || -> Result<&'data [u8]> {
+            let len = data.read::<u8>().read_error("Invalid vers string length")?;
+            data.read_bytes(usize::from(*len))
+                .read_error("Invalid vers string length")
+                .map(|string| string.0)
+        };
+        let short_version = read_string()?;
+        let long_version = read_string()?;
+        Ok(VersionResource {
+            header,
+            short_version,
+            long_version,
+        })
+    }
+
+    /// Return the resource header.
+    #[inline]
+    pub fn header(&self) -> &'data pef::VersResource {
+        self.header
+    }
+
+    /// Return the major version, decoded from BCD.
+    #[inline]
+    pub fn major(&self) -> u8 {
+        from_bcd(self.header.major)
+    }
+
+    /// Return the minor version.
+    #[inline]
+    pub fn minor(&self) -> u8 {
+        self.header.minor_and_bug_fix >> 4
+    }
+
+    /// Return the bug fix version.
+    #[inline]
+    pub fn bug_fix(&self) -> u8 {
+        self.header.minor_and_bug_fix & 0xf
+    }
+
+    /// Return the release stage, which is one of the `VERS_STAGE_*` values.
+    #[inline]
+    pub fn stage(&self) -> u8 {
+        self.header.stage
+    }
+
+    /// Return the revision for a non-final release.
+    #[inline]
+    pub fn non_release(&self) -> u8 {
+        self.header.non_release
+    }
+
+    /// Return the region code.
+    #[inline]
+    pub fn region(&self) -> u16 {
+        self.header.region.get(BE)
+    }
+
+    /// Return the short version string, such as "2.1.3".
+    #[inline]
+    pub fn short_version(&self) -> &'data [u8] {
+        self.short_version
+    }
+
+    /// Return the long version string, such as "2.1.3, © 1998 Example Inc.".
+    ///
+    /// This is usually in the Mac OS Roman encoding.
+    #[inline]
+    pub fn long_version(&self) -> &'data [u8] {
+        self.long_version
+    }
+}
+
+impl<'data> fmt::Display for VersionResource<'data> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major(), self.minor())?;
+        if self.bug_fix() != 0 {
+            write!(f, ".{}", self.bug_fix())?;
+        }
+        let stage = match self.stage() {
+            pef::VERS_STAGE_DEVELOPMENT => "d",
+            pef::VERS_STAGE_ALPHA => "a",
+            pef::VERS_STAGE_BETA => "b",
+            _ => return Ok(()),
+        };
+        write!(f, "{}{}", stage, self.non_release())
+    }
+}
+
+fn from_bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0xf)
+}
+
+impl pef::SizeResource {
+    /// Parse the data of a 'SIZE' resource.
+    pub fn parse(data: &[u8]) -> Result<&Self> {
+        Bytes(data)
+            .read_at(0)
+            .read_error("Invalid SIZE resource size")
+    }
+
+    /// Return the flags, which are a combination of the `SIZE_*` values.
+    #[inline]
+    pub fn flags(&self) -> u16 {
+        self.flags.get(BE)
+    }
+
+    /// Return the preferred partition size in bytes.
+    #[inline]
+    pub fn preferred_size(&self) -> u32 {
+        self.preferred_size.get(BE)
+    }
+
+    /// Return the minimum partition size in bytes.
+    #[inline]
+    pub fn minimum_size(&self) -> u32 {
+        self.minimum_size.get(BE)
+    }
+}
@@ -0,0 +1,58 @@
+use core::convert::TryFrom;
+use core::mem;
+
+use crate::pef;
+
+use super::PefFile;
+
+/// The magic bytes at the start of every PEF container.
+const MAGIC: &[u8; 8] = b"Joy!peff";
+
+/// An iterator over the PEF containers embedded in a block of data.
+///
+/// This finds containers at any offset, such as those stored in the data of a
+/// Mach-O CFM bundle or appended to another file, without needing a 'cfrg' resource
+/// to describe them. Candidates with an invalid container header are skipped.
+///
+/// Returned by [`PefFile::scan`].
+#[derive(Debug, Clone)]
+pub struct PefScanIterator<'data> {
+    data: &'data [u8],
+    offset: usize,
+}
+
+impl<'data> Iterator for PefScanIterator<'data> {
+    /// The offset of the container within the data, and the parsed container.
+    type Item = (u64, PefFile<'data>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(found) = memchr::memmem::find(self.data.get(self.offset..)?, MAGIC) {
+            let start = self.offset + found;
+            match PefFile::parse(&self.data[start..]) {
+                Ok(file) => {
+                    // Skip the contents of the container, so that nested magic bytes
+                    // are not reported separately.
+                    let size = file
+                        .sections
+                        .max_section_file_offset()
+                        .max(mem::size_of::<pef::PEFContainerHeader>() as u64);
+                    self.offset = usize::try_from(size)
+                        .ok()
+                        .and_then(|size| start.checked_add(size))
+                        .unwrap_or(self.data.len());
+                    return Some((start as u64, file));
+                }
+                Err(_) => self.offset = start + MAGIC.len(),
+            }
+        }
+        self.offset = self.data.len();
+        None
+    }
+}
+
+impl<'data> PefFile<'data> {
+    /// Find and parse every PEF container in `data`.
+    pub fn scan(data: &'data [u8]) -> PefScanIterator<'data> {
+        PefScanIterator { data, offset: 0 }
+    }
+}
@@ -1,16 +1,19 @@
+use alloc::borrow::Cow;
+use alloc::vec;
+use core::convert::TryFrom;
 use core::marker::PhantomData;
-use core::{cmp, iter, slice, str};
+use core::{iter, mem, slice, str};
 
-use crate::endian::LittleEndian as LE;
+use crate::endian::BigEndian as BE;
 use crate::pef;
-use crate::pef::PEFSectionHeader;
 use crate::read::util::StringTable;
 use crate::read::{
-    self, CompressedData, CompressedFileRange, ObjectSection, ObjectSegment, ReadError, ReadRef,
-    Relocation, RelocationMap, Result, SectionFlags, SectionIndex, SectionKind, SegmentFlags,
+    self, CompressedData, CompressedFileRange, Error, ObjectSection, ObjectSegment, ReadError,
+    ReadRef, Relocation, RelocationEncoding, RelocationFlags, RelocationKind, RelocationMap,
+    RelocationTarget, Result, SectionFlags, SectionIndex, SectionKind, SegmentFlags, SymbolIndex,
 };
 
-use super::PefFile;
+use super::{LoaderRelocation, LoaderRelocationTarget, PefFile};
 
 /// An iterator for the loadable sections in a [`PefFile`].
 #[derive(Debug)]
@@ -29,10 +32,12 @@ where
     type Item = PefSegment<'data, 'file, R>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iter.next().map(|section| PefSegment {
-            file: self.file,
-            section,
-        })
+        self.iter
+            .find(|section| section.is_instantiated())
+            .map(|section| PefSegment {
+                file: self.file,
+                section,
+            })
     }
 }
 
@@ -63,11 +68,7 @@ where
     }
 }
 
-impl<'data, 'file, R> read::private::Sealed for PefSegment<'data, 'file, R>
-where
-    R: ReadRef<'data>,
-{
-}
+impl<'data, 'file, R> read::private::Sealed for PefSegment<'data, 'file, R> where R: ReadRef<'data> {}
 
 impl<'data, 'file, R> ObjectSegment<'data> for PefSegment<'data, 'file, R>
 where
@@ -75,45 +76,58 @@ where
 {
     #[inline]
     fn address(&self) -> u64 {
-        todo!();
+        self.section.default_address.get(BE).into()
     }
 
     #[inline]
     fn size(&self) -> u64 {
-        todo!();
+        self.section.total_size.get(BE).into()
     }
 
     #[inline]
     fn align(&self) -> u64 {
-        todo!();
+        self.section.align()
     }
 
     #[inline]
     fn file_range(&self) -> (u64, u64) {
-        todo!();
+        let (offset, size) = self.section.pef_file_range();
+        (offset.into(), size.into())
     }
 
     fn data(&self) -> Result<&'data [u8]> {
-        todo!();
+        self.section.pef_data(self.file.data)
     }
 
     fn data_range(&self, address: u64, size: u64) -> Result<Option<&'data [u8]>> {
-        todo!();
+        Ok(read::util::data_range(
+            self.data()?,
+            self.address(),
+            address,
+            size,
+        ))
     }
 
     #[inline]
     fn name_bytes(&self) -> Result<Option<&[u8]>> {
-        todo!();
+        self.file.sections.section_name(self.section)
     }
 
     #[inline]
     fn name(&self) -> Result<Option<&str>> {
-        todo!();
+        let name = match self.name_bytes()? {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        str::from_utf8(name)
+            .ok()
+            .read_error("Non UTF-8 PEF section name")
+            .map(Some)
     }
 
     #[inline]
     fn flags(&self) -> SegmentFlags {
-        todo!();
+        SegmentFlags::None
     }
 }
 
@@ -170,11 +184,7 @@ where
     }
 }
 
-impl<'data, 'file, R> read::private::Sealed for PefSection<'data, 'file, R>
-where
-    R: ReadRef<'data>,
-{
-}
+impl<'data, 'file, R> read::private::Sealed for PefSection<'data, 'file, R> where R: ReadRef<'data> {}
 
 impl<'data, 'file, R> ObjectSection<'data> for PefSection<'data, 'file, R>
 where
@@ -189,30 +199,36 @@ where
 
     #[inline]
     fn address(&self) -> u64 {
-        todo!();
+        self.section.default_address.get(BE).into()
     }
 
     #[inline]
     fn size(&self) -> u64 {
-        todo!();
+        self.section.pef_size().into()
     }
 
     #[inline]
     fn align(&self) -> u64 {
-        todo!();
+        self.section.align()
     }
 
     #[inline]
     fn file_range(&self) -> Option<(u64, u64)> {
-        todo!();
+        let (offset, size) = self.section.pef_file_range();
+        Some((offset.into(), size.into()))
     }
 
     fn data(&self) -> Result<&'data [u8]> {
-        todo!();
+        self.section.pef_data(self.file.data)
     }
 
     fn data_range(&self, address: u64, size: u64) -> Result<Option<&'data [u8]>> {
-        todo!();
+        Ok(read::util::data_range(
+            self.data()?,
+            self.address(),
+            address,
+            size,
+        ))
     }
 
     #[inline]
@@ -227,7 +243,10 @@ where
 
     #[inline]
     fn name_bytes(&self) -> Result<&'data [u8]> {
-        todo!();
+        self.file
+            .sections
+            .section_name(self.section)
+            .map(|name| name.unwrap_or(&[]))
     }
 
     #[inline]
@@ -235,7 +254,7 @@ where
         let name = self.name_bytes()?;
         str::from_utf8(name)
             .ok()
-            .read_error("Non UTF-8 PE section name")
+            .read_error("Non UTF-8 PEF section name")
     }
 
     #[inline]
@@ -250,44 +269,86 @@ where
 
     #[inline]
     fn kind(&self) -> SectionKind {
-        todo!();
-    }
-
+        match self.section.kind() {
+            Some(pef::SectionKind::Code) => SectionKind::Text,
+            Some(pef::SectionKind::UnpackedData)
+            | Some(pef::SectionKind::PatternInitializedData)
+            | Some(pef::SectionKind::ExecutableData) => SectionKind::Data,
+            Some(pef::SectionKind::Constant) => SectionKind::ReadOnlyData,
+            Some(pef::SectionKind::Loader) => SectionKind::Metadata,
+            Some(pef::SectionKind::Debug) => SectionKind::Debug,
+            Some(pef::SectionKind::Exception) | Some(pef::SectionKind::Traceback) => {
+                SectionKind::ReadOnlyData
+            }
+            None => SectionKind::Unknown,
+        }
+    }
+
+    /// Return the loader relocations for this section.
+    ///
+    /// Each relocation adds the address of a section or imported symbol to a 32-bit word.
+    /// Malformed relocation instructions result in no relocations.
     fn relocations(&self) -> PefRelocationIterator<'data, 'file, R> {
-        PefRelocationIterator(PhantomData)
+        let relocations = self
+            .file
+            .loader_relocations(SectionIndex(self.index.0 - 1))
+            .unwrap_or_default();
+        PefRelocationIterator {
+            relocations: relocations.into_iter(),
+            marker: PhantomData,
+        }
     }
 
     fn relocation_map(&self) -> read::Result<RelocationMap> {
-        todo!();
+        RelocationMap::new(self.file, self)
     }
 
     fn flags(&self) -> SectionFlags {
-        todo!();
+        SectionFlags::None
     }
 }
 
 /// The table of section headers in a PEF file.
-#[derive(Debug, Default, Clone, Copy)]
-pub struct SectionTable<'data> {
+///
+/// Section indices start at 0, which matches the indices used by the loader section.
+#[derive(Debug, Clone, Copy)]
+pub struct SectionTable<'data, R = &'data [u8]>
+where
+    R: ReadRef<'data>,
+{
     sections: &'data [pef::PEFSectionHeader],
+    strings: StringTable<'data, R>,
 }
 
-impl<'data> SectionTable<'data> {
+impl<'data, R: ReadRef<'data>> Default for SectionTable<'data, R> {
+    fn default() -> Self {
+        SectionTable {
+            sections: &[],
+            strings: StringTable::default(),
+        }
+    }
+}
+
+impl<'data, R: ReadRef<'data>> SectionTable<'data, R> {
     /// Parse the section table.
     ///
-    /// `data` must be the entire file data.
-    /// `offset` must be after the optional file header.
-    pub fn parse<R: ReadRef<'data>>(
-        header: &pef::PEFSectionHeader,
-        data: R,
-        offset: u64,
-    ) -> Result<Self> {
-        todo!();
+    /// `data` must be the entire container data.
+    pub fn parse(header: &pef::PEFContainerHeader, data: R) -> Result<Self> {
+        let offset = mem::size_of::<pef::PEFContainerHeader>() as u64;
+        let count = header.section_count.get(BE);
+        let sections = data
+            .read_slice_at(offset, count.into())
+            .read_error("Invalid PEF section headers")?;
+        // The section name table immediately follows the section headers.
+        // It has no explicit size, so bound it by the end of the data.
+        let strings_offset =
+            offset + u64::from(count) * mem::size_of::<pef::PEFSectionHeader>() as u64;
+        let strings_end = data.len().read_error("Invalid PEF container size")?;
+        let strings = StringTable::new(data, strings_offset, strings_end);
+        Ok(SectionTable { sections, strings })
     }
 
     /// Iterate over the section headers.
-    ///
-    /// Warning: section indices start at 1.
     #[inline]
     pub fn iter(&self) -> slice::Iter<'data, pef::PEFSectionHeader> {
         self.sections.iter()
@@ -298,7 +359,7 @@ impl<'data> SectionTable<'data> {
         self.sections
             .iter()
             .enumerate()
-            .map(|(i, section)| (SectionIndex(i + 1), section))
+            .map(|(i, section)| (SectionIndex(i), section))
     }
 
     /// Return true if the section table is empty.
@@ -314,144 +375,224 @@ impl<'data> SectionTable<'data> {
     }
 
     /// Return the section header at the given index.
-    ///
-    /// The index is 1-based.
-    pub fn section(&self, index: SectionIndex) -> read::Result<&'data pef::PEFSectionHeader> {
+    pub fn section(&self, index: SectionIndex) -> Result<&'data pef::PEFSectionHeader> {
         self.sections
-            .get(index.0.wrapping_sub(1))
-            .read_error("Invalid COFF/PE section index")
+            .get(index.0)
+            .read_error("Invalid PEF section index")
     }
 
-    /// Return the section header with the given name.
+    /// Return the section name table.
+    #[inline]
+    pub fn strings(&self) -> StringTable<'data, R> {
+        self.strings
+    }
+
+    /// Return the name of the given section.
     ///
-    /// The returned index is 1-based.
+    /// Returns `Ok(None)` if the section does not have a name.
+    pub fn section_name(&self, section: &pef::PEFSectionHeader) -> Result<Option<&'data [u8]>> {
+        section.name(self.strings)
+    }
+
+    /// Return the section header with the given name.
     ///
     /// Ignores sections with invalid names.
-    pub fn section_by_name<R: ReadRef<'data>>(
+    pub fn section_by_name(
         &self,
-        strings: StringTable<'data, R>,
         name: &[u8],
     ) -> Option<(SectionIndex, &'data pef::PEFSectionHeader)> {
-        todo!();
+        self.enumerate()
+            .find(|(_, section)| self.section_name(section) == Ok(Some(name)))
     }
 
-    /// Compute the maximum file offset used by sections.
+    /// Return the loader section header.
     ///
-    /// This will usually match the end of file, unless the PE file has a
-    /// [data overlay](https://security.stackexchange.com/questions/77336/how-is-the-file-overlay-read-by-an-exe-virus)
+    /// Returns `Ok(None)` if there is no loader section.
+    pub fn loader(&self) -> Result<Option<&'data pef::PEFSectionHeader>> {
+        let mut loader = None;
+        for section in self.sections {
+            if section.section_kind == pef::SectionKind::Loader as u8 {
+                if loader.is_some() {
+                    return Err(Error("Multiple PEF loader sections"));
+                }
+                loader = Some(section);
+            }
+        }
+        Ok(loader)
+    }
+
+    /// Compute the maximum file offset used by sections.
     pub fn max_section_file_offset(&self) -> u64 {
-        todo!();
+        self.sections
+            .iter()
+            .map(|section| {
+                let (offset, size) = section.pef_file_range();
+                u64::from(offset) + u64::from(size)
+            })
+            .max()
+            .unwrap_or(0)
     }
 }
 
-impl<'data> SectionTable<'data> {
-    /// Return the file offset of the given virtual address, and the size up
-    /// to the end of the section containing it.
-    ///
-    /// Returns `None` if no section contains the address.
-    pub fn pe_file_range_at(&self, va: u32) -> Option<(u32, u32)> {
-        todo!();
+impl pef::PEFSectionHeader {
+    /// Return the section kind, or `None` if it is unknown.
+    #[inline]
+    pub fn kind(&self) -> Option<pef::SectionKind> {
+        pef::SectionKind::from_u8(self.section_kind)
     }
 
-    /// Return the data starting at the given virtual address, up to the end of the
-    /// section containing it.
-    ///
-    /// Ignores sections with invalid data.
-    ///
-    /// Returns `None` if no section contains the address.
-    pub fn pe_data_at<R: ReadRef<'data>>(&self, data: R, va: u32) -> Option<&'data [u8]> {
-        self.iter().find_map(|section| section.pe_data_at(data, va))
+    /// Return the share kind, or `None` if it is unknown.
+    #[inline]
+    pub fn share_kind(&self) -> Option<pef::ShareKind> {
+        pef::ShareKind::from_u8(self.share_kind)
     }
 
-    /// Return the data of the section that contains the given virtual address in a PE file.
-    ///
-    /// Also returns the virtual address of that section.
-    ///
-    /// Ignores sections with invalid data.
-    pub fn pe_data_containing<R: ReadRef<'data>>(
-        &self,
-        data: R,
-        va: u32,
-    ) -> Option<(&'data [u8], u32)> {
-        self.iter()
-            .find_map(|section| section.pe_data_containing(data, va))
+    /// Return true if the section is instantiated in memory.
+    #[inline]
+    pub fn is_instantiated(&self) -> bool {
+        self.kind().map_or(false, pef::SectionKind::is_instantiated)
     }
 
-    /// Return the section that contains a given virtual address.
-    pub fn section_containing(&self, va: u32) -> Option<&'data PEFSectionHeader> {
-        self.iter().find(|section| section.contains_rva(va))
+    /// Return the name of the section.
+    ///
+    /// `strings` must be the section name table.
+    /// Returns `Ok(None)` if the section does not have a name.
+    pub fn name<'data, R: ReadRef<'data>>(
+        &self,
+        strings: StringTable<'data, R>,
+    ) -> Result<Option<&'data [u8]>> {
+        let offset = self.name_offset.get(BE);
+        if offset == pef::NO_SECTION_NAME {
+            return Ok(None);
+        }
+        let offset = u32::try_from(offset)
+            .ok()
+            .read_error("Invalid PEF section name offset")?;
+        strings
+            .get(offset)
+            .read_error("Invalid PEF section name offset")
+            .map(Some)
     }
-}
 
-impl pef::PEFSectionHeader {
-    /// Return the offset and size of the section in a PE file.
-    ///
-    /// The size of the range will be the minimum of the file size and virtual size.
+    /// Return the offset and size of the section contents in the container.
+    #[inline]
     pub fn pef_file_range(&self) -> (u32, u32) {
-        todo!();
+        (self.container_offset.get(BE), self.packed_size.get(BE))
     }
 
-    /// Return the file offset of the given virtual address, and the remaining size up
-    /// to the end of the section.
+    /// Return the address and size of the section in memory.
     ///
-    /// Returns `None` if the section does not contain the address.
-    pub fn pef_file_range_at(&self, va: u32) -> Option<(u32, u32)> {
-        todo!();
-    }
-
-    /// Return the virtual address and size of the section.
+    /// Non-instantiated sections have a size of 0.
     pub fn pef_address_range(&self) -> (u32, u32) {
-        todo!();
+        let size = if self.is_instantiated() {
+            self.total_size.get(BE)
+        } else {
+            0
+        };
+        (self.default_address.get(BE), size)
     }
 
-    /// Return the section data in a PE file.
+    /// Return the section contents in the container.
+    ///
+    /// `data` must be the entire container data.
     ///
-    /// The length of the data will be the minimum of the file size and virtual size.
-    pub fn pe_data<'data, R: ReadRef<'data>>(&self, data: R) -> Result<&'data [u8]> {
-        todo!();
+    /// For pattern-initialized data sections, this is the packed data.
+    pub fn pef_data<'data, R: ReadRef<'data>>(&self, data: R) -> Result<&'data [u8]> {
+        let (offset, size) = self.pef_file_range();
+        data.read_bytes_at(offset.into(), size.into())
+            .read_error("Invalid PEF section offset or size")
     }
 
-    /// Return the data starting at the given virtual address, up to the end of the
-    /// section.
+    /// Return the initialized contents of the section in memory.
     ///
-    /// Ignores sections with invalid data.
+    /// `data` must be the entire container data.
     ///
-    /// Returns `None` if the section does not contain the address.
-    pub fn pe_data_at<'data, R: ReadRef<'data>>(&self, data: R, va: u32) -> Option<&'data [u8]> {
-        todo!();
+    /// For pattern-initialized data sections, this unpacks the pattern data,
+    /// and returns an error if the unpacked size is larger than
+    /// [`default_pattern_data_limit`](super::default_pattern_data_limit).
+    /// Use [`unpack_pattern_data`](super::unpack_pattern_data) directly for
+    /// a different limit.
+    ///
+    /// This does not include the zero-initialized data that follows the
+    /// initialized data.
+    pub fn pef_unpacked_data<'data, R: ReadRef<'data>>(&self, data: R) -> Result<Cow<'data, [u8]>> {
+        let packed = self.pef_data(data)?;
+        let unpacked_size = self.unpacked_size.get(BE);
+        if self.section_kind == pef::SectionKind::PatternInitializedData as u8 {
+            let max_size = super::default_pattern_data_limit(packed.len());
+            return super::unpack_pattern_data(packed, unpacked_size, max_size).map(Cow::Owned);
+        }
+        packed
+            .get(..unpacked_size as usize)
+            .read_error("Invalid PEF section unpacked size")
+            .map(Cow::Borrowed)
+    }
+
+    /// Return the size of the section.
+    ///
+    /// For instantiated sections this is the size in memory, including any
+    /// zero-initialized data. For other sections this is the size in the container.
+    pub fn pef_size(&self) -> u32 {
+        if self.is_instantiated() {
+            self.total_size.get(BE)
+        } else {
+            self.packed_size.get(BE)
+        }
     }
 
-    /// Tests whether a given RVA is part of this section
-    pub fn contains_rva(&self, va: u32) -> bool {
-        todo!();
+    /// Return the section alignment in bytes.
+    ///
+    /// Returns 0 if the alignment field is too large to represent.
+    pub fn align(&self) -> u64 {
+        1u64.checked_shl(self.alignment.into()).unwrap_or(0)
     }
 
-    /// Return the section data if it contains the given virtual address.
-    ///
-    /// Also returns the virtual address of that section.
-    ///
-    /// Ignores sections with invalid data.
-    pub fn pe_data_containing<'data, R: ReadRef<'data>>(
-        &self,
-        data: R,
-        va: u32,
-    ) -> Option<(&'data [u8], u32)> {
-        todo!();
+    /// Return true if the section contains the given address.
+    pub fn contains_address(&self, address: u32) -> bool {
+        let (section_address, size) = self.pef_address_range();
+        address
+            .checked_sub(section_address)
+            .map_or(false, |offset| offset < size)
     }
 }
 
 /// An iterator for the relocations in an [`PefSection`].
-///
-/// This is a stub that doesn't implement any functionality.
 #[derive(Debug)]
-pub struct PefRelocationIterator<'data, 'file, R = &'data [u8]>(
-    PhantomData<(&'data (), &'file (), R)>,
-);
+pub struct PefRelocationIterator<'data, 'file, R = &'data [u8]> {
+    relocations: vec::IntoIter<LoaderRelocation>,
+    marker: PhantomData<(&'data (), &'file (), R)>,
+}
 
 impl<'data, 'file, R> Iterator for PefRelocationIterator<'data, 'file, R> {
     type Item = (u64, Relocation);
 
     fn next(&mut self) -> Option<Self::Item> {
-        None
+        let relocation = self.relocations.next()?;
+        let target = match relocation.target {
+            // Section indices in the unified API start at 1.
+            LoaderRelocationTarget::Section(index) => {
+                RelocationTarget::Section(SectionIndex(index.0 + 1))
+            }
+            // Imported symbols are the first symbols in the symbol table.
+            LoaderRelocationTarget::Import(index) => {
+                RelocationTarget::Symbol(SymbolIndex(index as usize))
+            }
+        };
+        Some((
+            relocation.offset.into(),
+            Relocation {
+                kind: RelocationKind::Absolute,
+                encoding: RelocationEncoding::Generic,
+                size: 32,
+                target,
+                addend: 0,
+                implicit_addend: true,
+                flags: RelocationFlags::Generic {
+                    kind: RelocationKind::Absolute,
+                    encoding: RelocationEncoding::Generic,
+                    size: 32,
+                },
+            },
+        ))
     }
 }
@@ -0,0 +1,107 @@
+use crate::endian::{BigEndian as BE, U16, U32};
+use crate::pef;
+use crate::read::Bytes;
+
+/// A traceback table in a PowerPC code section.
+///
+/// Returned by [`TracebackTableIterator`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TracebackTable<'data> {
+    /// The offset of the function in the section.
+    pub function_offset: u32,
+    /// The length of the function code, excluding the traceback table.
+    pub function_size: u32,
+    /// The language identifier.
+    pub language: u8,
+    /// The function name, if present.
+    pub name: Option<&'data [u8]>,
+}
+
+impl<'data> TracebackTable<'data> {
+    /// Parse a traceback table.
+    ///
+    /// `data` must be the section contents and `offset` must be the offset of
+    /// the zero word that starts the table.
+    ///
+    /// Returns `None` if the table is invalid, or if it doesn't give the length
+    /// of the function.
+    pub fn parse(data: &'data [u8], offset: u32) -> Option<Self> {
+        let mut tail = Bytes(data);
+        tail.skip(offset as usize).ok()?;
+        if tail.read::<U32<BE>>().ok()?.get(BE) != 0 {
+            return None;
+        }
+        let fixed = tail.read_bytes(8).ok()?.0;
+        let version = fixed[0];
+        let language = fixed[1];
+        let flags = [fixed[2], fixed[3]];
+        let fixed_parms = fixed[6];
+        let float_parms = fixed[7] >> 1;
+        if version != 0 || flags[0] & pef::TB_HAS_TBOFF == 0 {
+            return None;
+        }
+        if fixed_parms != 0 || float_parms != 0 {
+            // parminfo
+            tail.skip(4).ok()?;
+        }
+        let function_size = tail.read::<U32<BE>>().ok()?.get(BE);
+        if function_size == 0 || function_size % 4 != 0 || function_size > offset {
+            return None;
+        }
+        if flags[1] & pef::TB_INT_HNDL != 0 {
+            // hand_mask
+            tail.skip(4).ok()?;
+        }
+        if flags[0] & pef::TB_HAS_CTL != 0 {
+            let count = tail.read::<U32<BE>>().ok()?.get(BE);
+            tail.skip((count as usize).checked_mul(4)?).ok()?;
+        }
+        let name = if flags[1] & pef::TB_NAME_PRESENT != 0 {
+            let len = tail.read::<U16<BE>>().ok()?.get(BE);
+            Some(tail.read_bytes(len.into()).ok()?.0)
+        } else {
+            None
+        };
+        Some(TracebackTable {
+            function_offset: offset - function_size,
+            function_size,
+            language,
+            name,
+        })
+    }
+}
+
+/// An iterator over the traceback tables in a PowerPC code section.
+///
+/// This scans the section for word-aligned traceback tables, so it may
+/// not find tables for functions that are not followed by one.
+#[derive(Debug, Clone)]
+pub struct TracebackTableIterator<'data> {
+    data: &'data [u8],
+    offset: u32,
+}
+
+impl<'data> TracebackTableIterator<'data> {
+    /// Construct a new iterator for the contents of a code section.
+    pub fn new(data: &'data [u8]) -> Self {
+        TracebackTableIterator { data, offset: 0 }
+    }
+}
+
+impl<'data> Iterator for TracebackTableIterator<'data> {
+    type Item = TracebackTable<'data>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let offset = self.offset;
+            let end = offset.checked_add(4)?;
+            if end as usize > self.data.len() {
+                return None;
+            }
+            self.offset = end;
+            if let Some(table) = TracebackTable::parse(self.data, offset) {
+                return Some(table);
+            }
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::{mem, str};
+
+use crate::endian::BigEndian as BE;
+use crate::pef;
+use crate::pod::Pod;
+use crate::read::{Error, ReadError, ReadRef, Result, SymbolMap, SymbolMapEntry};
+use crate::xsym;
+
+use super::PefFile;
+
+/// An MPW SYM (xSYM) debug symbol file.
+///
+/// This contains the source-level debugging information for a PEF container.
+/// Use [`Self::symbol_map`] and [`Self::statements`] to map the modules, labels and
+/// statements onto the code sections of the corresponding [`PefFile`].
+///
+/// All table indices are zero-based.
+#[derive(Debug)]
+pub struct XsymFile<'data, R = &'data [u8]>
+where
+    R: ReadRef<'data>,
+{
+    header: &'data xsym::DiskSymHeaderBlock,
+    page_size: u64,
+    data: R,
+}
+
+impl<'data, R: ReadRef<'data>> XsymFile<'data, R> {
+    /// Parse the raw xSYM file data.
+    pub fn parse(data: R) -> Result<Self> {
+        let header = data
+            .read_at::<xsym::DiskSymHeaderBlock>(0)
+            .read_error("Invalid xSYM header size or alignment")?;
+        let page_size = header.page_size.get(BE);
+        if usize::from(page_size) < mem::size_of::<xsym::DiskSymHeaderBlock>() {
+            return Err(Error("Invalid xSYM page size"));
+        }
+        Ok(XsymFile {
+            header,
+            page_size: page_size.into(),
+            data,
+        })
+    }
+
+    /// Return the file header.
+    pub fn header(&self) -> &'data xsym::DiskSymHeaderBlock {
+        self.header
+    }
+
+    /// Return the version string from the header.
+    pub fn version(&self) -> Result<&'data [u8]> {
+        let id = &self.header.id;
+        id.get(1..1 + usize::from(id[0]))
+            .read_error("Invalid xSYM version string length")
+    }
+
+    /// Return the name at the given index in the name table.
+    ///
+    /// Names are Pascal strings, and the index is in units of 2 bytes.
+    pub fn name(&self, index: u32) -> Result<&'data [u8]> {
+        let table = &self.header.nte;
+        let start = u64::from(table.first_page.get(BE)) * self.page_size;
+        let size = u64::from(table.page_count.get(BE)) * self.page_size;
+        let offset = u64::from(index) * 2;
+        if offset >= size {
+            return Err(Error("Invalid xSYM name index"));
+        }
+        let len = self
+            .data
+            .read_at::<u8>(start + offset)
+            .read_error("Invalid xSYM name offset")?;
+        if offset + 1 + u64::from(*len) > size {
+            return Err(Error("Invalid xSYM name length"));
+        }
+        self.data
+            .read_bytes_at(start + offset + 1, (*len).into())
+            .read_error("Invalid xSYM name length")
+    }
+
+    /// Return the resources table entry at the given index.
+    pub fn resource(&self, index: u32) -> Result<&'data xsym::DiskResourcesTableEntry> {
+        self.table_entry(&self.header.rte, index)
+    }
+
+    /// Return the modules table entry at the given index.
+    pub fn module(&self, index: u32) -> Result<&'data xsym::DiskModulesTableEntry> {
+        self.table_entry(&self.header.mte, index)
+    }
+
+    /// Return the file references table entry at the given index.
+    pub fn file_reference(&self, index: u32) -> Result<&'data xsym::DiskFileReferencesTableEntry> {
+        self.table_entry(&self.header.frte, index)
+    }
+
+    /// Return the contained statements table entry at the given index.
+    pub fn statement(&self, index: u32) -> Result<&'data xsym::DiskContainedStatementsTableEntry> {
+        self.table_entry(&self.header.csnte, index)
+    }
+
+    /// Return the contained labels table entry at the given index.
+    pub fn label(&self, index: u32) -> Result<&'data xsym::DiskContainedLabelsTableEntry> {
+        self.table_entry(&self.header.clte, index)
+    }
+
+    /// Return the name of the source file for a file references table entry.
+    ///
+    /// The entry must be a [`xsym::FILE_NAME_INDEX`] entry.
+    pub fn file_name(&self, index: u32) -> Result<&'data [u8]> {
+        let entry = self.file_reference(index)?;
+        if entry.tag.get(BE) != xsym::FILE_NAME_INDEX {
+            return Err(Error("Invalid xSYM file name index"));
+        }
+        self.name(entry.value.get(BE))
+    }
+
+    fn table_entry<T: Pod>(&self, table: &xsym::DiskTableInfo, index: u32) -> Result<&'data T> {
+        if index >= table.object_count.get(BE) {
+            return Err(Error("Invalid xSYM table index"));
+        }
+        let entry_size = mem::size_of::<T>() as u64;
+        // Entries never cross a page boundary.
+        let per_page = self.page_size / entry_size;
+        let page = u64::from(index) / per_page;
+        if page >= u64::from(table.page_count.get(BE)) {
+            return Err(Error("Invalid xSYM table page count"));
+        }
+        let offset = (u64::from(table.first_page.get(BE)) + page) * self.page_size
+            + u64::from(index) % per_page * entry_size;
+        self.data
+            .read_at(offset)
+            .read_error("Invalid xSYM table entry offset")
+    }
+
+    /// Calculate the address and size of every module.
+    ///
+    /// Resources are matched in order with the code sections of `pef`.
+    /// Modules in resources without a matching code section have no address.
+    fn module_ranges(&self, pef: &PefFile<'data, R>) -> Result<Vec<Option<(u64, u64)>>> {
+        let code: Vec<_> = pef
+            .sections
+            .iter()
+            .filter(|section| section.kind() == Some(pef::SectionKind::Code))
+            .map(|section| section.pef_address_range())
+            .collect();
+        let count = self.header.mte.object_count.get(BE);
+        let mut ranges = Vec::new();
+        for index in 0..count {
+            let module = self.module(index)?;
+            let resource = usize::from(module.rte_index.get(BE));
+            let range = match code.get(resource) {
+                Some(&(address, size)) => {
+                    let offset = module.res_offset.get(BE);
+                    let module_size = module.size.get(BE);
+                    if u64::from(offset) + u64::from(module_size) > u64::from(size) {
+                        return Err(Error("Invalid xSYM module offset or size"));
+                    }
+                    Some((u64::from(address) + u64::from(offset), module_size.into()))
+                }
+                None => None,
+            };
+            ranges.push(range);
+        }
+        Ok(ranges)
+    }
+
+    /// Construct a map from addresses to the functions and labels in `pef`.
+    ///
+    /// Procedure and function modules are included with their module size.
+    /// Labels extend to the next label in the same module, or to the end of the module.
+    pub fn symbol_map(&self, pef: &PefFile<'data, R>) -> Result<SymbolMap<XsymSymbol<'data>>> {
+        let ranges = self.module_ranges(pef)?;
+        let mut symbols = Vec::new();
+        for (index, range) in ranges.iter().enumerate() {
+            let Some((address, size)) = *range else {
+                continue;
+            };
+            let module = self.module(index as u32)?;
+            if module.kind != xsym::MODULE_KIND_PROCEDURE
+                && module.kind != xsym::MODULE_KIND_FUNCTION
+            {
+                continue;
+            }
+            let name = self.utf8_name(module.nte_index.get(BE))?;
+            if !name.is_empty() {
+                symbols.push(XsymSymbol {
+                    address,
+                    size,
+                    name,
+                });
+            }
+        }
+
+        let mut labels = Vec::new();
+        for index in 0..self.header.clte.object_count.get(BE) {
+            let label = self.label(index)?;
+            let mte_index = label.mte_index.get(BE);
+            if mte_index == xsym::END_OF_LIST || mte_index == xsym::FILE_NAME_INDEX {
+                continue;
+            }
+            let (address, size) = ranges
+                .get(usize::from(mte_index))
+                .copied()
+                .read_error("Invalid xSYM label module index")?
+                .read_error("Invalid xSYM label module")?;
+            let offset = u64::from(label.mte_offset.get(BE));
+            if offset >= size {
+                return Err(Error("Invalid xSYM label offset"));
+            }
+            let name = self.utf8_name(label.nte_index.get(BE))?;
+            labels.push((mte_index, address + offset, address + size, name));
+        }
+        labels.sort_by_key(|&(module, address, ..)| (module, address));
+        for (i, &(module, address, end, name)) in labels.iter().enumerate() {
+            let next = match labels.get(i + 1) {
+                Some(&(next_module, next_address, ..)) if next_module == module => next_address,
+                _ => end,
+            };
+            symbols.push(XsymSymbol {
+                address,
+                size: next - address,
+                name,
+            });
+        }
+        Ok(SymbolMap::new(symbols))
+    }
+
+    /// Return the statements for the code sections of `pef`, sorted by address.
+    pub fn statements(&self, pef: &PefFile<'data, R>) -> Result<Vec<XsymStatement<'data>>> {
+        let ranges = self.module_ranges(pef)?;
+        let mut statements = Vec::new();
+        let mut file = None;
+        let mut source_offset = 0i64;
+        for index in 0..self.header.csnte.object_count.get(BE) {
+            let entry = self.statement(index)?;
+            match entry.mte_index.get(BE) {
+                xsym::END_OF_LIST => file = None,
+                xsym::FILE_NAME_INDEX => {
+                    let frte_index = entry.file_delta.get(BE) as u16;
+                    file = Some(self.file_name(frte_index.into())?);
+                    source_offset = entry.mte_offset.get(BE).into();
+                }
+                mte_index => {
+                    let file = file.read_error("Missing xSYM statement source file")?;
+                    source_offset += i64::from(entry.file_delta.get(BE));
+                    let source_offset = u32::try_from(source_offset)
+                        .ok()
+                        .read_error("Invalid xSYM statement source offset")?;
+                    let Some((address, size)) = ranges
+                        .get(usize::from(mte_index))
+                        .copied()
+                        .read_error("Invalid xSYM statement module index")?
+                    else {
+                        continue;
+                    };
+                    let offset = u64::from(entry.mte_offset.get(BE));
+                    if offset >= size {
+                        return Err(Error("Invalid xSYM statement offset"));
+                    }
+                    statements.push(XsymStatement {
+                        address: address + offset,
+                        file,
+                        source_offset,
+                    });
+                }
+            }
+        }
+        statements.sort_by_key(|statement| statement.address);
+        Ok(statements)
+    }
+
+    fn utf8_name(&self, index: u32) -> Result<&'data str> {
+        str::from_utf8(self.name(index)?)
+            .ok()
+            .read_error("Non UTF-8 xSYM name")
+    }
+}
+
+/// A function or label from an [`XsymFile`].
+///
+/// Returned by [`XsymFile::symbol_map`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XsymSymbol<'data> {
+    address: u64,
+    size: u64,
+    name: &'data str,
+}
+
+impl<'data> XsymSymbol<'data> {
+    /// The symbol address.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The symbol size.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The symbol name.
+    #[inline]
+    pub fn name(&self) -> &'data str {
+        self.name
+    }
+}
+
+impl<'data> SymbolMapEntry for XsymSymbol<'data> {
+    #[inline]
+    fn address(&self) -> u64 {
+        self.address
+    }
+}
+
+/// A statement from an [`XsymFile`].
+///
+/// SYM files record the character offset of each statement in its source file
+/// rather than a line number. Use [`Self::line`] with the source text to find the line.
+///
+/// Returned by [`XsymFile::statements`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XsymStatement<'data> {
+    address: u64,
+    file: &'data [u8],
+    source_offset: u32,
+}
+
+impl<'data> XsymStatement<'data> {
+    /// The address of the first instruction of the statement.
+    #[inline]
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// The name of the source file.
+    #[inline]
+    pub fn file(&self) -> &'data [u8] {
+        self.file
+    }
+
+    /// The byte offset of the statement in the source file.
+    #[inline]
+    pub fn source_offset(&self) -> u32 {
+        self.source_offset
+    }
+
+    /// Calculate the one-based line number of the statement in `source`.
+    ///
+    /// Lines may end with CR (as is usual for Mac OS), LF, or CR LF.
+    pub fn line(&self, source: &[u8]) -> u32 {
+        let end = usize::try_from(self.source_offset)
+            .map_or(source.len(), |offset| offset.min(source.len()));
+        let mut line = 1;
+        let mut previous = 0;
+        for &byte in &source[..end] {
+            if byte == b'\r' || (byte == b'\n' && previous != b'\r') {
+                line += 1;
+            }
+            previous = byte;
+        }
+        line
+    }
+}
@@ -0,0 +1,1467 @@
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+
+use crate::elf;
+use crate::endian::{Endian, Endianness};
+use crate::read::{
+    self, Architecture, Object, ObjectSection, ObjectSymbol, Relocation, RelocationEncoding,
+    RelocationFlags, RelocationKind, RelocationTarget, SectionIndex, SymbolFlags, SymbolIndex,
+    SymbolSection,
+};
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::btree_map::BTreeMap as Map;
+#[cfg(feature = "std")]
+use std::collections::hash_map::HashMap as Map;
+
+/// An error that occurred while applying relocations with a [`Relocator`].
+///
+/// `offset` is the offset of the relocation within the section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RelocateError {
+    /// The file could not be parsed.
+    Read(read::Error),
+    /// The relocation type is not supported for the architecture.
+    ///
+    /// This includes relocations that require a GOT, PLT or dynamic TLS.
+    UnsupportedRelocation {
+        /// The offset of the relocation.
+        offset: u64,
+        /// The relocation flags.
+        flags: RelocationFlags,
+    },
+    /// The relocation target is not supported, such as a common symbol.
+    UnsupportedTarget {
+        /// The offset of the relocation.
+        offset: u64,
+        /// The relocation target.
+        target: RelocationTarget,
+    },
+    /// The relocation refers to an undefined symbol that has no value.
+    ///
+    /// Use [`Relocator::set_symbol_value`] to provide a value.
+    UndefinedSymbol {
+        /// The offset of the relocation.
+        offset: u64,
+        /// The index of the symbol.
+        symbol: SymbolIndex,
+    },
+    /// The relocated value does not fit in the relocated field.
+    Overflow {
+        /// The offset of the relocation.
+        offset: u64,
+        /// The relocation flags.
+        flags: RelocationFlags,
+        /// The relocated value before it was encoded.
+        value: u64,
+    },
+    /// The relocated value does not have the alignment required by the relocated field.
+    Misaligned {
+        /// The offset of the relocation.
+        offset: u64,
+        /// The relocation flags.
+        flags: RelocationFlags,
+        /// The relocated value before it was encoded.
+        value: u64,
+    },
+    /// The relocated field is outside the section data.
+    InvalidOffset {
+        /// The offset of the relocation.
+        offset: u64,
+    },
+    /// A TLS relocation was found but no thread pointer was set.
+    ///
+    /// Use [`Relocator::set_thread_pointer`] to set it.
+    MissingThreadPointer {
+        /// The offset of the relocation.
+        offset: u64,
+    },
+    /// A TOC relative relocation was found but no TOC base was set.
+    ///
+    /// Use [`Relocator::set_toc_base`] to set it.
+    MissingTocBase {
+        /// The offset of the relocation.
+        offset: u64,
+    },
+}
+
+impl fmt::Display for RelocateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RelocateError::Read(error) => error.fmt(f),
+            RelocateError::UnsupportedRelocation { offset, flags } => {
+                write!(f, "Unsupported relocation {:?} at {:#x}", flags, offset)
+            }
+            RelocateError::UnsupportedTarget { offset, target } => {
+                write!(
+                    f,
+                    "Unsupported relocation target {:?} at {:#x}",
+                    target, offset
+                )
+            }
+            RelocateError::UndefinedSymbol { offset, symbol } => {
+                write!(
+                    f,
+                    "Relocation at {:#x} refers to undefined symbol {}",
+                    offset, symbol.0
+                )
+            }
+            RelocateError::Overflow {
+                offset,
+                flags,
+                value,
+            } => write!(
+                f,
+                "Relocated value {:#x} for {:?} at {:#x} is out of range",
+                value, flags, offset
+            ),
+            RelocateError::Misaligned {
+                offset,
+                flags,
+                value,
+            } => write!(
+                f,
+                "Relocated value {:#x} for {:?} at {:#x} is misaligned",
+                value, flags, offset
+            ),
+            RelocateError::InvalidOffset { offset } => {
+                write!(f, "Invalid relocation offset {:#x}", offset)
+            }
+            RelocateError::MissingThreadPointer { offset } => {
+                write!(
+                    f,
+                    "TLS relocation at {:#x} requires a thread pointer",
+                    offset
+                )
+            }
+            RelocateError::MissingTocBase { offset } => {
+                write!(f, "TOC relocation at {:#x} requires a TOC base", offset)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RelocateError {}
+#[cfg(all(not(feature = "std"), core_error))]
+impl core::error::Error for RelocateError {}
+
+impl From<read::Error> for RelocateError {
+    fn from(error: read::Error) -> Self {
+        RelocateError::Read(error)
+    }
+}
+
+/// Applies the relocations in the sections of a relocatable object file.
+///
+/// This is intended for loading object files directly into memory. The caller
+/// chooses the address of each section and the values of undefined symbols,
+/// and then calls [`Self::relocate_section`] to obtain the contents of each section.
+///
+/// All relocations that can be resolved without a GOT, PLT or dynamic TLS are supported
+/// for ELF files for x86-64, i386, AArch64, ARM, 64-bit RISC-V, 64-bit PowerPC
+/// and s390x. This includes relaxing x86-64 `R_X86_64_(REX_)GOTPCRELX` relocations
+/// to direct references, and local-exec TLS relocations. For other file formats,
+/// only relocations with a [`RelocationEncoding::Generic`] encoding are supported.
+#[derive(Debug)]
+pub struct Relocator<'data, 'file, T>
+where
+    T: Object<'data>,
+{
+    file: &'file T,
+    section_addresses: Vec<Option<u64>>,
+    symbol_values: Map<Vec<u8>, u64>,
+    thread_pointer: Option<u64>,
+    toc_base: Option<u64>,
+    marker: core::marker::PhantomData<&'data ()>,
+}
+
+impl<'data, 'file, T> Relocator<'data, 'file, T>
+where
+    T: Object<'data>,
+{
+    /// Create a new relocator for the given file.
+    ///
+    /// Sections initially have the address given in the file.
+    pub fn new(file: &'file T) -> Self {
+        Relocator {
+            file,
+            section_addresses: Vec::new(),
+            symbol_values: Map::new(),
+            thread_pointer: None,
+            toc_base: None,
+            marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Set the address that a section will be loaded at.
+    pub fn set_section_address(&mut self, index: SectionIndex, address: u64) {
+        if self.section_addresses.len() <= index.0 {
+            self.section_addresses.resize(index.0 + 1, None);
+        }
+        self.section_addresses[index.0] = Some(address);
+    }
+
+    /// Set the value of an undefined symbol.
+    ///
+    /// Undefined weak symbols without a value resolve to 0.
+    pub fn set_symbol_value(&mut self, name: &[u8], value: u64) {
+        self.symbol_values.insert(name.to_vec(), value);
+    }
+
+    /// Set the address of the thread pointer.
+    ///
+    /// TLS relocations resolve to the address of the symbol minus the thread pointer,
+    /// so the addresses of the TLS sections should be set to where their initial
+    /// images will be placed relative to the thread pointer. For example, on x86-64
+    /// the TLS block ends at the thread pointer, while on AArch64 it starts 16 bytes
+    /// after the thread pointer.
+    pub fn set_thread_pointer(&mut self, address: u64) {
+        self.thread_pointer = Some(address);
+    }
+
+    /// Set the TOC base address for 64-bit PowerPC.
+    ///
+    /// This is the value of the `.TOC.` symbol, which is conventionally the address
+    /// of the `.got` or `.toc` section plus 0x8000.
+    pub fn set_toc_base(&mut self, address: u64) {
+        self.toc_base = Some(address);
+    }
+
+    /// Return the address that a section will be loaded at.
+    pub fn section_address(&self, index: SectionIndex) -> read::Result<u64> {
+        if let Some(Some(address)) = self.section_addresses.get(index.0) {
+            return Ok(*address);
+        }
+        Ok(self.file.section_by_index(index)?.address())
+    }
+
+    /// Return the contents of a section with all of its relocations applied.
+    pub fn relocate_section(&self, index: SectionIndex) -> Result<Vec<u8>, RelocateError> {
+        let section = self.file.section_by_index(index)?;
+        let mut data = section.uncompressed_data()?.into_owned();
+        self.apply(index, &mut data)?;
+        Ok(data)
+    }
+
+    /// Apply the relocations for a section to `data`.
+    ///
+    /// `data` must contain the section contents, with any implicit addends.
+    pub fn apply(&self, index: SectionIndex, data: &mut [u8]) -> Result<(), RelocateError> {
+        let section = self.file.section_by_index(index)?;
+        let base = self.section_address(index)?;
+        let endian = if self.file.is_little_endian() {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
+        let architecture = self.file.architecture();
+        let relocations: Vec<(u64, Relocation)> = section.relocations().collect();
+
+        // RISC-V `R_RISCV_PCREL_LO12_*` relocations refer to the instruction
+        // with the corresponding `R_RISCV_PCREL_HI20`, so calculate those first.
+        let mut pcrel_hi = Vec::new();
+        if architecture == Architecture::Riscv64 {
+            for (offset, relocation) in &relocations {
+                if relocation.flags()
+                    == (RelocationFlags::Elf {
+                        r_type: elf::R_RISCV_PCREL_HI20,
+                    })
+                {
+                    let target = self.target(*offset, relocation)?;
+                    let value = target
+                        .value
+                        .wrapping_add(relocation.addend() as u64)
+                        .wrapping_sub(base.wrapping_add(*offset));
+                    pcrel_hi.push((*offset, value));
+                }
+            }
+        }
+
+        let mut context = Context {
+            relocator: self,
+            architecture,
+            endian,
+            base,
+            data,
+            pcrel_hi: &pcrel_hi,
+        };
+        for (offset, relocation) in &relocations {
+            context.apply(*offset, relocation)?;
+        }
+        Ok(())
+    }
+
+    /// Find the address of the target of a relocation.
+    fn target(&self, offset: u64, relocation: &Relocation) -> Result<Target, RelocateError> {
+        let unsupported = RelocateError::UnsupportedTarget {
+            offset,
+            target: relocation.target(),
+        };
+        match relocation.target() {
+            RelocationTarget::Symbol(index) => {
+                let symbol = self.file.symbol_by_index(index)?;
+                let value = match symbol.section() {
+                    SymbolSection::Section(section_index) => {
+                        // Symbol addresses may be absolute, so make them relative
+                        // to the section first.
+                        let section = self.file.section_by_index(section_index)?;
+                        let offset = symbol.address().wrapping_sub(section.address());
+                        self.section_address(section_index)?.wrapping_add(offset)
+                    }
+                    SymbolSection::Absolute => symbol.address(),
+                    SymbolSection::Undefined => {
+                        let name = symbol.name_bytes()?;
+                        match self.symbol_values.get(name) {
+                            Some(value) => *value,
+                            None if symbol.is_weak() => 0,
+                            None => {
+                                return Err(RelocateError::UndefinedSymbol {
+                                    offset,
+                                    symbol: index,
+                                })
+                            }
+                        }
+                    }
+                    _ => return Err(unsupported),
+                };
+                Ok(Target {
+                    value,
+                    size: symbol.size(),
+                    flags: symbol.flags(),
+                })
+            }
+            RelocationTarget::Section(index) => Ok(Target {
+                value: self.section_address(index)?,
+                size: 0,
+                flags: SymbolFlags::None,
+            }),
+            RelocationTarget::Absolute => Ok(Target {
+                value: 0,
+                size: 0,
+                flags: SymbolFlags::None,
+            }),
+        }
+    }
+}
+
+/// The resolved target of a relocation.
+#[derive(Debug, Clone, Copy)]
+struct Target {
+    value: u64,
+    size: u64,
+    flags: SymbolFlags<SectionIndex, SymbolIndex>,
+}
+
+/// The calculation performed by a relocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Calc {
+    /// No operation.
+    Nop,
+    /// S + A
+    Abs,
+    /// S + A - P
+    Pc,
+    /// Page(S + A) - Page(P), where pages are 4096 bytes.
+    Page,
+    /// S + A - TP
+    Tp,
+    /// S + A - TOC
+    Toc,
+    /// TOC + A
+    TocBase,
+    /// Z + A
+    Size,
+    /// Field + S + A
+    Add,
+    /// Field - (S + A)
+    Sub,
+    /// The value of the RISC-V `R_RISCV_PCREL_HI20` relocation at S.
+    PcrelLo,
+    /// S + A - P, after relaxing an x86-64 GOT load.
+    GotPcRelax,
+    /// Check RISC-V alignment padding.
+    Align,
+}
+
+/// Overflow checks for data fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Check {
+    None,
+    Signed,
+    Unsigned,
+    /// Either signed or unsigned.
+    Bitfield,
+}
+
+/// Adjustments for 16-bit PowerPC fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PpcAdjust {
+    Signed,
+    Lo,
+    Hi,
+    Ha,
+    Higher,
+    Highera,
+    Highest,
+    Highesta,
+}
+
+/// How the value of a relocation is encoded in the relocated field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// A data value with the given size in bytes.
+    Data(u8, Check),
+    /// The low 6 bits of a byte.
+    Bits6,
+    /// AArch64 ADR/ADRP 21-bit immediate of the value shifted right.
+    A64Adr { shift: u8, check: bool },
+    /// AArch64 ADD 12-bit immediate of the value shifted right, with an optional
+    /// unsigned check of the value.
+    A64Add { shift: u8, bits: u8 },
+    /// AArch64 load/store 12-bit immediate, scaled by the access size.
+    A64Ldst { scale: u8 },
+    /// AArch64 B/BL 26-bit branch offset.
+    A64Branch26,
+    /// AArch64 conditional branch and literal load 19-bit offset.
+    A64Branch19,
+    /// AArch64 TBZ/TBNZ 14-bit branch offset.
+    A64Branch14,
+    /// AArch64 MOVZ/MOVK 16-bit immediate of the value shifted right.
+    A64Movw { shift: u8, check: bool },
+    /// ARM B/BL/BLX 24-bit branch offset.
+    ArmBranch24,
+    /// ARM MOVW/MOVT 16-bit immediate of the value shifted right.
+    ArmMov { shift: u8 },
+    /// 31-bit place relative offset, as used in exception tables.
+    ArmPrel31,
+    /// Thumb-2 B.W/BL/BLX 24-bit branch offset.
+    ThumbBranch24,
+    /// Thumb-2 MOVW/MOVT 16-bit immediate of the value shifted right.
+    ThumbMov { shift: u8 },
+    /// RISC-V B-type 13-bit branch offset.
+    RvBranch,
+    /// RISC-V J-type 21-bit jump offset.
+    RvJal,
+    /// RISC-V AUIPC/LUI upper 20 bits.
+    RvHi20,
+    /// RISC-V I-type low 12 bits.
+    RvLo12I,
+    /// RISC-V S-type low 12 bits.
+    RvLo12S,
+    /// RISC-V AUIPC and JALR pair.
+    RvCall,
+    /// RISC-V compressed 9-bit branch offset.
+    RvcBranch,
+    /// RISC-V compressed 12-bit jump offset.
+    RvcJump,
+    /// PowerPC 16-bit field.
+    PpcHalf(PpcAdjust),
+    /// PowerPC DS-form 14-bit field, which must be 4 byte aligned.
+    PpcDs(PpcAdjust),
+    /// PowerPC I-form 24-bit branch offset.
+    PpcBranch24,
+    /// PowerPC B-form 14-bit branch offset.
+    PpcBranch14,
+    /// s390x halfword scaled PC relative offset with the given size in bits.
+    S390Dbl(u8),
+    /// s390x unsigned 12-bit displacement.
+    S390Disp12,
+    /// s390x signed 20-bit displacement.
+    S390Disp20,
+}
+
+/// The result of encoding a value in a field.
+enum FieldError {
+    Overflow,
+    Misaligned,
+    InvalidOffset,
+    Unsupported,
+}
+
+fn howto(architecture: Architecture, flags: RelocationFlags) -> Option<(Calc, Field)> {
+    use self::Calc::*;
+    use self::Check::Bitfield;
+    use self::Field::*;
+    use self::PpcAdjust::*;
+
+    let r_type = match flags {
+        RelocationFlags::Elf { r_type } => r_type,
+        RelocationFlags::Generic {
+            kind,
+            encoding: RelocationEncoding::Generic,
+            size,
+        } if size % 8 == 0 && size > 0 && size <= 64 => {
+            let calc = match kind {
+                RelocationKind::Absolute => Abs,
+                RelocationKind::Relative => Pc,
+                _ => return None,
+            };
+            let check = if size == 64 { Check::None } else { Bitfield };
+            return Some((calc, Data(size / 8, check)));
+        }
+        _ => return None,
+    };
+    let signed = Check::Signed;
+    let unsigned = Check::Unsigned;
+    let none = Check::None;
+    Some(match architecture {
+        Architecture::X86_64 => match r_type {
+            elf::R_X86_64_NONE => (Nop, Data(0, none)),
+            elf::R_X86_64_64 => (Abs, Data(8, none)),
+            elf::R_X86_64_PC32 | elf::R_X86_64_PLT32 => (Pc, Data(4, signed)),
+            elf::R_X86_64_32 => (Abs, Data(4, unsigned)),
+            elf::R_X86_64_32S => (Abs, Data(4, signed)),
+            elf::R_X86_64_16 => (Abs, Data(2, Bitfield)),
+            elf::R_X86_64_PC16 => (Pc, Data(2, signed)),
+            elf::R_X86_64_8 => (Abs, Data(1, Bitfield)),
+            elf::R_X86_64_PC8 => (Pc, Data(1, signed)),
+            elf::R_X86_64_PC64 => (Pc, Data(8, none)),
+            elf::R_X86_64_TPOFF32 => (Tp, Data(4, signed)),
+            elf::R_X86_64_TPOFF64 => (Tp, Data(8, none)),
+            elf::R_X86_64_SIZE32 => (Size, Data(4, unsigned)),
+            elf::R_X86_64_SIZE64 => (Size, Data(8, none)),
+            elf::R_X86_64_GOTPCRELX | elf::R_X86_64_REX_GOTPCRELX => (GotPcRelax, Data(4, signed)),
+            _ => return None,
+        },
+        Architecture::I386 => match r_type {
+            elf::R_386_NONE => (Nop, Data(0, none)),
+            elf::R_386_32 => (Abs, Data(4, none)),
+            elf::R_386_PC32 | elf::R_386_PLT32 => (Pc, Data(4, none)),
+            elf::R_386_16 => (Abs, Data(2, Bitfield)),
+            elf::R_386_PC16 => (Pc, Data(2, signed)),
+            elf::R_386_8 => (Abs, Data(1, Bitfield)),
+            elf::R_386_PC8 => (Pc, Data(1, signed)),
+            elf::R_386_TLS_LE => (Tp, Data(4, none)),
+            _ => return None,
+        },
+        Architecture::Aarch64 => match r_type {
+            elf::R_AARCH64_NONE | 256 => (Nop, Data(0, none)),
+            elf::R_AARCH64_ABS64 => (Abs, Data(8, none)),
+            elf::R_AARCH64_ABS32 => (Abs, Data(4, Bitfield)),
+            elf::R_AARCH64_ABS16 => (Abs, Data(2, Bitfield)),
+            elf::R_AARCH64_PREL64 => (Pc, Data(8, none)),
+            elf::R_AARCH64_PREL32 | elf::R_AARCH64_PLT32 => (Pc, Data(4, signed)),
+            elf::R_AARCH64_PREL16 => (Pc, Data(2, signed)),
+            elf::R_AARCH64_ADR_PREL_LO21 => (
+                Pc,
+                A64Adr {
+                    shift: 0,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_ADR_PREL_PG_HI21 => (
+                Page,
+                A64Adr {
+                    shift: 12,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_ADR_PREL_PG_HI21_NC => (
+                Page,
+                A64Adr {
+                    shift: 12,
+                    check: false,
+                },
+            ),
+            elf::R_AARCH64_ADD_ABS_LO12_NC => (Abs, A64Add { shift: 0, bits: 0 }),
+            elf::R_AARCH64_LDST8_ABS_LO12_NC => (Abs, A64Ldst { scale: 0 }),
+            elf::R_AARCH64_LDST16_ABS_LO12_NC => (Abs, A64Ldst { scale: 1 }),
+            elf::R_AARCH64_LDST32_ABS_LO12_NC => (Abs, A64Ldst { scale: 2 }),
+            elf::R_AARCH64_LDST64_ABS_LO12_NC => (Abs, A64Ldst { scale: 3 }),
+            elf::R_AARCH64_LDST128_ABS_LO12_NC => (Abs, A64Ldst { scale: 4 }),
+            elf::R_AARCH64_CALL26 | elf::R_AARCH64_JUMP26 => (Pc, A64Branch26),
+            elf::R_AARCH64_CONDBR19 | elf::R_AARCH64_LD_PREL_LO19 => (Pc, A64Branch19),
+            elf::R_AARCH64_TSTBR14 => (Pc, A64Branch14),
+            elf::R_AARCH64_MOVW_UABS_G0 => (
+                Abs,
+                A64Movw {
+                    shift: 0,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_MOVW_UABS_G0_NC => (
+                Abs,
+                A64Movw {
+                    shift: 0,
+                    check: false,
+                },
+            ),
+            elf::R_AARCH64_MOVW_UABS_G1 => (
+                Abs,
+                A64Movw {
+                    shift: 16,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_MOVW_UABS_G1_NC => (
+                Abs,
+                A64Movw {
+                    shift: 16,
+                    check: false,
+                },
+            ),
+            elf::R_AARCH64_MOVW_UABS_G2 => (
+                Abs,
+                A64Movw {
+                    shift: 32,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_MOVW_UABS_G2_NC => (
+                Abs,
+                A64Movw {
+                    shift: 32,
+                    check: false,
+                },
+            ),
+            elf::R_AARCH64_MOVW_UABS_G3 => (
+                Abs,
+                A64Movw {
+                    shift: 48,
+                    check: false,
+                },
+            ),
+            elf::R_AARCH64_TLSLE_MOVW_TPREL_G2 => (
+                Tp,
+                A64Movw {
+                    shift: 32,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_TLSLE_MOVW_TPREL_G1 => (
+                Tp,
+                A64Movw {
+                    shift: 16,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_TLSLE_MOVW_TPREL_G1_NC => (
+                Tp,
+                A64Movw {
+                    shift: 16,
+                    check: false,
+                },
+            ),
+            elf::R_AARCH64_TLSLE_MOVW_TPREL_G0 => (
+                Tp,
+                A64Movw {
+                    shift: 0,
+                    check: true,
+                },
+            ),
+            elf::R_AARCH64_TLSLE_MOVW_TPREL_G0_NC => (
+                Tp,
+                A64Movw {
+                    shift: 0,
+                    check: false,
+                },
+            ),
+            elf::R_AARCH64_TLSLE_ADD_TPREL_HI12 => (
+                Tp,
+                A64Add {
+                    shift: 12,
+                    bits: 24,
+                },
+            ),
+            elf::R_AARCH64_TLSLE_ADD_TPREL_LO12 => (Tp, A64Add { shift: 0, bits: 12 }),
+            elf::R_AARCH64_TLSLE_ADD_TPREL_LO12_NC => (Tp, A64Add { shift: 0, bits: 0 }),
+            elf::R_AARCH64_TLSLE_LDST8_TPREL_LO12_NC => (Tp, A64Ldst { scale: 0 }),
+            elf::R_AARCH64_TLSLE_LDST16_TPREL_LO12_NC => (Tp, A64Ldst { scale: 1 }),
+            elf::R_AARCH64_TLSLE_LDST32_TPREL_LO12_NC => (Tp, A64Ldst { scale: 2 }),
+            elf::R_AARCH64_TLSLE_LDST64_TPREL_LO12_NC => (Tp, A64Ldst { scale: 3 }),
+            elf::R_AARCH64_TLSLE_LDST128_TPREL_LO12_NC => (Tp, A64Ldst { scale: 4 }),
+            _ => return None,
+        },
+        Architecture::Arm => match r_type {
+            elf::R_ARM_NONE | elf::R_ARM_V4BX => (Nop, Data(0, none)),
+            elf::R_ARM_ABS32 | elf::R_ARM_TARGET1 => (Abs, Data(4, none)),
+            elf::R_ARM_REL32 => (Pc, Data(4, none)),
+            elf::R_ARM_ABS16 => (Abs, Data(2, Bitfield)),
+            elf::R_ARM_ABS8 => (Abs, Data(1, Bitfield)),
+            elf::R_ARM_PREL31 => (Pc, ArmPrel31),
+            elf::R_ARM_PC24 | elf::R_ARM_CALL | elf::R_ARM_JUMP24 | elf::R_ARM_PLT32 => {
+                (Pc, ArmBranch24)
+            }
+            elf::R_ARM_MOVW_ABS_NC => (Abs, ArmMov { shift: 0 }),
+            elf::R_ARM_MOVT_ABS => (Abs, ArmMov { shift: 16 }),
+            elf::R_ARM_MOVW_PREL_NC => (Pc, ArmMov { shift: 0 }),
+            elf::R_ARM_MOVT_PREL => (Pc, ArmMov { shift: 16 }),
+            elf::R_ARM_THM_PC22 | elf::R_ARM_THM_JUMP24 => (Pc, ThumbBranch24),
+            elf::R_ARM_THM_MOVW_ABS_NC => (Abs, ThumbMov { shift: 0 }),
+            elf::R_ARM_THM_MOVT_ABS => (Abs, ThumbMov { shift: 16 }),
+            elf::R_ARM_THM_MOVW_PREL_NC => (Pc, ThumbMov { shift: 0 }),
+            elf::R_ARM_THM_MOVT_PREL => (Pc, ThumbMov { shift: 16 }),
+            elf::R_ARM_TLS_LE32 => (Tp, Data(4, none)),
+            _ => return None,
+        },
+        Architecture::Riscv64 => match r_type {
+            elf::R_RISCV_NONE | elf::R_RISCV_RELAX | elf::R_RISCV_TPREL_ADD => (Nop, Data(0, none)),
+            elf::R_RISCV_32 => (Abs, Data(4, none)),
+            elf::R_RISCV_64 => (Abs, Data(8, none)),
+            elf::R_RISCV_32_PCREL | elf::R_RISCV_PLT32 => (Pc, Data(4, signed)),
+            elf::R_RISCV_BRANCH => (Pc, RvBranch),
+            elf::R_RISCV_JAL => (Pc, RvJal),
+            elf::R_RISCV_CALL | elf::R_RISCV_CALL_PLT => (Pc, RvCall),
+            elf::R_RISCV_PCREL_HI20 => (Pc, RvHi20),
+            elf::R_RISCV_PCREL_LO12_I => (PcrelLo, RvLo12I),
+            elf::R_RISCV_PCREL_LO12_S => (PcrelLo, RvLo12S),
+            elf::R_RISCV_HI20 => (Abs, RvHi20),
+            elf::R_RISCV_LO12_I => (Abs, RvLo12I),
+            elf::R_RISCV_LO12_S => (Abs, RvLo12S),
+            elf::R_RISCV_TPREL_HI20 => (Tp, RvHi20),
+            elf::R_RISCV_TPREL_LO12_I => (Tp, RvLo12I),
+            elf::R_RISCV_TPREL_LO12_S => (Tp, RvLo12S),
+            elf::R_RISCV_ADD8 => (Add, Data(1, none)),
+            elf::R_RISCV_ADD16 => (Add, Data(2, none)),
+            elf::R_RISCV_ADD32 => (Add, Data(4, none)),
+            elf::R_RISCV_ADD64 => (Add, Data(8, none)),
+            elf::R_RISCV_SUB6 => (Sub, Bits6),
+            elf::R_RISCV_SUB8 => (Sub, Data(1, none)),
+            elf::R_RISCV_SUB16 => (Sub, Data(2, none)),
+            elf::R_RISCV_SUB32 => (Sub, Data(4, none)),
+            elf::R_RISCV_SUB64 => (Sub, Data(8, none)),
+            elf::R_RISCV_SET6 => (Abs, Bits6),
+            elf::R_RISCV_SET8 => (Abs, Data(1, none)),
+            elf::R_RISCV_SET16 => (Abs, Data(2, none)),
+            elf::R_RISCV_SET32 => (Abs, Data(4, none)),
+            elf::R_RISCV_RVC_BRANCH => (Pc, RvcBranch),
+            elf::R_RISCV_RVC_JUMP => (Pc, RvcJump),
+            elf::R_RISCV_ALIGN => (Align, Data(0, none)),
+            _ => return None,
+        },
+        Architecture::PowerPc64 => match r_type {
+            elf::R_PPC64_NONE | elf::R_PPC64_TLS => (Nop, Data(0, none)),
+            elf::R_PPC64_ADDR64 => (Abs, Data(8, none)),
+            elf::R_PPC64_ADDR32 => (Abs, Data(4, Bitfield)),
+            elf::R_PPC64_REL64 => (Pc, Data(8, none)),
+            elf::R_PPC64_REL32 => (Pc, Data(4, signed)),
+            elf::R_PPC64_ADDR16 => (Abs, PpcHalf(Signed)),
+            elf::R_PPC64_ADDR16_LO => (Abs, PpcHalf(Lo)),
+            elf::R_PPC64_ADDR16_HI => (Abs, PpcHalf(Hi)),
+            elf::R_PPC64_ADDR16_HA => (Abs, PpcHalf(Ha)),
+            elf::R_PPC64_ADDR16_HIGHER => (Abs, PpcHalf(Higher)),
+            elf::R_PPC64_ADDR16_HIGHERA => (Abs, PpcHalf(Highera)),
+            elf::R_PPC64_ADDR16_HIGHEST => (Abs, PpcHalf(Highest)),
+            elf::R_PPC64_ADDR16_HIGHESTA => (Abs, PpcHalf(Highesta)),
+            elf::R_PPC64_ADDR16_DS => (Abs, PpcDs(Signed)),
+            elf::R_PPC64_ADDR16_LO_DS => (Abs, PpcDs(Lo)),
+            elf::R_PPC64_REL24 => (Pc, PpcBranch24),
+            elf::R_PPC64_REL14 => (Pc, PpcBranch14),
+            elf::R_PPC64_REL16 => (Pc, PpcHalf(Signed)),
+            elf::R_PPC64_REL16_LO => (Pc, PpcHalf(Lo)),
+            elf::R_PPC64_REL16_HI => (Pc, PpcHalf(Hi)),
+            elf::R_PPC64_REL16_HA => (Pc, PpcHalf(Ha)),
+            elf::R_PPC64_TOC => (TocBase, Data(8, none)),
+            elf::R_PPC64_TOC16 => (Toc, PpcHalf(Signed)),
+            elf::R_PPC64_TOC16_LO => (Toc, PpcHalf(Lo)),
+            elf::R_PPC64_TOC16_HI => (Toc, PpcHalf(Hi)),
+            elf::R_PPC64_TOC16_HA => (Toc, PpcHalf(Ha)),
+            elf::R_PPC64_TOC16_DS => (Toc, PpcDs(Signed)),
+            elf::R_PPC64_TOC16_LO_DS => (Toc, PpcDs(Lo)),
+            elf::R_PPC64_TPREL64 => (Tp, Data(8, none)),
+            elf::R_PPC64_TPREL16 => (Tp, PpcHalf(Signed)),
+            elf::R_PPC64_TPREL16_LO => (Tp, PpcHalf(Lo)),
+            elf::R_PPC64_TPREL16_HI => (Tp, PpcHalf(Hi)),
+            elf::R_PPC64_TPREL16_HA => (Tp, PpcHalf(Ha)),
+            elf::R_PPC64_TPREL16_DS => (Tp, PpcDs(Signed)),
+            elf::R_PPC64_TPREL16_LO_DS => (Tp, PpcDs(Lo)),
+            _ => return None,
+        },
+        Architecture::S390x => match r_type {
+            elf::R_390_NONE => (Nop, Data(0, none)),
+            elf::R_390_8 => (Abs, Data(1, Bitfield)),
+            elf::R_390_16 => (Abs, Data(2, Bitfield)),
+            elf::R_390_32 => (Abs, Data(4, Bitfield)),
+            elf::R_390_64 => (Abs, Data(8, none)),
+            elf::R_390_12 => (Abs, S390Disp12),
+            elf::R_390_20 => (Abs, S390Disp20),
+            elf::R_390_PC16 => (Pc, Data(2, signed)),
+            elf::R_390_PC32 | elf::R_390_PLT32 => (Pc, Data(4, signed)),
+            elf::R_390_PC64 | elf::R_390_PLT64 => (Pc, Data(8, none)),
+            elf::R_390_PC12DBL | elf::R_390_PLT12DBL => (Pc, S390Dbl(12)),
+            elf::R_390_PC16DBL | elf::R_390_PLT16DBL => (Pc, S390Dbl(16)),
+            elf::R_390_PC24DBL | elf::R_390_PLT24DBL => (Pc, S390Dbl(24)),
+            elf::R_390_PC32DBL | elf::R_390_PLT32DBL => (Pc, S390Dbl(32)),
+            elf::R_390_TLS_LE32 => (Tp, Data(4, signed)),
+            elf::R_390_TLS_LE64 => (Tp, Data(8, none)),
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+/// The state for applying the relocations of one section.
+struct Context<'a, 'data, 'file, T>
+where
+    T: Object<'data>,
+{
+    relocator: &'a Relocator<'data, 'file, T>,
+    architecture: Architecture,
+    endian: Endianness,
+    base: u64,
+    data: &'a mut [u8],
+    /// The offsets and values of `R_RISCV_PCREL_HI20` relocations.
+    pcrel_hi: &'a [(u64, u64)],
+}
+
+impl<'a, 'data, 'file, T> Context<'a, 'data, 'file, T>
+where
+    T: Object<'data>,
+{
+    fn apply(&mut self, offset: u64, relocation: &Relocation) -> Result<(), RelocateError> {
+        let flags = relocation.flags();
+        let unsupported = RelocateError::UnsupportedRelocation { offset, flags };
+        let (calc, field) = howto(self.architecture, flags).ok_or(unsupported)?;
+        if calc == Calc::Nop {
+            return Ok(());
+        }
+        let map_error = |error| match error {
+            FieldError::Overflow => RelocateError::Overflow {
+                offset,
+                flags,
+                value: 0,
+            },
+            FieldError::Misaligned => RelocateError::Misaligned {
+                offset,
+                flags,
+                value: 0,
+            },
+            FieldError::InvalidOffset => RelocateError::InvalidOffset { offset },
+            FieldError::Unsupported => unsupported,
+        };
+        let place = usize::try_from(offset)
+            .ok()
+            .filter(|place| *place <= self.data.len())
+            .ok_or(RelocateError::InvalidOffset { offset })?;
+
+        let target = self.relocator.target(offset, relocation)?;
+        let mut s = target.value;
+        let p = self.base.wrapping_add(offset);
+        let a = if relocation.has_implicit_addend() {
+            self.read_addend(place, field).map_err(map_error)?
+        } else {
+            relocation.addend() as u64
+        };
+
+        if self.architecture == Architecture::PowerPc64
+            && flags
+                == (RelocationFlags::Elf {
+                    r_type: elf::R_PPC64_REL24,
+                })
+        {
+            // Calls to ELFv2 functions that set up the TOC pointer go to the local entry point.
+            if let SymbolFlags::Elf { st_other, .. } = target.flags {
+                s = s.wrapping_add(ppc64_local_entry_offset(st_other));
+            }
+        }
+
+        let mut value = match calc {
+            Calc::Nop => return Ok(()),
+            Calc::Abs => s.wrapping_add(a),
+            Calc::Pc => s.wrapping_add(a).wrapping_sub(p),
+            Calc::Page => (s.wrapping_add(a) & !0xfff).wrapping_sub(p & !0xfff),
+            Calc::Tp => {
+                let tp = self
+                    .relocator
+                    .thread_pointer
+                    .ok_or(RelocateError::MissingThreadPointer { offset })?;
+                s.wrapping_add(a).wrapping_sub(tp)
+            }
+            Calc::Toc => {
+                let toc = self
+                    .relocator
+                    .toc_base
+                    .ok_or(RelocateError::MissingTocBase { offset })?;
+                s.wrapping_add(a).wrapping_sub(toc)
+            }
+            Calc::TocBase => {
+                let toc = self
+                    .relocator
+                    .toc_base
+                    .ok_or(RelocateError::MissingTocBase { offset })?;
+                toc.wrapping_add(a)
+            }
+            Calc::Size => target.size.wrapping_add(a),
+            Calc::Add => {
+                let old = self.read_data(place, field).map_err(map_error)?;
+                old.wrapping_add(s).wrapping_add(a)
+            }
+            Calc::Sub => {
+                let old = self.read_data(place, field).map_err(map_error)?;
+                old.wrapping_sub(s).wrapping_sub(a)
+            }
+            Calc::PcrelLo => {
+                // The symbol is the label of the instruction with the `R_RISCV_PCREL_HI20`.
+                let hi_offset = s.wrapping_sub(self.base);
+                self.pcrel_hi
+                    .iter()
+                    .find(|(offset, _)| *offset == hi_offset)
+                    .map(|(_, value)| *value)
+                    .ok_or(unsupported)?
+            }
+            Calc::GotPcRelax => s.wrapping_add(a).wrapping_sub(p),
+            Calc::Align => {
+                // The assembler inserts `A` bytes of padding, which the linker must
+                // reduce so that the following instruction is aligned. Only support
+                // the case where no reduction is needed.
+                let align = a.wrapping_add(2).next_power_of_two();
+                if p.wrapping_add(a) % align == 0 {
+                    return Ok(());
+                }
+                return Err(unsupported);
+            }
+        };
+
+        if self.architecture == Architecture::Arm {
+            if self
+                .arm_interwork(place, flags, target.value)
+                .map_err(map_error)?
+            {
+                // Thumb BLX calculates the offset from the aligned PC.
+                value = value.wrapping_add(p & 2);
+            }
+        }
+
+        let result = if calc == Calc::GotPcRelax {
+            self.relax_gotpcrel(place, value)
+        } else {
+            self.write(place, field, value)
+        };
+        result.map_err(|error| match error {
+            FieldError::Overflow => RelocateError::Overflow {
+                offset,
+                flags,
+                value,
+            },
+            FieldError::Misaligned => RelocateError::Misaligned {
+                offset,
+                flags,
+                value,
+            },
+            error => map_error(error),
+        })
+    }
+
+    /// Convert between ARM and Thumb calls when the target is in the other state.
+    ///
+    /// `target` is the symbol value, which has bit 0 set for Thumb functions.
+    ///
+    /// Returns true if a Thumb call was converted to BLX.
+    fn arm_interwork(
+        &mut self,
+        place: usize,
+        flags: RelocationFlags,
+        target: u64,
+    ) -> Result<bool, FieldError> {
+        let RelocationFlags::Elf { r_type } = flags else {
+            return Ok(false);
+        };
+        let thumb = target & 1 != 0;
+        match r_type {
+            elf::R_ARM_CALL => {
+                let insn = self.read_u32(place)?;
+                if thumb {
+                    // BL to BLX. The H bit is set by `write`.
+                    self.write_u32(place, 0xfa00_0000 | (insn & 0x00ff_ffff))?;
+                } else if insn >> 28 == 0xf {
+                    // BLX to BL.
+                    self.write_u32(place, 0xeb00_0000 | (insn & 0x00ff_ffff))?;
+                }
+            }
+            elf::R_ARM_JUMP24 | elf::R_ARM_PC24 | elf::R_ARM_PLT32 if thumb => {
+                // This requires a veneer.
+                return Err(FieldError::Unsupported);
+            }
+            elf::R_ARM_THM_PC22 => {
+                let insn = self.read_u16(place + 2)?;
+                if thumb {
+                    // BLX to BL.
+                    self.write_u16(place + 2, insn | 0x1000)?;
+                } else {
+                    // BL to BLX.
+                    self.write_u16(place + 2, insn & !0x1000)?;
+                    return Ok(true);
+                }
+            }
+            elf::R_ARM_THM_JUMP24 if !thumb => {
+                // This requires a veneer.
+                return Err(FieldError::Unsupported);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Relax an x86-64 GOT load to a direct reference.
+    ///
+    /// `mov foo@GOTPCREL(%rip), %reg` becomes `lea foo(%rip), %reg`,
+    /// `call *foo@GOTPCREL(%rip)` becomes `addr32 call foo`, and
+    /// `jmp *foo@GOTPCREL(%rip)` becomes `jmp foo; nop`.
+    ///
+    /// Other instructions are not supported.
+    fn relax_gotpcrel(&mut self, place: usize, value: u64) -> Result<(), FieldError> {
+        check_signed(value, 32)?;
+        let start = place.checked_sub(2).ok_or(FieldError::Unsupported)?;
+        self.bytes::<6>(start)?;
+        match (self.data[start], self.data[start + 1]) {
+            (0x8b, _) => {
+                self.data[start] = 0x8d;
+                self.write_u32(place, value as u32)?;
+            }
+            (0xff, 0x15) => {
+                self.data[start] = 0x67;
+                self.data[start + 1] = 0xe8;
+                self.write_u32(place, value as u32)?;
+            }
+            (0xff, 0x25) => {
+                // The displacement moves back one byte, so it is relative to
+                // one byte later.
+                check_signed(value.wrapping_add(1), 32)?;
+                self.data[start] = 0xe9;
+                self.write_u32(place - 1, value.wrapping_add(1) as u32)?;
+                self.data[place + 3] = 0x90;
+            }
+            _ => return Err(FieldError::Unsupported),
+        }
+        Ok(())
+    }
+
+    /// Read the implicit addend from a field.
+    fn read_addend(&self, place: usize, field: Field) -> Result<u64, FieldError> {
+        Ok(match field {
+            Field::Data(..) => {
+                let value = self.read_data(place, field)?;
+                match field {
+                    Field::Data(1, _) => value as u8 as i8 as u64,
+                    Field::Data(2, _) => value as u16 as i16 as u64,
+                    Field::Data(4, _) => value as u32 as i32 as u64,
+                    _ => value,
+                }
+            }
+            Field::ArmBranch24 => {
+                let insn = self.read_u32(place)?;
+                sign_extend(u64::from(insn & 0x00ff_ffff) << 2, 26)
+            }
+            Field::ArmMov { .. } => {
+                let insn = self.read_u32(place)?;
+                let imm = ((insn >> 4) & 0xf000) | (insn & 0xfff);
+                sign_extend(u64::from(imm), 16)
+            }
+            Field::ArmPrel31 => {
+                let value = self.read_u32(place)?;
+                sign_extend(u64::from(value & 0x7fff_ffff), 31)
+            }
+            Field::ThumbBranch24 => {
+                let hi = u64::from(self.read_u16(place)?);
+                let lo = u64::from(self.read_u16(place + 2)?);
+                let s = (hi >> 10) & 1;
+                let j1 = (lo >> 13) & 1;
+                let j2 = (lo >> 11) & 1;
+                let i1 = !(j1 ^ s) & 1;
+                let i2 = !(j2 ^ s) & 1;
+                let imm = (s << 24)
+                    | (i1 << 23)
+                    | (i2 << 22)
+                    | ((hi & 0x3ff) << 12)
+                    | ((lo & 0x7ff) << 1);
+                sign_extend(imm, 25)
+            }
+            Field::ThumbMov { .. } => {
+                let hi = u32::from(self.read_u16(place)?);
+                let lo = u32::from(self.read_u16(place + 2)?);
+                let imm =
+                    ((hi & 0xf) << 12) | ((hi & 0x400) << 1) | ((lo & 0x7000) >> 4) | (lo & 0xff);
+                sign_extend(u64::from(imm), 16)
+            }
+            _ => return Err(FieldError::Unsupported),
+        })
+    }
+
+    /// Read the current value of a data field.
+    fn read_data(&self, place: usize, field: Field) -> Result<u64, FieldError> {
+        Ok(match field {
+            Field::Data(1, _) => u64::from(*self.data.get(place).ok_or(FieldError::InvalidOffset)?),
+            Field::Data(2, _) => u64::from(self.read_u16(place)?),
+            Field::Data(4, _) => u64::from(self.read_u32(place)?),
+            Field::Data(8, _) => self.read_u64(place)?,
+            Field::Bits6 => {
+                u64::from(*self.data.get(place).ok_or(FieldError::InvalidOffset)? & 0x3f)
+            }
+            _ => return Err(FieldError::Unsupported),
+        })
+    }
+
+    /// Encode a value in a field.
+    fn write(&mut self, place: usize, field: Field, value: u64) -> Result<(), FieldError> {
+        match field {
+            Field::Data(size, check) => {
+                let bits = u32::from(size) * 8;
+                match check {
+                    Check::None => {}
+                    Check::Signed => check_signed(value, bits)?,
+                    Check::Unsigned => check_unsigned(value, bits)?,
+                    Check::Bitfield => {
+                        if check_signed(value, bits).is_err() {
+                            check_unsigned(value, bits)?;
+                        }
+                    }
+                }
+                match size {
+                    0 => {}
+                    1 => *self.data.get_mut(place).ok_or(FieldError::InvalidOffset)? = value as u8,
+                    2 => self.write_u16(place, value as u16)?,
+                    4 => self.write_u32(place, value as u32)?,
+                    8 => self.write_u64(place, value)?,
+                    _ => return Err(FieldError::Unsupported),
+                }
+            }
+            Field::Bits6 => {
+                let byte = self.data.get_mut(place).ok_or(FieldError::InvalidOffset)?;
+                *byte = (*byte & 0xc0) | (value as u8 & 0x3f);
+            }
+
+            Field::A64Adr { shift, check } => {
+                let imm = (value as i64 >> shift) as u64;
+                if check {
+                    check_signed(imm, 21)?;
+                }
+                let imm = imm as u32;
+                self.update_insn32(
+                    place,
+                    0x9f00_001f,
+                    ((imm & 3) << 29) | ((imm & 0x1f_fffc) << 3),
+                )?;
+            }
+            Field::A64Add { shift, bits } => {
+                if bits != 0 {
+                    check_unsigned(value, bits.into())?;
+                }
+                let imm = ((value >> shift) & 0xfff) as u32;
+                self.update_insn32(place, 0xffc0_03ff, imm << 10)?;
+            }
+            Field::A64Ldst { scale } => {
+                let imm = ((value & 0xfff) >> scale) as u32;
+                self.update_insn32(place, 0xffc0_03ff, imm << 10)?;
+            }
+            Field::A64Branch26 => {
+                check_aligned(value, 4)?;
+                check_signed(value, 28)?;
+                self.update_insn32(place, 0xfc00_0000, (value >> 2) as u32 & 0x03ff_ffff)?;
+            }
+            Field::A64Branch19 => {
+                check_aligned(value, 4)?;
+                check_signed(value, 21)?;
+                self.update_insn32(place, 0xff00_001f, ((value >> 2) as u32 & 0x7_ffff) << 5)?;
+            }
+            Field::A64Branch14 => {
+                check_aligned(value, 4)?;
+                check_signed(value, 16)?;
+                self.update_insn32(place, 0xfff8_001f, ((value >> 2) as u32 & 0x3fff) << 5)?;
+            }
+            Field::A64Movw { shift, check } => {
+                if check {
+                    check_unsigned(value, u32::from(shift) + 16)?;
+                }
+                let imm = ((value >> shift) & 0xffff) as u32;
+                self.update_insn32(place, 0xffe0_001f, imm << 5)?;
+            }
+
+            Field::ArmBranch24 => {
+                check_signed(value, 26)?;
+                let insn = self.read_u32(place)?;
+                let mut insn = (insn & 0xff00_0000) | ((value >> 2) as u32 & 0x00ff_ffff);
+                if insn >> 28 == 0xf {
+                    // BLX: the H bit holds bit 1 of the offset.
+                    insn = (insn & !0x0100_0000) | ((value as u32 & 2) << 23);
+                } else {
+                    check_aligned(value, 4)?;
+                }
+                self.write_u32(place, insn)?;
+            }
+            Field::ArmMov { shift } => {
+                let imm = ((value >> shift) & 0xffff) as u32;
+                self.update_insn32(place, 0xfff0_f000, ((imm & 0xf000) << 4) | (imm & 0xfff))?;
+            }
+            Field::ArmPrel31 => {
+                check_signed(value, 31)?;
+                let old = self.read_u32(place)?;
+                self.write_u32(place, (old & 0x8000_0000) | (value as u32 & 0x7fff_ffff))?;
+            }
+            Field::ThumbBranch24 => {
+                check_signed(value, 25)?;
+                let lo = u32::from(self.read_u16(place + 2)?);
+                let is_blx = lo & 0x1000 == 0;
+                // Ignore the Thumb bit of the target.
+                let value = value & !1;
+                check_aligned(value, if is_blx { 4 } else { 2 })?;
+                let value = value as u32;
+                let s = (value >> 24) & 1;
+                let j1 = (!(value >> 23) ^ s) & 1;
+                let j2 = (!(value >> 22) ^ s) & 1;
+                let hi = 0xf000 | (s << 10) | ((value >> 12) & 0x3ff);
+                let mut lo = (lo & 0xd000) | (j1 << 13) | (j2 << 11) | ((value >> 1) & 0x7ff);
+                if is_blx {
+                    lo &= !1;
+                }
+                self.write_u16(place, hi as u16)?;
+                self.write_u16(place + 2, lo as u16)?;
+            }
+            Field::ThumbMov { shift } => {
+                let imm = ((value >> shift) & 0xffff) as u32;
+                let hi = u32::from(self.read_u16(place)?);
+                let lo = u32::from(self.read_u16(place + 2)?);
+                let hi = (hi & 0xfbf0) | ((imm >> 12) & 0xf) | ((imm >> 1) & 0x400);
+                let lo = (lo & 0x8f00) | ((imm << 4) & 0x7000) | (imm & 0xff);
+                self.write_u16(place, hi as u16)?;
+                self.write_u16(place + 2, lo as u16)?;
+            }
+
+            Field::RvBranch => {
+                check_aligned(value, 2)?;
+                check_signed(value, 13)?;
+                let v = value as u32;
+                let imm = ((v & 0x1000) << 19)
+                    | ((v & 0x7e0) << 20)
+                    | ((v & 0x1e) << 7)
+                    | ((v & 0x800) >> 4);
+                self.update_rv32(place, 0x01ff_f07f, imm)?;
+            }
+            Field::RvJal => {
+                check_aligned(value, 2)?;
+                check_signed(value, 21)?;
+                let v = value as u32;
+                let imm = ((v & 0x10_0000) << 11)
+                    | ((v & 0x7fe) << 20)
+                    | ((v & 0x800) << 9)
+                    | (v & 0xf_f000);
+                self.update_rv32(place, 0x0000_0fff, imm)?;
+            }
+            Field::RvHi20 => {
+                let hi = value.wrapping_add(0x800);
+                check_signed(hi, 32)?;
+                self.update_rv32(place, 0x0000_0fff, hi as u32 & 0xffff_f000)?;
+            }
+            Field::RvLo12I => {
+                self.update_rv32(place, 0x000f_ffff, (value as u32 & 0xfff) << 20)?;
+            }
+            Field::RvLo12S => {
+                let v = value as u32;
+                let imm = ((v & 0xfe0) << 20) | ((v & 0x1f) << 7);
+                self.update_rv32(place, 0x01ff_f07f, imm)?;
+            }
+            Field::RvCall => {
+                let hi = value.wrapping_add(0x800);
+                check_signed(hi, 32)?;
+                self.update_rv32(place, 0x0000_0fff, hi as u32 & 0xffff_f000)?;
+                self.update_rv32(place + 4, 0x000f_ffff, (value as u32 & 0xfff) << 20)?;
+            }
+            Field::RvcBranch => {
+                check_aligned(value, 2)?;
+                check_signed(value, 9)?;
+                let v = value as u16;
+                let imm = ((v & 0x100) << 4)
+                    | ((v & 0x18) << 7)
+                    | ((v & 0xc0) >> 1)
+                    | ((v & 0x6) << 2)
+                    | ((v & 0x20) >> 3);
+                let insn = self.read_u16_le(place)?;
+                self.write_u16_le(place, (insn & 0xe383) | imm)?;
+            }
+            Field::RvcJump => {
+                check_aligned(value, 2)?;
+                check_signed(value, 12)?;
+                let v = value as u16;
+                let imm = ((v & 0x800) << 1)
+                    | ((v & 0x10) << 7)
+                    | ((v & 0x300) << 1)
+                    | ((v & 0x400) >> 2)
+                    | ((v & 0x40) << 1)
+                    | ((v & 0x80) >> 1)
+                    | ((v & 0xe) << 2)
+                    | ((v & 0x20) >> 3);
+                let insn = self.read_u16_le(place)?;
+                self.write_u16_le(place, (insn & 0xe003) | imm)?;
+            }
+
+            Field::PpcHalf(adjust) => {
+                let half = ppc_adjust(value, adjust)?;
+                self.write_u16(place, half)?;
+            }
+            Field::PpcDs(adjust) => {
+                check_aligned(value, 4)?;
+                let half = ppc_adjust(value, adjust)?;
+                let old = self.read_u16(place)?;
+                self.write_u16(place, (old & 3) | (half & !3))?;
+            }
+            Field::PpcBranch24 => {
+                check_aligned(value, 4)?;
+                check_signed(value, 26)?;
+                self.update_insn32(place, 0xfc00_0003, value as u32 & 0x03ff_fffc)?;
+            }
+            Field::PpcBranch14 => {
+                check_aligned(value, 4)?;
+                check_signed(value, 16)?;
+                self.update_insn32(place, 0xffff_0003, value as u32 & 0xfffc)?;
+            }
+
+            Field::S390Dbl(bits) => {
+                check_aligned(value, 2)?;
+                check_signed(value, u32::from(bits) + 1)?;
+                let imm = (value as i64 >> 1) as u64;
+                match bits {
+                    12 => {
+                        let old = self.read_u16(place)?;
+                        self.write_u16(place, (old & 0xf000) | (imm as u16 & 0xfff))?;
+                    }
+                    16 => self.write_u16(place, imm as u16)?,
+                    24 => {
+                        let field = self
+                            .data
+                            .get_mut(place..place + 3)
+                            .ok_or(FieldError::InvalidOffset)?;
+                        field.copy_from_slice(&(imm as u32).to_be_bytes()[1..]);
+                    }
+                    32 => self.write_u32(place, imm as u32)?,
+                    _ => return Err(FieldError::Unsupported),
+                }
+            }
+            Field::S390Disp12 => {
+                check_unsigned(value, 12)?;
+                let old = self.read_u16(place)?;
+                self.write_u16(place, (old & 0xf000) | (value as u16 & 0xfff))?;
+            }
+            Field::S390Disp20 => {
+                check_signed(value, 20)?;
+                let v = value as u32;
+                let imm = ((v & 0xfff) << 16) | ((v & 0xf_f000) >> 4);
+                self.update_insn32(place, 0xf000_00ff, imm)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn bytes<const N: usize>(&self, place: usize) -> Result<[u8; N], FieldError> {
+        self.data
+            .get(place..)
+            .and_then(|data| data.get(..N))
+            .and_then(|data| data.try_into().ok())
+            .ok_or(FieldError::InvalidOffset)
+    }
+
+    fn bytes_mut<const N: usize>(&mut self, place: usize) -> Result<&mut [u8], FieldError> {
+        self.data
+            .get_mut(place..)
+            .and_then(|data| data.get_mut(..N))
+            .ok_or(FieldError::InvalidOffset)
+    }
+
+    fn read_u16(&self, place: usize) -> Result<u16, FieldError> {
+        Ok(self.endian.read_u16_bytes(self.bytes(place)?))
+    }
+
+    fn read_u32(&self, place: usize) -> Result<u32, FieldError> {
+        Ok(self.endian.read_u32_bytes(self.bytes(place)?))
+    }
+
+    fn read_u64(&self, place: usize) -> Result<u64, FieldError> {
+        Ok(self.endian.read_u64_bytes(self.bytes(place)?))
+    }
+
+    fn write_u16(&mut self, place: usize, value: u16) -> Result<(), FieldError> {
+        let bytes = self.endian.write_u16_bytes(value);
+        self.bytes_mut::<2>(place)?.copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn write_u32(&mut self, place: usize, value: u32) -> Result<(), FieldError> {
+        let bytes = self.endian.write_u32_bytes(value);
+        self.bytes_mut::<4>(place)?.copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn write_u64(&mut self, place: usize, value: u64) -> Result<(), FieldError> {
+        let bytes = self.endian.write_u64_bytes(value);
+        self.bytes_mut::<8>(place)?.copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    /// Replace the bits of a 32-bit instruction that are not in `keep`.
+    fn update_insn32(&mut self, place: usize, keep: u32, bits: u32) -> Result<(), FieldError> {
+        let insn = self.read_u32(place)?;
+        self.write_u32(place, (insn & keep) | (bits & !keep))
+    }
+
+    // RISC-V instructions are always little endian.
+
+    fn read_u16_le(&self, place: usize) -> Result<u16, FieldError> {
+        Ok(u16::from_le_bytes(self.bytes(place)?))
+    }
+
+    fn write_u16_le(&mut self, place: usize, value: u16) -> Result<(), FieldError> {
+        self.bytes_mut::<2>(place)?
+            .copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    fn update_rv32(&mut self, place: usize, keep: u32, bits: u32) -> Result<(), FieldError> {
+        let insn = u32::from_le_bytes(self.bytes(place)?);
+        let insn = (insn & keep) | (bits & !keep);
+        self.bytes_mut::<4>(place)?
+            .copy_from_slice(&insn.to_le_bytes());
+        Ok(())
+    }
+}
+
+/// Return the offset of the local entry point of an ELFv2 PowerPC function.
+fn ppc64_local_entry_offset(st_other: u8) -> u64 {
+    match (st_other & elf::STO_PPC64_LOCAL_MASK) >> elf::STO_PPC64_LOCAL_BIT {
+        value @ 2..=6 => 1 << value,
+        _ => 0,
+    }
+}
+
+fn ppc_adjust(value: u64, adjust: PpcAdjust) -> Result<u16, FieldError> {
+    let ha = value.wrapping_add(0x8000);
+    Ok(match adjust {
+        PpcAdjust::Signed => {
+            check_signed(value, 16)?;
+            value as u16
+        }
+        PpcAdjust::Lo => value as u16,
+        PpcAdjust::Hi => (value >> 16) as u16,
+        PpcAdjust::Ha => (ha >> 16) as u16,
+        PpcAdjust::Higher => (value >> 32) as u16,
+        PpcAdjust::Highera => (ha >> 32) as u16,
+        PpcAdjust::Highest => (value >> 48) as u16,
+        PpcAdjust::Highesta => (ha >> 48) as u16,
+    })
+}
+
+fn sign_extend(value: u64, bits: u32) -> u64 {
+    let shift = 64 - bits;
+    ((value << shift) as i64 >> shift) as u64
+}
+
+fn check_signed(value: u64, bits: u32) -> Result<(), FieldError> {
+    if bits < 64 && sign_extend(value, bits) != value {
+        return Err(FieldError::Overflow);
+    }
+    Ok(())
+}
+
+fn check_unsigned(value: u64, bits: u32) -> Result<(), FieldError> {
+    if bits < 64 && value >> bits != 0 {
+        return Err(FieldError::Overflow);
+    }
+    Ok(())
+}
+
+fn check_aligned(value: u64, align: u64) -> Result<(), FieldError> {
+    if value & (align - 1) != 0 {
+        return Err(FieldError::Misaligned);
+    }
+    Ok(())
+}
@@ -6,7 +6,7 @@ use crate::read::{
     self, Architecture, CodeView, ComdatKind, CompressedData, CompressedFileRange, Export,
     FileFlags, Import, ObjectKind, ObjectMap, Relocation, RelocationMap, Result, SectionFlags,
     SectionIndex, SectionKind, SegmentFlags, SubArchitecture, SymbolFlags, SymbolIndex, SymbolKind,
-    SymbolMap, SymbolMapName, SymbolScope, SymbolSection,
+    SymbolMap, SymbolMapName, SymbolScope, SymbolSection, SymbolVersion,
 };
 
 /// An object file.
@@ -220,44 +220,10 @@ pub trait Object<'data>: read::private::Sealed {
             // Sometimes symbols share addresses. Collect them all then choose the "best".
             let mut all_symbols = Vec::new();
             for symbol in table.symbols() {
-                // Must have an address.
-                if !symbol.is_definition() {
-                    continue;
+                if let Some((priority, index, name)) = symbol_map_key(&symbol) {
+                    // Tuple is ordered for sort.
+                    all_symbols.push((symbol.address(), priority, index, name));
                 }
-                // Must have a name.
-                let name = match symbol.name() {
-                    Ok(name) => name,
-                    _ => continue,
-                };
-                if name.is_empty() {
-                    continue;
-                }
-
-                // Lower is better.
-                let mut priority = 0u32;
-
-                // Prefer known kind.
-                match symbol.kind() {
-                    SymbolKind::Text | SymbolKind::Data => {}
-                    SymbolKind::Unknown => priority += 1,
-                    _ => continue,
-                }
-                priority *= 2;
-
-                // Prefer global visibility.
-                priority += match symbol.scope() {
-                    SymbolScope::Unknown => 3,
-                    SymbolScope::Compilation => 2,
-                    SymbolScope::Linkage => 1,
-                    SymbolScope::Dynamic => 0,
-                };
-                priority *= 4;
-
-                // Prefer later entries (earlier symbol is likely to be less specific).
-                let index = !0 - symbol.index().0;
-
-                // Tuple is ordered for sort.
-                all_symbols.push((symbol.address(), priority, index, name));
             }
             // Unstable sort is okay because tuple includes index.
             all_symbols.sort_unstable();
@@ -334,6 +300,46 @@ pub trait Object<'data>: read::private::Sealed {
     fn flags(&self) -> FileFlags;
 }
 
+/// Return the sort key for a symbol in [`Object::symbol_map`].
+///
+/// Returns `None` if the symbol should not be included in the map.
+/// Lower keys are preferred when symbols share an address.
+pub(crate) fn symbol_map_key<'data, S: ObjectSymbol<'data>>(
+    symbol: &S,
+) -> Option<(u32, usize, &'data str)> {
+    // Must have an address.
+    if !symbol.is_definition() {
+        return None;
+    }
+    // Must have a name.
+    let name = symbol.name().ok().filter(|name| !name.is_empty())?;
+
+    // Lower is better.
+    let mut priority = 0u32;
+
+    // Prefer known kind.
+    match symbol.kind() {
+        SymbolKind::Text | SymbolKind::Data => {}
+        SymbolKind::Unknown => priority += 1,
+        _ => return None,
+    }
+    priority *= 2;
+
+    // Prefer global visibility.
+    priority += match symbol.scope() {
+        SymbolScope::Unknown => 3,
+        SymbolScope::Compilation => 2,
+        SymbolScope::Linkage => 1,
+        SymbolScope::Dynamic => 0,
+    };
+    priority *= 4;
+
+    // Prefer later entries (earlier symbol is likely to be less specific).
+    let index = !0 - symbol.index().0;
+
+    Some((priority, index, name))
+}
+
 /// A loadable segment in an [`Object`].
 ///
 /// This trait is part of the unified read API.
@@ -530,6 +536,14 @@ pub trait ObjectSymbol<'data>: read::private::Sealed {
     /// The size of the symbol. May be zero if the size is unknown.
     fn size(&self) -> u64;
 
+    /// Return true if the size of the symbol was read from the file.
+    ///
+    /// Returns false if the size was inferred, such as from the address of the
+    /// next symbol. Currently this is only false for PEF symbols.
+    fn is_size_exact(&self) -> bool {
+        true
+    }
+
     /// Return the kind of this symbol.
     fn kind(&self) -> SymbolKind;
 
@@ -573,6 +587,14 @@ pub trait ObjectSymbol<'data>: read::private::Sealed {
 
     /// Symbol flags that are specific to each file format.
     fn flags(&self) -> SymbolFlags<SectionIndex, SymbolIndex>;
+
+    /// The version of the symbol.
+    ///
+    /// Returns `Ok(None)` if the symbol is not versioned, or if the file format
+    /// does not support symbol versions.
+    fn version(&self) -> Result<Option<SymbolVersion<'data>>> {
+        Ok(None)
+    }
 }
 
 /// An iterator for files that don't have dynamic relocations.
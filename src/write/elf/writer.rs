@@ -1978,6 +1978,117 @@ impl<'a> Writer<'a> {
         });
     }
 
+    /// Encode relocations in the format used by `SHT_CREL` sections.
+    ///
+    /// `is_rela` determines whether the addends are included in the encoding.
+    pub fn encode_crel(&self, is_rela: bool, relocations: &[Rel]) -> Vec<u8> {
+        let flag_bits = if is_rela { 3 } else { 2 };
+        let shift = relocations
+            .iter()
+            .fold(8, |bits, rel| bits | rel.r_offset)
+            .trailing_zeros();
+        let mut header = (relocations.len() as u64) << 3 | u64::from(shift);
+        if is_rela {
+            header |= elf::CREL_HDR_ADDEND;
+        }
+
+        let mut data = Vec::new();
+        util::write_uleb128(&mut data, header);
+        let mut prev = Rel {
+            r_offset: 0,
+            r_sym: 0,
+            r_type: 0,
+            r_addend: 0,
+        };
+        for rel in relocations {
+            let offset = rel.r_offset >> shift;
+            let delta = offset.wrapping_sub(prev.r_offset);
+            prev.r_offset = offset;
+
+            let sym = rel.r_sym != prev.r_sym;
+            let typ = rel.r_type != prev.r_type;
+            let addend = is_rela && rel.r_addend != prev.r_addend;
+            let b = (delta << flag_bits) as u8 | sym as u8 | (typ as u8) << 1 | (addend as u8) << 2;
+            if delta < 0x80 >> flag_bits {
+                data.push(b);
+            } else {
+                data.push(b | 0x80);
+                util::write_uleb128(&mut data, delta >> (7 - flag_bits));
+            }
+
+            if sym {
+                let delta = rel.r_sym.wrapping_sub(prev.r_sym) as i32;
+                util::write_sleb128(&mut data, delta.into());
+                prev.r_sym = rel.r_sym;
+            }
+            if typ {
+                let delta = rel.r_type.wrapping_sub(prev.r_type) as i32;
+                util::write_sleb128(&mut data, delta.into());
+                prev.r_type = rel.r_type;
+            }
+            if addend {
+                let delta = if self.is_64 {
+                    rel.r_addend.wrapping_sub(prev.r_addend)
+                } else {
+                    (rel.r_addend as i32)
+                        .wrapping_sub(prev.r_addend as i32)
+                        .into()
+                };
+                util::write_sleb128(&mut data, delta);
+                prev.r_addend = rel.r_addend;
+            }
+        }
+        data
+    }
+
+    /// Reserve a file range for a compact relocation section.
+    ///
+    /// `size` is the size of the data returned by [`Self::encode_crel`].
+    ///
+    /// Returns the offset of the range.
+    pub fn reserve_crel(&mut self, size: usize) -> usize {
+        self.reserve(size, 1)
+    }
+
+    /// Write the data for a compact relocation section.
+    ///
+    /// `data` must be the data returned by [`Self::encode_crel`].
+    pub fn write_crel(&mut self, data: &[u8]) {
+        self.buffer.write_bytes(data);
+    }
+
+    /// Write the section header for a compact relocation section.
+    ///
+    /// `section` is the index of the section the relocations apply to,
+    /// or 0 if none.
+    ///
+    /// `symtab` is the index of the symbol table the relocations refer to,
+    /// or 0 if none.
+    ///
+    /// `offset` is the file offset of the relocations.
+    /// `size` is the size of the section in bytes.
+    pub fn write_crel_section_header(
+        &mut self,
+        name: StringId,
+        section: SectionIndex,
+        symtab: SectionIndex,
+        offset: usize,
+        size: usize,
+    ) {
+        self.write_section_header(&SectionHeader {
+            name: Some(name),
+            sh_type: elf::SHT_CREL,
+            sh_flags: elf::SHF_INFO_LINK.into(),
+            sh_addr: 0,
+            sh_offset: offset as u64,
+            sh_size: size as u64,
+            sh_link: symtab.0,
+            sh_info: section.0,
+            sh_addralign: 1,
+            sh_entsize: 1,
+        });
+    }
+
     /// Write the section header for a relative relocation section.
     ///
     /// `offset` is the file offset of the relocations.
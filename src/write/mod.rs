@@ -1,13 +1,19 @@
 //! Interface for writing object files.
 //!
 //! This module provides a unified write API for relocatable object files
-//! using [`Object`]. This does not support writing executable files.
-//! This supports the following file formats: COFF, ELF, Mach-O, and XCOFF.
+//! using [`Object`]. This does not support writing executable files, except
+//! for PEF, which has no relocatable format.
+//! This supports the following file formats: COFF, ELF, Mach-O, PEF, and XCOFF.
 //!
 //! The submodules define helpers for writing the raw structs. These support
 //! writing both relocatable and executable files. There are writers for
 //! the following file formats: [COFF](coff::Writer), [ELF](elf::Writer),
 //! and [PE](pe::Writer).
+//!
+//! PEF containers are written with [`Object`] instead of a raw struct writer.
+//! The `Object::set_pef_*` and `Object::add_pef_imported_library` methods
+//! set the imported libraries, the main, init and term symbols, the container
+//! versions, and the [pattern data](pef::PatternDataLevel) compression level.
 
 use alloc::borrow::Cow;
 use alloc::string::String;
@@ -38,6 +44,11 @@ pub use macho::MachOBuildVersion;
 #[cfg(feature = "pe")]
 pub mod pe;
 
+#[cfg(feature = "pef")]
+pub mod pef;
+#[cfg(feature = "pef")]
+pub use pef::{PefContainerInfo, PefImportedLibrary, PefLibraryId};
+
 #[cfg(feature = "xcoff")]
 mod xcoff;
 
@@ -95,6 +106,24 @@ pub struct Object<'a> {
     /// Mach-O MH_SUBSECTIONS_VIA_SYMBOLS flag. Only ever set if format is Mach-O.
     #[cfg(feature = "macho")]
     macho_subsections_via_symbols: bool,
+    /// PEF imported libraries.
+    #[cfg(feature = "pef")]
+    pef_libraries: Vec<PefImportedLibrary>,
+    #[cfg(feature = "pef")]
+    pef_symbol_libraries: HashMap<SymbolId, PefLibraryId>,
+    /// PEF main symbol.
+    #[cfg(feature = "pef")]
+    pef_main: Option<SymbolId>,
+    /// PEF initialization function.
+    #[cfg(feature = "pef")]
+    pef_init: Option<SymbolId>,
+    /// PEF termination routine.
+    #[cfg(feature = "pef")]
+    pef_term: Option<SymbolId>,
+    #[cfg(feature = "pef")]
+    pef_container_info: PefContainerInfo,
+    #[cfg(feature = "pef")]
+    pef_pattern_data: Option<pef::PatternDataLevel>,
 }
 
 impl<'a> Object<'a> {
@@ -122,6 +151,20 @@ impl<'a> Object<'a> {
             macho_build_version: None,
             #[cfg(feature = "macho")]
             macho_subsections_via_symbols: false,
+            #[cfg(feature = "pef")]
+            pef_libraries: Vec::new(),
+            #[cfg(feature = "pef")]
+            pef_symbol_libraries: HashMap::new(),
+            #[cfg(feature = "pef")]
+            pef_main: None,
+            #[cfg(feature = "pef")]
+            pef_init: None,
+            #[cfg(feature = "pef")]
+            pef_term: None,
+            #[cfg(feature = "pef")]
+            pef_container_info: PefContainerInfo::default(),
+            #[cfg(feature = "pef")]
+            pef_pattern_data: None,
         }
     }
 
@@ -172,6 +215,8 @@ impl<'a> Object<'a> {
             BinaryFormat::Elf => &[],
             #[cfg(feature = "macho")]
             BinaryFormat::MachO => self.macho_segment_name(segment),
+            #[cfg(feature = "pef")]
+            BinaryFormat::Pef => &[],
             _ => unimplemented!(),
         }
     }
@@ -272,6 +317,8 @@ impl<'a> Object<'a> {
             BinaryFormat::Elf => self.elf_section_info(section),
             #[cfg(feature = "macho")]
             BinaryFormat::MachO => self.macho_section_info(section),
+            #[cfg(feature = "pef")]
+            BinaryFormat::Pef => self.pef_section_info(section),
             #[cfg(feature = "xcoff")]
             BinaryFormat::Xcoff => self.xcoff_section_info(section),
             _ => unimplemented!(),
@@ -582,6 +629,8 @@ impl<'a> Object<'a> {
             BinaryFormat::Elf => self.elf_translate_relocation(&mut relocation)?,
             #[cfg(feature = "macho")]
             BinaryFormat::MachO => self.macho_translate_relocation(&mut relocation)?,
+            #[cfg(feature = "pef")]
+            BinaryFormat::Pef => self.pef_translate_relocation(&mut relocation)?,
             #[cfg(feature = "xcoff")]
             BinaryFormat::Xcoff => self.xcoff_translate_relocation(&mut relocation)?,
             _ => unimplemented!(),
@@ -593,6 +642,8 @@ impl<'a> Object<'a> {
             BinaryFormat::Elf => self.elf_adjust_addend(&mut relocation)?,
             #[cfg(feature = "macho")]
             BinaryFormat::MachO => self.macho_adjust_addend(&mut relocation)?,
+            #[cfg(feature = "pef")]
+            BinaryFormat::Pef => self.pef_adjust_addend(&mut relocation)?,
             #[cfg(feature = "xcoff")]
             BinaryFormat::Xcoff => self.xcoff_adjust_addend(&mut relocation)?,
             _ => unimplemented!(),
@@ -617,6 +668,8 @@ impl<'a> Object<'a> {
             BinaryFormat::Elf => self.elf_relocation_size(relocation)?,
             #[cfg(feature = "macho")]
             BinaryFormat::MachO => self.macho_relocation_size(relocation)?,
+            #[cfg(feature = "pef")]
+            BinaryFormat::Pef => self.pef_relocation_size(relocation)?,
             #[cfg(feature = "xcoff")]
             BinaryFormat::Xcoff => self.xcoff_relocation_size(relocation)?,
             _ => unimplemented!(),
@@ -674,6 +727,8 @@ impl<'a> Object<'a> {
             BinaryFormat::Elf => self.elf_write(buffer),
             #[cfg(feature = "macho")]
             BinaryFormat::MachO => self.macho_write(buffer),
+            #[cfg(feature = "pef")]
+            BinaryFormat::Pef => self.pef_write(buffer),
             #[cfg(feature = "xcoff")]
             BinaryFormat::Xcoff => self.xcoff_write(buffer),
             _ => unimplemented!(),
@@ -0,0 +1,11 @@
+//! Support for writing PEF files.
+//!
+//! Provides [`encode_pattern_data`] for compressing the contents of
+//! pattern-initialized data sections.
+//! This is also used to provide PEF support for [`write::Object`](crate::write::Object).
+
+mod object;
+pub use self::object::*;
+
+mod pattern;
+pub use pattern::*;
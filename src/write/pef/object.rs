@@ -0,0 +1,765 @@
+use core::mem;
+
+use crate::endian::{BigEndian as BE, I16Bytes, U32Bytes, I32, U16, U32};
+use crate::pef;
+use crate::write::pef::{encode_pattern_data, PatternDataLevel};
+use crate::write::util::*;
+use crate::write::*;
+
+#[derive(Default, Clone, Copy)]
+struct SectionOffsets {
+    index: u16,
+    name_offset: i32,
+    offset: usize,
+}
+
+#[derive(Clone, Copy)]
+enum RelocationTarget {
+    Section(u16),
+    Import(u32),
+}
+
+/// An identifier used to reference a library imported by a PEF container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PefLibraryId(usize);
+
+/// A library imported by a PEF container.
+#[derive(Debug, Default, Clone)]
+pub struct PefImportedLibrary {
+    /// The name of the library.
+    pub name: Vec<u8>,
+    /// The oldest implementation version of the library that is compatible with the container.
+    pub old_imp_version: u32,
+    /// The version of the library that the container was linked against.
+    pub current_version: u32,
+    /// Whether the container can be prepared if the library is missing.
+    pub weak: bool,
+    /// Whether the library must be initialized before the container.
+    pub init_before: bool,
+}
+
+/// The customizable portion of a [`pef::PEFContainerHeader`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PefContainerInfo {
+    /// The creation time, in seconds since January 1, 1904.
+    pub date_time_stamp: u32,
+    /// The oldest definition version that the container is compatible with.
+    pub old_def_version: u32,
+    /// The oldest implementation version that the container is compatible with.
+    pub old_imp_version: u32,
+    /// The current version of the container.
+    pub current_version: u32,
+}
+
+// Public methods.
+impl<'a> Object<'a> {
+    /// Add a library that undefined symbols can be imported from.
+    ///
+    /// Requires `feature = "pef"`.
+    pub fn add_pef_imported_library(&mut self, library: PefImportedLibrary) -> PefLibraryId {
+        let id = PefLibraryId(self.pef_libraries.len());
+        self.pef_libraries.push(library);
+        id
+    }
+
+    /// Specify the library that an undefined symbol is imported from.
+    ///
+    /// Every undefined symbol must be assigned a library before writing a PEF container.
+    ///
+    /// Requires `feature = "pef"`.
+    #[inline]
+    pub fn set_pef_symbol_library(&mut self, symbol: SymbolId, library: PefLibraryId) {
+        self.pef_symbol_libraries.insert(symbol, library);
+    }
+
+    /// Specify the main symbol of a PEF container.
+    ///
+    /// Requires `feature = "pef"`.
+    #[inline]
+    pub fn set_pef_main_symbol(&mut self, symbol: Option<SymbolId>) {
+        self.pef_main = symbol;
+    }
+
+    /// Specify the initialization function transition vector of a PEF container.
+    ///
+    /// Requires `feature = "pef"`.
+    #[inline]
+    pub fn set_pef_init_symbol(&mut self, symbol: Option<SymbolId>) {
+        self.pef_init = symbol;
+    }
+
+    /// Specify the termination routine transition vector of a PEF container.
+    ///
+    /// Requires `feature = "pef"`.
+    #[inline]
+    pub fn set_pef_term_symbol(&mut self, symbol: Option<SymbolId>) {
+        self.pef_term = symbol;
+    }
+
+    /// Specify the date and version fields of a PEF container header.
+    ///
+    /// Requires `feature = "pef"`.
+    #[inline]
+    pub fn set_pef_container_info(&mut self, info: PefContainerInfo) {
+        self.pef_container_info = info;
+    }
+
+    /// Specify whether data sections of a PEF container are compressed.
+    ///
+    /// If `Some`, then data sections are written as pattern-initialized data
+    /// using the given level. Sections with `SectionFlags::Pef` are unaffected,
+    /// except that pattern-initialized data sections use the given level instead
+    /// of the default level.
+    ///
+    /// Requires `feature = "pef"`.
+    #[inline]
+    pub fn set_pef_pattern_data(&mut self, level: Option<PatternDataLevel>) {
+        self.pef_pattern_data = level;
+    }
+}
+
+// Private methods.
+impl<'a> Object<'a> {
+    pub(crate) fn pef_section_info(
+        &self,
+        section: StandardSection,
+    ) -> (&'static [u8], &'static [u8], SectionKind, SectionFlags) {
+        match section {
+            StandardSection::Text => (&[], &b".text"[..], SectionKind::Text, SectionFlags::None),
+            StandardSection::Data => (&[], &b".data"[..], SectionKind::Data, SectionFlags::None),
+            StandardSection::ReadOnlyData | StandardSection::ReadOnlyString => (
+                &[],
+                &b".rdata"[..],
+                SectionKind::ReadOnlyData,
+                SectionFlags::None,
+            ),
+            StandardSection::ReadOnlyDataWithRel => (
+                &[],
+                &b".data.rel.ro"[..],
+                SectionKind::ReadOnlyDataWithRel,
+                SectionFlags::None,
+            ),
+            StandardSection::UninitializedData => (
+                &[],
+                &b".bss"[..],
+                SectionKind::UninitializedData,
+                SectionFlags::None,
+            ),
+            StandardSection::Tls
+            | StandardSection::UninitializedTls
+            | StandardSection::TlsVariables
+            | StandardSection::Common
+            | StandardSection::GnuProperty => {
+                // Unsupported section.
+                (&[], &[], section.kind(), SectionFlags::None)
+            }
+        }
+    }
+
+    pub(crate) fn pef_translate_relocation(&mut self, reloc: &mut Relocation) -> Result<()> {
+        // The loader only supports adding addresses to 32-bit words.
+        match reloc.flags {
+            RelocationFlags::Generic {
+                kind: RelocationKind::Absolute,
+                encoding: RelocationEncoding::Generic,
+                size: 32,
+            } => Ok(()),
+            _ => Err(Error(format!("unimplemented relocation {:?}", reloc))),
+        }
+    }
+
+    pub(crate) fn pef_adjust_addend(&mut self, relocation: &mut Relocation) -> Result<bool> {
+        // Sections are relocated by their base address, so the place must contain the
+        // offset of the symbol within its section.
+        let symbol = &self.symbols[relocation.symbol.0];
+        match symbol.section {
+            SymbolSection::Section(_) | SymbolSection::Absolute => {
+                relocation.addend += symbol.value as i64;
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    pub(crate) fn pef_relocation_size(&self, _reloc: &Relocation) -> Result<u8> {
+        Ok(32)
+    }
+
+    fn pef_section_kind(&self, section: &Section<'_>) -> Result<(pef::SectionKind, u8)> {
+        if let SectionFlags::Pef {
+            section_kind,
+            share_kind,
+        } = section.flags
+        {
+            let kind = pef::SectionKind::from_u8(section_kind).ok_or_else(|| {
+                Error(format!(
+                    "invalid section `{}` kind {}",
+                    section.name().unwrap_or(""),
+                    section_kind
+                ))
+            })?;
+            return Ok((kind, share_kind));
+        }
+        let (kind, share_kind) = match section.kind {
+            SectionKind::Text => (pef::SectionKind::Code, pef::ShareKind::GlobalShare),
+            SectionKind::ReadOnlyData | SectionKind::ReadOnlyString => {
+                (pef::SectionKind::Constant, pef::ShareKind::GlobalShare)
+            }
+            SectionKind::Data | SectionKind::ReadOnlyDataWithRel
+                if self.pef_pattern_data.is_some() && !section.data.is_empty() =>
+            {
+                (
+                    pef::SectionKind::PatternInitializedData,
+                    pef::ShareKind::ProcessShare,
+                )
+            }
+            SectionKind::Data
+            | SectionKind::ReadOnlyDataWithRel
+            | SectionKind::UninitializedData => {
+                (pef::SectionKind::UnpackedData, pef::ShareKind::ProcessShare)
+            }
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented section `{}` kind {:?}",
+                    section.name().unwrap_or(""),
+                    section.kind
+                )));
+            }
+        };
+        Ok((kind, share_kind as u8))
+    }
+
+    fn pef_symbol_class(&self, symbol: &Symbol) -> u8 {
+        if let SymbolFlags::Pef { symbol_class } = symbol.flags {
+            return symbol_class;
+        }
+        match symbol.kind {
+            SymbolKind::Text => match symbol.section {
+                SymbolSection::Section(id) if self.sections[id.0].kind == SectionKind::Text => {
+                    pef::CODE_SYMBOL
+                }
+                _ => pef::TVECT_SYMBOL,
+            },
+            _ => pef::DATA_SYMBOL,
+        }
+    }
+
+    fn pef_symbol_location(
+        &self,
+        symbol_id: SymbolId,
+        section_offsets: &[SectionOffsets],
+    ) -> Result<(u16, u32)> {
+        let symbol = self.symbol(symbol_id);
+        match symbol.section {
+            SymbolSection::Section(id) => Ok((section_offsets[id.0].index, symbol.value as u32)),
+            _ => Err(Error(format!(
+                "symbol `{}` is not defined in a section",
+                symbol.name().unwrap_or("")
+            ))),
+        }
+    }
+
+    pub(crate) fn pef_write(&self, buffer: &mut dyn WritableBuffer) -> Result<()> {
+        if self.endian != Endianness::Big {
+            return Err(Error(String::from("PEF containers must be big-endian")));
+        }
+        let architecture = match self.architecture {
+            Architecture::PowerPc => pef::ARCHITECTURE_PPC,
+            Architecture::M68k => pef::ARCHITECTURE_68K,
+            _ => {
+                return Err(Error(format!(
+                    "unimplemented architecture {:?}",
+                    self.architecture
+                )));
+            }
+        };
+
+        // Determine the section kinds. Instantiated sections must precede all other
+        // sections, and the loader section is placed last.
+        let mut section_kinds = Vec::with_capacity(self.sections.len());
+        for section in &self.sections {
+            let (kind, share_kind) = self.pef_section_kind(section)?;
+            if kind == pef::SectionKind::Loader {
+                return Err(Error(format!(
+                    "unimplemented section `{}` kind {:?}",
+                    section.name().unwrap_or(""),
+                    kind
+                )));
+            }
+            if !kind.is_instantiated() && !section.relocations.is_empty() {
+                return Err(Error(format!(
+                    "relocations in non-instantiated section `{}`",
+                    section.name().unwrap_or("")
+                )));
+            }
+            section_kinds.push((kind, share_kind));
+        }
+        let mut section_order: Vec<usize> = (0..self.sections.len()).collect();
+        section_order.sort_by_key(|&index| !section_kinds[index].0.is_instantiated());
+        let inst_section_count = section_kinds
+            .iter()
+            .filter(|(kind, _)| kind.is_instantiated())
+            .count();
+        let section_count = self.sections.len() + 1;
+        if section_count > i16::MAX as usize {
+            return Err(Error(format!("too many sections {}", section_count)));
+        }
+
+        // Calculate offsets and build the section name table.
+        let mut offset = 0;
+        let mut section_offsets = vec![SectionOffsets::default(); self.sections.len()];
+        let mut section_names = Vec::new();
+        for (pef_index, &index) in section_order.iter().enumerate() {
+            let section = &self.sections[index];
+            section_offsets[index].index = pef_index as u16;
+            section_offsets[index].name_offset = if section.name.is_empty() {
+                pef::NO_SECTION_NAME
+            } else {
+                let name_offset = section_names.len() as i32;
+                section_names.extend_from_slice(&section.name);
+                section_names.push(0);
+                name_offset
+            };
+        }
+
+        // Container header.
+        offset += mem::size_of::<pef::PEFContainerHeader>();
+        // Section headers.
+        offset += section_count * mem::size_of::<pef::PEFSectionHeader>();
+        // Section name table.
+        offset += section_names.len();
+
+        // Encode pattern-initialized data.
+        let level = self.pef_pattern_data.unwrap_or_default();
+        let section_data: Vec<Cow<'_, [u8]>> = self
+            .sections
+            .iter()
+            .zip(&section_kinds)
+            .map(|(section, (kind, _))| {
+                if *kind == pef::SectionKind::PatternInitializedData {
+                    Cow::Owned(encode_pattern_data(&section.data, level))
+                } else {
+                    Cow::Borrowed(&*section.data)
+                }
+            })
+            .collect();
+
+        // Calculate offsets of section data.
+        for &index in &section_order {
+            offset = align(offset, 16);
+            section_offsets[index].offset = offset;
+            offset += section_data[index].len();
+        }
+
+        // Build the loader section.
+        let loader = self.pef_loader(&section_offsets, &section_order)?;
+        offset = align(offset, 4);
+        let loader_offset = offset;
+        offset += loader.len();
+
+        // Start writing.
+        buffer
+            .reserve(offset)
+            .map_err(|_| Error(String::from("Cannot allocate buffer")))?;
+
+        // Write container header.
+        let info = &self.pef_container_info;
+        let header = pef::PEFContainerHeader {
+            tag1: U32::new(BE, pef::TAG1),
+            tag2: U32::new(BE, pef::TAG2),
+            architecture: U32::new(BE, architecture),
+            format_version: U32::new(BE, pef::FORMAT_VERSION),
+            date_time_stamp: U32::new(BE, info.date_time_stamp),
+            old_def_version: U32::new(BE, info.old_def_version),
+            old_imp_version: U32::new(BE, info.old_imp_version),
+            current_version: U32::new(BE, info.current_version),
+            section_count: U16::new(BE, section_count as u16),
+            inst_section_count: U16::new(BE, inst_section_count as u16),
+            reserved_a: U32::new(BE, 0),
+        };
+        buffer.write(&header);
+
+        // Write section headers.
+        for &index in &section_order {
+            let section = &self.sections[index];
+            let (kind, share_kind) = section_kinds[index];
+            let section_header = pef::PEFSectionHeader {
+                name_offset: I32::new(BE, section_offsets[index].name_offset),
+                default_address: U32::new(BE, 0),
+                total_size: U32::new(BE, section.size as u32),
+                unpacked_size: U32::new(BE, section.data.len() as u32),
+                packed_size: U32::new(BE, section_data[index].len() as u32),
+                container_offset: U32::new(BE, section_offsets[index].offset as u32),
+                section_kind: kind as u8,
+                share_kind,
+                alignment: section.align.trailing_zeros() as u8,
+                reserved_a: 0,
+            };
+            buffer.write(&section_header);
+        }
+        let loader_header = pef::PEFSectionHeader {
+            name_offset: I32::new(BE, pef::NO_SECTION_NAME),
+            default_address: U32::new(BE, 0),
+            total_size: U32::new(BE, 0),
+            unpacked_size: U32::new(BE, 0),
+            packed_size: U32::new(BE, loader.len() as u32),
+            container_offset: U32::new(BE, loader_offset as u32),
+            section_kind: pef::SectionKind::Loader as u8,
+            share_kind: pef::ShareKind::GlobalShare as u8,
+            alignment: 2,
+            reserved_a: 0,
+        };
+        buffer.write(&loader_header);
+
+        // Write section name table.
+        buffer.write_bytes(&section_names);
+
+        // Write section data.
+        for &index in &section_order {
+            write_align(buffer, 16);
+            debug_assert_eq!(section_offsets[index].offset, buffer.len());
+            buffer.write_bytes(&section_data[index]);
+        }
+
+        // Write loader section.
+        write_align(buffer, 4);
+        debug_assert_eq!(loader_offset, buffer.len());
+        buffer.write_bytes(&loader);
+
+        debug_assert_eq!(offset, buffer.len());
+        Ok(())
+    }
+
+    fn pef_loader(
+        &self,
+        section_offsets: &[SectionOffsets],
+        section_order: &[usize],
+    ) -> Result<Vec<u8>> {
+        let mut strings = Vec::new();
+        let mut add_string = |name: &[u8], terminate: bool| -> Result<u32> {
+            let offset = strings.len() as u32;
+            if offset > 0x00FF_FFFF {
+                return Err(Error(String::from("PEF loader string table is too large")));
+            }
+            strings.extend_from_slice(name);
+            if terminate {
+                strings.push(0);
+            }
+            Ok(offset)
+        };
+
+        // Calculate imported libraries and symbols.
+        let mut library_symbols = vec![Vec::new(); self.pef_libraries.len()];
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            if !symbol.is_undefined()
+                || symbol.name.is_empty()
+                || symbol.kind == SymbolKind::File
+                || symbol.kind == SymbolKind::Section
+            {
+                continue;
+            }
+            let library = self
+                .pef_symbol_libraries
+                .get(&SymbolId(index))
+                .ok_or_else(|| {
+                    Error(format!(
+                        "undefined symbol `{}` has no PEF library",
+                        symbol.name().unwrap_or("")
+                    ))
+                })?;
+            library_symbols[library.0].push(index);
+        }
+        let mut import_indices = vec![None; self.symbols.len()];
+        let mut libraries = Vec::with_capacity(self.pef_libraries.len());
+        let mut imports = Vec::new();
+        for (library, symbols) in self.pef_libraries.iter().zip(library_symbols.iter()) {
+            let mut options = 0;
+            if library.weak {
+                options |= pef::WEAK_IMPORT_LIB;
+            }
+            if library.init_before {
+                options |= pef::INIT_LIB_BEFORE;
+            }
+            libraries.push(pef::PEFImportedLibrary {
+                name_offset: U32::new(BE, add_string(&library.name, true)?),
+                old_imp_version: U32::new(BE, library.old_imp_version),
+                current_version: U32::new(BE, library.current_version),
+                imported_symbol_count: U32::new(BE, symbols.len() as u32),
+                first_imported_symbol: U32::new(BE, imports.len() as u32),
+                options,
+                reserved_a: 0,
+                reserved_b: U16::new(BE, 0),
+            });
+            for &index in symbols {
+                let symbol = &self.symbols[index];
+                let mut class = self.pef_symbol_class(symbol);
+                if symbol.weak {
+                    class |= pef::WEAK_IMPORT_SYMBOL;
+                }
+                let name_offset = add_string(&symbol.name, true)?;
+                import_indices[index] = Some(imports.len() as u32);
+                imports.push(pef::PEFImportedSymbol {
+                    class_and_name: U32::new(BE, (u32::from(class) << 24) | name_offset),
+                });
+            }
+        }
+
+        // Calculate relocations.
+        let mut relocation_headers = Vec::new();
+        let mut relocation_instructions = Vec::new();
+        for &index in section_order {
+            let section = &self.sections[index];
+            if section.relocations.is_empty() {
+                continue;
+            }
+            let mut relocations = Vec::with_capacity(section.relocations.len());
+            for reloc in &section.relocations {
+                let symbol = &self.symbols[reloc.symbol.0];
+                let target = match symbol.section {
+                    SymbolSection::Section(id) => {
+                        RelocationTarget::Section(section_offsets[id.0].index)
+                    }
+                    SymbolSection::Undefined => match import_indices[reloc.symbol.0] {
+                        Some(import) => RelocationTarget::Import(import),
+                        None => {
+                            return Err(Error(format!(
+                                "relocation against undefined symbol `{}` that is not imported",
+                                symbol.name().unwrap_or("")
+                            )));
+                        }
+                    },
+                    // The value is already stored at the place.
+                    SymbolSection::Absolute => continue,
+                    _ => {
+                        return Err(Error(format!(
+                            "unimplemented relocation {:?} for symbol `{}`",
+                            reloc,
+                            symbol.name().unwrap_or("")
+                        )));
+                    }
+                };
+                if reloc.offset + 4 > section.data.len() as u64 {
+                    return Err(Error(format!(
+                        "invalid relocation offset {}+4 (max {})",
+                        reloc.offset,
+                        section.data.len()
+                    )));
+                }
+                relocations.push((reloc.offset as u32, target));
+            }
+            if relocations.is_empty() {
+                continue;
+            }
+            relocations.sort_by_key(|(offset, _)| *offset);
+            let first_reloc_offset = relocation_instructions.len() * 2;
+            pef_relocation_instructions(&relocations, &mut relocation_instructions)?;
+            relocation_headers.push(pef::PEFLoaderRelocationHeader {
+                section_index: U16::new(BE, section_offsets[index].index),
+                reserved_a: U16::new(BE, 0),
+                reloc_count: U32::new(
+                    BE,
+                    (relocation_instructions.len() - first_reloc_offset / 2) as u32,
+                ),
+                first_reloc_offset: U32::new(BE, first_reloc_offset as u32),
+            });
+        }
+
+        // Calculate exports, ordered by hash table index.
+        let mut exports = Vec::new();
+        for (index, symbol) in self.symbols.iter().enumerate() {
+            if symbol.scope != SymbolScope::Dynamic
+                || symbol.name.is_empty()
+                || symbol.kind == SymbolKind::File
+                || symbol.kind == SymbolKind::Section
+            {
+                continue;
+            }
+            let section_index = match symbol.section {
+                SymbolSection::Section(id) => section_offsets[id.0].index as i16,
+                SymbolSection::Absolute => pef::EXPORTED_ABSOLUTE_SECTION,
+                _ => continue,
+            };
+            if symbol.name.len() > 0xFFFF {
+                return Err(Error(format!(
+                    "symbol name `{}` is too long",
+                    symbol.name().unwrap_or("")
+                )));
+            }
+            exports.push((index, section_index, pef::hash_word(&symbol.name)));
+        }
+        let hash_power = pef::hash_table_power(exports.len() as u32);
+        exports.sort_by_key(|(_, _, hash)| pef::hash_table_index(*hash, hash_power));
+        let mut hash_slots = vec![0u32; 1 << hash_power];
+        for (export_index, (_, _, hash)) in exports.iter().enumerate().rev() {
+            // Iterating in reverse leaves the first index of each chain in the slot.
+            let slot = &mut hash_slots[pef::hash_table_index(*hash, hash_power) as usize];
+            *slot = (((*slot >> 18) + 1) << 18) | export_index as u32;
+        }
+        let mut exported_symbols = Vec::with_capacity(exports.len());
+        for &(index, section_index, _) in &exports {
+            let symbol = &self.symbols[index];
+            let class = self.pef_symbol_class(symbol);
+            let name_offset = add_string(&symbol.name, false)?;
+            exported_symbols.push(pef::PEFExportedSymbol {
+                class_and_name: U32Bytes::new(BE, (u32::from(class) << 24) | name_offset),
+                symbol_value: U32Bytes::new(BE, symbol.value as u32),
+                section_index: I16Bytes::new(BE, section_index),
+            });
+        }
+
+        // Calculate entry points.
+        let entry = |symbol: Option<SymbolId>| -> Result<(i32, u32)> {
+            match symbol {
+                Some(symbol) => self
+                    .pef_symbol_location(symbol, section_offsets)
+                    .map(|(index, offset)| (i32::from(index), offset)),
+                None => Ok((pef::NO_SECTION, 0)),
+            }
+        };
+        let (main_section, main_offset) = entry(self.pef_main)?;
+        let (init_section, init_offset) = entry(self.pef_init)?;
+        let (term_section, term_offset) = entry(self.pef_term)?;
+
+        // Calculate offsets.
+        let mut offset = mem::size_of::<pef::PEFLoaderInfoHeader>();
+        offset += libraries.len() * mem::size_of::<pef::PEFImportedLibrary>();
+        offset += imports.len() * mem::size_of::<pef::PEFImportedSymbol>();
+        offset += relocation_headers.len() * mem::size_of::<pef::PEFLoaderRelocationHeader>();
+        let reloc_instr_offset = offset;
+        offset += relocation_instructions.len() * 2;
+        let loader_strings_offset = offset;
+        offset += strings.len();
+        offset = align(offset, 4);
+        let export_hash_offset = offset;
+        offset += hash_slots.len() * mem::size_of::<pef::PEFExportedSymbolHashSlot>();
+        offset += exports.len() * mem::size_of::<pef::PEFExportedSymbolKey>();
+        offset += exports.len() * mem::size_of::<pef::PEFExportedSymbol>();
+
+        // Write the loader section.
+        let mut loader = Vec::with_capacity(offset);
+        let loader_header = pef::PEFLoaderInfoHeader {
+            main_section: I32::new(BE, main_section),
+            main_offset: U32::new(BE, main_offset),
+            init_section: I32::new(BE, init_section),
+            init_offset: U32::new(BE, init_offset),
+            term_section: I32::new(BE, term_section),
+            term_offset: U32::new(BE, term_offset),
+            imported_library_count: U32::new(BE, libraries.len() as u32),
+            total_imported_symbol_count: U32::new(BE, imports.len() as u32),
+            reloc_section_count: U32::new(BE, relocation_headers.len() as u32),
+            reloc_instr_offset: U32::new(BE, reloc_instr_offset as u32),
+            loader_strings_offset: U32::new(BE, loader_strings_offset as u32),
+            export_hash_offset: U32::new(BE, export_hash_offset as u32),
+            export_hash_table_power: U32::new(BE, hash_power),
+            exported_symbol_count: U32::new(BE, exports.len() as u32),
+        };
+        loader.write_pod(&loader_header);
+        loader.write_pod_slice(&libraries);
+        loader.write_pod_slice(&imports);
+        loader.write_pod_slice(&relocation_headers);
+        debug_assert_eq!(reloc_instr_offset, loader.len());
+        for instruction in &relocation_instructions {
+            loader.write_pod(&U16::new(BE, *instruction));
+        }
+        debug_assert_eq!(loader_strings_offset, loader.len());
+        loader.write_bytes(&strings);
+        write_align(&mut loader, 4);
+        debug_assert_eq!(export_hash_offset, loader.len());
+        for slot in &hash_slots {
+            loader.write_pod(&U32::new(BE, *slot));
+        }
+        for (_, _, hash) in &exports {
+            loader.write_pod(&U32::new(BE, *hash));
+        }
+        loader.write_pod_slice(&exported_symbols);
+        debug_assert_eq!(offset, loader.len());
+        Ok(loader)
+    }
+}
+
+/// Encode the loader relocation instructions for the relocations of a section.
+///
+/// `relocations` must be sorted by offset.
+fn pef_relocation_instructions(
+    relocations: &[(u32, RelocationTarget)],
+    instructions: &mut Vec<u16>,
+) -> Result<()> {
+    let mut position = 0;
+    let mut import_index = 0;
+    let mut i = 0;
+    while i < relocations.len() {
+        let (offset, target) = relocations[i];
+        if offset != position {
+            if offset > position && offset - position <= 0x1000 {
+                instructions.push(pef::RELOC_INCR_POSITION << 9 | (offset - position - 1) as u16);
+            } else if offset < 1 << 26 {
+                instructions.push(pef::RELOC_SET_POSITION << 9 | (offset >> 16) as u16);
+                instructions.push(offset as u16);
+            } else {
+                return Err(Error(format!("invalid relocation offset {}", offset)));
+            }
+            position = offset;
+        }
+
+        // Find the number of following relocations that can be combined into a run.
+        let mut run = 1;
+        while run < 512 && i + run < relocations.len() {
+            let (next_offset, next_target) = relocations[i + run];
+            if next_offset != offset + 4 * run as u32 {
+                break;
+            }
+            let combine = match (target, next_target) {
+                (RelocationTarget::Section(a), RelocationTarget::Section(b)) => a == b && a <= 1,
+                (RelocationTarget::Import(a), RelocationTarget::Import(b)) => b == a + run as u32,
+                _ => false,
+            };
+            if !combine {
+                break;
+            }
+            run += 1;
+        }
+
+        match target {
+            // The initial values of sectionC and sectionD are the addresses of
+            // sections 0 and 1, and they are never changed.
+            RelocationTarget::Section(0) => {
+                instructions.push(pef::RELOC_BY_SECT_C << 9 | (run - 1) as u16);
+            }
+            RelocationTarget::Section(1) => {
+                instructions.push(pef::RELOC_BY_SECT_D << 9 | (run - 1) as u16);
+            }
+            RelocationTarget::Section(index) => {
+                debug_assert_eq!(run, 1);
+                if index < 512 {
+                    instructions.push(pef::RELOC_SM_BY_SECTION << 9 | index);
+                } else {
+                    instructions.push(
+                        pef::RELOC_LG_SET_OR_BY_SECTION << 9 | pef::RELOC_LG_BY_SECTION_SUBOP << 6,
+                    );
+                    instructions.push(index);
+                }
+            }
+            RelocationTarget::Import(index) => {
+                let mut count = run;
+                if index != import_index {
+                    if index < 512 {
+                        instructions.push(pef::RELOC_SM_BY_IMPORT << 9 | index as u16);
+                    } else if index < 1 << 26 {
+                        instructions.push(pef::RELOC_LG_BY_IMPORT << 9 | (index >> 16) as u16);
+                        instructions.push(index as u16);
+                    } else {
+                        return Err(Error(format!("invalid import index {}", index)));
+                    }
+                    count -= 1;
+                }
+                if count != 0 {
+                    instructions.push(pef::RELOC_IMPORT_RUN << 9 | (count - 1) as u16);
+                }
+                import_index = index + run as u32;
+            }
+        }
+        position += 4 * run as u32;
+        i += run;
+    }
+    Ok(())
+}
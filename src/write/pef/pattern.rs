@@ -0,0 +1,254 @@
+use alloc::vec::Vec;
+
+use crate::pef;
+
+/// The amount of effort spent searching for patterns in
+/// [`encode_pattern_data`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PatternDataLevel {
+    /// Only search for runs of zeros and short repeated blocks.
+    Fast,
+    /// Also search for longer repeated blocks and interleaved patterns.
+    ///
+    /// This produces smaller output, but is slower.
+    #[default]
+    Best,
+}
+
+impl PatternDataLevel {
+    fn max_repeat_size(self) -> usize {
+        match self {
+            PatternDataLevel::Fast => 4,
+            PatternDataLevel::Best => 16,
+        }
+    }
+
+    fn max_interleave_size(self) -> usize {
+        match self {
+            PatternDataLevel::Fast => 0,
+            PatternDataLevel::Best => 8,
+        }
+    }
+}
+
+/// A candidate instruction.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    opcode: u8,
+    count: usize,
+    custom_size: usize,
+    repeat_count: usize,
+    /// The number of bytes produced by the instruction.
+    covered: usize,
+    /// The number of bytes needed to encode the instruction.
+    encoded: usize,
+}
+
+impl Candidate {
+    /// The number of bytes saved compared to copying the data with a block.
+    fn saving(&self) -> usize {
+        self.covered.saturating_sub(self.encoded)
+    }
+}
+
+/// Encode data as the contents of a pattern-initialized data section.
+///
+/// The result can be decoded with `read::pef::unpack_pattern_data`, and
+/// always produces exactly `data`.
+///
+/// `level` trades off the time spent searching for patterns against the size
+/// of the result.
+pub fn encode_pattern_data(data: &[u8], level: PatternDataLevel) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut literal_start = 0;
+    let mut pos = 0;
+    while pos < data.len() {
+        let candidate = match best_candidate(data, pos, level) {
+            // Require a saving greater than the cost of splitting a literal block.
+            Some(candidate) if candidate.saving() > 1 => candidate,
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+        write_block(&mut out, &data[literal_start..pos]);
+        write_candidate(&mut out, &data[pos..], &candidate);
+        pos += candidate.covered;
+        literal_start = pos;
+    }
+    write_block(&mut out, &data[literal_start..]);
+    out
+}
+
+fn best_candidate(data: &[u8], pos: usize, level: PatternDataLevel) -> Option<Candidate> {
+    let data = &data[pos..];
+    let mut best: Option<Candidate> = None;
+    let mut consider = |candidate: Candidate| {
+        if best.map_or(true, |best| candidate.saving() > best.saving()) {
+            best = Some(candidate);
+        }
+    };
+
+    let zeros = limit(data.iter().take_while(|&&byte| byte == 0).count());
+    if zeros != 0 {
+        consider(Candidate {
+            opcode: pef::PK_DATA_ZERO,
+            count: zeros,
+            custom_size: 0,
+            repeat_count: 0,
+            covered: zeros,
+            encoded: header_size(zeros),
+        });
+    }
+
+    for size in 1..=level.max_repeat_size() {
+        if size > data.len() / 2 {
+            break;
+        }
+        let block = &data[..size];
+        let repeat_count = limit(
+            data.chunks_exact(size)
+                .take_while(|chunk| *chunk == block)
+                .count(),
+        );
+        if repeat_count < 2 {
+            continue;
+        }
+        consider(Candidate {
+            opcode: pef::PK_DATA_REPEAT,
+            count: size,
+            custom_size: 0,
+            repeat_count,
+            covered: size * repeat_count,
+            encoded: header_size(size) + argument_size(repeat_count - 1) + size,
+        });
+    }
+
+    let max_interleave_size = level.max_interleave_size();
+    for common_size in 1..=max_interleave_size {
+        if common_size > data.len() {
+            break;
+        }
+        let common = &data[..common_size];
+        let is_zero = common_size <= zeros;
+        for custom_size in 1..=max_interleave_size {
+            let stride = custom_size + common_size;
+            // Stop at custom blocks that only continue the common pattern, since
+            // those are better encoded as part of a run.
+            let repeat_count = limit(
+                data[common_size..]
+                    .chunks_exact(stride)
+                    .take_while(|chunk| {
+                        let (custom, next) = chunk.split_at(custom_size);
+                        next == common
+                            && !custom
+                                .iter()
+                                .zip(common.iter().cycle())
+                                .all(|(a, b)| a == b)
+                    })
+                    .count(),
+            );
+            if repeat_count == 0 {
+                continue;
+            }
+            let (opcode, common_encoded) = if is_zero {
+                (pef::PK_DATA_REPEAT_ZERO, 0)
+            } else {
+                (pef::PK_DATA_REPEAT_BLOCK, common_size)
+            };
+            consider(Candidate {
+                opcode,
+                count: common_size,
+                custom_size,
+                repeat_count,
+                covered: common_size + stride * repeat_count,
+                encoded: header_size(common_size)
+                    + argument_size(custom_size)
+                    + argument_size(repeat_count)
+                    + common_encoded
+                    + custom_size * repeat_count,
+            });
+        }
+    }
+
+    best
+}
+
+/// Limit a count so that it can be encoded as an argument.
+fn limit(count: usize) -> usize {
+    count.min(u32::MAX as usize)
+}
+
+fn write_candidate(out: &mut Vec<u8>, data: &[u8], candidate: &Candidate) {
+    write_header(out, candidate.opcode, candidate.count);
+    match candidate.opcode {
+        pef::PK_DATA_ZERO => {}
+        pef::PK_DATA_REPEAT => {
+            write_argument(out, candidate.repeat_count - 1);
+            out.extend_from_slice(&data[..candidate.count]);
+        }
+        _ => {
+            write_argument(out, candidate.custom_size);
+            write_argument(out, candidate.repeat_count);
+            if candidate.opcode == pef::PK_DATA_REPEAT_BLOCK {
+                out.extend_from_slice(&data[..candidate.count]);
+            }
+            let stride = candidate.custom_size + candidate.count;
+            for chunk in data[candidate.count..]
+                .chunks_exact(stride)
+                .take(candidate.repeat_count)
+            {
+                out.extend_from_slice(&chunk[..candidate.custom_size]);
+            }
+        }
+    }
+}
+
+fn write_block(out: &mut Vec<u8>, mut data: &[u8]) {
+    while !data.is_empty() {
+        let len = limit(data.len());
+        write_header(out, pef::PK_DATA_BLOCK, len);
+        out.extend_from_slice(&data[..len]);
+        data = &data[len..];
+    }
+}
+
+fn header_size(count: usize) -> usize {
+    if count != 0 && count <= pef::PK_DATA_MAX_COUNT5 as usize {
+        1
+    } else {
+        1 + argument_size(count)
+    }
+}
+
+fn write_header(out: &mut Vec<u8>, opcode: u8, count: usize) {
+    let opcode = opcode << pef::PK_DATA_OPCODE_SHIFT;
+    if count != 0 && count <= pef::PK_DATA_MAX_COUNT5 as usize {
+        out.push(opcode | count as u8);
+    } else {
+        out.push(opcode);
+        write_argument(out, count);
+    }
+}
+
+fn argument_size(value: usize) -> usize {
+    let mut size = 1;
+    let mut value = value >> pef::PK_DATA_VCOUNT_SHIFT;
+    while value != 0 {
+        size += 1;
+        value >>= pef::PK_DATA_VCOUNT_SHIFT;
+    }
+    size
+}
+
+fn write_argument(out: &mut Vec<u8>, value: usize) {
+    for i in (0..argument_size(value)).rev() {
+        let mut byte = (value >> (i as u32 * pef::PK_DATA_VCOUNT_SHIFT)) as u8;
+        byte &= pef::PK_DATA_VCOUNT_MASK;
+        if i != 0 {
+            byte |= pef::PK_DATA_VCOUNT_END_MASK;
+        }
+        out.push(byte);
+    }
+}
@@ -878,6 +878,83 @@ pub const R_TOCU: u8 = 0x30;
 /// Relative to TOC lower.
 pub const R_TOCL: u8 = 0x31;
 
+/// Loader section header.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct LoaderHeader32 {
+    /// Loader section version number.
+    pub l_version: U32<BE>,
+    /// Number of symbol table entries.
+    pub l_nsyms: U32<BE>,
+    /// Number of relocation table entries.
+    pub l_nreloc: U32<BE>,
+    /// Length of import file ID string table.
+    pub l_istlen: U32<BE>,
+    /// Number of import file IDs.
+    pub l_nimpid: U32<BE>,
+    /// Offset to start of import file IDs.
+    pub l_impoff: U32<BE>,
+    /// Length of string table.
+    pub l_stlen: U32<BE>,
+    /// Offset to start of string table.
+    pub l_stoff: U32<BE>,
+}
+
+/// Loader section symbol table entry.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct LoaderSymbol32 {
+    /// Symbol name.
+    ///
+    /// If the first 4 bytes are zero, then the second 4 bytes are
+    /// an offset into the loader string table.
+    pub l_name: [u8; 8],
+    /// Address field.
+    pub l_value: U32<BE>,
+    /// Section number.
+    pub l_scnum: I16<BE>,
+    /// Symbol type, export, import flags.
+    pub l_smtype: u8,
+    /// Symbol storage class.
+    pub l_smclas: u8,
+    /// Import file ID; ordinal of import file IDs.
+    pub l_ifile: U32<BE>,
+    /// Parameter type-check field.
+    pub l_parm: U32<BE>,
+}
+
+// Values for `l_smtype`.
+//
+/// Imported symbol.
+pub const L_IMPORT: u8 = 0x40;
+/// Entry point symbol.
+pub const L_ENTRY: u8 = 0x20;
+/// Exported symbol.
+pub const L_EXPORT: u8 = 0x10;
+/// Weak symbol.
+pub const L_WEAK: u8 = 0x08;
+/// Mask for the symbol type, which is one of the `XTY_*` values.
+pub const L_SYMTYPE_MASK: u8 = 0x07;
+
+/// Loader section relocation table entry.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct LoaderRel32 {
+    /// Address field.
+    pub l_vaddr: U32<BE>,
+    /// Loader section symbol table index of referenced item.
+    ///
+    /// Indices 0, 1 and 2 refer to the `.text`, `.data` and `.bss` sections.
+    /// Other indices are 3 greater than the index of the loader symbol.
+    pub l_symndx: U32<BE>,
+    /// Relocation size and information.
+    pub l_rsize: u8,
+    /// Relocation type.
+    pub l_rtype: u8,
+    /// Section number being relocated.
+    pub l_rsecnm: I16<BE>,
+}
+
 unsafe_impl_pod!(
     FileHeader32,
     FileHeader64,
@@ -902,4 +979,7 @@ unsafe_impl_pod!(
     DwarfAux64,
     Rel32,
     Rel64,
+    LoaderHeader32,
+    LoaderSymbol32,
+    LoaderRel32,
 );
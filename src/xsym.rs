@@ -0,0 +1,231 @@
+//! MPW SYM (xSYM) definitions.
+//!
+//! Source-level debugging information for PEF containers is not stored in the
+//! container itself. MPW and CodeWarrior linkers write it to a separate `.xSYM`
+//! (or `.SYM`) file that the debugger loads alongside the fragment.
+//!
+//! The file is divided into fixed size pages. The header gives the page size and
+//! the location of each table. A table occupies a run of whole pages and entries
+//! never straddle a page boundary, so each page holds `page_size / entry_size`
+//! entries followed by unused padding.
+//!
+//! All multi-byte fields are big-endian and only 2-byte aligned, so the structures
+//! in this module use unaligned integer types throughout.
+
+#![allow(missing_docs)]
+
+use crate::endian::{BigEndian as BE, I16Bytes, U16Bytes, U32Bytes};
+use crate::pod::Pod;
+
+/// Marks the end of a list in the contained labels and statements tables.
+pub const END_OF_LIST: u16 = 0xffff;
+/// Marks an entry that changes the current source file in the contained labels,
+/// statements and file references tables.
+pub const FILE_NAME_INDEX: u16 = 0xfffe;
+
+// Values for `DiskModulesTableEntry::kind`.
+pub const MODULE_KIND_NONE: u8 = 0;
+pub const MODULE_KIND_PROGRAM: u8 = 1;
+pub const MODULE_KIND_UNIT: u8 = 2;
+pub const MODULE_KIND_PROCEDURE: u8 = 3;
+pub const MODULE_KIND_FUNCTION: u8 = 4;
+pub const MODULE_KIND_DATA: u8 = 5;
+pub const MODULE_KIND_BLOCK: u8 = 6;
+
+// Values for `DiskModulesTableEntry::scope` and `DiskContainedLabelsTableEntry::scope`.
+pub const SYMBOL_SCOPE_LOCAL: u8 = 0;
+pub const SYMBOL_SCOPE_GLOBAL: u8 = 1;
+
+/// The location of a table within the file.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskTableInfo {
+    /// The first page of the table.
+    pub first_page: U16Bytes<BE>,
+    /// The number of pages in the table.
+    pub page_count: U16Bytes<BE>,
+    /// The number of entries in the table.
+    ///
+    /// For the name table this is the size of the table in bytes.
+    pub object_count: U32Bytes<BE>,
+}
+
+/// The file header. This always occupies the start of the first page.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskSymHeaderBlock {
+    /// Version string as a Pascal string, e.g. "MPW Symbol File 3.2".
+    pub id: [u8; 32],
+    /// The size in bytes of each page.
+    pub page_size: U16Bytes<BE>,
+    /// The page containing the name hash table.
+    pub hash_page: U16Bytes<BE>,
+    /// Index of the module table entry for the program root.
+    pub root_mte: U16Bytes<BE>,
+    /// Modification date of the executable, in seconds since January 1, 1904.
+    pub mod_date: U32Bytes<BE>,
+    /// File references table.
+    pub frte: DiskTableInfo,
+    /// Resources table.
+    pub rte: DiskTableInfo,
+    /// Modules table.
+    pub mte: DiskTableInfo,
+    /// Contained modules table.
+    pub cmte: DiskTableInfo,
+    /// Contained variables table.
+    pub cvte: DiskTableInfo,
+    /// Contained statements table.
+    pub csnte: DiskTableInfo,
+    /// Contained labels table.
+    pub clte: DiskTableInfo,
+    /// Contained types table.
+    pub ctte: DiskTableInfo,
+    /// Types table.
+    pub tte: DiskTableInfo,
+    /// Name table.
+    pub nte: DiskTableInfo,
+    /// Type information table.
+    pub tinfo: DiskTableInfo,
+    /// File information table.
+    pub fite: DiskTableInfo,
+    /// Constants pool.
+    pub constants: DiskTableInfo,
+    /// Creator of the executable.
+    pub file_creator: U32Bytes<BE>,
+    /// File type of the executable.
+    pub file_type: U32Bytes<BE>,
+}
+
+/// A reference to an offset within a source file.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskFileReference {
+    /// Index of the file name entry in the file references table.
+    pub frte_index: U16Bytes<BE>,
+    /// Byte offset within the source file.
+    pub offset: U32Bytes<BE>,
+}
+
+/// An entry in the resources table.
+///
+/// For PEF containers each entry describes one code section, in container order.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskResourcesTableEntry {
+    /// Resource type.
+    pub res_type: U32Bytes<BE>,
+    /// Resource ID.
+    pub res_number: I16Bytes<BE>,
+    /// Name table index of the resource name.
+    pub nte_index: U32Bytes<BE>,
+    /// First module table entry contained in this resource.
+    pub mte_first: U16Bytes<BE>,
+    /// Last module table entry contained in this resource.
+    pub mte_last: U16Bytes<BE>,
+    /// Size of the resource.
+    pub res_size: U32Bytes<BE>,
+}
+
+/// An entry in the modules table.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskModulesTableEntry {
+    /// Index of the resources table entry containing this module.
+    pub rte_index: U16Bytes<BE>,
+    /// Offset of the module within the resource.
+    pub res_offset: U32Bytes<BE>,
+    /// Size of the module.
+    pub size: U32Bytes<BE>,
+    /// One of the `MODULE_KIND_*` values.
+    pub kind: u8,
+    /// One of the `SYMBOL_SCOPE_*` values.
+    pub scope: u8,
+    /// Index of the parent module table entry.
+    pub parent: U16Bytes<BE>,
+    /// Start of the module's implementation in the source.
+    pub imp_fref: DiskFileReference,
+    /// End of the module's implementation in the source file.
+    pub imp_end: U32Bytes<BE>,
+    /// Name table index of the module name.
+    pub nte_index: U32Bytes<BE>,
+    /// First contained modules table entry.
+    pub cmte_index: U16Bytes<BE>,
+    /// First contained variables table entry.
+    pub cvte_index: U32Bytes<BE>,
+    /// First contained labels table entry.
+    pub clte_index: U16Bytes<BE>,
+    /// First contained types table entry.
+    pub ctte_index: U16Bytes<BE>,
+    /// First contained statements table entry.
+    pub csnte_idx_1: U32Bytes<BE>,
+    /// Last contained statements table entry.
+    pub csnte_idx_2: U32Bytes<BE>,
+}
+
+/// An entry in the file references table.
+///
+/// If `tag` is [`FILE_NAME_INDEX`] then this entry starts a new source file,
+/// and `value` is the name table index of the file name. Otherwise `tag` is a
+/// module table index, and `value` is the offset of that module in the
+/// most recently named source file.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskFileReferencesTableEntry {
+    pub tag: U16Bytes<BE>,
+    pub value: U32Bytes<BE>,
+    /// Modification date of the source file, if this is a file name entry.
+    pub mod_date: U32Bytes<BE>,
+}
+
+/// An entry in the contained statements table.
+///
+/// If `mte_index` is [`FILE_NAME_INDEX`] then this entry changes the current
+/// source file: `file_delta` is the index of the file name entry in the file
+/// references table, and `mte_offset` is the starting offset in that file.
+///
+/// If `mte_index` is [`END_OF_LIST`] then this entry ends the statements for
+/// the current module.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskContainedStatementsTableEntry {
+    /// Index of the module table entry containing the statement.
+    pub mte_index: U16Bytes<BE>,
+    /// Change in source file offset from the previous statement.
+    pub file_delta: I16Bytes<BE>,
+    /// Offset of the statement within the module.
+    pub mte_offset: U32Bytes<BE>,
+}
+
+/// An entry in the contained labels table.
+///
+/// If `mte_index` is [`FILE_NAME_INDEX`] then this entry changes the current
+/// source file: `nte_index` is the index of the file name entry in the file
+/// references table, and `mte_offset` is the starting offset in that file.
+///
+/// If `mte_index` is [`END_OF_LIST`] then this entry ends the labels for
+/// the current module.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct DiskContainedLabelsTableEntry {
+    /// Index of the module table entry containing the label.
+    pub mte_index: U16Bytes<BE>,
+    /// Offset of the label within the module.
+    pub mte_offset: U32Bytes<BE>,
+    /// Name table index of the label name.
+    pub nte_index: U32Bytes<BE>,
+    /// Change in source file offset from the previous label.
+    pub file_delta: I16Bytes<BE>,
+    /// One of the `SYMBOL_SCOPE_*` values.
+    pub scope: U16Bytes<BE>,
+}
+
+unsafe_impl_pod!(
+    DiskTableInfo,
+    DiskSymHeaderBlock,
+    DiskFileReference,
+    DiskResourcesTableEntry,
+    DiskModulesTableEntry,
+    DiskFileReferencesTableEntry,
+    DiskContainedStatementsTableEntry,
+    DiskContainedLabelsTableEntry,
+);
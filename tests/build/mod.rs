@@ -1,3 +1,4 @@
 #![cfg(feature = "build")]
 
 mod elf;
+mod pef;
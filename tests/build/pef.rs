@@ -0,0 +1,555 @@
+#![cfg(all(feature = "read", feature = "write", feature = "pef"))]
+
+use object::build;
+use object::endian::BigEndian as BE;
+use object::endian::{I16Bytes, U32Bytes, I32, U16, U32};
+use object::read::pef::{
+    evaluate_relocations, LoaderRelocation, LoaderSection, PefFile, SectionTable,
+};
+use object::read::{Object, ReadRef};
+use object::{pef, write};
+use object::{
+    Architecture, BinaryFormat, Endianness, RelocationEncoding, RelocationFlags, RelocationKind,
+    SectionFlags, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+};
+
+fn relocation(offset: u64, symbol: write::SymbolId) -> write::Relocation {
+    write::Relocation {
+        offset,
+        symbol,
+        addend: 0,
+        flags: RelocationFlags::Generic {
+            kind: RelocationKind::Absolute,
+            encoding: RelocationEncoding::Generic,
+            size: 32,
+        },
+    }
+}
+
+fn pef_object() -> Vec<u8> {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+    object.set_pef_container_info(write::PefContainerInfo {
+        date_time_stamp: 0xB000_0000,
+        old_def_version: 1,
+        old_imp_version: 2,
+        current_version: 3,
+    });
+
+    let text = object.section_id(write::StandardSection::Text);
+    let func_offset = object.append_section_data(text, &[0x4e, 0x80, 0x00, 0x20], 4);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: func_offset,
+        size: 4,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+
+    let data = object.section_id(write::StandardSection::Data);
+    let tvect_offset = object.append_section_data(data, &[0; 8], 4);
+    let tvect = object.add_symbol(write::Symbol {
+        name: b"main".to_vec(),
+        value: tvect_offset,
+        size: 8,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(data),
+        flags: SymbolFlags::Pef {
+            symbol_class: pef::TVECT_SYMBOL,
+        },
+    });
+    let toc_offset = object.append_section_data(data, &[0; 16], 4);
+    object.set_pef_main_symbol(Some(tvect));
+
+    let debug = object.add_section(Vec::new(), b"debug".to_vec(), SectionKind::Debug);
+    object.section_mut(debug).flags = SectionFlags::Pef {
+        section_kind: pef::SectionKind::Debug as u8,
+        share_kind: pef::ShareKind::GlobalShare as u8,
+    };
+    object.append_section_data(debug, b"debug info", 1);
+
+    let mut imports = Vec::new();
+    for (library_name, names) in [
+        (
+            &b"InterfaceLib"[..],
+            &[&b"SysBeep"[..], &b"Gestalt"[..]][..],
+        ),
+        (&b"MathLib"[..], &[&b"sqrt"[..]][..]),
+    ] {
+        let library = object.add_pef_imported_library(write::PefImportedLibrary {
+            name: library_name.to_vec(),
+            old_imp_version: 0x100,
+            current_version: 0x200,
+            weak: false,
+            init_before: false,
+        });
+        for name in names {
+            let symbol = object.add_symbol(write::Symbol {
+                name: name.to_vec(),
+                value: 0,
+                size: 0,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Unknown,
+                weak: false,
+                section: write::SymbolSection::Undefined,
+                flags: SymbolFlags::None,
+            });
+            object.set_pef_symbol_library(symbol, library);
+            imports.push(symbol);
+        }
+    }
+
+    object
+        .add_relocation(data, relocation(tvect_offset, func))
+        .unwrap();
+    let toc = object.section_symbol(data);
+    object
+        .add_relocation(data, relocation(tvect_offset + 4, toc))
+        .unwrap();
+    for (i, import) in imports.iter().enumerate() {
+        object
+            .add_relocation(data, relocation(toc_offset + 4 * i as u64, *import))
+            .unwrap();
+    }
+
+    object.write().unwrap()
+}
+
+#[test]
+fn round_trip() {
+    let bytes = pef_object();
+    let builder = build::pef::Builder::read(&*bytes).unwrap();
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+    assert_eq!(bytes, out);
+}
+
+/// Assemble a container by hand, using layout choices that `Builder::write` would not make.
+///
+/// The loader section is placed before the other sections in the file, the sections are
+/// not aligned to 16 bytes, the section names are in reverse order, the export names come
+/// first in the loader string table, and one export name shares bytes with an import name.
+fn pef_hand_assembled() -> Vec<u8> {
+    use std::mem::size_of;
+
+    let code = [0x60, 0x00, 0x00, 0x00, 0x4e, 0x80, 0x00, 0x20];
+    // Unpacks to a transition vector followed by two imported symbol pointers.
+    let pattern = [
+        // Zero 4 bytes.
+        0x04, // Block of 4 bytes.
+        0x24, 0x00, 0x00, 0x00, 0x10, // Repeat 4 bytes twice.
+        0x44, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let section_names = b"data\0code\0";
+
+    let strings = b"mainzetaInterfaceLib\0SysBeep\0Gestalt\0";
+    let string = |name: &[u8]| {
+        strings
+            .windows(name.len())
+            .position(|window| window == name)
+            .unwrap() as u32
+    };
+    let class_and_name = |class: u8, name: &[u8]| (u32::from(class) << 24) | string(name);
+
+    // Exports must be grouped by hash chain. Within a chain, use reverse name order.
+    let hash_power = 2;
+    let mut exports = vec![
+        (&b"main"[..], pef::TVECT_SYMBOL, 1, 0),
+        (b"zeta", pef::CODE_SYMBOL, 0, 4),
+        (
+            b"Beep",
+            pef::TVECT_SYMBOL,
+            pef::EXPORTED_REEXPORTED_IMPORT_SECTION,
+            0,
+        ),
+    ];
+    exports.sort_by_key(|(name, ..)| std::cmp::Reverse(*name));
+    exports.sort_by_key(|(name, ..)| pef::hash_table_index(pef::hash_word(name), hash_power));
+    let mut hash_slots = vec![0u32; 1 << hash_power];
+    for (index, (name, ..)) in exports.iter().enumerate().rev() {
+        let slot =
+            &mut hash_slots[pef::hash_table_index(pef::hash_word(name), hash_power) as usize];
+        *slot = (((*slot >> 18) + 1) << 18) | index as u32;
+    }
+
+    let relocations = [
+        // One transition vector.
+        pef::RELOC_T_VECTOR_8 << 9,
+        // Two imports.
+        (pef::RELOC_IMPORT_RUN << 9) | 1,
+    ];
+
+    let reloc_instr_offset = size_of::<pef::PEFLoaderInfoHeader>()
+        + size_of::<pef::PEFImportedLibrary>()
+        + 2 * size_of::<pef::PEFImportedSymbol>()
+        + size_of::<pef::PEFLoaderRelocationHeader>();
+    let loader_strings_offset = reloc_instr_offset + 2 * relocations.len();
+    let export_hash_offset = (loader_strings_offset + strings.len() + 3) & !3;
+
+    let mut loader = Vec::new();
+    loader.extend_from_slice(object::bytes_of(&pef::PEFLoaderInfoHeader {
+        main_section: I32::new(BE, 1),
+        main_offset: U32::new(BE, 0),
+        init_section: I32::new(BE, pef::NO_SECTION),
+        init_offset: U32::new(BE, 0),
+        term_section: I32::new(BE, pef::NO_SECTION),
+        term_offset: U32::new(BE, 0),
+        imported_library_count: U32::new(BE, 1),
+        total_imported_symbol_count: U32::new(BE, 2),
+        reloc_section_count: U32::new(BE, 1),
+        reloc_instr_offset: U32::new(BE, reloc_instr_offset as u32),
+        loader_strings_offset: U32::new(BE, loader_strings_offset as u32),
+        export_hash_offset: U32::new(BE, export_hash_offset as u32),
+        export_hash_table_power: U32::new(BE, hash_power),
+        exported_symbol_count: U32::new(BE, exports.len() as u32),
+    }));
+    loader.extend_from_slice(object::bytes_of(&pef::PEFImportedLibrary {
+        name_offset: U32::new(BE, string(b"InterfaceLib")),
+        old_imp_version: U32::new(BE, 0x100),
+        current_version: U32::new(BE, 0x200),
+        imported_symbol_count: U32::new(BE, 2),
+        first_imported_symbol: U32::new(BE, 0),
+        options: 0,
+        reserved_a: 0,
+        reserved_b: U16::new(BE, 0),
+    }));
+    for (name, class) in [
+        (&b"SysBeep"[..], pef::TVECT_SYMBOL),
+        (b"Gestalt", pef::TVECT_SYMBOL | pef::WEAK_IMPORT_SYMBOL),
+    ] {
+        loader.extend_from_slice(object::bytes_of(&pef::PEFImportedSymbol {
+            class_and_name: U32::new(BE, class_and_name(class, name)),
+        }));
+    }
+    loader.extend_from_slice(object::bytes_of(&pef::PEFLoaderRelocationHeader {
+        section_index: U16::new(BE, 1),
+        reserved_a: U16::new(BE, 0),
+        reloc_count: U32::new(BE, relocations.len() as u32),
+        first_reloc_offset: U32::new(BE, 0),
+    }));
+    for relocation in relocations {
+        loader.extend_from_slice(&relocation.to_be_bytes());
+    }
+    loader.extend_from_slice(strings);
+    loader.resize(export_hash_offset, 0);
+    for slot in &hash_slots {
+        loader.extend_from_slice(&slot.to_be_bytes());
+    }
+    for (name, ..) in &exports {
+        loader.extend_from_slice(&pef::hash_word(name).to_be_bytes());
+    }
+    for &(name, class, section, value) in &exports {
+        loader.extend_from_slice(object::bytes_of(&pef::PEFExportedSymbol {
+            class_and_name: U32Bytes::new(BE, class_and_name(class, name)),
+            symbol_value: U32Bytes::new(BE, value),
+            section_index: I16Bytes::new(BE, section),
+        }));
+    }
+
+    let loader_offset = (size_of::<pef::PEFContainerHeader>()
+        + 3 * size_of::<pef::PEFSectionHeader>()
+        + section_names.len()
+        + 3)
+        & !3;
+    let code_offset = (loader_offset + loader.len() + 3) & !3;
+    let data_offset = code_offset + code.len();
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(object::bytes_of(&pef::PEFContainerHeader {
+        tag1: U32::new(BE, pef::TAG1),
+        tag2: U32::new(BE, pef::TAG2),
+        architecture: U32::new(BE, pef::ARCHITECTURE_PPC),
+        format_version: U32::new(BE, pef::FORMAT_VERSION),
+        date_time_stamp: U32::new(BE, 0xB000_0000),
+        old_def_version: U32::new(BE, 1),
+        old_imp_version: U32::new(BE, 2),
+        current_version: U32::new(BE, 3),
+        section_count: U16::new(BE, 3),
+        inst_section_count: U16::new(BE, 2),
+        reserved_a: U32::new(BE, 0),
+    }));
+    for (name_offset, total_size, unpacked_size, packed_size, offset, section_kind, share_kind) in [
+        (
+            5,
+            code.len(),
+            code.len(),
+            code.len(),
+            code_offset,
+            pef::SectionKind::Code,
+            pef::ShareKind::GlobalShare,
+        ),
+        (
+            0,
+            32,
+            16,
+            pattern.len(),
+            data_offset,
+            pef::SectionKind::PatternInitializedData,
+            pef::ShareKind::ProcessShare,
+        ),
+        (
+            pef::NO_SECTION_NAME,
+            loader.len(),
+            loader.len(),
+            loader.len(),
+            loader_offset,
+            pef::SectionKind::Loader,
+            pef::ShareKind::GlobalShare,
+        ),
+    ] {
+        bytes.extend_from_slice(object::bytes_of(&pef::PEFSectionHeader {
+            name_offset: I32::new(BE, name_offset),
+            default_address: U32::new(BE, 0),
+            total_size: U32::new(BE, total_size as u32),
+            unpacked_size: U32::new(BE, unpacked_size as u32),
+            packed_size: U32::new(BE, packed_size as u32),
+            container_offset: U32::new(BE, offset as u32),
+            section_kind: section_kind as u8,
+            share_kind: share_kind as u8,
+            alignment: 4,
+            reserved_a: 0,
+        }));
+    }
+    bytes.extend_from_slice(section_names);
+    bytes.resize(loader_offset, 0);
+    bytes.extend_from_slice(&loader);
+    bytes.resize(code_offset, 0);
+    bytes.extend_from_slice(&code);
+    bytes.extend_from_slice(&pattern);
+    bytes
+}
+
+/// The name, kind, total size and unpacked data of a section.
+type SectionContents = (Option<Vec<u8>>, u8, u32, Vec<u8>);
+
+/// The parts of a container that must be preserved when it is rewritten.
+#[derive(Debug, PartialEq, Eq)]
+struct Contents {
+    sections: Vec<SectionContents>,
+    libraries: Vec<(Vec<u8>, u32, u32, u8)>,
+    imports: Vec<(Vec<u8>, u8)>,
+    exports: Vec<(Vec<u8>, u8, i16, u32)>,
+    hash_chains: Vec<Vec<Vec<u8>>>,
+    relocations: Vec<(u16, Vec<LoaderRelocation>)>,
+    main: (i32, u32),
+}
+
+fn contents(data: &[u8]) -> Contents {
+    let header = data.read_at::<pef::PEFContainerHeader>(0).unwrap();
+    let sections = SectionTable::parse(header, data).unwrap();
+    let loader = sections.loader().unwrap().unwrap();
+    let loader = LoaderSection::parse_section(data, loader).unwrap();
+
+    let mut exports = Vec::new();
+    let mut export_names = Vec::new();
+    for (index, symbol) in loader.exported_symbols().iter().enumerate() {
+        let name = loader.exported_symbol_name(index).unwrap().to_vec();
+        export_names.push(name.clone());
+        exports.push((
+            name,
+            (symbol.class_and_name.get(BE) >> 24) as u8,
+            symbol.section_index.get(BE),
+            symbol.symbol_value.get(BE),
+        ));
+    }
+    exports.sort();
+
+    let power = loader.header().export_hash_table_power.get(BE);
+    let keys = loader.export_keys();
+    let mut hash_chains = Vec::new();
+    for (slot_index, slot) in loader.hash_slots().iter().enumerate() {
+        let first = slot.first_symbol() as usize;
+        let count = slot.symbol_count() as usize;
+        let mut chain = Vec::new();
+        for (key, name) in keys[first..][..count]
+            .iter()
+            .zip(&export_names[first..][..count])
+        {
+            let hash = key.full_hash_word.get(BE);
+            assert_eq!(hash, pef::hash_word(name));
+            assert_eq!(pef::hash_table_index(hash, power), slot_index as u32);
+            chain.push(name.clone());
+        }
+        chain.sort();
+        hash_chains.push(chain);
+    }
+
+    let mut relocations = Vec::new();
+    for header in loader.relocation_headers() {
+        let index = header.section_index.get(BE);
+        let section = sections
+            .section(object::SectionIndex(index.into()))
+            .unwrap();
+        let blocks = loader.relocation_instructions(header).unwrap();
+        relocations.push((
+            index,
+            evaluate_relocations(blocks, section.total_size.get(BE)).unwrap(),
+        ));
+    }
+
+    let info = loader.header();
+    Contents {
+        sections: sections
+            .iter()
+            .filter(|section| section.section_kind != pef::SectionKind::Loader as u8)
+            .map(|section| {
+                (
+                    sections.section_name(section).unwrap().map(<[u8]>::to_vec),
+                    section.section_kind,
+                    section.total_size.get(BE),
+                    section.pef_unpacked_data(data).unwrap().into_owned(),
+                )
+            })
+            .collect(),
+        libraries: loader
+            .libraries()
+            .iter()
+            .map(|library| {
+                (
+                    loader.library_name(library).unwrap().to_vec(),
+                    library.first_imported_symbol.get(BE),
+                    library.imported_symbol_count.get(BE),
+                    library.options,
+                )
+            })
+            .collect(),
+        imports: loader
+            .imported_symbols()
+            .iter()
+            .map(|symbol| {
+                (
+                    loader.imported_symbol_name(symbol).unwrap().to_vec(),
+                    (symbol.class_and_name.get(BE) >> 24) as u8,
+                )
+            })
+            .collect(),
+        exports,
+        hash_chains,
+        relocations,
+        main: (info.main_section.get(BE), info.main_offset.get(BE)),
+    }
+}
+
+#[test]
+fn round_trip_hand_assembled() {
+    let bytes = pef_hand_assembled();
+    let original = contents(&bytes);
+    assert_eq!(original.exports.len(), 3);
+    assert_eq!(original.hash_chains.len(), 4);
+    assert_eq!(
+        original.sections[1].3,
+        [0, 0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(original.relocations[0].1.len(), 4);
+
+    // Re-exported imports are exports without an address.
+    let file = PefFile::<&[u8]>::parse(&*bytes).unwrap();
+    let mut exports = file
+        .exports()
+        .unwrap()
+        .iter()
+        .map(|export| (export.name(), export.address()))
+        .collect::<Vec<_>>();
+    exports.sort();
+    assert_eq!(exports, [(&b"Beep"[..], 0), (b"main", 0), (b"zeta", 4)]);
+
+    let builder = build::pef::Builder::read(&*bytes).unwrap();
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+    // The layout is different, but the contents are the same.
+    assert_ne!(bytes, out);
+    assert_eq!(original, contents(&out));
+
+    // The pattern data is copied without repacking it.
+    let section_data = |data: &[u8]| {
+        let header = data.read_at::<pef::PEFContainerHeader>(0).unwrap();
+        let sections = SectionTable::parse(header, data).unwrap();
+        let section = sections.section(object::SectionIndex(1)).unwrap();
+        section.pef_data(data).unwrap().to_vec()
+    };
+    assert_eq!(section_data(&bytes), section_data(&out));
+}
+
+#[test]
+fn modify() {
+    let bytes = pef_object();
+    let mut builder = build::pef::Builder::read(&*bytes).unwrap();
+
+    builder.header.current_version = 4;
+    let library = builder.libraries.find(b"InterfaceLib").unwrap();
+    let library = builder.libraries.get_mut(library);
+    library.name = b"CarbonLib"[..].into();
+    library.set_weak(true);
+    for import in builder.imports.iter_mut() {
+        if &*import.name == b"Gestalt" {
+            import.set_weak(true);
+        }
+    }
+    for section in builder.sections.iter_mut() {
+        if section.section_kind == pef::SectionKind::Debug as u8 {
+            section.delete = true;
+        }
+    }
+
+    let mut out = Vec::new();
+    builder.write(&mut out).unwrap();
+    let out = &*out;
+
+    let header = out.read_at::<pef::PEFContainerHeader>(0).unwrap();
+    assert_eq!(header.current_version.get(BE), 4);
+    assert_eq!(header.old_def_version.get(BE), 1);
+    assert_eq!(header.section_count.get(BE), 3);
+    assert_eq!(header.inst_section_count.get(BE), 2);
+
+    let sections = SectionTable::parse(header, out).unwrap();
+    assert!(sections.section_by_name(b"debug").is_none());
+    let loader = sections.loader().unwrap().unwrap();
+    let loader = LoaderSection::parse_section(out, loader).unwrap();
+
+    let libraries = loader.libraries();
+    assert_eq!(libraries.len(), 2);
+    assert_eq!(loader.library_name(&libraries[0]).unwrap(), b"CarbonLib");
+    assert_eq!(libraries[0].options, pef::WEAK_IMPORT_LIB);
+    assert_eq!(loader.library_name(&libraries[1]).unwrap(), b"MathLib");
+    assert_eq!(libraries[1].options, 0);
+
+    let symbols = loader.library_symbols(&libraries[0]).unwrap();
+    assert_eq!(symbols.len(), 2);
+    assert_eq!(
+        loader.imported_symbol_name(&symbols[0]).unwrap(),
+        b"SysBeep"
+    );
+    assert_eq!(symbols[0].symbol_flags(), 0);
+    assert_eq!(
+        loader.imported_symbol_name(&symbols[1]).unwrap(),
+        b"Gestalt"
+    );
+    assert_eq!(symbols[1].symbol_flags(), pef::WEAK_IMPORT_SYMBOL);
+    let symbols = loader.library_symbols(&libraries[1]).unwrap();
+    assert_eq!(loader.imported_symbol_name(&symbols[0]).unwrap(), b"sqrt");
+
+    // The relocations and exports are unchanged.
+    let original = build::pef::Builder::read(&*bytes).unwrap();
+    let modified = build::pef::Builder::read(out).unwrap();
+    let relocations = |builder: &build::pef::Builder<'_>| {
+        builder
+            .sections
+            .iter()
+            .map(|section| section.relocations.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(relocations(&original)[..2], relocations(&modified)[..2]);
+    assert_eq!(original.main, modified.main);
+    let exports = modified
+        .exports
+        .iter()
+        .map(|export| &*export.name)
+        .collect::<Vec<_>>();
+    assert_eq!(exports.len(), 2);
+    assert!(exports.contains(&&b"func"[..]));
+    assert!(exports.contains(&&b"main"[..]));
+}
@@ -23,4 +23,394 @@ fn pef_test_sections() {
             assert_eq!(section.name(), Ok(actual_section_name));
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "pef")]
+#[test]
+fn pef_evaluate_relocations() {
+    use object::endian::{BigEndian as BE, U16};
+    use object::read::pef::{evaluate_relocations, LoaderRelocation, LoaderRelocationTarget};
+    use object::SectionIndex;
+
+    let blocks = [
+        0x4401, // TVector12, run length 2
+        0x6003, // SmByImport 3
+        0x4a00, // ImportRun 1
+        0x6402, // SmSetSectD 2
+        0x4200, // BySectD 1
+        0x8003, // IncrPosition 4
+        0x9101, // Repeat the previous 2 blocks twice
+    ]
+    .map(|block| U16::new(BE, block));
+    let relocations = evaluate_relocations(&blocks, 0x100).unwrap();
+    let section = |index| LoaderRelocationTarget::Section(SectionIndex(index));
+    let expected = [
+        (0, section(0)),
+        (4, section(1)),
+        (12, section(0)),
+        (16, section(1)),
+        (24, LoaderRelocationTarget::Import(3)),
+        (28, LoaderRelocationTarget::Import(4)),
+        (32, section(2)),
+        (40, section(2)),
+        (48, section(2)),
+    ]
+    .map(|(offset, target)| LoaderRelocation { offset, target });
+    assert_eq!(relocations, expected);
+
+    // Relocations must be within the section.
+    assert!(evaluate_relocations(&blocks, 48).is_err());
+}
+
+#[cfg(feature = "pef")]
+#[test]
+fn pef_xsym() {
+    use object::read::pef::{PefFile, XsymFile};
+
+    fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..][..bytes.len()].copy_from_slice(bytes);
+    }
+
+    // A container with a single 0x40 byte code section.
+    let mut pef = vec![0; 0x90];
+    put(&mut pef, 0, b"Joy!peffpwpc");
+    put(&mut pef, 12, &1u32.to_be_bytes());
+    put(&mut pef, 32, &[0, 1, 0, 1]);
+    put(&mut pef, 40, &(-1i32).to_be_bytes());
+    for (i, value) in [0u32, 0x40, 0x40, 0x40, 0x50].iter().enumerate() {
+        put(&mut pef, 44 + i * 4, &value.to_be_bytes());
+    }
+    put(&mut pef, 64, &[0, 4, 4, 0]);
+    let pef = PefFile::parse(&*pef).unwrap();
+    assert!(!pef.has_debug_symbols());
+
+    const PAGE: usize = 0x100;
+    let mut sym = vec![0; 7 * PAGE];
+    put(&mut sym, 0, b"\x13MPW Symbol File 3.2");
+    put(&mut sym, 32, &(PAGE as u16).to_be_bytes());
+    // frte, rte, mte, csnte, clte and nte table infos.
+    for (table, page, count) in [
+        (0, 6, 1u32),
+        (1, 2, 1),
+        (2, 3, 2),
+        (5, 5, 5),
+        (6, 4, 1),
+        (9, 1, 0),
+    ] {
+        let offset = 42 + table * 8;
+        put(&mut sym, offset, &[0, page, 0, 1]);
+        put(&mut sym, offset + 4, &count.to_be_bytes());
+    }
+    // Names.
+    put(
+        &mut sym,
+        PAGE,
+        b"\x04main\0\x06helper\0\x04loop\0\x06main.c",
+    );
+    // Modules.
+    for (i, (offset, kind, name)) in [(0u32, 4, 0u32), (0x20, 3, 3)].iter().enumerate() {
+        let entry = PAGE * 3 + i * 46;
+        put(&mut sym, entry + 2, &offset.to_be_bytes());
+        put(&mut sym, entry + 6, &0x20u32.to_be_bytes());
+        put(&mut sym, entry + 10, &[*kind, 1]);
+        put(&mut sym, entry + 24, &name.to_be_bytes());
+    }
+    // Labels.
+    put(&mut sym, PAGE * 4, &[0, 1, 0, 0, 0, 8, 0, 0, 0, 7]);
+    // Statements.
+    let statements: [[u8; 8]; 5] = [
+        [0xff, 0xfe, 0, 0, 0, 0, 0, 10],
+        [0, 0, 0, 0, 0, 0, 0, 0],
+        [0, 0, 0, 12, 0, 0, 0, 8],
+        [0, 1, 0, 20, 0, 0, 0, 4],
+        [0xff, 0xff, 0, 0, 0, 0, 0, 0],
+    ];
+    for (i, statement) in statements.iter().enumerate() {
+        put(&mut sym, PAGE * 5 + i * 8, statement);
+    }
+    // File references.
+    put(&mut sym, PAGE * 6, &[0xff, 0xfe, 0, 0, 0, 10]);
+
+    let xsym = XsymFile::parse(&*sym).unwrap();
+    assert_eq!(xsym.version(), Ok(&b"MPW Symbol File 3.2"[..]));
+
+    let map = xsym.symbol_map(&pef).unwrap();
+    let symbols: Vec<_> = map
+        .symbols()
+        .iter()
+        .map(|symbol| (symbol.address(), symbol.size(), symbol.name()))
+        .collect();
+    assert_eq!(
+        symbols,
+        [
+            (0, 0x20, "main"),
+            (0x20, 0x20, "helper"),
+            (0x28, 0x18, "loop")
+        ]
+    );
+    assert_eq!(map.get(0x2c).map(|symbol| symbol.name()), Some("loop"));
+
+    let source = b"int main()\r{\r  return helper();\r}\r";
+    let statements: Vec<_> = xsym
+        .statements(&pef)
+        .unwrap()
+        .iter()
+        .map(|s| (s.address(), s.file(), s.source_offset(), s.line(source)))
+        .collect();
+    assert_eq!(
+        statements,
+        [
+            (0, &b"main.c"[..], 10, 1),
+            (8, &b"main.c"[..], 22, 3),
+            (0x24, &b"main.c"[..], 42, 5),
+        ]
+    );
+}
+
+#[cfg(feature = "pef")]
+#[test]
+fn pef_resource_fork() {
+    use object::pef;
+    use object::read::pef::{MacFile, MacFileKind, PefFile};
+
+    fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..][..bytes.len()].copy_from_slice(bytes);
+    }
+
+    // A container with no sections, preceded by some padding in the data fork.
+    let mut data_fork = vec![0; 0x10 + 40];
+    put(&mut data_fork, 0x10, b"Joy!peffpwpc");
+    put(&mut data_fork, 0x10 + 12, &1u32.to_be_bytes());
+    put(&mut data_fork, 0x10 + 28, &0x0200_0000u32.to_be_bytes());
+
+    // A 'cfrg' resource with one member in the data fork.
+    let mut cfrg = vec![0; 32 + 48];
+    put(&mut cfrg, 10, &1u16.to_be_bytes());
+    put(&mut cfrg, 30, &1u16.to_be_bytes());
+    put(&mut cfrg, 32, b"pwpc");
+    put(&mut cfrg, 32 + 8, &0x0200_0000u32.to_be_bytes());
+    put(&mut cfrg, 32 + 12, &0x0100_0000u32.to_be_bytes());
+    put(
+        &mut cfrg,
+        32 + 22,
+        &[pef::CFRG_USAGE_IMPORT_LIBRARY, pef::CFRG_DATA_FORK_LOCATOR],
+    );
+    put(&mut cfrg, 32 + 24, &0x10u32.to_be_bytes());
+    put(&mut cfrg, 32 + 40, &48u16.to_be_bytes());
+    put(&mut cfrg, 32 + 42, b"\x05MyLib");
+
+    // The resource fork: header, data, then the map with one type and one named resource.
+    let data_length = 4 + cfrg.len();
+    let map_length = 28 + 10 + 12 + 5;
+    let mut resource_fork = vec![0; 16 + data_length + map_length];
+    put(&mut resource_fork, 0, &16u32.to_be_bytes());
+    put(
+        &mut resource_fork,
+        4,
+        &(16 + data_length as u32).to_be_bytes(),
+    );
+    put(&mut resource_fork, 8, &(data_length as u32).to_be_bytes());
+    put(&mut resource_fork, 12, &(map_length as u32).to_be_bytes());
+    put(&mut resource_fork, 16, &(cfrg.len() as u32).to_be_bytes());
+    put(&mut resource_fork, 20, &cfrg);
+    let map = 16 + data_length;
+    put(&mut resource_fork, map + 24, &[0, 28, 0, 50]);
+    put(&mut resource_fork, map + 28, &[0, 0]);
+    put(&mut resource_fork, map + 30, b"cfrg\0\0\0\x0a");
+    put(&mut resource_fork, map + 38, &[0, 0, 0, 0]);
+    put(&mut resource_fork, map + 50, b"\x04cfrg");
+
+    let check = |file: &MacFile| {
+        let resources = file.resources().unwrap().unwrap();
+        let all: Vec<_> = resources.resources().map(Result::unwrap).collect();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].res_type(), pef::RESOURCE_TYPE_CFRG);
+        assert_eq!(all[0].id(), 0);
+        assert_eq!(all[0].name(), Ok(Some(&b"cfrg"[..])));
+        assert_eq!(all[0].data(), Ok(&cfrg[..]));
+        assert!(resources
+            .resource(pef::RESOURCE_TYPE_CFRG, 1)
+            .unwrap()
+            .is_none());
+
+        let fragments = file.fragments().unwrap();
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(fragments[0].name(), Some(&b"MyLib"[..]));
+        assert_eq!(fragments[0].data(), &data_fork[0x10..]);
+        let member = fragments[0].member().unwrap();
+        assert_eq!(member.architecture(), pef::ARCHITECTURE_PPC);
+        assert_eq!(member.current_version(), 0x0200_0000);
+        assert_eq!(member.old_def_version(), 0x0100_0000);
+        let pef = PefFile::parse(fragments[0].data()).unwrap();
+        assert_eq!(
+            pef.pef_header().current_version.get(object::BigEndian),
+            0x0200_0000
+        );
+    };
+
+    check(&MacFile::from_forks(&data_fork, &resource_fork));
+
+    // MacBinary III: each fork is padded to 128 bytes.
+    let mut macbinary = vec![0; 128 + 128 + resource_fork.len()];
+    put(&mut macbinary, 1, b"\x05MyLib");
+    put(&mut macbinary, 65, b"shlbcfmg");
+    put(&mut macbinary, 83, &(data_fork.len() as u32).to_be_bytes());
+    put(
+        &mut macbinary,
+        87,
+        &(resource_fork.len() as u32).to_be_bytes(),
+    );
+    put(&mut macbinary, 102, b"mBIN");
+    put(&mut macbinary, 128, &data_fork);
+    put(&mut macbinary, 256, &resource_fork);
+    let file = MacFile::parse(&macbinary).unwrap();
+    assert_eq!(file.kind(), MacFileKind::MacBinary);
+    assert_eq!(file.name(), Some(&b"MyLib"[..]));
+    assert_eq!(file.file_type(), Some(0x7368_6c62));
+    assert_eq!(file.creator(), Some(0x6366_6d67));
+    check(&file);
+
+    // AppleSingle with the forks and the Finder info.
+    let mut apple_single = vec![0; 26 + 3 * 12];
+    put(&mut apple_single, 0, &pef::APPLE_SINGLE_MAGIC.to_be_bytes());
+    put(&mut apple_single, 4, &0x0002_0000u32.to_be_bytes());
+    put(&mut apple_single, 24, &3u16.to_be_bytes());
+    for (i, (id, entry)) in [
+        (9u32, &b"shlbcfmg"[..]),
+        (1, &data_fork),
+        (2, &resource_fork),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let offset = apple_single.len() as u32;
+        put(&mut apple_single, 26 + i * 12, &id.to_be_bytes());
+        put(&mut apple_single, 30 + i * 12, &offset.to_be_bytes());
+        put(
+            &mut apple_single,
+            34 + i * 12,
+            &(entry.len() as u32).to_be_bytes(),
+        );
+        apple_single.extend_from_slice(entry);
+    }
+    let file = MacFile::parse(&apple_single).unwrap();
+    assert_eq!(file.kind(), MacFileKind::AppleSingle);
+    assert_eq!(file.file_type(), Some(0x7368_6c62));
+    check(&file);
+
+    // A raw PEF container is a fragment without a 'cfrg' member.
+    let file = MacFile::parse(&data_fork[0x10..]).unwrap();
+    assert_eq!(file.kind(), MacFileKind::Raw);
+    let fragments = file.fragments().unwrap();
+    assert_eq!(fragments.len(), 1);
+    assert!(fragments[0].member().is_none());
+}
+
+#[cfg(feature = "pef")]
+#[test]
+fn pef_m68k_jump_table() {
+    use object::read::pef::{JumpTableEntry, JumpTableIterator};
+
+    let data = [
+        0x00, 0x10, 0x3f, 0x3c, 0x00, 0x02, 0xa9, 0xf0, // unloaded
+        0x00, 0x03, 0x4e, 0xf9, 0x00, 0x12, 0x34, 0x56, // loaded
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // end
+    ];
+    let entries: Vec<_> = JumpTableIterator::new(&data).collect();
+    assert_eq!(
+        entries,
+        [
+            JumpTableEntry::Unloaded {
+                offset: 0x10,
+                segment: 2
+            },
+            JumpTableEntry::Loaded {
+                segment: 3,
+                address: 0x0012_3456
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "pef")]
+#[test]
+fn pef_application_resources() {
+    use object::pef;
+    use object::read::pef::{MacFile, VersionResource};
+
+    // Build a resource fork containing unnamed resources.
+    fn resource_fork(resources: &[(&[u8; 4], i16, &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut types = Vec::new();
+        let mut references = Vec::new();
+        let type_list_size = 2 + 8 * resources.len();
+        for (res_type, id, contents) in resources {
+            types.extend_from_slice(*res_type);
+            types.extend_from_slice(&[0, 0]);
+            types.extend_from_slice(&((type_list_size + references.len()) as u16).to_be_bytes());
+            references.extend_from_slice(&id.to_be_bytes());
+            references.extend_from_slice(&[0xff, 0xff]);
+            references.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            references.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&(contents.len() as u32).to_be_bytes());
+            data.extend_from_slice(contents);
+        }
+        let mut map = vec![0; 28];
+        map[24..26].copy_from_slice(&28u16.to_be_bytes());
+        let names = 28 + type_list_size + references.len();
+        map[26..28].copy_from_slice(&(names as u16).to_be_bytes());
+        map.extend_from_slice(&(resources.len() as u16 - 1).to_be_bytes());
+        map.extend_from_slice(&types);
+        map.extend_from_slice(&references);
+
+        let mut fork = Vec::new();
+        fork.extend_from_slice(&16u32.to_be_bytes());
+        fork.extend_from_slice(&(16 + data.len() as u32).to_be_bytes());
+        fork.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        fork.extend_from_slice(&(map.len() as u32).to_be_bytes());
+        fork.extend_from_slice(&data);
+        fork.extend_from_slice(&map);
+        fork
+    }
+
+    let vers = b"\x02\x13\x80\x00\x00\x00\x052.1.3\x172.1.3, (c) 1998 Example";
+    let beta = b"\x01\x00\x60\x04\x00\x00\x051.0b4\x00";
+    let size_default = [0x58, 0x80, 0, 0x40, 0, 0, 0, 0x20, 0, 0];
+    let size_user = [0x58, 0x80, 0, 0x60, 0, 0, 0, 0x20, 0, 0];
+    let fork = resource_fork(&[
+        (b"vers", 1, vers),
+        (b"vers", 2, beta),
+        (b"SIZE", -1, &size_default),
+    ]);
+    let file = MacFile::from_forks(&[], &fork);
+
+    let version = file.version(pef::VERS_FILE_ID).unwrap().unwrap();
+    assert_eq!(version.major(), 2);
+    assert_eq!(version.minor(), 1);
+    assert_eq!(version.bug_fix(), 3);
+    assert_eq!(version.stage(), pef::VERS_STAGE_FINAL);
+    assert_eq!(version.region(), 0);
+    assert_eq!(version.short_version(), b"2.1.3");
+    assert_eq!(version.long_version(), b"2.1.3, (c) 1998 Example");
+    assert_eq!(version.to_string(), "2.1.3");
+
+    let version = file.version(pef::VERS_PRODUCT_ID).unwrap().unwrap();
+    assert_eq!(version.to_string(), "1.0b4");
+    assert!(file.version(3).unwrap().is_none());
+    assert!(VersionResource::parse(b"\x01\x00\x80\x00\x00\x00\x05").is_err());
+
+    let size = file.size_resource().unwrap().unwrap();
+    assert_eq!(size.flags(), 0x5880);
+    assert_ne!(size.flags() & pef::SIZE_IS_32_BIT_COMPATIBLE, 0);
+    assert_eq!(size.preferred_size(), 0x40_0000);
+    assert_eq!(size.minimum_size(), 0x20_0000);
+
+    // The Finder's copy takes precedence.
+    let fork = resource_fork(&[(b"SIZE", -1, &size_default), (b"SIZE", 0, &size_user)]);
+    let file = MacFile::from_forks(&[], &fork);
+    assert_eq!(
+        file.size_resource().unwrap().unwrap().preferred_size(),
+        0x60_0000
+    );
+    assert!(file.version(pef::VERS_FILE_ID).unwrap().is_none());
+}
@@ -1,4 +1,4 @@
-use object::read::elf::{FileHeader, SectionHeader};
+use object::read::elf::{ElfFile, ElfKernelModule, FileHeader, SectionHeader};
 use object::read::{Object, ObjectSection, ObjectSymbol};
 use object::{
     elf, read, write, Architecture, BinaryFormat, Endianness, LittleEndian, SectionIndex,
@@ -300,3 +300,1549 @@ fn gnu_property_inner<Elf: FileHeader<Endian = Endianness>>(architecture: Archit
     assert!(props.next().unwrap().is_none());
     assert!(notes.next().unwrap().is_none());
 }
+
+/// Write a core file containing a single `PT_NOTE` segment.
+fn core_file(is_64: bool, e_machine: u16, notes: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let endian = Endianness::Little;
+    let mut segment = Vec::new();
+    for (n_type, desc) in notes {
+        segment
+            .write_all(object::bytes_of(&elf::NoteHeader32 {
+                n_namesz: U32::new(endian, 5),
+                n_descsz: U32::new(endian, desc.len() as u32),
+                n_type: U32::new(endian, *n_type),
+            }))
+            .unwrap();
+        segment.write_all(b"CORE\0\0\0\0").unwrap();
+        segment.write_all(desc).unwrap();
+        segment.resize((segment.len() + 3) & !3, 0);
+    }
+
+    let mut buffer = Vec::new();
+    let mut writer = write::elf::Writer::new(endian, is_64, &mut buffer);
+    writer.reserve_file_header();
+    writer.reserve_program_headers(1);
+    let offset = writer.reserve(segment.len(), 4);
+    writer
+        .write_file_header(&write::elf::FileHeader {
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_type: elf::ET_CORE,
+            e_machine,
+            e_entry: 0,
+            e_flags: 0,
+        })
+        .unwrap();
+    writer.write_align_program_headers();
+    writer.write_program_header(&write::elf::ProgramHeader {
+        p_type: elf::PT_NOTE,
+        p_flags: 0,
+        p_offset: offset as u64,
+        p_vaddr: 0,
+        p_paddr: 0,
+        p_filesz: segment.len() as u64,
+        p_memsz: 0,
+        p_align: 4,
+    });
+    writer.write_align(4);
+    writer.write(&segment);
+    buffer
+}
+
+fn word(is_64: bool, value: u64) -> Vec<u8> {
+    if is_64 {
+        value.to_le_bytes().to_vec()
+    } else {
+        (value as u32).to_le_bytes().to_vec()
+    }
+}
+
+#[test]
+fn core() {
+    core_inner::<elf::FileHeader32<Endianness>>(elf::EM_386, 17, 12, 15);
+    core_inner::<elf::FileHeader64<Endianness>>(elf::EM_X86_64, 27, 16, 19);
+}
+
+fn core_inner<Elf: FileHeader<Endian = Endianness>>(
+    e_machine: u16,
+    register_count: usize,
+    pc: usize,
+    sp: usize,
+) {
+    let is_64 = Elf::is_type_64_sized();
+    let word_size = if is_64 { 8 } else { 4 };
+
+    let prstatus = |pid: u32, signal: u32| {
+        let mut desc = Vec::new();
+        desc.extend_from_slice(&signal.to_le_bytes());
+        desc.extend_from_slice(&1u32.to_le_bytes());
+        desc.extend_from_slice(&0u32.to_le_bytes());
+        desc.extend_from_slice(&(signal as u16).to_le_bytes());
+        desc.extend_from_slice(&[0; 2]);
+        desc.extend(word(is_64, 0x100));
+        desc.extend(word(is_64, 0x200));
+        for id in [pid, 1, pid, pid] {
+            desc.extend_from_slice(&id.to_le_bytes());
+        }
+        // utime, stime, cutime, cstime
+        for value in [2, 500, 1, 250, 0, 0, 0, 0] {
+            desc.extend(word(is_64, value));
+        }
+        for i in 0..register_count {
+            desc.extend(word(is_64, 0x1000 + i as u64));
+        }
+        // pr_fpvalid
+        desc.extend(word(is_64, 1));
+        desc
+    };
+
+    let mut prpsinfo = vec![b'R' - b'A', b'R', 0, 0xfb];
+    if is_64 {
+        prpsinfo.extend_from_slice(&[0; 4]);
+        prpsinfo.extend(word(is_64, 0x40_0000));
+        prpsinfo.extend_from_slice(&1000u32.to_le_bytes());
+        prpsinfo.extend_from_slice(&100u32.to_le_bytes());
+    } else {
+        prpsinfo.extend(word(is_64, 0x40_0000));
+        prpsinfo.extend_from_slice(&1000u16.to_le_bytes());
+        prpsinfo.extend_from_slice(&100u16.to_le_bytes());
+    }
+    for id in [42u32, 1, 42, 42] {
+        prpsinfo.extend_from_slice(&id.to_le_bytes());
+    }
+    let mut fname = [0; 16];
+    fname[..5].copy_from_slice(b"crash");
+    prpsinfo.extend_from_slice(&fname);
+    let mut psargs = [0; 80];
+    psargs[..15].copy_from_slice(b"./crash --now 1");
+    prpsinfo.extend_from_slice(&psargs);
+
+    let mut auxv = Vec::new();
+    for value in [
+        elf::AT_PAGESZ,
+        0x1000,
+        elf::AT_ENTRY,
+        0x40_1000,
+        elf::AT_NULL,
+        0,
+    ] {
+        auxv.extend(word(is_64, value));
+    }
+
+    let mut file = Vec::new();
+    for value in [
+        2,
+        0x1000,
+        0x40_0000,
+        0x40_1000,
+        0,
+        0x7f00_0000,
+        0x7f00_3000,
+        2,
+    ] {
+        file.extend(word(is_64, value));
+    }
+    file.extend_from_slice(b"/usr/bin/crash\0/usr/lib/libc.so.6\0");
+
+    let bytes = core_file(
+        is_64,
+        e_machine,
+        &[
+            (elf::NT_PRSTATUS, prstatus(42, 11)),
+            (elf::NT_PRPSINFO, prpsinfo),
+            (elf::NT_AUXV, auxv),
+            (elf::NT_FILE, file),
+            (elf::NT_PRSTATUS, prstatus(43, 0)),
+        ],
+    );
+
+    let file = read::elf::ElfFile::<Elf>::parse(&*bytes).unwrap();
+    let core = read::elf::ElfCore::parse(&file).unwrap();
+    assert_eq!(core.notes().len(), 5);
+
+    let threads = core.threads().unwrap();
+    assert_eq!(threads.len(), 2);
+    let thread = &threads[0];
+    assert_eq!(thread.signal(), 11);
+    assert_eq!(thread.signal_code(), 1);
+    assert_eq!(thread.current_signal(), 11);
+    assert_eq!(thread.pending_signals(), 0x100);
+    assert_eq!(thread.held_signals(), 0x200);
+    assert_eq!(thread.pid(), 42);
+    assert_eq!(thread.ppid(), 1);
+    assert_eq!(
+        thread.user_time(),
+        std::time::Duration::from_micros(2_000_500)
+    );
+    assert_eq!(
+        thread.system_time(),
+        std::time::Duration::from_micros(1_000_250)
+    );
+    assert_eq!(thread.register_data().len(), register_count * word_size);
+    assert_eq!(thread.register_count(), register_count);
+    assert_eq!(thread.register(0), Some(0x1000));
+    assert_eq!(thread.register(register_count), None);
+    assert_eq!(thread.pc(), Some(0x1000 + pc as u64));
+    assert_eq!(thread.sp(), Some(0x1000 + sp as u64));
+    let pc_name = if is_64 { "rip" } else { "eip" };
+    assert_eq!(thread.register_name(pc), Some(pc_name));
+    assert_eq!(threads[1].pid(), 43);
+    assert_eq!(threads[1].signal(), 0);
+
+    let info = core.process_info().unwrap().unwrap();
+    assert_eq!(info.state_name(), b'R');
+    assert_eq!(info.nice(), -5);
+    assert_eq!(info.flags(), 0x40_0000);
+    assert_eq!(info.uid(), 1000);
+    assert_eq!(info.gid(), 100);
+    assert_eq!(info.pid(), 42);
+    assert_eq!(info.file_name(), b"crash");
+    assert_eq!(info.arguments(), b"./crash --now 1");
+
+    let auxv = core
+        .auxv()
+        .unwrap()
+        .map(|entry| entry.map(|entry| (entry.a_type(), entry.a_val())))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(auxv, [(elf::AT_PAGESZ, 0x1000), (elf::AT_ENTRY, 0x40_1000)]);
+    assert_eq!(core.auxv_value(elf::AT_ENTRY).unwrap(), Some(0x40_1000));
+    assert_eq!(core.auxv_value(elf::AT_PHDR).unwrap(), None);
+
+    let files = core.mapped_files().unwrap();
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].start(), 0x40_0000);
+    assert_eq!(files[0].end(), 0x40_1000);
+    assert_eq!(files[0].file_offset(), 0);
+    assert_eq!(files[0].path(), b"/usr/bin/crash");
+    assert_eq!(files[1].start(), 0x7f00_0000);
+    assert_eq!(files[1].file_offset(), 0x2000);
+    assert_eq!(files[1].path(), b"/usr/lib/libc.so.6");
+}
+
+#[test]
+fn no_section_headers() {
+    no_section_headers_inner(false);
+    no_section_headers_inner(true);
+}
+
+fn no_section_headers_inner(gnu_hash: bool) {
+    let mut buffer = Vec::new();
+    let mut writer = write::elf::Writer::new(Endianness::Little, true, &mut buffer);
+    writer.reserve_file_header();
+    writer.reserve_program_headers(2);
+
+    let libc = writer.add_dynamic_string(b"libc.so.6");
+    let glibc = writer.add_dynamic_string(b"GLIBC_2.2.5");
+    let puts = writer.add_dynamic_string(b"puts");
+    let export = writer.add_dynamic_string(b"my_export");
+    writer.reserve_null_dynamic_symbol_index();
+    writer.reserve_dynamic_symbol_index();
+    writer.reserve_dynamic_symbol_index();
+    let dynsym_offset = writer.reserve_dynsym();
+    let dynstr_offset = writer.reserve_dynstr();
+    let hash_offset = if gnu_hash {
+        writer.reserve_gnu_hash(1, 1, 1)
+    } else {
+        writer.reserve_hash(1, 3)
+    };
+    let versym_offset = writer.reserve_gnu_versym();
+    let verneed_offset = writer.reserve_gnu_verneed(1, 1);
+    let rela_offset = writer.reserve_relocations(1, true);
+    let plt_offset = writer.reserve_relocations(1, true);
+    let dynamic_offset = writer.reserve_dynamic(15);
+    let file_size = writer.reserved_len() as u64;
+
+    writer
+        .write_file_header(&write::elf::FileHeader {
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_type: elf::ET_DYN,
+            e_machine: elf::EM_X86_64,
+            e_entry: 0,
+            e_flags: 0,
+        })
+        .unwrap();
+    writer.write_align_program_headers();
+    writer.write_program_header(&write::elf::ProgramHeader {
+        p_type: elf::PT_LOAD,
+        p_flags: elf::PF_R,
+        p_offset: 0,
+        p_vaddr: 0,
+        p_paddr: 0,
+        p_filesz: file_size,
+        p_memsz: file_size,
+        p_align: 0x1000,
+    });
+    writer.write_program_header(&write::elf::ProgramHeader {
+        p_type: elf::PT_DYNAMIC,
+        p_flags: elf::PF_R,
+        p_offset: dynamic_offset as u64,
+        p_vaddr: dynamic_offset as u64,
+        p_paddr: 0,
+        p_filesz: file_size - dynamic_offset as u64,
+        p_memsz: file_size - dynamic_offset as u64,
+        p_align: 8,
+    });
+
+    writer.write_null_dynamic_symbol();
+    writer.write_dynamic_symbol(&write::elf::Sym {
+        name: Some(puts),
+        section: None,
+        st_info: (elf::STB_GLOBAL << 4) | elf::STT_FUNC,
+        st_other: 0,
+        st_shndx: elf::SHN_UNDEF,
+        st_value: 0,
+        st_size: 0,
+    });
+    writer.write_dynamic_symbol(&write::elf::Sym {
+        name: Some(export),
+        section: None,
+        st_info: (elf::STB_GLOBAL << 4) | elf::STT_FUNC,
+        st_other: 0,
+        st_shndx: 7,
+        st_value: 0x1234,
+        st_size: 0x10,
+    });
+    writer.write_dynstr();
+    if gnu_hash {
+        writer.write_gnu_hash(2, 6, 1, 1, 1, |_| elf::gnu_hash(b"my_export"));
+    } else {
+        writer.write_hash(1, 3, |i| match i {
+            1 => Some(elf::hash(b"puts")),
+            2 => Some(elf::hash(b"my_export")),
+            _ => None,
+        });
+    }
+    writer.write_null_gnu_versym();
+    writer.write_gnu_versym(2);
+    writer.write_gnu_versym(1);
+    writer.write_align_gnu_verneed();
+    writer.write_gnu_verneed(&write::elf::Verneed {
+        version: elf::VER_NEED_CURRENT,
+        aux_count: 1,
+        file: libc,
+    });
+    writer.write_gnu_vernaux(&write::elf::Vernaux {
+        flags: 0,
+        index: 2,
+        name: glibc,
+    });
+    writer.write_align_relocation();
+    writer.write_relocation(
+        true,
+        &write::elf::Rel {
+            r_offset: 0x3000,
+            r_sym: 0,
+            r_type: elf::R_X86_64_RELATIVE,
+            r_addend: 0x1234,
+        },
+    );
+    writer.write_align_relocation();
+    writer.write_relocation(
+        true,
+        &write::elf::Rel {
+            r_offset: 0x3008,
+            r_sym: 1,
+            r_type: elf::R_X86_64_JUMP_SLOT,
+            r_addend: 0,
+        },
+    );
+    writer.write_align_dynamic();
+    writer.write_dynamic(elf::DT_SYMTAB, dynsym_offset as u64);
+    writer.write_dynamic(elf::DT_STRTAB, dynstr_offset as u64);
+    writer.write_dynamic(elf::DT_STRSZ, (hash_offset - dynstr_offset) as u64);
+    if gnu_hash {
+        writer.write_dynamic(elf::DT_GNU_HASH, hash_offset as u64);
+    } else {
+        writer.write_dynamic(elf::DT_HASH, hash_offset as u64);
+    }
+    writer.write_dynamic(elf::DT_VERSYM, versym_offset as u64);
+    writer.write_dynamic(elf::DT_VERNEED, verneed_offset as u64);
+    writer.write_dynamic(elf::DT_VERNEEDNUM, 1);
+    writer.write_dynamic(elf::DT_RELA, rela_offset as u64);
+    writer.write_dynamic(elf::DT_RELASZ, 24);
+    writer.write_dynamic(elf::DT_RELAENT, 24);
+    writer.write_dynamic(elf::DT_JMPREL, plt_offset as u64);
+    writer.write_dynamic(elf::DT_PLTRELSZ, 24);
+    writer.write_dynamic(elf::DT_PLTREL, elf::DT_RELA.into());
+    writer.write_dynamic_string(elf::DT_NEEDED, libc);
+    writer.write_dynamic(elf::DT_NULL, 0);
+    assert_eq!(writer.reserved_len(), buffer.len());
+
+    let file = read::elf::ElfFile64::<Endianness>::parse(&*buffer).unwrap();
+    assert_eq!(file.sections().count(), 0);
+    let dynamic = file.elf_dynamic_table();
+    assert_eq!(dynamic.hash().is_some(), !gnu_hash);
+    assert_eq!(dynamic.gnu_hash().is_some(), gnu_hash);
+
+    let names = file
+        .dynamic_symbols()
+        .map(|symbol| symbol.name().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["puts", "my_export"]);
+    assert_eq!(file.symbols().count(), 2);
+    let symbol = file.symbol_by_index(object::SymbolIndex(2)).unwrap();
+    assert_eq!(symbol.name(), Ok("my_export"));
+    assert_eq!(symbol.address(), 0x1234);
+
+    let imports = file.imports().unwrap();
+    assert_eq!(imports.len(), 1);
+    assert_eq!(imports[0].name(), b"puts");
+    assert_eq!(imports[0].library(), b"libc.so.6");
+    let exports = file.exports().unwrap();
+    assert_eq!(exports.len(), 1);
+    assert_eq!(exports[0].name(), b"my_export");
+    assert_eq!(exports[0].address(), 0x1234);
+
+    let relocations = file.dynamic_relocations().unwrap().collect::<Vec<_>>();
+    assert_eq!(relocations.len(), 2);
+    assert_eq!(relocations[0].0, 0x3000);
+    assert_eq!(relocations[0].1.addend(), 0x1234);
+    assert_eq!(relocations[1].0, 0x3008);
+    assert_eq!(
+        relocations[1].1.target(),
+        read::RelocationTarget::Symbol(object::SymbolIndex(1))
+    );
+}
+
+fn sleb128(data: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            data.push(byte);
+            return;
+        }
+        data.push(byte | 0x80);
+    }
+}
+
+#[test]
+fn android_relocations() {
+    let mut packed = b"APS2".to_vec();
+    for value in [5, 0x1000] {
+        sleb128(&mut packed, value);
+    }
+    // Three relative relocations with a common offset delta and type.
+    let flags = elf::RELOCATION_GROUPED_BY_INFO_FLAG
+        | elf::RELOCATION_GROUPED_BY_OFFSET_DELTA_FLAG
+        | elf::RELOCATION_GROUP_HAS_ADDEND_FLAG;
+    for value in [3, flags as i64, 8, elf::R_X86_64_RELATIVE.into()] {
+        sleb128(&mut packed, value);
+    }
+    for addend in [0x100, 0x10, -0x20] {
+        sleb128(&mut packed, addend);
+    }
+    // Two symbol relocations without addends.
+    for value in [
+        2,
+        0,
+        0x100,
+        (1 << 32) | i64::from(elf::R_X86_64_GLOB_DAT),
+        8,
+        (2 << 32) | i64::from(elf::R_X86_64_JUMP_SLOT),
+    ] {
+        sleb128(&mut packed, value);
+    }
+
+    let mut buffer = Vec::new();
+    let mut writer = write::elf::Writer::new(Endianness::Little, true, &mut buffer);
+    writer.reserve_file_header();
+    let name = writer.add_section_name(b".rela.dyn");
+    writer.reserve_null_section_index();
+    writer.reserve_section_index();
+    writer.reserve_shstrtab_section_index();
+    let offset = writer.reserve(packed.len(), 1);
+    writer.reserve_shstrtab();
+    writer.reserve_section_headers();
+    writer
+        .write_file_header(&write::elf::FileHeader {
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_type: elf::ET_DYN,
+            e_machine: elf::EM_X86_64,
+            e_entry: 0,
+            e_flags: 0,
+        })
+        .unwrap();
+    writer.write(&packed);
+    writer.write_shstrtab();
+    writer.write_null_section_header();
+    writer.write_section_header(&write::elf::SectionHeader {
+        name: Some(name),
+        sh_type: elf::SHT_ANDROID_RELA,
+        sh_flags: elf::SHF_ALLOC.into(),
+        sh_addr: 0,
+        sh_offset: offset as u64,
+        sh_size: packed.len() as u64,
+        sh_link: 0,
+        sh_info: 0,
+        sh_addralign: 1,
+        sh_entsize: 1,
+    });
+    writer.write_shstrtab_section_header();
+
+    let file = read::elf::ElfFile64::<Endianness>::parse(&*buffer).unwrap();
+    let relocations = file
+        .dynamic_relocations()
+        .unwrap()
+        .map(|(offset, relocation)| {
+            let symbol = match relocation.target() {
+                read::RelocationTarget::Symbol(symbol) => symbol.0,
+                _ => 0,
+            };
+            let r_type = match relocation.flags() {
+                object::RelocationFlags::Elf { r_type } => r_type,
+                _ => unreachable!(),
+            };
+            assert!(!relocation.has_implicit_addend());
+            (offset, r_type, symbol, relocation.addend())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        relocations,
+        [
+            (0x1008, elf::R_X86_64_RELATIVE, 0, 0x100),
+            (0x1010, elf::R_X86_64_RELATIVE, 0, 0x110),
+            (0x1018, elf::R_X86_64_RELATIVE, 0, 0xf0),
+            (0x1118, elf::R_X86_64_GLOB_DAT, 1, 0),
+            (0x1120, elf::R_X86_64_JUMP_SLOT, 2, 0),
+        ]
+    );
+
+    // Addends are not allowed for `SHT_ANDROID_REL`.
+    let mut iter =
+        read::elf::AndroidRelocationIterator::<elf::FileHeader64<Endianness>>::new(&packed, false)
+            .unwrap();
+    assert!(iter.next().is_err());
+    assert!(iter.next().unwrap().is_none());
+}
+
+#[test]
+fn crel() {
+    crel_inner(
+        Architecture::X86_64,
+        [elf::R_X86_64_PC32, elf::R_X86_64_PLT32, elf::R_X86_64_64],
+    );
+    crel_inner(
+        Architecture::I386,
+        [elf::R_386_PC32, elf::R_386_PLT32, elf::R_386_32],
+    );
+}
+
+fn crel_inner(architecture: Architecture, r_types: [u32; 3]) {
+    let mut object = write::Object::new(BinaryFormat::Elf, architecture, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 0x1000], 16);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 0x100], 8);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let var = object.add_symbol(write::Symbol {
+        name: b"var".to_vec(),
+        value: 0x10,
+        size: 8,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Section(data),
+        flags: SymbolFlags::None,
+    });
+    for (section, offset, symbol, r_type, addend) in [
+        (text, 0x11, func, r_types[1], -4),
+        (text, 0x19, var, r_types[0], -4),
+        (text, 0x21, var, r_types[0], 0x10),
+        (text, 0xf01, func, r_types[1], -4),
+        (data, 0x0, var, r_types[2], 0),
+        (data, 0x8, func, r_types[2], 0),
+        (data, 0x20, var, r_types[2], -0x10),
+    ] {
+        object
+            .add_relocation(
+                section,
+                write::Relocation {
+                    offset,
+                    symbol,
+                    addend,
+                    flags: object::RelocationFlags::Elf { r_type },
+                },
+            )
+            .unwrap();
+    }
+    let bytes = object.write().unwrap();
+
+    let mut builder = object::build::elf::Builder::read(&*bytes).unwrap();
+    assert!(!builder.use_crel);
+    builder.use_crel = true;
+    let mut crel_bytes = Vec::new();
+    builder.write(&mut crel_bytes).unwrap();
+
+    let object = read::File::parse(&*bytes).unwrap();
+    let crel_object = read::File::parse(&*crel_bytes).unwrap();
+    for name in [".rel.text", ".rela.text", ".rel.data", ".rela.data"] {
+        assert!(crel_object.section_by_name(name).is_none());
+    }
+    for name in [".text", ".data"] {
+        let crel_name = format!(".crel{}", name);
+        let crel_section = crel_object.section_by_name(&crel_name).unwrap();
+        assert_eq!(
+            crel_section.flags(),
+            object::SectionFlags::Elf {
+                sh_flags: elf::SHF_INFO_LINK.into()
+            }
+        );
+
+        let relocations = |file: &read::File<'_>| {
+            file.section_by_name(name)
+                .unwrap()
+                .relocations()
+                .map(|(offset, relocation)| {
+                    (
+                        offset,
+                        relocation.flags(),
+                        relocation.target(),
+                        relocation.addend(),
+                        relocation.has_implicit_addend(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected = relocations(&object);
+        assert!(!expected.is_empty());
+        assert_eq!(relocations(&crel_object), expected);
+    }
+
+    // Reading the compact relocations and writing them again is lossless.
+    let builder = object::build::elf::Builder::read(&*crel_bytes).unwrap();
+    assert!(builder.use_crel);
+    let mut crel_bytes2 = Vec::new();
+    builder.write(&mut crel_bytes2).unwrap();
+    assert_eq!(crel_bytes, crel_bytes2);
+}
+
+#[test]
+fn symbol_versions() {
+    let mut buffer = Vec::new();
+    let mut writer = write::elf::Writer::new(Endianness::Little, true, &mut buffer);
+    writer.reserve_file_header();
+
+    let soname = writer.add_dynamic_string(b"libtest.so");
+    let libc = writer.add_dynamic_string(b"libc.so.6");
+    let glibc = writer.add_dynamic_string(b"GLIBC_2.2.5");
+    let vers_1 = writer.add_dynamic_string(b"VERS_1");
+    let vers_2 = writer.add_dynamic_string(b"VERS_2");
+    let puts = writer.add_dynamic_string(b"puts");
+    let foo = writer.add_dynamic_string(b"foo");
+    let bar = writer.add_dynamic_string(b"bar");
+
+    writer.reserve_null_section_index();
+    writer.reserve_dynsym_section_index();
+    writer.reserve_dynstr_section_index();
+    writer.reserve_gnu_versym_section_index();
+    writer.reserve_gnu_verdef_section_index();
+    writer.reserve_gnu_verneed_section_index();
+    writer.reserve_shstrtab_section_index();
+
+    writer.reserve_null_dynamic_symbol_index();
+    for _ in 0..4 {
+        writer.reserve_dynamic_symbol_index();
+    }
+    writer.reserve_dynsym();
+    writer.reserve_dynstr();
+    writer.reserve_gnu_versym();
+    writer.reserve_gnu_verdef(3, 3);
+    writer.reserve_gnu_verneed(1, 1);
+    writer.reserve_shstrtab();
+    writer.reserve_section_headers();
+
+    writer
+        .write_file_header(&write::elf::FileHeader {
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_type: elf::ET_DYN,
+            e_machine: elf::EM_X86_64,
+            e_entry: 0,
+            e_flags: 0,
+        })
+        .unwrap();
+
+    writer.write_null_dynamic_symbol();
+    for (name, st_shndx, st_value) in [
+        (puts, 0, 0),
+        (foo, 1, 0x1000),
+        (foo, 1, 0x2000),
+        (bar, 1, 0x3000),
+    ] {
+        writer.write_dynamic_symbol(&write::elf::Sym {
+            name: Some(name),
+            section: None,
+            st_info: (elf::STB_GLOBAL << 4) | elf::STT_FUNC,
+            st_other: 0,
+            st_shndx,
+            st_value,
+            st_size: 0,
+        });
+    }
+    writer.write_dynstr();
+
+    writer.write_null_gnu_versym();
+    writer.write_gnu_versym(2);
+    writer.write_gnu_versym(3 | elf::VERSYM_HIDDEN);
+    writer.write_gnu_versym(4);
+    writer.write_gnu_versym(elf::VER_NDX_GLOBAL);
+
+    writer.write_align_gnu_verdef();
+    writer.write_gnu_verdef(&write::elf::Verdef {
+        version: elf::VER_DEF_CURRENT,
+        flags: elf::VER_FLG_BASE,
+        index: 1,
+        aux_count: 1,
+        name: soname,
+    });
+    writer.write_gnu_verdef(&write::elf::Verdef {
+        version: elf::VER_DEF_CURRENT,
+        flags: 0,
+        index: 3,
+        aux_count: 1,
+        name: vers_1,
+    });
+    writer.write_gnu_verdef(&write::elf::Verdef {
+        version: elf::VER_DEF_CURRENT,
+        flags: 0,
+        index: 4,
+        aux_count: 1,
+        name: vers_2,
+    });
+
+    writer.write_align_gnu_verneed();
+    writer.write_gnu_verneed(&write::elf::Verneed {
+        version: elf::VER_NEED_CURRENT,
+        aux_count: 1,
+        file: libc,
+    });
+    writer.write_gnu_vernaux(&write::elf::Vernaux {
+        flags: 0,
+        index: 2,
+        name: glibc,
+    });
+    writer.write_shstrtab();
+
+    writer.write_null_section_header();
+    writer.write_dynsym_section_header(0, 1);
+    writer.write_dynstr_section_header(0);
+    writer.write_gnu_versym_section_header(0);
+    writer.write_gnu_verdef_section_header(0);
+    writer.write_gnu_verneed_section_header(0);
+    writer.write_shstrtab_section_header();
+    assert_eq!(writer.reserved_len(), buffer.len());
+
+    let file = read::File::parse(&*buffer).unwrap();
+    let versions = file
+        .dynamic_symbols()
+        .map(|symbol| {
+            let version = symbol.version().unwrap().map(|version| {
+                (
+                    String::from_utf8(version.name().to_vec()).unwrap(),
+                    version.is_hidden(),
+                    version.is_default(),
+                )
+            });
+            (symbol.name().unwrap(), version)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        versions,
+        [
+            ("puts", Some(("GLIBC_2.2.5".into(), false, false))),
+            ("foo", Some(("VERS_1".into(), true, false))),
+            ("foo", Some(("VERS_2".into(), false, true))),
+            ("bar", None),
+        ]
+    );
+
+    // The static symbol table is not versioned.
+    assert!(file.symbols().next().is_none());
+
+    let imports = file.imports().unwrap();
+    assert_eq!(imports.len(), 1);
+    assert_eq!(imports[0].library(), b"libc.so.6");
+    let version = imports[0].version().unwrap();
+    assert_eq!(version.name(), b"GLIBC_2.2.5");
+    assert!(!version.is_default());
+
+    let exports = file
+        .exports()
+        .unwrap()
+        .iter()
+        .map(|export| {
+            (
+                export.address(),
+                export.version().map(|version| version.name()),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        [
+            (0x1000, Some(&b"VERS_1"[..])),
+            (0x2000, Some(&b"VERS_2"[..])),
+            (0x3000, None),
+        ]
+    );
+
+    // Invalid version sections are an error for imports, but not for symbols.
+    let verneed = file.section_by_name(".gnu.version_r").unwrap();
+    let (offset, _) = verneed.file_range().unwrap();
+    // vn_file
+    let offset = offset as usize + 4;
+    buffer[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let file = read::File::parse(&*buffer).unwrap();
+    assert!(file.imports().is_err());
+    let symbol = file.dynamic_symbols().next().unwrap();
+    assert_eq!(symbol.name(), Ok("puts"));
+    assert_eq!(symbol.version(), Ok(None));
+}
+
+#[test]
+fn ppc64_function_descriptors() {
+    // ELFv1 function symbols refer to descriptors, ELFv2 function symbols refer to code.
+    ppc64_function_descriptors_inner(1, 0x10040);
+    ppc64_function_descriptors_inner(2, 0x20018);
+}
+
+fn ppc64_function_descriptors_inner(abi: u32, entry: u64) {
+    let mut buffer = Vec::new();
+    let mut writer = write::elf::Writer::new(Endianness::Big, true, &mut buffer);
+    writer.reserve_file_header();
+
+    let text_name = writer.add_section_name(b".text");
+    let opd_name = writer.add_section_name(b".opd");
+    let func = writer.add_string(b"func");
+    let var = writer.add_string(b"var");
+
+    writer.reserve_null_section_index();
+    writer.reserve_section_index();
+    let opd_index = writer.reserve_section_index();
+    writer.reserve_symtab_section_index();
+    writer.reserve_strtab_section_index();
+    writer.reserve_shstrtab_section_index();
+
+    writer.reserve_null_symbol_index();
+    writer.reserve_symbol_index(Some(opd_index));
+    writer.reserve_symbol_index(Some(opd_index));
+
+    let text_offset = writer.reserve(0x100, 16);
+    let opd_offset = writer.reserve(0x30, 8);
+    writer.reserve_symtab();
+    writer.reserve_strtab();
+    writer.reserve_shstrtab();
+    writer.reserve_section_headers();
+
+    writer
+        .write_file_header(&write::elf::FileHeader {
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_type: elf::ET_DYN,
+            e_machine: elf::EM_PPC64,
+            e_entry: 0,
+            e_flags: abi,
+        })
+        .unwrap();
+
+    writer.pad_until(text_offset);
+    writer.write(&[0; 0x100]);
+    writer.pad_until(opd_offset);
+    for code in [0x10000u64, 0x10040] {
+        writer.write(&code.to_be_bytes());
+        writer.write(&[0; 16]);
+    }
+
+    writer.write_null_symbol();
+    for (name, st_type, st_value) in [
+        (func, elf::STT_FUNC, 0x20018),
+        (var, elf::STT_OBJECT, 0x20000),
+    ] {
+        writer.write_symbol(&write::elf::Sym {
+            name: Some(name),
+            section: Some(opd_index),
+            st_info: (elf::STB_GLOBAL << 4) | st_type,
+            st_other: 0,
+            st_shndx: 0,
+            st_value,
+            st_size: 0x18,
+        });
+    }
+    writer.write_strtab();
+    writer.write_shstrtab();
+
+    writer.write_null_section_header();
+    writer.write_section_header(&write::elf::SectionHeader {
+        name: Some(text_name),
+        sh_type: elf::SHT_PROGBITS,
+        sh_flags: (elf::SHF_ALLOC | elf::SHF_EXECINSTR).into(),
+        sh_addr: 0x10000,
+        sh_offset: text_offset as u64,
+        sh_size: 0x100,
+        sh_link: 0,
+        sh_info: 0,
+        sh_addralign: 16,
+        sh_entsize: 0,
+    });
+    writer.write_section_header(&write::elf::SectionHeader {
+        name: Some(opd_name),
+        sh_type: elf::SHT_PROGBITS,
+        sh_flags: (elf::SHF_ALLOC | elf::SHF_WRITE).into(),
+        sh_addr: 0x20000,
+        sh_offset: opd_offset as u64,
+        sh_size: 0x30,
+        sh_link: 0,
+        sh_info: 0,
+        sh_addralign: 8,
+        sh_entsize: 0,
+    });
+    writer.write_symtab_section_header(1);
+    writer.write_strtab_section_header();
+    writer.write_shstrtab_section_header();
+    assert_eq!(writer.reserved_len(), buffer.len());
+
+    let file = read::elf::ElfFile64::<Endianness>::parse(&*buffer).unwrap();
+    let func = file.symbol_by_name("func").unwrap();
+    assert_eq!(func.address(), 0x20018);
+    assert_eq!(func.entry_address(), entry);
+    // Only function symbols refer to descriptors.
+    let var = file.symbol_by_name("var").unwrap();
+    assert_eq!(var.address(), 0x20000);
+    assert_eq!(var.entry_address(), 0x20000);
+
+    let map = file.symbol_map();
+    assert_eq!(map.get(entry + 4).unwrap().name(), "func");
+}
+
+fn prel31(place: u64, target: u64) -> u32 {
+    (target.wrapping_sub(place) as u32) & 0x7fff_ffff
+}
+
+#[test]
+fn arm_exidx() {
+    use object::read::elf::{ArmEhabiTarget, ArmExidxUnwind, ArmUnwindOpcode};
+
+    let mut buffer = Vec::new();
+    let mut writer = write::elf::Writer::new(Endianness::Little, false, &mut buffer);
+    writer.reserve_file_header();
+
+    let text_name = writer.add_section_name(b".text");
+    let exidx_name = writer.add_section_name(b".ARM.exidx");
+    let extab_name = writer.add_section_name(b".ARM.extab");
+
+    writer.reserve_null_section_index();
+    let text_index = writer.reserve_section_index();
+    writer.reserve_section_index();
+    writer.reserve_section_index();
+    writer.reserve_shstrtab_section_index();
+
+    let text_offset = writer.reserve(0x200, 4);
+    let exidx_offset = writer.reserve(0x20, 4);
+    let extab_offset = writer.reserve(0x14, 4);
+    writer.reserve_shstrtab();
+    writer.reserve_section_headers();
+
+    writer
+        .write_file_header(&write::elf::FileHeader {
+            os_abi: elf::ELFOSABI_NONE,
+            abi_version: 0,
+            e_type: elf::ET_EXEC,
+            e_machine: elf::EM_ARM,
+            e_entry: 0,
+            e_flags: elf::EF_ARM_EABI_VER5,
+        })
+        .unwrap();
+
+    let (text_address, exidx_address, extab_address) = (0x8000, 0x9000, 0x9100);
+    writer.pad_until(text_offset);
+    writer.write(&[0; 0x200]);
+    writer.pad_until(exidx_offset);
+    for (i, &(function, unwind)) in [
+        (0x8000, elf::EXIDX_CANTUNWIND),
+        // Inline compact model: pop {r4, r14}; finish.
+        (0x8010, 0x80a8_b0b0),
+        (0x8040, prel31(exidx_address + 0x14, extab_address)),
+        (0x8080, prel31(exidx_address + 0x1c, extab_address + 0xc)),
+    ]
+    .iter()
+    .enumerate()
+    {
+        let place = exidx_address + i as u64 * 8;
+        writer.write(&prel31(place, function).to_le_bytes());
+        writer.write(&unwind.to_le_bytes());
+    }
+    writer.pad_until(extab_offset);
+    for word in [
+        // Compact model with one extra word of opcodes.
+        0x8101_c908u32,
+        0xb281_01b0,
+        0,
+        // Generic model.
+        prel31(extab_address + 0xc, 0x8100),
+        0x1234_5678,
+    ] {
+        writer.write(&word.to_le_bytes());
+    }
+    writer.write_shstrtab();
+
+    writer.write_null_section_header();
+    for (name, sh_type, sh_flags, sh_addr, sh_offset, sh_size, sh_link) in [
+        (
+            text_name,
+            elf::SHT_PROGBITS,
+            elf::SHF_ALLOC | elf::SHF_EXECINSTR,
+            text_address,
+            text_offset,
+            0x200,
+            0,
+        ),
+        (
+            exidx_name,
+            elf::SHT_ARM_EXIDX,
+            elf::SHF_ALLOC | elf::SHF_LINK_ORDER,
+            exidx_address,
+            exidx_offset,
+            0x20,
+            text_index.0,
+        ),
+        (
+            extab_name,
+            elf::SHT_PROGBITS,
+            elf::SHF_ALLOC,
+            extab_address,
+            extab_offset,
+            0x14,
+            0,
+        ),
+    ] {
+        writer.write_section_header(&write::elf::SectionHeader {
+            name: Some(name),
+            sh_type,
+            sh_flags: sh_flags.into(),
+            sh_addr,
+            sh_offset: sh_offset as u64,
+            sh_size,
+            sh_link,
+            sh_info: 0,
+            sh_addralign: 4,
+            sh_entsize: 0,
+        });
+    }
+    writer.write_shstrtab_section_header();
+    assert_eq!(writer.reserved_len(), buffer.len());
+
+    let file = read::elf::ElfFile32::<Endianness>::parse(&*buffer).unwrap();
+    assert!(file
+        .section_by_name(".text")
+        .unwrap()
+        .arm_exidx()
+        .unwrap()
+        .is_none());
+    let section = file.section_by_name(".ARM.exidx").unwrap();
+    let table = section.arm_exidx().unwrap().unwrap();
+    assert_eq!(table.len(), 4);
+    let entries = table.entries().collect::<Result<Vec<_>, _>>().unwrap();
+    let functions = entries
+        .iter()
+        .map(|entry| entry.function())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        functions,
+        [0x8000, 0x8010, 0x8040, 0x8080].map(ArmEhabiTarget::Address)
+    );
+
+    assert!(matches!(entries[0].unwind(), ArmExidxUnwind::CantUnwind));
+
+    let ArmExidxUnwind::Compact {
+        personality,
+        opcodes,
+        data,
+    } = entries[1].unwind().clone()
+    else {
+        panic!("unexpected unwind {:?}", entries[1].unwind());
+    };
+    assert_eq!(personality, 0);
+    assert_eq!(data, &[]);
+    assert_eq!(
+        opcodes.collect::<Result<Vec<_>, _>>().unwrap(),
+        [
+            ArmUnwindOpcode::PopRegisters(0x4010),
+            ArmUnwindOpcode::Finish
+        ]
+    );
+
+    let ArmExidxUnwind::Compact {
+        personality,
+        opcodes,
+        data,
+    } = entries[2].unwind().clone()
+    else {
+        panic!("unexpected unwind {:?}", entries[2].unwind());
+    };
+    assert_eq!(personality, 1);
+    assert_eq!(&data[..4], &[0; 4]);
+    assert_eq!(
+        opcodes.collect::<Result<Vec<_>, _>>().unwrap(),
+        [
+            ArmUnwindOpcode::PopVfp { first: 0, count: 9 },
+            ArmUnwindOpcode::IncrementVsp(0x408),
+            ArmUnwindOpcode::Finish,
+        ]
+    );
+
+    let ArmExidxUnwind::Generic { personality, data } = entries[3].unwind().clone() else {
+        panic!("unexpected unwind {:?}", entries[3].unwind());
+    };
+    assert_eq!(personality, ArmEhabiTarget::Address(0x8100));
+    assert_eq!(data, &0x1234_5678u32.to_le_bytes());
+
+    assert!(table.find(0x7fff).unwrap().is_none());
+    for (address, function) in [(0x8000, 0x8000), (0x8020, 0x8010), (0x9000, 0x8080)] {
+        assert_eq!(
+            table.find(address).unwrap().unwrap().function(),
+            ArmEhabiTarget::Address(function)
+        );
+    }
+}
+
+#[test]
+fn arm_exidx_relocatable() {
+    use object::read::elf::{ArmEhabiTarget, ArmExidxUnwind, ArmUnwindOpcode};
+
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Little);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0; 0x100], 4);
+    let extab = object.add_section(
+        Vec::new(),
+        b".ARM.extab".to_vec(),
+        SectionKind::ReadOnlyData,
+    );
+    object.append_section_data(extab, &[0, 0, 0, 0, 0x78, 0x56, 0x34, 0x12], 4);
+    let exidx = object.add_section(
+        Vec::new(),
+        b".ARM.exidx".to_vec(),
+        SectionKind::Elf(elf::SHT_ARM_EXIDX),
+    );
+    // The implicit addends are stored in the section data.
+    let mut exidx_data = [0; 16];
+    exidx_data[0..4].copy_from_slice(&0x10u32.to_le_bytes());
+    exidx_data[4..8].copy_from_slice(&0x80a8_b0b0u32.to_le_bytes());
+    exidx_data[8..12].copy_from_slice(&0x40u32.to_le_bytes());
+    object.append_section_data(exidx, &exidx_data, 4);
+
+    let personality = object.add_symbol(write::Symbol {
+        name: b"__gxx_personality_v0".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Linkage,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    let text_symbol = object.section_symbol(text);
+    let extab_symbol = object.section_symbol(extab);
+    for (section, offset, symbol) in [
+        (extab, 0, personality),
+        (exidx, 0, text_symbol),
+        (exidx, 8, text_symbol),
+        (exidx, 12, extab_symbol),
+    ] {
+        object
+            .add_relocation(
+                section,
+                write::Relocation {
+                    offset,
+                    symbol,
+                    addend: 0,
+                    flags: object::RelocationFlags::Elf {
+                        r_type: elf::R_ARM_PREL31,
+                    },
+                },
+            )
+            .unwrap();
+    }
+    let bytes = object.write().unwrap();
+
+    let file = read::elf::ElfFile32::<Endianness>::parse(&*bytes).unwrap();
+    let text_index = file.section_by_name(".text").unwrap().index();
+    let personality_index = file.symbol_by_name("__gxx_personality_v0").unwrap().index();
+    let section = file.section_by_name(".ARM.exidx").unwrap();
+    let table = section.arm_exidx().unwrap().unwrap();
+    assert!(table.find(0).is_err());
+    let entries = table.entries().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(
+        entries[0].function(),
+        ArmEhabiTarget::Section {
+            section: text_index,
+            offset: 0x10
+        }
+    );
+    let ArmExidxUnwind::Compact { opcodes, .. } = entries[0].unwind().clone() else {
+        panic!("unexpected unwind {:?}", entries[0].unwind());
+    };
+    assert_eq!(
+        opcodes.collect::<Result<Vec<_>, _>>().unwrap(),
+        [
+            ArmUnwindOpcode::PopRegisters(0x4010),
+            ArmUnwindOpcode::Finish
+        ]
+    );
+
+    assert_eq!(
+        entries[1].function(),
+        ArmEhabiTarget::Section {
+            section: text_index,
+            offset: 0x40
+        }
+    );
+    let ArmExidxUnwind::Generic { personality, data } = entries[1].unwind().clone() else {
+        panic!("unexpected unwind {:?}", entries[1].unwind());
+    };
+    assert_eq!(
+        personality,
+        ArmEhabiTarget::Symbol {
+            symbol: personality_index,
+            addend: 0
+        }
+    );
+    assert_eq!(data, &0x1234_5678u32.to_le_bytes());
+}
+
+#[test]
+fn known_notes() {
+    use object::read::elf::{KnownNote, NoteIterator};
+
+    let endian = LittleEndian;
+    let mut buffer = Vec::new();
+    let mut add_note = |name: &[u8], n_type: u32, desc: &[u8]| {
+        buffer
+            .write_all(object::bytes_of(&elf::NoteHeader64 {
+                n_namesz: U32::new(endian, name.len() as u32 + 1),
+                n_descsz: U32::new(endian, desc.len() as u32),
+                n_type: U32::new(endian, n_type),
+            }))
+            .unwrap();
+        buffer.write_all(name).unwrap();
+        buffer.push(0);
+        while buffer.len() % 4 != 0 {
+            buffer.push(0);
+        }
+        buffer.write_all(desc).unwrap();
+        while buffer.len() % 4 != 0 {
+            buffer.push(0);
+        }
+    };
+
+    let words = |words: &[u32]| {
+        words
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>()
+    };
+    add_note(
+        elf::ELF_NOTE_GNU,
+        elf::NT_GNU_ABI_TAG,
+        &words(&[elf::ELF_NOTE_OS_LINUX, 3, 2, 0]),
+    );
+    add_note(elf::ELF_NOTE_GNU, elf::NT_GNU_BUILD_ID, &[1, 2, 3, 4]);
+    add_note(elf::ELF_NOTE_GO, elf::NT_GO_BUILD_ID, b"abc/def");
+    add_note(
+        elf::ELF_NOTE_FDO,
+        elf::NT_FDO_PACKAGING_METADATA,
+        b"{\"type\":\"rpm\"}\0",
+    );
+    add_note(elf::ELF_NOTE_FDO, elf::NT_FDO_DLOPEN_METADATA, b"[]\0");
+    let mut stapsdt = Vec::new();
+    for address in [0x1234u64, 0x5000, 0x6000] {
+        stapsdt.extend_from_slice(&address.to_le_bytes());
+    }
+    stapsdt.extend_from_slice(b"libc\0setjmp\08@%rdi -4@%esi\0");
+    add_note(elf::ELF_NOTE_STAPSDT, elf::NT_STAPSDT, &stapsdt);
+    add_note(
+        elf::ELF_NOTE_LINUX,
+        elf::NT_ARM_TLS,
+        &0x7fff_0000_1000u64.to_le_bytes(),
+    );
+    add_note(elf::ELF_NOTE_LINUX, elf::NT_ARM_SYSTEM_CALL, &words(&[!0]));
+    let mut hw_watch = words(&[0x0601_0004, 0]);
+    hw_watch.extend_from_slice(&0x4000u64.to_le_bytes());
+    hw_watch.extend_from_slice(&words(&[0x1f5, 0]));
+    add_note(elf::ELF_NOTE_LINUX, elf::NT_ARM_HW_WATCH, &hw_watch);
+    add_note(
+        elf::ELF_NOTE_LINUX,
+        elf::NT_ARM_PAC_MASK,
+        &words(&[0, 0xff80, 0, 0xff7f]),
+    );
+    add_note(elf::ELF_NOTE_LINUX, 0xffff, &[]);
+    // A known note with an invalid descriptor.
+    add_note(elf::ELF_NOTE_GNU, elf::NT_GNU_ABI_TAG, &[0; 8]);
+
+    let mut notes =
+        NoteIterator::<elf::FileHeader64<LittleEndian>>::new(endian, 4, &buffer).unwrap();
+    let mut next = || notes.next().unwrap().unwrap().parse_known(endian);
+
+    match next().unwrap().unwrap() {
+        KnownNote::GnuAbiTag { os, version } => {
+            assert_eq!(os, elf::ELF_NOTE_OS_LINUX);
+            assert_eq!(version, [3, 2, 0]);
+        }
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::GnuBuildId(build_id) => assert_eq!(build_id, &[1, 2, 3, 4]),
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::GoBuildId(build_id) => assert_eq!(build_id, b"abc/def"),
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::FdoPackagingMetadata(json) => assert_eq!(json, b"{\"type\":\"rpm\"}"),
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::FdoDlopenMetadata(json) => assert_eq!(json, b"[]"),
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::Stapsdt(probe) => {
+            assert_eq!(probe.pc(), 0x1234);
+            assert_eq!(probe.base(), 0x5000);
+            assert_eq!(probe.semaphore(), 0x6000);
+            assert_eq!(probe.provider(), b"libc");
+            assert_eq!(probe.name(), b"setjmp");
+            assert_eq!(probe.args(), b"8@%rdi -4@%esi");
+        }
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::ArmTls(tls) => assert_eq!(tls, 0x7fff_0000_1000),
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::ArmSystemCall(number) => assert_eq!(number, -1),
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::ArmHwWatch { info, registers } => {
+            assert_eq!(info, 0x0601_0004);
+            assert_eq!(registers.len(), 1);
+            assert_eq!(registers[0].addr.get(endian), 0x4000);
+            assert_eq!(registers[0].ctrl.get(endian), 0x1f5);
+        }
+        note => panic!("unexpected note {:?}", note),
+    }
+    match next().unwrap().unwrap() {
+        KnownNote::ArmPacMask {
+            data_mask,
+            insn_mask,
+        } => {
+            assert_eq!(data_mask, 0xff80_0000_0000);
+            assert_eq!(insn_mask, 0xff7f_0000_0000);
+        }
+        note => panic!("unexpected note {:?}", note),
+    }
+    assert!(next().unwrap().is_none());
+    assert!(next().is_err());
+}
+
+#[test]
+fn kernel_module() {
+    kernel_module_inner(Architecture::X86_64, elf::R_X86_64_PC32);
+    kernel_module_inner(Architecture::I386, elf::R_386_32);
+}
+
+fn kernel_module_inner(architecture: Architecture, r_type: u32) {
+    let endian = Endianness::Little;
+    let mut object = write::Object::new(BinaryFormat::Elf, architecture, endian);
+    let is_64 = architecture == Architecture::X86_64;
+    let word = if is_64 { 8 } else { 4 };
+
+    let modinfo = object.add_section(Vec::new(), b".modinfo".to_vec(), SectionKind::ReadOnlyData);
+    object.append_section_data(
+        modinfo,
+        b"license=GPL\0depends=\0\0\0vermagic=6.1.0 SMP mod_unload\0",
+        1,
+    );
+
+    let versions = object.add_section(Vec::new(), b"__versions".to_vec(), SectionKind::Data);
+    for (crc, name) in [
+        (0x1234_5678u64, &b"module_layout"[..]),
+        (0x9abc_def0, b"printk"),
+    ] {
+        let mut entry = vec![0; 64];
+        entry[..word].copy_from_slice(&crc.to_le_bytes()[..word]);
+        entry[word..][..name.len()].copy_from_slice(name);
+        object.append_section_data(versions, &entry, 8);
+    }
+
+    let this_module = object.add_section(
+        Vec::new(),
+        b".gnu.linkonce.this_module".to_vec(),
+        SectionKind::Data,
+    );
+    let mut module = vec![0; 0x100];
+    let name_offset = if is_64 { 24 } else { 12 };
+    module[name_offset..][..4].copy_from_slice(b"test");
+    object.append_section_data(this_module, &module, 8);
+
+    let text = object.section_id(write::StandardSection::Text);
+    let strings = object.add_section(
+        Vec::new(),
+        b"__ksymtab_strings".to_vec(),
+        SectionKind::ReadOnlyString,
+    );
+    object.append_section_data(strings, b"foo\0\0bar\0MY_NS\0baz\0\0", 1);
+    let strings_symbol = object.section_symbol(strings);
+    let ksymtab = object.add_section(Vec::new(), b"__ksymtab".to_vec(), SectionKind::Data);
+    let ksymtab_gpl = object.add_section(Vec::new(), b"__ksymtab_gpl".to_vec(), SectionKind::Data);
+    for (section, name, value, name_offset, namespace_offset) in [
+        (ksymtab, "foo", 0x10, 0, 4),
+        (ksymtab, "bar", 0x20, 5, 9),
+        (ksymtab_gpl, "baz", 0x30, 15, 19),
+    ] {
+        let function = object.add_symbol(write::Symbol {
+            name: name.as_bytes().to_vec(),
+            value,
+            size: 0x10,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: write::SymbolSection::Section(text),
+            flags: SymbolFlags::None,
+        });
+        let offset = object.append_section_data(section, &[0; 12], 4);
+        object.add_symbol(write::Symbol {
+            name: format!("__ksymtab_{}", name).into_bytes(),
+            value: offset,
+            size: 12,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: write::SymbolSection::Section(section),
+            flags: SymbolFlags::None,
+        });
+        for (field, symbol, addend) in [
+            (0, function, 0),
+            (4, strings_symbol, name_offset),
+            (8, strings_symbol, namespace_offset),
+        ] {
+            object
+                .add_relocation(
+                    section,
+                    write::Relocation {
+                        offset: offset + field,
+                        symbol,
+                        addend,
+                        flags: object::RelocationFlags::Elf { r_type },
+                    },
+                )
+                .unwrap();
+        }
+    }
+    let mut bytes = object.write().unwrap();
+    check_kernel_module(is_64, &bytes, None);
+
+    // Append a PKCS#7 signature.
+    let signature = b"signed data";
+    bytes.extend_from_slice(signature);
+    bytes.extend_from_slice(&[0, 0, elf::PKEY_ID_PKCS7, 0, 0, 0, 0, 0]);
+    bytes.extend_from_slice(&(signature.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(elf::MODULE_SIG_STRING);
+    check_kernel_module(is_64, &bytes, Some(signature));
+
+    // Not a kernel module.
+    let object = write::Object::new(BinaryFormat::Elf, architecture, endian);
+    let bytes = object.write().unwrap();
+    if is_64 {
+        let file = ElfFile::<elf::FileHeader64<Endianness>>::parse(&*bytes).unwrap();
+        assert!(ElfKernelModule::parse(&file).is_err());
+    } else {
+        let file = ElfFile::<elf::FileHeader32<Endianness>>::parse(&*bytes).unwrap();
+        assert!(ElfKernelModule::parse(&file).is_err());
+    }
+}
+
+fn check_kernel_module(is_64: bool, bytes: &[u8], signature: Option<&[u8]>) {
+    if is_64 {
+        check_kernel_module_inner::<elf::FileHeader64<Endianness>>(bytes, signature);
+    } else {
+        check_kernel_module_inner::<elf::FileHeader32<Endianness>>(bytes, signature);
+    }
+}
+
+fn check_kernel_module_inner<Elf: FileHeader<Endian = Endianness>>(
+    bytes: &[u8],
+    signature: Option<&[u8]>,
+) {
+    let file = ElfFile::<Elf>::parse(bytes).unwrap();
+    let module = ElfKernelModule::parse(&file).unwrap();
+
+    assert_eq!(module.name(), Some(&b"test"[..]));
+    assert_eq!(
+        module.modinfo().collect::<Vec<_>>(),
+        [
+            (&b"license"[..], &b"GPL"[..]),
+            (b"depends", b""),
+            (b"vermagic", b"6.1.0 SMP mod_unload"),
+        ]
+    );
+    assert_eq!(module.modinfo_value(b"license"), Some(&b"GPL"[..]));
+    assert_eq!(module.modinfo_value(b"srcversion"), None);
+
+    let versions = module
+        .versions()
+        .map(|version| {
+            let version = version.unwrap();
+            (version.crc(), version.name())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        versions,
+        [
+            (0x1234_5678, &b"module_layout"[..]),
+            (0x9abc_def0, b"printk")
+        ]
+    );
+
+    let exports = module
+        .exports()
+        .iter()
+        .map(|export| (export.name(), export.is_gpl(), export.namespace()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        [
+            (&b"foo"[..], false, None),
+            (b"bar", false, Some(&b"MY_NS"[..])),
+            (b"baz", true, None),
+        ]
+    );
+
+    match signature {
+        Some(signature) => {
+            let module_signature = module.signature().unwrap();
+            assert_eq!(module_signature.id_type(), elf::PKEY_ID_PKCS7);
+            assert_eq!(module_signature.signer(), b"");
+            assert_eq!(module_signature.key_id(), b"");
+            assert_eq!(module_signature.signature(), signature);
+        }
+        None => assert!(module.signature().is_none()),
+    }
+}
@@ -13,6 +13,8 @@ mod comdat;
 mod common;
 mod elf;
 mod macho;
+mod pef;
+mod relocate;
 mod section_flags;
 mod tls;
 
@@ -0,0 +1,976 @@
+#![cfg(all(feature = "read", feature = "write", feature = "pef"))]
+
+use object::endian::BigEndian as BE;
+use object::read::{self, Object, ObjectSection, ObjectSymbol, ReadRef};
+use object::{pef, write};
+use object::{
+    Architecture, BinaryFormat, Endianness, RelocationEncoding, RelocationFlags, RelocationKind,
+    SectionIndex, SectionKind, SymbolFlags, SymbolKind, SymbolScope, SymbolSection,
+};
+
+fn relocation(offset: u64, symbol: write::SymbolId) -> write::Relocation {
+    write::Relocation {
+        offset,
+        symbol,
+        addend: 0,
+        flags: RelocationFlags::Generic {
+            kind: RelocationKind::Absolute,
+            encoding: RelocationEncoding::Generic,
+            size: 32,
+        },
+    }
+}
+
+#[test]
+fn pef_powerpc() {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+    object.set_pef_container_info(write::PefContainerInfo {
+        date_time_stamp: 0xB000_0000,
+        old_def_version: 1,
+        old_imp_version: 2,
+        current_version: 3,
+    });
+
+    let text = object.section_id(write::StandardSection::Text);
+    let func_offset = object.append_section_data(text, &[0x4e, 0x80, 0x00, 0x20], 4);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: func_offset,
+        size: 4,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+
+    let data = object.section_id(write::StandardSection::Data);
+    let tvect_offset = object.append_section_data(data, &[0; 8], 4);
+    let tvect = object.add_symbol(write::Symbol {
+        name: b"main".to_vec(),
+        value: tvect_offset,
+        size: 8,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(data),
+        flags: SymbolFlags::Pef {
+            symbol_class: pef::TVECT_SYMBOL,
+        },
+    });
+    let toc_offset = object.append_section_data(data, &[0; 16], 4);
+    object.set_pef_main_symbol(Some(tvect));
+
+    let bss = object.section_id(write::StandardSection::UninitializedData);
+    object.append_section_bss(bss, 0x100, 4);
+
+    let library = object.add_pef_imported_library(write::PefImportedLibrary {
+        name: b"InterfaceLib".to_vec(),
+        old_imp_version: 0x100,
+        current_version: 0x200,
+        weak: false,
+        init_before: false,
+    });
+    let mut imports = Vec::new();
+    for (name, weak) in [(&b"SysBeep"[..], false), (&b"Gestalt"[..], true)] {
+        let symbol = object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Unknown,
+            weak,
+            section: write::SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+        object.set_pef_symbol_library(symbol, library);
+        imports.push(symbol);
+    }
+
+    // Transition vector: code address and TOC address.
+    object
+        .add_relocation(data, relocation(tvect_offset, func))
+        .unwrap();
+    let toc = object.section_symbol(data);
+    object
+        .add_relocation(data, relocation(tvect_offset + 4, toc))
+        .unwrap();
+    // TOC entries for the imports.
+    object
+        .add_relocation(data, relocation(toc_offset, imports[0]))
+        .unwrap();
+    object
+        .add_relocation(data, relocation(toc_offset + 4, imports[1]))
+        .unwrap();
+    object
+        .add_relocation(data, relocation(toc_offset + 12, toc))
+        .unwrap();
+
+    let bytes = &*object.write().unwrap();
+
+    let header = bytes.read_at::<pef::PEFContainerHeader>(0).unwrap();
+    assert_eq!(header.tag1.get(BE), pef::TAG1);
+    assert_eq!(header.tag2.get(BE), pef::TAG2);
+    assert_eq!(header.architecture.get(BE), pef::ARCHITECTURE_PPC);
+    assert_eq!(header.format_version.get(BE), pef::FORMAT_VERSION);
+    assert_eq!(header.date_time_stamp.get(BE), 0xB000_0000);
+    assert_eq!(header.old_def_version.get(BE), 1);
+    assert_eq!(header.old_imp_version.get(BE), 2);
+    assert_eq!(header.current_version.get(BE), 3);
+    assert_eq!(header.section_count.get(BE), 4);
+    assert_eq!(header.inst_section_count.get(BE), 3);
+
+    let sections = bytes.read_slice_at::<pef::PEFSectionHeader>(40, 4).unwrap();
+    let names_offset = 40 + 4 * 28;
+    let section_name = |section: &pef::PEFSectionHeader| {
+        let offset = names_offset + section.name_offset.get(BE) as u64;
+        bytes
+            .read_bytes_at_until(offset..bytes.len() as u64, 0)
+            .unwrap()
+    };
+    let section_data = |section: &pef::PEFSectionHeader| {
+        bytes
+            .read_bytes_at(
+                section.container_offset.get(BE).into(),
+                section.packed_size.get(BE).into(),
+            )
+            .unwrap()
+    };
+
+    let code = &sections[0];
+    assert_eq!(section_name(code), b".text");
+    assert_eq!(code.section_kind, pef::SectionKind::Code as u8);
+    assert_eq!(code.container_offset.get(BE) % 16, 0);
+    assert_eq!(section_data(code), &[0x4e, 0x80, 0x00, 0x20]);
+
+    let data = &sections[1];
+    assert_eq!(section_name(data), b".data");
+    assert_eq!(data.section_kind, pef::SectionKind::UnpackedData as u8);
+    assert_eq!(data.share_kind, pef::ShareKind::ProcessShare as u8);
+    assert_eq!(data.total_size.get(BE), 24);
+    // The place contains the offset of the symbol in its section.
+    assert_eq!(
+        section_data(data),
+        &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+
+    let bss = &sections[2];
+    assert_eq!(section_name(bss), b".bss");
+    assert_eq!(bss.section_kind, pef::SectionKind::UnpackedData as u8);
+    assert_eq!(bss.unpacked_size.get(BE), 0);
+    assert_eq!(bss.total_size.get(BE), 0x100);
+
+    let loader = &sections[3];
+    assert_eq!(loader.section_kind, pef::SectionKind::Loader as u8);
+    assert_eq!(loader.name_offset.get(BE), pef::NO_SECTION_NAME);
+    let loader = section_data(loader);
+
+    let info = loader.read_at::<pef::PEFLoaderInfoHeader>(0).unwrap();
+    assert_eq!(info.main_section.get(BE), 1);
+    assert_eq!(info.main_offset.get(BE), 0);
+    assert_eq!(info.init_section.get(BE), pef::NO_SECTION);
+    assert_eq!(info.term_section.get(BE), pef::NO_SECTION);
+    assert_eq!(info.imported_library_count.get(BE), 1);
+    assert_eq!(info.total_imported_symbol_count.get(BE), 2);
+    assert_eq!(info.reloc_section_count.get(BE), 1);
+    assert_eq!(info.exported_symbol_count.get(BE), 2);
+    assert_eq!(info.export_hash_table_power.get(BE), 0);
+    let strings_offset = u64::from(info.loader_strings_offset.get(BE));
+    let loader_string = |offset: u32| {
+        loader
+            .read_bytes_at_until(strings_offset + u64::from(offset)..loader.len() as u64, 0)
+            .unwrap()
+    };
+
+    let library = loader.read_at::<pef::PEFImportedLibrary>(56).unwrap();
+    assert_eq!(loader_string(library.name_offset.get(BE)), b"InterfaceLib");
+    assert_eq!(library.old_imp_version.get(BE), 0x100);
+    assert_eq!(library.current_version.get(BE), 0x200);
+    assert_eq!(library.imported_symbol_count.get(BE), 2);
+    assert_eq!(library.first_imported_symbol.get(BE), 0);
+    assert_eq!(library.options, 0);
+
+    let symbols = loader
+        .read_slice_at::<pef::PEFImportedSymbol>(56 + 24, 2)
+        .unwrap();
+    assert_eq!(loader_string(symbols[0].name_offset()), b"SysBeep");
+    assert_eq!(symbols[0].symbol_class(), pef::TVECT_SYMBOL);
+    assert_eq!(symbols[0].symbol_flags(), 0);
+    assert_eq!(loader_string(symbols[1].name_offset()), b"Gestalt");
+    assert_eq!(symbols[1].symbol_flags(), pef::WEAK_IMPORT_SYMBOL);
+
+    let relocs = loader
+        .read_at::<pef::PEFLoaderRelocationHeader>(56 + 24 + 8)
+        .unwrap();
+    assert_eq!(relocs.section_index.get(BE), 1);
+    let instructions = loader
+        .read_slice_at::<object::U16<BE>>(
+            u64::from(info.reloc_instr_offset.get(BE) + relocs.first_reloc_offset.get(BE)),
+            relocs.reloc_count.get(BE) as usize,
+        )
+        .unwrap()
+        .iter()
+        .map(|x| x.get(BE))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        instructions,
+        [
+            // Code section address at offset 0.
+            0x4000, // Data section address at offset 4.
+            0x4200, // Imports 0 and 1 at offset 8.
+            0x4a01, // Data section address at offset 20.
+            0x8003, 0x4200,
+        ]
+    );
+
+    let export_hash_offset = u64::from(info.export_hash_offset.get(BE));
+    let slot = loader
+        .read_at::<pef::PEFExportedSymbolHashSlot>(export_hash_offset)
+        .unwrap();
+    assert_eq!(slot.symbol_count(), 2);
+    assert_eq!(slot.first_symbol(), 0);
+    let keys = loader
+        .read_slice_at::<pef::PEFExportedSymbolKey>(export_hash_offset + 4, 2)
+        .unwrap();
+    let exports = loader
+        .read_slice_at::<pef::PEFExportedSymbol>(export_hash_offset + 12, 2)
+        .unwrap();
+    let export_name = |index: usize| {
+        loader
+            .read_bytes_at(
+                strings_offset + u64::from(exports[index].name_offset()),
+                keys[index].name_length().into(),
+            )
+            .unwrap()
+    };
+    assert_eq!(export_name(0), b"func");
+    assert_eq!(keys[0].full_hash_word.get(BE), pef::hash_word(b"func"));
+    assert_eq!(exports[0].symbol_class(), pef::CODE_SYMBOL);
+    assert_eq!(exports[0].section_index.get(BE), 0);
+    assert_eq!(export_name(1), b"main");
+    assert_eq!(exports[1].symbol_class(), pef::TVECT_SYMBOL);
+    assert_eq!(exports[1].section_index.get(BE), 1);
+    assert_eq!(exports[1].symbol_value.get(BE), 0);
+
+    let file = read::File::parse(bytes).unwrap();
+    assert_eq!(file.format(), BinaryFormat::Pef);
+    assert_eq!(file.architecture(), Architecture::PowerPc);
+    let sections = file
+        .sections()
+        .map(|section| (section.index(), section.name().unwrap(), section.kind()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sections,
+        [
+            (SectionIndex(1), ".text", SectionKind::Text),
+            (SectionIndex(2), ".data", SectionKind::Data),
+            (SectionIndex(3), ".bss", SectionKind::Data),
+            (SectionIndex(4), "", SectionKind::Metadata),
+        ]
+    );
+    assert_eq!(file.segments().count(), 3);
+    let text = file.section_by_index(SectionIndex(1)).unwrap();
+    assert_eq!(text.data().unwrap(), &[0x4e, 0x80, 0x00, 0x20]);
+    let data = file.section_by_name(".data").unwrap();
+    assert_eq!(file.entry(), data.address());
+
+    let symbols = file
+        .symbols()
+        .map(|symbol| (symbol.name().unwrap(), symbol.section(), symbol.is_weak()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symbols,
+        [
+            ("SysBeep", SymbolSection::Undefined, false),
+            ("Gestalt", SymbolSection::Undefined, true),
+            ("func", SymbolSection::Section(SectionIndex(1)), false),
+            ("main", SymbolSection::Section(SectionIndex(2)), false),
+        ]
+    );
+    let imports = file
+        .imports()
+        .unwrap()
+        .iter()
+        .map(|import| (import.library(), import.name()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            (&b"InterfaceLib"[..], &b"SysBeep"[..]),
+            (&b"InterfaceLib"[..], &b"Gestalt"[..]),
+        ]
+    );
+    let exports = file
+        .exports()
+        .unwrap()
+        .iter()
+        .map(|export| (export.name(), export.address()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exports,
+        [
+            (&b"func"[..], text.address()),
+            (&b"main"[..], data.address())
+        ]
+    );
+}
+
+#[test]
+fn pef_missing_library() {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+    let data = object.add_section(Vec::new(), b".data".to_vec(), SectionKind::Data);
+    object.append_section_data(data, &[0; 4], 4);
+    let symbol = object.add_symbol(write::Symbol {
+        name: b"SysBeep".to_vec(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Unknown,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_relocation(data, relocation(0, symbol)).unwrap();
+    assert!(object.write().is_err());
+}
+
+#[test]
+fn pef_relocation_not_imported() {
+    // Undefined symbols without a name are never imported.
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+    let data = object.add_section(Vec::new(), b".data".to_vec(), SectionKind::Data);
+    object.append_section_data(data, &[0; 4], 4);
+    let symbol = object.add_symbol(write::Symbol {
+        name: Vec::new(),
+        value: 0,
+        size: 0,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Unknown,
+        weak: false,
+        section: write::SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+    object.add_relocation(data, relocation(0, symbol)).unwrap();
+    assert!(object.write().is_err());
+}
+
+#[test]
+fn pef_symbol_sizes() {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+
+    // A function followed by a traceback table.
+    fn function(code: &mut Vec<u8>, instructions: &[u32], name: &[u8]) {
+        for instruction in instructions {
+            code.extend_from_slice(&instruction.to_be_bytes());
+        }
+        code.extend_from_slice(&[0, 0, 0, 0]);
+        code.extend_from_slice(&[0, 0, pef::TB_HAS_TBOFF, pef::TB_NAME_PRESENT, 0, 0, 0, 0]);
+        code.extend_from_slice(&(instructions.len() as u32 * 4).to_be_bytes());
+        code.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        code.extend_from_slice(name);
+        code.resize((code.len() + 3) & !3, 0);
+    }
+    let mut code = Vec::new();
+    function(&mut code, &[0x6000_0000, 0x4e80_0020], b"func");
+    let helper_offset = code.len() as u64;
+    function(
+        &mut code,
+        &[0x6000_0000, 0x6000_0000, 0x4e80_0020],
+        b"helper",
+    );
+
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &code, 16);
+    let data = object.section_id(write::StandardSection::Data);
+    object.append_section_data(data, &[0; 24], 4);
+    for (name, section, value) in [(&b"func"[..], text, 0), (b"a", data, 0), (b"b", data, 8)] {
+        object.add_symbol(write::Symbol {
+            name: name.to_vec(),
+            value,
+            size: 0,
+            kind: if section == text {
+                SymbolKind::Text
+            } else {
+                SymbolKind::Data
+            },
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: write::SymbolSection::Section(section),
+            flags: SymbolFlags::None,
+        });
+    }
+
+    let bytes = &*object.write().unwrap();
+    let file = read::pef::PefFile::parse(bytes).unwrap();
+    let text_address = file.section_by_name(".text").unwrap().address();
+    let data_address = file.section_by_name(".data").unwrap().address();
+    let symbols = file
+        .symbols()
+        .map(|symbol| {
+            (
+                symbol.name().unwrap(),
+                symbol.address(),
+                symbol.size(),
+                symbol.is_size_exact(),
+                symbol.scope(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symbols,
+        [
+            ("func", text_address, 8, true, SymbolScope::Dynamic),
+            ("a", data_address, 8, false, SymbolScope::Dynamic),
+            ("b", data_address + 8, 16, false, SymbolScope::Dynamic),
+            (
+                "helper",
+                text_address + helper_offset,
+                12,
+                true,
+                SymbolScope::Compilation
+            ),
+        ]
+    );
+
+    // The unified API reports the same sizes.
+    let file = read::File::parse(bytes).unwrap();
+    let sizes = file
+        .symbols()
+        .map(|symbol| (symbol.size(), symbol.is_size_exact()))
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [(8, true), (8, false), (16, false), (12, true)]);
+
+    let map = file.symbol_map();
+    assert_eq!(
+        map.get(text_address + helper_offset + 4).unwrap().name(),
+        "helper"
+    );
+
+    // The traceback table function is not exported.
+    let exports = file
+        .exports()
+        .unwrap()
+        .iter()
+        .map(|export| export.name())
+        .collect::<Vec<_>>();
+    assert_eq!(exports, [&b"func"[..], b"a", b"b"]);
+}
+
+/// Generate data containing the patterns that the encoder searches for.
+fn pattern_test_data(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed | 1;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    let mut data = Vec::with_capacity(len);
+    while data.len() < len {
+        let n = next();
+        let size = (n >> 8) as usize % 64;
+        match n % 5 {
+            0 => data.resize(data.len() + size, 0),
+            1 => data.extend((0..size).map(|_| next() as u8)),
+            2 => {
+                let block = ((n >> 32) as u32).to_be_bytes();
+                let block = &block[..1 + (n >> 16) as usize % 4];
+                for _ in 0..size {
+                    data.extend_from_slice(block);
+                }
+            }
+            _ => {
+                // Interleaved common and custom blocks.
+                let common = if n % 5 == 3 {
+                    vec![0; 1 + (n >> 16) as usize % 8]
+                } else {
+                    (0..1 + (n >> 16) as usize % 8)
+                        .map(|_| next() as u8)
+                        .collect()
+                };
+                let custom_size = 1 + (n >> 24) as usize % 8;
+                data.extend_from_slice(&common);
+                for _ in 0..size {
+                    data.extend((0..custom_size).map(|_| next() as u8));
+                    data.extend_from_slice(&common);
+                }
+            }
+        }
+    }
+    data.truncate(len);
+    data
+}
+
+#[test]
+fn pef_pattern_data() {
+    let levels = [
+        write::pef::PatternDataLevel::Fast,
+        write::pef::PatternDataLevel::Best,
+    ];
+    for seed in 0..200 {
+        let data = pattern_test_data(seed, seed as usize * 37);
+        for level in levels {
+            let encoded = write::pef::encode_pattern_data(&data, level);
+            let decoded = object::read::pef::unpack_pattern_data(
+                &encoded,
+                data.len() as u32,
+                data.len() as u32,
+            )
+            .unwrap_or_else(|e| panic!("seed {} level {:?}: {}", seed, level, e));
+            assert_eq!(decoded, data, "seed {} level {:?}", seed, level);
+        }
+    }
+
+    // Each opcode is used where it is the best fit.
+    let data = [0; 1000];
+    let encoded = write::pef::encode_pattern_data(&data, Default::default());
+    assert_eq!(encoded, [pef::PK_DATA_ZERO << 5, 0x87, 0x68]);
+    let data = b"abcabcabcabc";
+    let encoded = write::pef::encode_pattern_data(data, Default::default());
+    assert_eq!(
+        encoded,
+        [(pef::PK_DATA_REPEAT << 5) | 3, 3, b'a', b'b', b'c']
+    );
+    let data = [0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0];
+    let encoded = write::pef::encode_pattern_data(&data, Default::default());
+    assert_eq!(encoded, [(pef::PK_DATA_REPEAT_ZERO << 5) | 3, 1, 2, 2, 3]);
+}
+
+#[test]
+fn pef_pattern_data_limit() {
+    use object::read::pef::{default_pattern_data_limit, unpack_pattern_data};
+
+    // 4 GiB of zeros in 6 bytes is rejected before allocating.
+    let encoded = [pef::PK_DATA_ZERO << 5, 0x8f, 0xff, 0xff, 0xff, 0x7f];
+    let limit = default_pattern_data_limit(encoded.len());
+    assert_eq!(limit, 1 << 24);
+    assert!(unpack_pattern_data(&encoded, u32::MAX, limit).is_err());
+
+    // More than 16 MiB of zeros requires a larger limit.
+    let size = (16 << 20) + 1;
+    let encoded = [pef::PK_DATA_ZERO << 5, 0x88, 0x80, 0x80, 0x01];
+    let limit = default_pattern_data_limit(encoded.len());
+    assert!(unpack_pattern_data(&encoded, size, limit).is_err());
+    let unpacked = unpack_pattern_data(&encoded, size, size).unwrap();
+    assert_eq!(unpacked.len(), size as usize);
+    assert!(unpacked.iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn pef_pattern_data_section() {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+    object.set_pef_pattern_data(Some(write::pef::PatternDataLevel::Best));
+    let data = object.section_id(write::StandardSection::Data);
+    let mut contents = vec![0; 256];
+    contents[64..68].copy_from_slice(&[1, 2, 3, 4]);
+    object.append_section_data(data, &contents, 16);
+
+    let bytes = &*object.write().unwrap();
+    let header = bytes.read_at::<pef::PEFContainerHeader>(0).unwrap();
+    assert_eq!(header.section_count.get(BE), 2);
+    let section = bytes.read_at::<pef::PEFSectionHeader>(40).unwrap();
+    assert_eq!(
+        section.section_kind,
+        pef::SectionKind::PatternInitializedData as u8
+    );
+    assert_eq!(section.total_size.get(BE), 256);
+    assert_eq!(section.unpacked_size.get(BE), 256);
+    assert!(section.packed_size.get(BE) < 16);
+    let packed = bytes
+        .read_bytes_at(
+            section.container_offset.get(BE).into(),
+            section.packed_size.get(BE).into(),
+        )
+        .unwrap();
+    let unpacked_size = section.unpacked_size.get(BE);
+    let unpacked =
+        object::read::pef::unpack_pattern_data(packed, unpacked_size, unpacked_size).unwrap();
+    assert_eq!(unpacked, contents);
+}
+
+#[test]
+fn pef_m68k() {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::M68k, Endianness::Big);
+
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0x4e, 0x71], 2);
+    // RTS
+    let func_offset = object.append_section_data(text, &[0x4e, 0x75], 2);
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: func_offset,
+        size: 2,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+
+    // Transition vector: code address and A5 world.
+    let data = object.section_id(write::StandardSection::Data);
+    let tvect_offset = object.append_section_data(data, &[0; 8], 4);
+    let tvect = object.add_symbol(write::Symbol {
+        name: b"main".to_vec(),
+        value: tvect_offset,
+        size: 8,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(data),
+        flags: SymbolFlags::Pef {
+            symbol_class: pef::TVECT_SYMBOL,
+        },
+    });
+    object.set_pef_main_symbol(Some(tvect));
+    object
+        .add_relocation(data, relocation(tvect_offset, func))
+        .unwrap();
+    let a5 = object.section_symbol(data);
+    object
+        .add_relocation(data, relocation(tvect_offset + 4, a5))
+        .unwrap();
+
+    let bytes = &*object.write().unwrap();
+    let file = read::pef::PefFile::<&[u8]>::parse(bytes).unwrap();
+    assert_eq!(file.architecture(), Architecture::M68k);
+
+    let text = file.section_by_name(".text").unwrap();
+    let data = file.section_by_name(".data").unwrap();
+    let code_address = text.address() + func_offset;
+    assert_eq!(file.entry(), code_address);
+
+    let tvector = file
+        .transition_vector(SectionIndex(data.index().0 - 1), tvect_offset as u32)
+        .unwrap()
+        .unwrap();
+    assert_eq!(tvector.code, code_address);
+    assert_eq!(tvector.code_section, text.index());
+    assert_eq!(tvector.data, data.address());
+    // The code is not segmented.
+    assert_eq!(file.jump_table_entry(&tvector).unwrap(), None);
+
+    // The exported transition vector is a function at the code address.
+    let main = file.symbol_by_name("main").unwrap();
+    assert_eq!(main.kind(), SymbolKind::Text);
+    assert_eq!(main.address(), code_address);
+    assert_eq!(main.section(), SymbolSection::Section(text.index()));
+
+    let relocations: Vec<_> = data
+        .relocations()
+        .map(|(offset, relocation)| (offset, relocation.target(), relocation.size()))
+        .collect();
+    assert_eq!(
+        relocations,
+        [
+            (
+                tvect_offset,
+                read::RelocationTarget::Section(text.index()),
+                32
+            ),
+            (
+                tvect_offset + 4,
+                read::RelocationTarget::Section(data.index()),
+                32
+            ),
+        ]
+    );
+    assert_eq!(text.relocations().count(), 0);
+}
+
+#[test]
+fn pef_m68k_jump_table() {
+    use object::read::pef::{JumpTableEntry, JumpTableIterator};
+
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::M68k, Endianness::Big);
+
+    // Jump table entries for routines in unloaded segments 2 and 3.
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0x4e, 0x71, 0x4e, 0x71], 4);
+    let jump_table_offset = object.append_section_data(
+        text,
+        &[
+            0x00, 0x10, 0x3f, 0x3c, 0x00, 0x02, 0xa9, 0xf0, // segment 2
+            0x00, 0x20, 0x3f, 0x3c, 0x00, 0x03, 0xa9, 0xf0, // segment 3
+        ],
+        4,
+    );
+    let jump_table = object.add_symbol(write::Symbol {
+        name: b"jump_table".to_vec(),
+        value: jump_table_offset,
+        size: 16,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        flags: SymbolFlags::None,
+    });
+
+    let data = object.section_id(write::StandardSection::Data);
+    let tvect_offset = object.append_section_data(data, &[0; 8], 4);
+    let tvect = object.add_symbol(write::Symbol {
+        name: b"main".to_vec(),
+        value: tvect_offset,
+        size: 8,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(data),
+        flags: SymbolFlags::Pef {
+            symbol_class: pef::TVECT_SYMBOL,
+        },
+    });
+    object.set_pef_main_symbol(Some(tvect));
+    object
+        .add_relocation(data, relocation(tvect_offset, jump_table))
+        .unwrap();
+
+    let bytes = &*object.write().unwrap();
+    let file = read::pef::PefFile::<&[u8]>::parse(bytes).unwrap();
+    let text = file.section_by_name(".text").unwrap();
+    let data = file.section_by_name(".data").unwrap();
+    let tvector = file
+        .transition_vector(SectionIndex(data.index().0 - 1), tvect_offset as u32)
+        .unwrap()
+        .unwrap();
+    assert_eq!(tvector.code, text.address() + jump_table_offset);
+    assert_eq!(
+        file.jump_table_entry(&tvector).unwrap(),
+        Some(JumpTableEntry::Unloaded {
+            offset: 0x10,
+            segment: 2
+        })
+    );
+
+    let entries: Vec<_> =
+        JumpTableIterator::new(&text.data().unwrap()[jump_table_offset as usize..]).collect();
+    assert_eq!(entries.len(), 2);
+
+    // A code pointer outside the section is an error.
+    let mut tvector = tvector;
+    tvector.code = text.address() + text.size() + 1;
+    assert!(file.jump_table_entry(&tvector).is_err());
+}
+
+#[test]
+fn pef_huge_sizes() {
+    use object::read::pef::PefFile;
+
+    // A CFM-68K exported transition vector in a pattern-initialized data section.
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::M68k, Endianness::Big);
+    object.set_pef_pattern_data(Some(write::pef::PatternDataLevel::Best));
+    let text = object.section_id(write::StandardSection::Text);
+    let func_offset = object.append_section_data(text, &[0x4e, 0x75], 2);
+    let func = object.section_symbol(text);
+    let data = object.section_id(write::StandardSection::Data);
+    let tvect_offset = object.append_section_data(data, &[0; 8], 4);
+    object.add_symbol(write::Symbol {
+        name: b"main".to_vec(),
+        value: tvect_offset,
+        size: 8,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: write::SymbolSection::Section(data),
+        flags: SymbolFlags::Pef {
+            symbol_class: pef::TVECT_SYMBOL,
+        },
+    });
+    object
+        .add_relocation(data, relocation(tvect_offset, func))
+        .unwrap();
+    let a5 = object.section_symbol(data);
+    object
+        .add_relocation(data, relocation(tvect_offset + 4, a5))
+        .unwrap();
+    let mut bytes = object.write().unwrap();
+
+    let main = |bytes: &[u8]| {
+        let file = object::File::parse(bytes).unwrap();
+        let main = file.symbol_by_name("main").unwrap();
+        (main.kind(), main.address())
+    };
+    assert_eq!(main(&bytes), (SymbolKind::Text, func_offset));
+
+    // Set the sizes in the header to the maximum, without changing the contents.
+    let file = PefFile::<&[u8]>::parse(&*bytes).unwrap();
+    let (index, _) = file.pef_sections().section_by_name(b".data").unwrap();
+    let offset = 40 + index.0 * 28;
+    let section = &mut bytes[offset..][..28];
+    section[8..16].copy_from_slice(&[0xff; 8]);
+
+    // The transition vector can't be resolved.
+    assert_eq!(main(&bytes), (SymbolKind::Data, tvect_offset));
+    let file = PefFile::<&[u8]>::parse(&*bytes).unwrap();
+    let section = file.pef_sections().section(index).unwrap();
+    assert_eq!(section.unpacked_size.get(BE), u32::MAX);
+    assert!(section.pef_unpacked_data(&*bytes).is_err());
+    assert!(file.transition_vector(index, tvect_offset as u32).is_err());
+    assert_eq!(file.loader_relocations(index).unwrap().len(), 2);
+}
+
+#[test]
+fn pef_relocation_limit() {
+    use object::endian::U16;
+    use object::read::pef::evaluate_relocations;
+
+    let blocks = |blocks: &[u16]| {
+        blocks
+            .iter()
+            .map(|block| U16::new(BE, *block))
+            .collect::<Vec<_>>()
+    };
+
+    // Repeat a run of 512 words 2^22 times, which is more than the words in the section.
+    let repeat = blocks(&[
+        (pef::RELOC_BY_SECT_D << 9) | 511,
+        (pef::RELOC_LG_REPEAT << 9) | 0x3f,
+        0xffff,
+    ]);
+    assert!(evaluate_relocations(&repeat, 0x10_0000).is_err());
+
+    // Repeat a run of 512 transition vectors 3 times.
+    let repeat = blocks(&[
+        (pef::RELOC_T_VECTOR_8 << 9) | 511,
+        (pef::RELOC_SM_REPEAT << 9) | 1,
+    ]);
+    assert_eq!(evaluate_relocations(&repeat, 3072 * 4).unwrap().len(), 3072);
+    assert!(evaluate_relocations(&repeat, 3072 * 4 - 4).is_err());
+
+    // Repeat a run of 4 words 3 times.
+    let repeat = blocks(&[
+        (pef::RELOC_BY_SECT_D << 9) | 3,
+        (pef::RELOC_SM_REPEAT << 9) | 2,
+    ]);
+    assert_eq!(evaluate_relocations(&repeat, u32::MAX).unwrap().len(), 16);
+}
+
+#[test]
+fn pef_carbon() {
+    use object::read::pef::{
+        carbon_support, is_carbon_symbol, CarbonCompatibility, CarbonSupport, PefFile,
+    };
+
+    fn build(libraries: &[(&[u8], &[&[u8]])]) -> Vec<u8> {
+        let mut object =
+            write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+        let data = object.section_id(write::StandardSection::Data);
+        for (library, names) in libraries {
+            let library = object.add_pef_imported_library(write::PefImportedLibrary {
+                name: library.to_vec(),
+                old_imp_version: 0,
+                current_version: 0,
+                weak: false,
+                init_before: false,
+            });
+            for name in *names {
+                let symbol = object.add_symbol(write::Symbol {
+                    name: name.to_vec(),
+                    value: 0,
+                    size: 0,
+                    kind: SymbolKind::Text,
+                    scope: SymbolScope::Unknown,
+                    weak: false,
+                    section: write::SymbolSection::Undefined,
+                    flags: SymbolFlags::None,
+                });
+                object.set_pef_symbol_library(symbol, library);
+                let offset = object.append_section_data(data, &[0; 4], 4);
+                object
+                    .add_relocation(data, relocation(offset, symbol))
+                    .unwrap();
+            }
+        }
+        object.write().unwrap()
+    }
+
+    assert!(is_carbon_symbol(b"SysBeep"));
+    assert!(is_carbon_symbol(b"WaitNextEvent"));
+    assert!(!is_carbon_symbol(b"InitGraf"));
+    assert!(!is_carbon_symbol(b"SFGetFile"));
+    assert_eq!(carbon_support(b"SysBeep"), CarbonSupport::Supported);
+    assert_eq!(carbon_support(b"InitGraf"), CarbonSupport::Unsupported);
+    assert_eq!(carbon_support(b"MyRoutine"), CarbonSupport::Unknown);
+
+    let classic = build(&[
+        (
+            b"InterfaceLib",
+            &[b"InitGraf", b"SysBeep", b"SFGetFile", b"MyRoutine"],
+        ),
+        (b"MathLib", &[b"sqrt"]),
+    ]);
+    let report = PefFile::<&[u8]>::parse(&*classic)
+        .unwrap()
+        .carbon_report()
+        .unwrap();
+    assert_eq!(report.compatibility(), CarbonCompatibility::ClassicOnly);
+    assert_eq!(
+        report.unsupported_imports(),
+        [&b"InitGraf"[..], b"SFGetFile"]
+    );
+    assert_eq!(report.unknown_imports(), [&b"MyRoutine"[..]]);
+
+    let carbon = build(&[(b"CarbonLib", &[b"InitGraf", b"SysBeep"])]);
+    let report = PefFile::<&[u8]>::parse(&*carbon)
+        .unwrap()
+        .carbon_report()
+        .unwrap();
+    assert_eq!(report.compatibility(), CarbonCompatibility::Carbon);
+    assert!(report.unsupported_imports().is_empty());
+
+    let mixed = build(&[
+        (b"CarbonLib", &[b"SysBeep"]),
+        (b"InterfaceLib", &[b"Gestalt"]),
+    ]);
+    let report = PefFile::<&[u8]>::parse(&*mixed)
+        .unwrap()
+        .carbon_report()
+        .unwrap();
+    assert_eq!(report.compatibility(), CarbonCompatibility::Mixed);
+    assert!(report.unsupported_imports().is_empty());
+
+    let none = build(&[(b"MathLib", &[b"sqrt"])]);
+    let report = PefFile::<&[u8]>::parse(&*none)
+        .unwrap()
+        .carbon_report()
+        .unwrap();
+    assert_eq!(report.compatibility(), CarbonCompatibility::None);
+}
+
+#[test]
+fn pef_scan() {
+    let mut object = write::Object::new(BinaryFormat::Pef, Architecture::PowerPc, Endianness::Big);
+    let text = object.section_id(write::StandardSection::Text);
+    object.append_section_data(text, &[0x4e, 0x80, 0x00, 0x20], 4);
+    let pef = object.write().unwrap();
+
+    // Two containers at unaligned offsets, with an invalid header between them.
+    let mut blob = vec![0xaa; 3];
+    blob.extend_from_slice(&pef);
+    blob.extend_from_slice(b"Joy!peff junk that is not a container header");
+    let second = blob.len();
+    blob.extend_from_slice(&pef);
+    blob.extend_from_slice(&[0; 16]);
+
+    assert_eq!(
+        object::FileKind::parse_at(&*blob, 3).unwrap(),
+        object::FileKind::Pef
+    );
+    assert_eq!(
+        object::FileKind::parse_at(&*blob, second as u64).unwrap(),
+        object::FileKind::Pef
+    );
+    assert!(object::FileKind::parse_at(&*blob, 0).is_err());
+
+    let found: Vec<_> = read::pef::PefFile::scan(&blob).collect();
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].0, 3);
+    assert_eq!(found[1].0, second as u64);
+    for (_, file) in &found {
+        assert_eq!(file.architecture(), Architecture::PowerPc);
+        assert_eq!(
+            file.section_by_name(".text").unwrap().data().unwrap(),
+            [0x4e, 0x80, 0x00, 0x20]
+        );
+    }
+}
@@ -0,0 +1,377 @@
+use object::read::{Object, ObjectSection, RelocateError, Relocator};
+use object::{
+    elf, read, write, Architecture, BinaryFormat, Endianness, RelocationFlags, SectionKind,
+    SymbolFlags, SymbolKind, SymbolScope,
+};
+
+fn add_symbol(
+    object: &mut write::Object,
+    name: &[u8],
+    section: Option<write::SectionId>,
+    value: u64,
+    kind: SymbolKind,
+) -> write::SymbolId {
+    object.add_symbol(write::Symbol {
+        name: name.to_vec(),
+        value,
+        size: 0,
+        kind,
+        scope: if section.is_some() {
+            SymbolScope::Compilation
+        } else {
+            SymbolScope::Unknown
+        },
+        weak: false,
+        section: match section {
+            Some(section) => write::SymbolSection::Section(section),
+            None => write::SymbolSection::Undefined,
+        },
+        flags: SymbolFlags::None,
+    })
+}
+
+fn add_relocation(
+    object: &mut write::Object,
+    section: write::SectionId,
+    offset: u64,
+    symbol: write::SymbolId,
+    addend: i64,
+    r_type: u32,
+) {
+    object
+        .add_relocation(
+            section,
+            write::Relocation {
+                offset,
+                symbol,
+                addend,
+                flags: RelocationFlags::Elf { r_type },
+            },
+        )
+        .unwrap();
+}
+
+fn section_index(file: &read::File, name: &str) -> object::SectionIndex {
+    file.section_by_name(name).unwrap().index()
+}
+
+#[test]
+fn relocate_x86_64() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+    let text = object.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+    #[rustfmt::skip]
+    object.set_section_data(text, vec![
+        // call ext
+        0xe8, 0, 0, 0, 0,
+        // mov ext@GOTPCREL(%rip), %rax
+        0x48, 0x8b, 0x05, 0, 0, 0, 0,
+        // mov $data, %rax
+        0x48, 0xc7, 0xc0, 0, 0, 0, 0,
+        // mov %fs:tls@tpoff, %eax
+        0x64, 0x8b, 0x04, 0x25, 0, 0, 0, 0,
+        // jmp *ext@GOTPCREL(%rip)
+        0xff, 0x25, 0, 0, 0, 0,
+    ], 16);
+    let data = object.add_section(Vec::new(), b".data".to_vec(), SectionKind::Data);
+    object.set_section_data(data, vec![0; 8], 8);
+    let tdata = object.add_section(Vec::new(), b".tdata".to_vec(), SectionKind::Tls);
+    object.set_section_data(tdata, vec![0; 8], 8);
+    let bad = object.add_section(Vec::new(), b".text.bad".to_vec(), SectionKind::Text);
+    object.set_section_data(bad, vec![0; 4], 4);
+
+    let func = add_symbol(&mut object, b"func", Some(text), 0, SymbolKind::Text);
+    let ext = add_symbol(&mut object, b"ext", None, 0, SymbolKind::Text);
+    let tls = add_symbol(&mut object, b"tls", Some(tdata), 4, SymbolKind::Tls);
+    let data_symbol = object.section_symbol(data);
+    add_relocation(&mut object, text, 1, ext, -4, elf::R_X86_64_PLT32);
+    add_relocation(&mut object, text, 8, ext, -4, elf::R_X86_64_REX_GOTPCRELX);
+    add_relocation(&mut object, text, 15, data_symbol, 0, elf::R_X86_64_32S);
+    add_relocation(&mut object, text, 23, tls, 0, elf::R_X86_64_TPOFF32);
+    add_relocation(&mut object, text, 29, ext, -4, elf::R_X86_64_GOTPCRELX);
+    add_relocation(&mut object, data, 0, func, 8, elf::R_X86_64_64);
+    add_relocation(&mut object, bad, 0, ext, -4, elf::R_X86_64_GOTPCREL);
+    let bytes = object.write().unwrap();
+
+    let file = read::File::parse(&*bytes).unwrap();
+    let text = section_index(&file, ".text");
+    let data = section_index(&file, ".data");
+    let tdata = section_index(&file, ".tdata");
+    let bad = section_index(&file, ".text.bad");
+    let mut relocator = Relocator::new(&file);
+    relocator.set_section_address(text, 0x1000);
+    relocator.set_section_address(data, 0x2000);
+    relocator.set_section_address(tdata, 0x8ff0);
+    relocator.set_thread_pointer(0x9000);
+
+    assert!(matches!(
+        relocator.relocate_section(text),
+        Err(RelocateError::UndefinedSymbol { offset: 1, .. })
+    ));
+    relocator.set_symbol_value(b"ext", 0x5000);
+
+    #[rustfmt::skip]
+    assert_eq!(relocator.relocate_section(text).unwrap(), [
+        0xe8, 0xfb, 0x3f, 0, 0,
+        0x48, 0x8d, 0x05, 0xf4, 0x3f, 0, 0,
+        0x48, 0xc7, 0xc0, 0, 0x20, 0, 0,
+        0x64, 0x8b, 0x04, 0x25, 0xf4, 0xff, 0xff, 0xff,
+        0xe9, 0xe0, 0x3f, 0, 0, 0x90,
+    ]);
+    assert_eq!(
+        relocator.relocate_section(data).unwrap(),
+        0x1008u64.to_le_bytes()
+    );
+    assert_eq!(
+        relocator.relocate_section(bad),
+        Err(RelocateError::UnsupportedRelocation {
+            offset: 0,
+            flags: RelocationFlags::Elf {
+                r_type: elf::R_X86_64_GOTPCREL
+            },
+        })
+    );
+}
+
+#[test]
+fn relocate_aarch64() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little);
+    let text = object.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+    let mut code = Vec::new();
+    // adrp x0, data; add x0, x0, :lo12:data; bl ext; ldr x1, [x0, :lo12:data+8]
+    for insn in [0x9000_0000u32, 0x9100_0000, 0x9400_0000, 0xf940_0001] {
+        code.extend_from_slice(&insn.to_le_bytes());
+    }
+    object.set_section_data(text, code, 4);
+    let far = object.add_section(Vec::new(), b".text.far".to_vec(), SectionKind::Text);
+    object.set_section_data(far, 0x9400_0000u32.to_le_bytes().to_vec(), 4);
+    let data = object.add_section(Vec::new(), b".data".to_vec(), SectionKind::Data);
+    object.set_section_data(data, vec![0; 16], 8);
+
+    let ext = add_symbol(&mut object, b"ext", None, 0, SymbolKind::Text);
+    let data_symbol = object.section_symbol(data);
+    add_relocation(
+        &mut object,
+        text,
+        0,
+        data_symbol,
+        0,
+        elf::R_AARCH64_ADR_PREL_PG_HI21,
+    );
+    add_relocation(
+        &mut object,
+        text,
+        4,
+        data_symbol,
+        0,
+        elf::R_AARCH64_ADD_ABS_LO12_NC,
+    );
+    add_relocation(&mut object, text, 8, ext, 0, elf::R_AARCH64_CALL26);
+    add_relocation(
+        &mut object,
+        text,
+        12,
+        data_symbol,
+        8,
+        elf::R_AARCH64_LDST64_ABS_LO12_NC,
+    );
+    add_relocation(&mut object, far, 0, ext, 0, elf::R_AARCH64_CALL26);
+    let bytes = object.write().unwrap();
+
+    let file = read::File::parse(&*bytes).unwrap();
+    let text = section_index(&file, ".text");
+    let far = section_index(&file, ".text.far");
+    let data = section_index(&file, ".data");
+    let mut relocator = Relocator::new(&file);
+    relocator.set_section_address(text, 0x10000);
+    relocator.set_section_address(far, 0x1001_0000);
+    relocator.set_section_address(data, 0x23_4560);
+    relocator.set_symbol_value(b"ext", 0x11000);
+
+    let mut expect = Vec::new();
+    for insn in [0x9000_1120u32, 0x9115_8000, 0x9400_03fe, 0xf942_b401] {
+        expect.extend_from_slice(&insn.to_le_bytes());
+    }
+    assert_eq!(relocator.relocate_section(text).unwrap(), expect);
+    assert!(matches!(
+        relocator.relocate_section(far),
+        Err(RelocateError::Overflow { offset: 0, .. })
+    ));
+}
+
+#[test]
+fn relocate_riscv64() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::Riscv64, Endianness::Little);
+    let text = object.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+    let mut code = Vec::new();
+    // call ext; 1: auipc a0, %pcrel_hi(data); addi a0, a0, %pcrel_lo(1b); sw a1, %pcrel_lo(1b)(a0)
+    for insn in [
+        0x0000_0097u32,
+        0x0000_80e7,
+        0x0000_0517,
+        0x0005_0513,
+        0x00b5_2023,
+    ] {
+        code.extend_from_slice(&insn.to_le_bytes());
+    }
+    object.set_section_data(text, code, 4);
+    let data = object.add_section(Vec::new(), b".data".to_vec(), SectionKind::Data);
+    object.set_section_data(data, vec![0; 4], 4);
+
+    let ext = add_symbol(&mut object, b"ext", None, 0, SymbolKind::Text);
+    let label = add_symbol(&mut object, b".L1", Some(text), 8, SymbolKind::Label);
+    let data_symbol = object.section_symbol(data);
+    add_relocation(&mut object, text, 0, ext, 0, elf::R_RISCV_CALL_PLT);
+    add_relocation(
+        &mut object,
+        text,
+        8,
+        data_symbol,
+        0,
+        elf::R_RISCV_PCREL_HI20,
+    );
+    add_relocation(&mut object, text, 12, label, 0, elf::R_RISCV_PCREL_LO12_I);
+    add_relocation(&mut object, text, 16, label, 0, elf::R_RISCV_PCREL_LO12_S);
+    let bytes = object.write().unwrap();
+
+    let file = read::File::parse(&*bytes).unwrap();
+    let text = section_index(&file, ".text");
+    let data = section_index(&file, ".data");
+    let mut relocator = Relocator::new(&file);
+    relocator.set_section_address(text, 0x8000_0000);
+    relocator.set_section_address(data, 0x8000_1ffc);
+    relocator.set_symbol_value(b"ext", 0x8000_2344);
+
+    let mut expect = Vec::new();
+    for insn in [
+        0x0000_2097u32,
+        0x3440_80e7,
+        0x0000_2517,
+        0xff45_0513,
+        0xfeb5_2a23,
+    ] {
+        expect.extend_from_slice(&insn.to_le_bytes());
+    }
+    assert_eq!(relocator.relocate_section(text).unwrap(), expect);
+}
+
+#[test]
+fn relocate_arm() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::Arm, Endianness::Little);
+    let text = object.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+    let mut code = Vec::new();
+    // bl thumb_func; movw r0, #:lower16:data; movt r0, #:upper16:data; .word data+4
+    for insn in [0xebff_fffeu32, 0xe300_0000, 0xe340_0000, 0] {
+        code.extend_from_slice(&insn.to_le_bytes());
+    }
+    object.set_section_data(text, code, 4);
+    let thumb = object.add_section(Vec::new(), b".text.thumb".to_vec(), SectionKind::Text);
+    // nop; nop; bl arm_func
+    let mut code = Vec::new();
+    for insn in [0xbf00u16, 0xbf00, 0xf7ff, 0xfffe] {
+        code.extend_from_slice(&insn.to_le_bytes());
+    }
+    object.set_section_data(thumb, code, 4);
+
+    let arm_func = add_symbol(&mut object, b"arm_func", Some(text), 0, SymbolKind::Text);
+    let thumb_func = add_symbol(&mut object, b"thumb_func", Some(thumb), 1, SymbolKind::Text);
+    let data = add_symbol(&mut object, b"data", None, 0, SymbolKind::Data);
+    add_relocation(&mut object, text, 0, thumb_func, 0, elf::R_ARM_CALL);
+    add_relocation(&mut object, text, 4, data, 0, elf::R_ARM_MOVW_ABS_NC);
+    add_relocation(&mut object, text, 8, data, 0, elf::R_ARM_MOVT_ABS);
+    add_relocation(&mut object, text, 12, data, 4, elf::R_ARM_ABS32);
+    add_relocation(&mut object, thumb, 4, arm_func, 0, elf::R_ARM_THM_PC22);
+    let bytes = object.write().unwrap();
+
+    let file = read::File::parse(&*bytes).unwrap();
+    let text = section_index(&file, ".text");
+    let thumb = section_index(&file, ".text.thumb");
+    let mut relocator = Relocator::new(&file);
+    relocator.set_section_address(text, 0x8000);
+    relocator.set_section_address(thumb, 0x9000);
+    relocator.set_symbol_value(b"data", 0x1234_5678);
+
+    let mut expect = Vec::new();
+    for insn in [0xfa00_03feu32, 0xe305_0678, 0xe341_0234, 0x1234_567c] {
+        expect.extend_from_slice(&insn.to_le_bytes());
+    }
+    assert_eq!(relocator.relocate_section(text).unwrap(), expect);
+    let mut expect = Vec::new();
+    for insn in [0xbf00u16, 0xbf00, 0xf7fe, 0xeffc] {
+        expect.extend_from_slice(&insn.to_le_bytes());
+    }
+    assert_eq!(relocator.relocate_section(thumb).unwrap(), expect);
+}
+
+#[test]
+fn relocate_ppc64() {
+    let mut object =
+        write::Object::new(BinaryFormat::Elf, Architecture::PowerPc64, Endianness::Big);
+    let text = object.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+    let mut code = Vec::new();
+    // bl func; lis r3, data@ha; ld r4, data@l(r3)
+    for insn in [0x4800_0001u32, 0x3c60_0000, 0xe883_0000] {
+        code.extend_from_slice(&insn.to_be_bytes());
+    }
+    code.resize(0x110, 0);
+    object.set_section_data(text, code, 4);
+
+    let func = object.add_symbol(write::Symbol {
+        name: b"func".to_vec(),
+        value: 0x100,
+        size: 0x10,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Compilation,
+        weak: false,
+        section: write::SymbolSection::Section(text),
+        // Local entry point at offset 8.
+        flags: SymbolFlags::Elf {
+            st_info: (elf::STB_LOCAL << 4) | elf::STT_FUNC,
+            st_other: 3 << elf::STO_PPC64_LOCAL_BIT,
+        },
+    });
+    let data = add_symbol(&mut object, b"data", None, 0, SymbolKind::Data);
+    add_relocation(&mut object, text, 0, func, 0, elf::R_PPC64_REL24);
+    add_relocation(&mut object, text, 6, data, 0, elf::R_PPC64_ADDR16_HA);
+    add_relocation(&mut object, text, 10, data, 0, elf::R_PPC64_ADDR16_LO_DS);
+    let bytes = object.write().unwrap();
+
+    let file = read::File::parse(&*bytes).unwrap();
+    let text = section_index(&file, ".text");
+    let mut relocator = Relocator::new(&file);
+    relocator.set_section_address(text, 0x1000_0000);
+    relocator.set_symbol_value(b"data", 0x1234_8000);
+
+    let relocated = relocator.relocate_section(text).unwrap();
+    let mut expect = Vec::new();
+    for insn in [0x4800_0109u32, 0x3c60_1235, 0xe883_8000] {
+        expect.extend_from_slice(&insn.to_be_bytes());
+    }
+    assert_eq!(relocated[..12], expect);
+}
+
+#[test]
+fn relocate_s390x() {
+    let mut object = write::Object::new(BinaryFormat::Elf, Architecture::S390x, Endianness::Big);
+    let text = object.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
+    // larl %r1, data
+    object.set_section_data(text, vec![0xc0, 0x10, 0, 0, 0, 0], 4);
+    let data = object.add_section(Vec::new(), b".data".to_vec(), SectionKind::Data);
+    object.set_section_data(data, vec![0; 4], 4);
+    let data_symbol = object.section_symbol(data);
+    add_relocation(&mut object, text, 2, data_symbol, 2, elf::R_390_PC32DBL);
+    let bytes = object.write().unwrap();
+
+    let file = read::File::parse(&*bytes).unwrap();
+    let text = section_index(&file, ".text");
+    let data = section_index(&file, ".data");
+    let mut relocator = Relocator::new(&file);
+    relocator.set_section_address(text, 0x1000);
+    relocator.set_section_address(data, 0x2000);
+    assert_eq!(
+        relocator.relocate_section(text).unwrap(),
+        [0xc0, 0x10, 0, 0, 0x08, 0x00]
+    );
+}